@@ -0,0 +1,41 @@
+//! Machine-readable descriptions of the configuration, for editors and other tools
+//! (`hooksmith api ...`).
+//!
+//! The output carries a `schema_version`. Adding fields keeps the version, while removing
+//! or changing the meaning of one bumps it.
+
+use serde::Serialize;
+
+/// Version of the schema of `hooksmith api hooks`.
+pub const HOOKS_SCHEMA_VERSION: u32 = 1;
+
+/// Output of `hooksmith api hooks`.
+#[derive(Debug, Serialize)]
+pub(crate) struct HookList<'a> {
+    pub schema_version: u32,
+    /// Configured hooks, sorted by name
+    pub hooks: Vec<HookEntry<'a>>,
+}
+
+/// A configured hook.
+#[derive(Debug, Serialize)]
+pub(crate) struct HookEntry<'a> {
+    pub name: &'a str,
+    /// Whether a hook file exists in the hooks directory
+    pub installed: bool,
+    /// Whether the hook runs when Git triggers it, `false` after `hooksmith disable`
+    pub enabled: bool,
+    /// Description of the hook, `null` when it has none
+    pub description: Option<&'a str>,
+    /// Commands in the order they run: path-based blocks, then `commands`, then groups
+    pub commands: Vec<CommandEntry<'a>>,
+}
+
+/// A command of a hook.
+#[derive(Debug, Serialize)]
+pub(crate) struct CommandEntry<'a> {
+    /// Name of the command, `null` when it has none
+    pub name: Option<&'a str>,
+    /// Command line run by the shell
+    pub run: &'a str,
+}
@@ -0,0 +1,234 @@
+//! Benchmarks of hooks (`hooksmith run --bench`): the hooks run several times, and the
+//! durations of each command are summarized across the runs.
+
+use crate::hooksmith::{HookTiming, Hooksmith};
+use std::{fmt::Write, time::Duration};
+
+/// Number of commands listed as the slowest ones.
+const SLOWEST_COMMANDS: usize = 5;
+
+/// Minimum, mean and maximum of a set of durations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationStats {
+    pub min: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+impl DurationStats {
+    /// Summarize durations.
+    ///
+    /// # Arguments
+    /// * `durations` - The durations, at least one
+    fn new(durations: &[Duration]) -> Self {
+        let total = durations.iter().sum::<Duration>();
+        let count = u32::try_from(durations.len()).unwrap_or(u32::MAX).max(1);
+
+        Self {
+            min: durations.iter().min().copied().unwrap_or_default(),
+            mean: total / count,
+            max: durations.iter().max().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Durations of a command across the runs of a benchmark.
+#[derive(Debug, Clone)]
+struct CommandBench {
+    hook_name: String,
+    /// Stable identifier of the command, see `HookCommand::key`
+    key: String,
+    /// How the command is shown: its name, or the command itself
+    label: String,
+    durations: Vec<Duration>,
+}
+
+/// Results of the runs of a benchmark.
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    /// Commands in the order they first ran
+    commands: Vec<CommandBench>,
+    /// Total duration of each run
+    runs: Vec<Duration>,
+}
+
+impl BenchReport {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a run of the benchmarked hooks.
+    ///
+    /// # Arguments
+    /// * `hooks` - The timings of the hooks, in the order they ran
+    /// * `duration` - Total duration of the run
+    pub fn record_run(&mut self, hooks: &[HookTiming], duration: Duration) {
+        for hook in hooks {
+            for command in &hook.commands {
+                let position = self.commands.iter().position(|bench| {
+                    bench.hook_name == hook.hook_name && bench.key == command.key
+                });
+
+                match position {
+                    Some(idx) => self.commands[idx].durations.push(command.duration),
+                    None => self.commands.push(CommandBench {
+                        hook_name: hook.hook_name.clone(),
+                        key: command.key.clone(),
+                        label: command.name.clone().unwrap_or(command.command.clone()),
+                        durations: vec![command.duration],
+                    }),
+                }
+            }
+        }
+
+        self.runs.push(duration);
+    }
+
+    /// Render the report: min/mean/max of each command grouped by hook, of the total, and
+    /// the slowest commands by mean duration.
+    pub fn render(&self) -> String {
+        let runs = self.runs.len();
+        let mut output = format!("\n⏱️  Benchmark over {runs} run(s):\n");
+
+        let mut current_hook = None;
+        for command in &self.commands {
+            if current_hook != Some(&command.hook_name) {
+                let _ = writeln!(output, "  Hook '{}'", command.hook_name);
+                current_hook = Some(&command.hook_name);
+            }
+
+            let _ = write!(
+                output,
+                "    {}: {}",
+                truncate(&command.label),
+                format_stats(&DurationStats::new(&command.durations))
+            );
+            if command.durations.len() < runs {
+                let _ = write!(output, " (ran {}/{runs} times)", command.durations.len());
+            }
+            output.push('\n');
+        }
+
+        let _ = writeln!(
+            output,
+            "  Total: {}",
+            format_stats(&DurationStats::new(&self.runs))
+        );
+
+        let slowest = self.slowest();
+        if !slowest.is_empty() {
+            output.push_str("\n🐢 Slowest commands (mean):\n");
+            for (rank, (command, stats)) in slowest.iter().enumerate() {
+                let _ = writeln!(
+                    output,
+                    "  {}. {} › {}: {}",
+                    rank + 1,
+                    command.hook_name,
+                    truncate(&command.label),
+                    Hooksmith::format_duration(&stats.mean)
+                );
+            }
+        }
+
+        output
+    }
+
+    /// The slowest commands by mean duration, slowest first.
+    fn slowest(&self) -> Vec<(&CommandBench, DurationStats)> {
+        let mut commands = self
+            .commands
+            .iter()
+            .map(|command| (command, DurationStats::new(&command.durations)))
+            .collect::<Vec<_>>();
+        commands.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.mean));
+        commands.truncate(SLOWEST_COMMANDS);
+
+        commands
+    }
+}
+
+/// Format durations as `min 12ms, mean 15ms, max 20ms`.
+fn format_stats(stats: &DurationStats) -> String {
+    format!(
+        "min {}, mean {}, max {}",
+        Hooksmith::format_duration(&stats.min),
+        Hooksmith::format_duration(&stats.mean),
+        Hooksmith::format_duration(&stats.max)
+    )
+}
+
+/// Shorten long commands, as in the timing report.
+fn truncate(label: &str) -> String {
+    if label.chars().count() > 60 {
+        format!("{}...", label.chars().take(57).collect::<String>())
+    } else {
+        label.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooksmith::CommandTiming;
+
+    fn hook_timing(hook_name: &str, commands: &[(&str, u64)]) -> HookTiming {
+        HookTiming {
+            hook_name: hook_name.to_string(),
+            commands: commands
+                .iter()
+                .map(|(command, millis)| CommandTiming {
+                    command: (*command).to_string(),
+                    name: None,
+                    key: (*command).to_string(),
+                    duration: Duration::from_millis(*millis),
+                    failed: false,
+                    exit_code: Some(0),
+                })
+                .collect(),
+            total_duration: Duration::ZERO,
+            skipped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_bench_report() {
+        let mut report = BenchReport::new();
+        report.record_run(
+            &[hook_timing("pre-commit", &[("fmt", 10), ("clippy", 300)])],
+            Duration::from_millis(310),
+        );
+        report.record_run(
+            &[hook_timing("pre-commit", &[("fmt", 30), ("clippy", 500)])],
+            Duration::from_millis(530),
+        );
+        // A command that only ran once, e.g. in a path-based block
+        report.record_run(
+            &[hook_timing(
+                "pre-commit",
+                &[("fmt", 20), ("clippy", 400), ("npm test", 900)],
+            )],
+            Duration::from_millis(1320),
+        );
+
+        assert_eq!(
+            DurationStats::new(&report.commands[0].durations),
+            DurationStats {
+                min: Duration::from_millis(10),
+                mean: Duration::from_millis(20),
+                max: Duration::from_millis(30),
+            }
+        );
+
+        let rendered = report.render();
+        assert!(rendered.contains("Benchmark over 3 run(s)"));
+        assert!(rendered.contains("    fmt: min 10ms, mean 20ms, max 30ms\n"));
+        assert!(
+            rendered.contains("    npm test: min 900ms, mean 900ms, max 900ms (ran 1/3 times)\n")
+        );
+        assert!(rendered.contains("  Total: min 310ms, mean 720ms, max 1.3s\n"));
+        assert!(rendered.contains(
+            "  1. pre-commit › npm test: 900ms\n  2. pre-commit › clippy: 400ms\n  3. pre-commit › fmt: 20ms\n"
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+//! Checks implemented by hooksmith itself, written `builtin: <name>` in the configuration.
+//! They run in the hooksmith process instead of a shell, so they need nothing installed.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Default maximum length of the subject line, and width of the body, of `commit-format`.
+const DEFAULT_COMMIT_LINE_WIDTH: usize = 72;
+
+/// Line Git places in the message file of verbose commits; everything below it is removed
+/// from the message.
+const SCISSORS_LINE: &str = "# ------------------------ >8 ------------------------";
+
+/// A check built into hooksmith.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "builtin", rename_all = "kebab-case")]
+pub enum Builtin {
+    /// Check the layout of the commit message whose path is `$1`, for `commit-msg`
+    CommitFormat(CommitFormat),
+}
+
+impl Builtin {
+    /// Name of the check, as written after `builtin:`.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CommitFormat(_) => "commit-format",
+        }
+    }
+
+    /// Run the check.
+    ///
+    /// # Arguments
+    /// * `hook_args` - The arguments Git gave the hook
+    ///
+    /// # Errors
+    /// * If the input of the check cannot be read, e.g. a missing commit message file
+    ///
+    /// # Returns
+    /// * The problems found, empty when the check passes
+    pub fn check(&self, hook_args: &[String]) -> std::io::Result<Vec<Violation>> {
+        match self {
+            Self::CommitFormat(rules) => {
+                let Some(path) = hook_args.first() else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "`builtin: commit-format` reads the commit message file passed as `$1`, run it from `commit-msg`",
+                    ));
+                };
+
+                Ok(rules.check(&std::fs::read_to_string(path)?))
+            }
+        }
+    }
+}
+
+/// Rules of `builtin: commit-format`, each of which can be turned off with `false`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitFormat {
+    /// Maximum number of characters of the subject line
+    #[serde(
+        default = "default_line_width",
+        deserialize_with = "deserialize_limit",
+        serialize_with = "serialize_limit"
+    )]
+    pub subject_length: Option<usize>,
+    /// Whether the subject must be followed by a blank line
+    #[serde(default = "default_true")]
+    pub blank_line: bool,
+    /// Maximum number of characters of the lines of the body. Lines without spaces, such as
+    /// long URLs, can't be wrapped and are left alone.
+    #[serde(
+        default = "default_line_width",
+        deserialize_with = "deserialize_limit",
+        serialize_with = "serialize_limit"
+    )]
+    pub body_width: Option<usize>,
+}
+
+impl Default for CommitFormat {
+    fn default() -> Self {
+        Self {
+            subject_length: default_line_width(),
+            blank_line: true,
+            body_width: default_line_width(),
+        }
+    }
+}
+
+impl CommitFormat {
+    /// Check a commit message. Comment lines are ignored, as Git removes them, and so is
+    /// everything below the scissors line of verbose commits.
+    ///
+    /// # Arguments
+    /// * `message` - Content of the commit message file
+    ///
+    /// # Returns
+    /// * The problems found, with the line and column they start at, in file order
+    #[must_use]
+    pub fn check(&self, message: &str) -> Vec<Violation> {
+        let lines = message
+            .lines()
+            .take_while(|line| *line != SCISSORS_LINE)
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .filter(|(_, line)| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        let mut violations = Vec::new();
+
+        let Some(&(subject_line, subject)) = lines.first() else {
+            return violations;
+        };
+
+        let subject_length = subject.chars().count();
+        if let Some(limit) = self.subject_length.filter(|limit| subject_length > *limit) {
+            violations.push(Violation {
+                line: subject_line,
+                column: limit + 1,
+                message: format!(
+                    "the subject is {subject_length} characters long, the limit is {limit}"
+                ),
+            });
+        }
+
+        if let Some(&(line, second)) = lines.get(1) {
+            if self.blank_line && !second.trim().is_empty() {
+                violations.push(Violation {
+                    line,
+                    column: 1,
+                    message: "the subject must be followed by a blank line".to_string(),
+                });
+            }
+        }
+
+        if let Some(limit) = self.body_width {
+            for &(line, text) in lines.iter().skip(1) {
+                let length = text.chars().count();
+
+                if length > limit && text.trim().contains(char::is_whitespace) {
+                    violations.push(Violation {
+                        line,
+                        column: limit + 1,
+                        message: format!(
+                            "the line is {length} characters long, wrap the body at {limit}"
+                        ),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// A problem found by a built-in check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Line of the problem, starting at 1
+    pub line: usize,
+    /// Column of the problem, in characters, starting at 1
+    pub column: usize,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+const fn default_line_width() -> Option<usize> {
+    Some(DEFAULT_COMMIT_LINE_WIDTH)
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// A limit written as a number, `true` for the default one or `false` to turn it off.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Limit {
+    Enabled(bool),
+    Value(usize),
+}
+
+/// Deserialize a limit, see `Limit`.
+fn deserialize_limit<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Limit::deserialize(deserializer)? {
+        Limit::Enabled(true) => default_line_width(),
+        Limit::Enabled(false) => None,
+        Limit::Value(limit) => Some(limit),
+    })
+}
+
+/// Serialize a limit as its value, or `false` when it is turned off.
+#[allow(clippy::ref_option)]
+fn serialize_limit<S>(limit: &Option<usize>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match limit {
+        Some(limit) => serializer.serialize_u64(*limit as u64),
+        None => serializer.serialize_bool(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_format() {
+        let rules = CommitFormat {
+            subject_length: Some(20),
+            blank_line: true,
+            body_width: Some(30),
+        };
+
+        assert!(rules
+            .check("Fix the parser\n\nThe body is short enough.\n")
+            .is_empty());
+        // Comments and the diff of verbose commits are ignored
+        assert!(rules
+            .check(&format!("# Please enter the commit message\nFix the parser\n\n{SCISSORS_LINE}\n+ a very long line of the diff that is kept as it is\n"))
+            .is_empty());
+
+        let violations = rules.check(
+            "Fix the parser of the configuration\nThe body starts right away and is too wide.\nhttps://example.com/a/very/long/link/that/cannot/be/wrapped\n",
+        );
+        assert_eq!(
+            violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "1:21: the subject is 35 characters long, the limit is 20",
+                "2:1: the subject must be followed by a blank line",
+                "2:31: the line is 43 characters long, wrap the body at 30",
+            ]
+        );
+
+        // Each rule can be turned off
+        let relaxed = CommitFormat {
+            subject_length: None,
+            blank_line: false,
+            body_width: None,
+        };
+        assert!(relaxed
+            .check("Fix the parser of the configuration\nThe body starts right away and is too wide.\n")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_commit_format_options() {
+        let builtin: Builtin =
+            serde_yaml::from_str("builtin: commit-format\nsubject_length: 50\nbody_width: false\n")
+                .unwrap();
+        assert_eq!(
+            builtin,
+            Builtin::CommitFormat(CommitFormat {
+                subject_length: Some(50),
+                blank_line: true,
+                body_width: None,
+            })
+        );
+
+        let builtin: Builtin = serde_yaml::from_str("builtin: commit-format\n").unwrap();
+        assert_eq!(builtin, Builtin::CommitFormat(CommitFormat::default()));
+
+        assert!(
+            serde_yaml::from_str::<Builtin>("builtin: commit-format\nsubject_len: 50\n").is_err()
+        );
+        assert!(serde_yaml::from_str::<Builtin>("builtin: commit-style\n").is_err());
+    }
+}
@@ -1,27 +1,212 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{num::NonZeroUsize, path::PathBuf};
 
-/// Commands enum for hooksmith CLI.
+/// Formats of the results of `hooksmith run`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    /// Progress and command output only
+    #[default]
+    #[value(alias = "human")]
+    Text,
+    /// A JUnit XML report, in addition to the progress and command output
+    Junit,
+    /// A JSON report of the results and captured output of every command, instead of the
+    /// progress and command output
+    Json,
+}
+
+/// Subcommands of `hooksmith api`.
 #[derive(Subcommand, PartialEq)]
-pub(crate) enum Command {
-    /// Compare installed hooks with the configuration file
-    #[command(about = "Compare installed hooks with configuration file")]
-    Compare,
+pub(crate) enum ApiCommand {
+    /// Print the configured hooks as JSON: whether each one is installed and enabled, and
+    /// its commands
+    #[command(about = "Print the configured hooks, their state and commands as JSON")]
+    Hooks,
+}
+
+/// Subcommands of `hooksmith cache`.
+#[derive(Subcommand, PartialEq)]
+pub(crate) enum CacheCommand {
+    /// Forget the last successful run of every hook recorded by `run --since-last-run`
+    #[command(about = "Forget the recorded last successful runs of hooks")]
+    Clear,
+}
+
+/// A command of the CLI, split by whether it reads the configuration.
+// Parsed once, so the size of the variants doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, PartialEq)]
+pub(crate) enum CliCommand {
+    /// Commands run without reading the configuration
+    #[command(flatten)]
+    Standalone(StandaloneCommand),
+
+    /// Commands acting on the configuration
+    #[command(flatten)]
+    Configured(Command),
+}
+
+/// Commands run without reading the configuration, e.g. because they create it.
+#[derive(Subcommand, PartialEq)]
+pub(crate) enum StandaloneCommand {
+    /// Manage the state recorded between runs
+    #[command(about = "Manage the state recorded between runs")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+
+    /// Turn all hooks off without uninstalling them
+    #[command(about = "Turn all hooks off without uninstalling them")]
+    Disable,
+
+    /// Turn hooks back on after `disable`
+    #[command(about = "Turn hooks back on after `disable`")]
+    Enable,
+
+    /// Explain what a git hook does and when it fires
+    #[command(about = "Explain what a Git hook does and when it fires")]
+    Explain {
+        /// Name of the git hook to explain
+        hook_name: String,
+    },
 
     /// Initialize hooksmith configuration interactively
     #[command(
         about = "Initialize hooksmith configuration interactively",
         alias = "i"
     )]
-    Init,
+    Init {
+        /// Start from a built-in template instead of selecting hooks
+        /// (rust, rust-strict, node, python, generic)
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+
+        /// Add the selected hooks to the existing configuration file instead of overwriting it
+        #[arg(long, default_value_t = false, conflicts_with = "template")]
+        append: bool,
+    },
+
+    /// Create the configuration file from a `.pre-commit-config.yaml` of the pre-commit
+    /// framework
+    #[command(about = "Create the configuration file from a pre-commit configuration")]
+    Import {
+        /// The pre-commit configuration file to convert
+        #[arg(default_value = ".pre-commit-config.yaml", value_name = "FILE")]
+        from: PathBuf,
+    },
+}
+
+/// Commands acting on the configuration.
+#[derive(Subcommand, PartialEq)]
+pub(crate) enum Command {
+    /// Machine-readable output for editors and other tools
+    #[command(about = "Machine-readable output for editors and other tools")]
+    Api {
+        #[command(subcommand)]
+        action: ApiCommand,
+    },
+
+    /// Show the configuration
+    #[command(about = "Show the configuration")]
+    Config {
+        /// Print the configuration hooksmith acts on, with the global configuration
+        /// merged and defaults applied
+        #[arg(long, required_unless_present_any = ["tree", "explain"])]
+        effective: bool,
+
+        /// Print JSON instead of YAML (or of a table, with `--explain`)
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Print the effective configuration as a tree of hooks and their commands
+        #[arg(long, default_value_t = false, conflicts_with = "json")]
+        tree: bool,
+
+        /// Print where each setting and hook comes from: the repository configuration,
+        /// the global configuration or the defaults, with the file and line setting it
+        #[arg(long, default_value_t = false, conflicts_with_all = ["effective", "tree"])]
+        explain: bool,
+    },
+
+    /// Compare installed hooks with the configuration file
+    #[command(about = "Compare installed hooks with configuration file")]
+    Compare {
+        /// Exit with an error if the hooks directory holds hooks hooksmith didn't install
+        #[arg(long, default_value_t = false)]
+        fail_on_foreign: bool,
+    },
+
+    /// Run a one-off command the way the commands of hooks run
+    #[command(about = "Run a one-off command the way the commands of hooks run")]
+    Exec {
+        /// Apply the settings of this hook too (`hook_timeout`, `umask`, `command_prefix`,
+        /// output capture)
+        #[arg(long, value_name = "HOOK")]
+        hook: Option<String>,
+
+        /// Capture the output and print it once the command finishes
+        #[arg(long, default_value_t = false)]
+        capture: bool,
+
+        /// Stop the command after this many seconds, overriding the hook's `hook_timeout`
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+
+        /// Also write the output of the command to this file, while still printing it
+        #[arg(long, value_name = "PATH")]
+        tee: Option<PathBuf>,
+
+        /// The command, run by the configured shell; its words are joined with spaces
+        /// (given after `--`)
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
 
     /// Install all hooks listed in the config file
     #[command(about = "Install all hooks listed in the config file")]
-    Install,
+    Install {
+        /// Fail instead of creating the hooks directory when it doesn't exist
+        #[arg(long, default_value_t = false)]
+        no_create_dir: bool,
+
+        /// With --dry-run, write the hook scripts to this directory instead of the hooks
+        /// directory, for review
+        #[arg(long, value_name = "DIR")]
+        emit_to: Option<PathBuf>,
+
+        /// Overwrite hooks hooksmith didn't install instead of backing them up to
+        /// `<hook>.bak`
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Keep hooks hooksmith didn't install running before hooksmith, by moving them to
+        /// `<hook>.local`
+        #[arg(long, default_value_t = false, conflicts_with = "force")]
+        append: bool,
+    },
+
+    /// List the configured hooks, whether each one is installed, and their commands
+    #[command(about = "List the configured hooks, whether they are installed, and their commands")]
+    List {
+        /// Only list the hooks hooksmith has installed
+        #[arg(long, default_value_t = false)]
+        installed_only: bool,
+    },
+
+    /// Comment out the hooks of the configuration file that aren't Git hooks, keeping a
+    /// backup of the file
+    #[command(about = "Comment out the hooks of the configuration that aren't Git hooks")]
+    PruneConfig {
+        /// Rewrite the configuration without asking for confirmation
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
 
     /// Run a specific hook
     #[command(about = "Run a specific hook")]
     Run {
-        /// Names of the hooks to run
+        /// Names of the hooks to run, or glob patterns such as `pre-*`
         #[arg(default_value = None)]
         hook_names: Option<Vec<String>>,
 
@@ -29,22 +214,134 @@ pub(crate) enum Command {
         #[arg(short, long, default_value_t = false)]
         interactive: bool,
 
+        /// Run every configured hook
+        #[arg(long, default_value_t = false, conflicts_with_all = ["hook_names", "interactive"])]
+        all: bool,
+
+        /// Hooks to leave out of the selection, separated by commas
+        #[arg(long, value_name = "HOOKS", value_delimiter = ',')]
+        except: Vec<String>,
+
+        /// Hook, or command as `<hook>:<index>` (1-based, in run order) or `<hook>:<name>`,
+        /// to skip; repeatable, on top of those listed in `HOOKSMITH_SKIP`
+        #[arg(long, value_name = "TARGET")]
+        skip: Vec<String>,
+
+        /// Only parse the settings and the given hooks, so that mistakes in other hooks of
+        /// the configuration don't stop them from running
+        #[arg(
+            long,
+            default_value_t = false,
+            requires = "hook_names",
+            conflicts_with_all = ["all", "interactive"]
+        )]
+        no_validate_all: bool,
+
         /// Show performance timing for hook execution
         #[arg(short, long, default_value_t = false)]
         profile: bool,
+
+        /// Maximum number of bytes of output printed per command, for hooks that capture
+        /// their output
+        #[arg(long, value_name = "N")]
+        max_output_bytes: Option<usize>,
+
+        /// Only process the files changed since each hook's last successful run
+        #[arg(long, default_value_t = false)]
+        since_last_run: bool,
+
+        /// Stash unstaged changes while the hooks run, so that they only see what is staged
+        #[arg(long, default_value_t = false)]
+        stash: bool,
+
+        /// Skip the hooks whose `files` patterns match none of the changed files (for
+        /// `pre-push`, the files of the pushed commits, read from stdin)
+        #[arg(long, default_value_t = false)]
+        only_changed_hooks: bool,
+
+        /// Print a line for each hook or block of commands that is skipped (default in
+        /// verbose mode)
+        #[arg(long, default_value_t = false, conflicts_with = "hide_skipped")]
+        show_skipped: bool,
+
+        /// Don't print skipped hooks and blocks of commands, even in verbose mode
+        #[arg(long, default_value_t = false)]
+        hide_skipped: bool,
+
+        /// Also write the output of every command to this file, while still printing it
+        #[arg(long, value_name = "PATH")]
+        tee: Option<PathBuf>,
+
+        /// Print the Git-related environment variables (`GIT_*`) each hook starts with. Set
+        /// `HOOKSMITH_SHOW_GIT_ENV=1` to get them for the hooks Git triggers.
+        #[arg(long, default_value_t = false)]
+        git_env: bool,
+
+        /// Command to run once before the hooks, which don't run if it fails (repeatable)
+        #[arg(long, value_name = "COMMAND")]
+        pre_run: Vec<String>,
+
+        /// Command to run once after the hooks, whether they succeeded or not (repeatable)
+        #[arg(long, value_name = "COMMAND")]
+        post_run: Vec<String>,
+
+        /// With `--dry-run`, print the exact argv, environment and working directory of
+        /// each command instead of the readable preview
+        #[arg(long, default_value_t = false)]
+        resolve: bool,
+
+        /// Run the hooks N times (5 by default) and print the min/mean/max duration of each
+        /// command. The commands really run each time.
+        #[arg(
+            long,
+            value_name = "N",
+            num_args = 0..=1,
+            default_missing_value = "5",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+            conflicts_with = "profile"
+        )]
+        bench: Option<usize>,
+
+        /// Write the given commit message to a temporary file and pass its path as `$1`
+        #[arg(long, value_name = "MESSAGE", conflicts_with = "hook_args")]
+        stdin_message: Option<String>,
+
+        /// Arguments forwarded to the hook's commands as `$1`, `$2`, ... (given after `--`)
+        #[arg(last = true, value_name = "ARGS")]
+        hook_args: Vec<String>,
     },
 
     /// Uninstall hooks
     #[command(about = "Uninstall hooks")]
     Uninstall {
-        /// Optional name of the hook to uninstall. If not provided, all hooks will be uninstalled.
-        #[arg(default_value = None)]
-        hook_name: Option<String>,
+        /// Names of the hooks to uninstall, or glob patterns such as `pre-*`. If none are
+        /// provided, all hooks will be uninstalled.
+        hook_names: Vec<String>,
+
+        /// Don't ask for confirmation when a pattern matches several hooks
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
     },
 
+    /// Rewrite the installed scripts of configured hooks that are out of date, e.g. after
+    /// upgrading hooksmith; scripts not written by hooksmith are left alone
+    #[command(about = "Refresh the installed hook scripts after upgrading hooksmith")]
+    Upgrade,
+
     /// Validate hooks configuration
     #[command(about = "Validate hooks in configuration file against standard Git hooks")]
     Validate,
+
+    /// Check that the configuration is valid, every hook is installed and up to date, and
+    /// referenced scripts exist, exiting with a non-zero status otherwise (for CI)
+    #[command(about = "Check that installed hooks and scripts match the configuration (for CI)")]
+    Verify,
+
+    /// Diagnose why hooks may not fire: the repository, the hooks directory and whether it
+    /// is writable, and whether each configured hook is a Git hook installed as an
+    /// executable script, exiting with a non-zero status if a check fails
+    #[command(about = "Diagnose setup problems that keep hooks from firing")]
+    Doctor,
 }
 
 /// Command line interface structure for hooksmith.
@@ -56,54 +353,550 @@ pub(crate) enum Command {
 pub(crate) struct Cli {
     /// Command to execute
     #[command(subcommand)]
-    pub(crate) command: Command,
+    pub(crate) command: CliCommand,
 
-    /// Path to the hooksmith.yaml file
-    #[arg(short, long, default_value_t = String::from("hooksmith.yaml"))]
-    pub(crate) config_path: String,
+    /// Path to the configuration file: a YAML file, or a `Cargo.toml` or `.json` file
+    /// embedding the configuration [default: hooksmith.yaml, or else the hooksmith section
+    /// of Cargo.toml or package.json]
+    #[arg(short, long)]
+    pub(crate) config_path: Option<String>,
 
     /// Whether to print verbose output
     #[arg(short, long, default_value_t = false)]
     pub(crate) verbose: bool,
 
     /// Whether to perform a dry run
-    #[arg(long, default_value_t = false)]
+    #[arg(long, global = true, default_value_t = false)]
     pub(crate) dry_run: bool,
+
+    /// Shell running the commands instead of the configured `shell`, optionally with its
+    /// arguments (e.g. `pwsh -Command`); hooks setting their own `shell` keep it
+    #[arg(long, global = true, value_name = "SHELL")]
+    pub(crate) shell: Option<String>,
+
+    /// Only use the repository configuration, ignoring the user's global configuration
+    #[arg(long, default_value_t = false)]
+    pub(crate) no_global_config: bool,
+
+    /// Exit with a non-zero status if any warning was emitted
+    #[arg(long, global = true, default_value_t = false)]
+    pub(crate) warnings_as_errors: bool,
+
+    /// Print errors on a single line, without their title and suggestion
+    #[arg(long, global = true, default_value_t = false)]
+    pub(crate) concise_errors: bool,
+
+    /// Only print errors: hide warnings, success messages and the weekly reminder enabled
+    /// with `reminder: true` in the global configuration
+    #[arg(short, long, global = true, default_value_t = false)]
+    pub(crate) quiet: bool,
+
+    /// How many hooks `run` runs at the same time, printing the output of each command
+    /// once it is done
+    #[arg(short, long, global = true, value_name = "N", default_value = "1")]
+    pub(crate) jobs: NonZeroUsize,
+
+    /// Format of the results of `run`
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) format: OutputFormat,
+
+    /// Write the results of `run` to this file instead of stdout (with `--format junit` or
+    /// `json`)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub(crate) output: Option<PathBuf>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// The parsed command acting on the configuration.
+    fn configured(command: CliCommand) -> Command {
+        match command {
+            CliCommand::Configured(command) => command,
+            CliCommand::Standalone(_) => panic!("Expected a command acting on the configuration"),
+        }
+    }
+
+    /// The parsed command run without reading the configuration.
+    fn standalone(command: CliCommand) -> StandaloneCommand {
+        match command {
+            CliCommand::Standalone(command) => command,
+            CliCommand::Configured(_) => panic!("Expected a command run without the configuration"),
+        }
+    }
+
     #[test]
     fn test_cli_parsing() {
         // Test basic command parsing
         let args = vec!["hooksmith", "install"];
         let cli = Cli::parse_from(args);
 
-        match cli.command {
-            Command::Install => {}
+        match configured(cli.command) {
+            Command::Install {
+                no_create_dir,
+                emit_to,
+                force,
+                append,
+            } => {
+                assert!(!no_create_dir);
+                assert!(emit_to.is_none());
+                assert!(!force);
+                assert!(!append);
+            }
             _ => panic!("Expected Install command"),
         }
 
+        let args = vec!["hooksmith", "install", "--no-create-dir"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Install { no_create_dir, .. } => assert!(no_create_dir),
+            _ => panic!("Expected Install command with --no-create-dir"),
+        }
+
+        let args = vec!["hooksmith", "--dry-run", "install", "--emit-to", "review"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Install { emit_to, .. } => {
+                assert!(cli.dry_run);
+                assert_eq!(emit_to, Some(PathBuf::from("review")));
+            }
+            _ => panic!("Expected Install command with --emit-to"),
+        }
+
+        let args = vec!["hooksmith", "install", "--force"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Install { force, .. } => assert!(force),
+            _ => panic!("Expected Install command with --force"),
+        }
+
+        let args = vec!["hooksmith", "install", "--append"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Install { append, .. } => assert!(append),
+            _ => panic!("Expected Install command with --append"),
+        }
+        assert!(Cli::try_parse_from(["hooksmith", "install", "--append", "--force"]).is_err());
+
+        let args = vec!["hooksmith", "init", "--template", "rust"];
+        let cli = Cli::parse_from(args);
+
+        match standalone(cli.command) {
+            StandaloneCommand::Init { template, append } => {
+                assert_eq!(template.as_deref(), Some("rust"));
+                assert!(!append);
+            }
+            _ => panic!("Expected Init command with a template"),
+        }
+
         // Test with arguments
         let args = vec!["hooksmith", "run", "pre-commit", "pre-push"];
         let cli = Cli::parse_from(args);
 
-        match cli.command {
+        match configured(cli.command) {
             Command::Run {
                 hook_names,
                 interactive,
+                all,
+                except,
+                skip,
+                no_validate_all,
                 profile,
+                max_output_bytes,
+                since_last_run,
+                stash,
+                only_changed_hooks,
+                show_skipped,
+                hide_skipped,
+                resolve,
+                tee,
+                git_env,
+                pre_run,
+                post_run,
+                bench,
+                stdin_message,
+                hook_args,
             } => {
                 assert_eq!(
                     hook_names,
                     Some(vec!["pre-commit".to_string(), "pre-push".to_string()])
                 );
                 assert!(!interactive);
+                assert!(!all);
+                assert!(except.is_empty());
+                assert!(skip.is_empty());
+                assert!(!no_validate_all);
                 assert!(!profile);
+                assert_eq!(max_output_bytes, None);
+                assert!(!since_last_run);
+                assert!(!stash);
+                assert!(!only_changed_hooks);
+                assert!(!show_skipped);
+                assert!(!hide_skipped);
+                assert!(!resolve);
+                assert_eq!(tee, None);
+                assert!(!git_env);
+                assert!(pre_run.is_empty() && post_run.is_empty());
+                assert_eq!(bench, None);
+                assert_eq!(stdin_message, None);
+                assert!(hook_args.is_empty());
             }
             _ => panic!("Expected Run command with hook_names=[pre-commit, pre-push]"),
         }
+
+        // Test commit message shortcut
+        let args = vec![
+            "hooksmith",
+            "run",
+            "commit-msg",
+            "--stdin-message",
+            "feat: add thing",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { stdin_message, .. } => {
+                assert_eq!(stdin_message.as_deref(), Some("feat: add thing"));
+            }
+            _ => panic!("Expected Run command with stdin_message"),
+        }
+
+        // Test arguments forwarded after `--`
+        let args = vec![
+            "hooksmith",
+            "run",
+            "pre-push",
+            "--",
+            "origin",
+            "--force",
+            "git@github.com:me/repo.git",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run {
+                hook_names,
+                hook_args,
+                ..
+            } => {
+                assert_eq!(hook_names, Some(vec!["pre-push".to_string()]));
+                assert_eq!(
+                    hook_args,
+                    vec!["origin", "--force", "git@github.com:me/repo.git"]
+                );
+            }
+            _ => panic!("Expected Run command with forwarded arguments"),
+        }
+
+        // Test incremental mode and cache management
+        let args = vec!["hooksmith", "run", "pre-commit", "--since-last-run"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { since_last_run, .. } => assert!(since_last_run),
+            _ => panic!("Expected Run command with --since-last-run"),
+        }
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--stash"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { stash, .. } => assert!(stash),
+            _ => panic!("Expected Run command with --stash"),
+        }
+
+        let args = vec!["hooksmith", "run", "pre-push", "--only-changed-hooks"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run {
+                only_changed_hooks, ..
+            } => assert!(only_changed_hooks),
+            _ => panic!("Expected Run command with --only-changed-hooks"),
+        }
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--hide-skipped"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run {
+                show_skipped,
+                hide_skipped,
+                ..
+            } => assert!(!show_skipped && hide_skipped),
+            _ => panic!("Expected Run command with --hide-skipped"),
+        }
+
+        let args = vec![
+            "hooksmith",
+            "run",
+            "pre-commit",
+            "--show-skipped",
+            "--hide-skipped",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        let args = vec![
+            "hooksmith",
+            "run",
+            "pre-commit",
+            "--format",
+            "junit",
+            "--output",
+            "report.xml",
+        ];
+        let cli = Cli::parse_from(args);
+
+        assert_eq!(cli.format, OutputFormat::Junit);
+        assert_eq!(cli.output, Some(PathBuf::from("report.xml")));
+
+        for (format, expected) in [("json", OutputFormat::Json), ("human", OutputFormat::Text)] {
+            let cli = Cli::parse_from(["hooksmith", "run", "pre-commit", "--format", format]);
+            assert_eq!(cli.format, expected);
+        }
+
+        // `--format` is global, so it is accepted before the subcommand too
+        let cli = Cli::parse_from(["hooksmith", "--format", "json", "run", "pre-commit"]);
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert_eq!(cli.output, None);
+        assert_eq!(
+            Cli::parse_from(["hooksmith", "validate"]).format,
+            OutputFormat::Text
+        );
+
+        // `--dry-run` is accepted after the subcommand too
+        let args = vec!["hooksmith", "run", "pre-commit", "--dry-run", "--resolve"];
+        let cli = Cli::parse_from(args);
+
+        assert!(cli.dry_run);
+        match configured(cli.command) {
+            Command::Run { resolve, .. } => assert!(resolve),
+            _ => panic!("Expected Run command with --resolve"),
+        }
+
+        assert_eq!(cli.jobs.get(), 1);
+        let cli = Cli::parse_from(["hooksmith", "run", "--all", "-j", "4"]);
+        assert_eq!(cli.jobs.get(), 4);
+        assert!(Cli::try_parse_from(["hooksmith", "run", "--all", "--jobs", "0"]).is_err());
+
+        let args = vec![
+            "hooksmith",
+            "run",
+            "--all",
+            "--except",
+            "pre-receive,pre-push",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { all, except, .. } => {
+                assert!(all);
+                assert_eq!(except, vec!["pre-receive", "pre-push"]);
+            }
+            _ => panic!("Expected Run command with --all --except"),
+        }
+
+        let args = vec![
+            "hooksmith",
+            "run",
+            "pre-commit",
+            "--skip",
+            "pre-push",
+            "--skip",
+            "pre-commit:2",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { skip, .. } => assert_eq!(skip, vec!["pre-push", "pre-commit:2"]),
+            _ => panic!("Expected Run command with --skip"),
+        }
+        assert!(Cli::try_parse_from(["hooksmith", "run", "pre-commit", "--all"]).is_err());
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--tee", "hooks.log"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { tee, .. } => assert_eq!(tee, Some(PathBuf::from("hooks.log"))),
+            _ => panic!("Expected Run command with --tee"),
+        }
+
+        let args = vec![
+            "hooksmith",
+            "run",
+            "pre-commit",
+            "--pre-run",
+            "echo starting",
+            "--post-run",
+            "echo done",
+            "--post-run",
+            "./upload.sh",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run {
+                pre_run, post_run, ..
+            } => {
+                assert_eq!(pre_run, vec!["echo starting"]);
+                assert_eq!(post_run, vec!["echo done", "./upload.sh"]);
+            }
+            _ => panic!("Expected Run command with --pre-run and --post-run"),
+        }
+
+        let args = vec![
+            "hooksmith",
+            "exec",
+            "--hook",
+            "pre-commit",
+            "--",
+            "cargo",
+            "fmt",
+            "--check",
+        ];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Exec {
+                hook,
+                capture,
+                timeout,
+                command,
+                ..
+            } => {
+                assert_eq!(hook.as_deref(), Some("pre-commit"));
+                assert!(!capture);
+                assert_eq!(timeout, None);
+                assert_eq!(command, vec!["cargo", "fmt", "--check"]);
+            }
+            _ => panic!("Expected Exec command with a command line"),
+        }
+        assert!(Cli::try_parse_from(["hooksmith", "exec"]).is_err());
+
+        assert!(
+            Cli::try_parse_from(["hooksmith", "run", "pre-commit", "--no-validate-all"]).is_ok()
+        );
+        assert!(Cli::try_parse_from(["hooksmith", "run", "--all", "--no-validate-all"]).is_err());
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--bench"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run { bench, .. } => assert_eq!(bench, Some(5)),
+            _ => panic!("Expected Run command with --bench"),
+        }
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--bench", "3"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Run {
+                hook_names, bench, ..
+            } => {
+                assert_eq!(hook_names, Some(vec!["pre-commit".to_string()]));
+                assert_eq!(bench, Some(3));
+            }
+            _ => panic!("Expected Run command with --bench 3"),
+        }
+
+        let args = vec!["hooksmith", "run", "pre-commit", "--bench", "0"];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        let args = vec!["hooksmith", "uninstall", "pre-*", "commit-msg", "--yes"];
+        let cli = Cli::parse_from(args);
+
+        match configured(cli.command) {
+            Command::Uninstall { hook_names, yes } => {
+                assert_eq!(
+                    hook_names,
+                    vec!["pre-*".to_string(), "commit-msg".to_string()]
+                );
+                assert!(yes);
+            }
+            _ => panic!("Expected Uninstall command with patterns"),
+        }
+
+        let args = vec!["hooksmith", "verify"];
+        let cli = Cli::parse_from(args);
+
+        assert!(matches!(configured(cli.command), Command::Verify));
+
+        let cli = Cli::parse_from(["hooksmith", "doctor"]);
+        assert!(matches!(configured(cli.command), Command::Doctor));
+
+        let cli = Cli::parse_from(["hooksmith", "upgrade"]);
+        assert!(matches!(configured(cli.command), Command::Upgrade));
+
+        let cli = Cli::parse_from(["hooksmith", "list", "--installed-only"]);
+        assert!(matches!(
+            configured(cli.command),
+            Command::List {
+                installed_only: true
+            }
+        ));
+
+        let args = vec!["hooksmith", "cache", "clear"];
+        let cli = Cli::parse_from(args);
+
+        assert!(matches!(
+            standalone(cli.command),
+            StandaloneCommand::Cache {
+                action: CacheCommand::Clear
+            }
+        ));
+
+        let cli = Cli::parse_from(["hooksmith", "api", "hooks"]);
+        assert!(matches!(
+            configured(cli.command),
+            Command::Api {
+                action: ApiCommand::Hooks
+            }
+        ));
+        let cli = Cli::parse_from(["hooksmith", "config", "--explain", "--json"]);
+        assert!(matches!(
+            configured(cli.command),
+            Command::Config {
+                explain: true,
+                json: true,
+                effective: false,
+                tree: false
+            }
+        ));
+        assert!(Cli::try_parse_from(["hooksmith", "config", "--explain", "--tree"]).is_err());
+
+        for (args, expected) in [
+            (vec!["hooksmith", "import"], ".pre-commit-config.yaml"),
+            (
+                vec!["hooksmith", "import", "ci/pre-commit.yaml"],
+                "ci/pre-commit.yaml",
+            ),
+        ] {
+            match standalone(Cli::parse_from(args).command) {
+                StandaloneCommand::Import { from } => assert_eq!(from, PathBuf::from(expected)),
+                _ => panic!("Expected Import command"),
+            }
+        }
+
+        let cli = Cli::parse_from(["hooksmith", "prune-config", "--yes"]);
+        assert!(matches!(
+            configured(cli.command),
+            Command::PruneConfig { yes: true }
+        ));
+        let cli = Cli::parse_from(["hooksmith", "disable"]);
+        assert!(matches!(
+            standalone(cli.command),
+            StandaloneCommand::Disable
+        ));
+        let cli = Cli::parse_from(["hooksmith", "enable"]);
+        assert!(matches!(standalone(cli.command), StandaloneCommand::Enable));
+
+        let cli = Cli::parse_from(["hooksmith", "validate", "--warnings-as-errors"]);
+        assert!(cli.warnings_as_errors);
+        assert!(matches!(configured(cli.command), Command::Validate));
     }
 }
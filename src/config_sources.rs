@@ -0,0 +1,172 @@
+//! Where each setting and hook of the effective configuration comes from, for
+//! `hooksmith config --explain`.
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Files the configuration was assembled from.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigSources {
+    /// The repository configuration file, `None` when it was read from something else
+    pub repository: Option<PathBuf>,
+    /// The user's global configuration file, when it was merged
+    pub global: Option<PathBuf>,
+    /// Keys of the settings taken from the global configuration, e.g. `shell` or `env.CI`
+    pub from_global: Vec<String>,
+}
+
+/// The layer a key of the effective configuration comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Origin {
+    /// The repository configuration
+    Repository,
+    /// The user's global configuration
+    Global,
+    /// Nothing sets it, hooksmith's default applies
+    Default,
+}
+
+/// A key of the effective configuration and where it was set.
+#[derive(Debug, Serialize)]
+pub(crate) struct KeySource {
+    /// The key, e.g. `shell`, `env.CI` or `pre-commit`
+    pub key: String,
+    pub origin: Origin,
+    /// The file setting it, `null` for defaults and configurations not read from a file
+    pub file: Option<PathBuf>,
+    /// The line of the key in the file, starting at 1, when it could be found
+    pub line: Option<usize>,
+}
+
+impl ConfigSources {
+    /// Find where each of the given keys is set.
+    ///
+    /// # Arguments
+    /// * `keys` - Keys of the effective configuration, with whether a layer sets them
+    pub fn explain(&self, keys: &[(String, bool)]) -> Vec<KeySource> {
+        let repository_lines = self.repository.as_deref().map(file_key_lines);
+        let global_lines = self.global.as_deref().map(file_key_lines);
+
+        keys.iter()
+            .map(|(key, is_set)| {
+                let (origin, file, lines) = if !is_set {
+                    (Origin::Default, None, None)
+                } else if self.from_global.contains(key) {
+                    (Origin::Global, self.global.clone(), global_lines.as_ref())
+                } else {
+                    (
+                        Origin::Repository,
+                        self.repository.clone(),
+                        repository_lines.as_ref(),
+                    )
+                };
+
+                KeySource {
+                    key: key.clone(),
+                    origin,
+                    file,
+                    line: lines.and_then(|lines| lines.get(key).copied()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Render the sources of the keys as an aligned table: key, origin and `file:line`.
+pub(crate) fn render_table(sources: &[KeySource]) -> String {
+    let width = sources
+        .iter()
+        .map(|source| source.key.len())
+        .max()
+        .unwrap_or_default();
+    let mut table = String::new();
+
+    for source in sources {
+        let origin = match source.origin {
+            Origin::Repository => "repository",
+            Origin::Global => "global",
+            Origin::Default => "default",
+        };
+        let location = match (&source.file, source.line) {
+            (Some(file), Some(line)) => format!("{}:{line}", file.display()),
+            (Some(file), None) => file.display().to_string(),
+            (None, _) => String::new(),
+        };
+
+        let row = format!("{:width$}  {origin:10}  {location}", source.key);
+        let _ = writeln!(table, "{}", row.trim_end());
+    }
+
+    table
+}
+
+/// Lines of the keys of a configuration file, see `key_lines`. Unreadable files have none.
+fn file_key_lines(path: &Path) -> HashMap<String, usize> {
+    std::fs::read_to_string(path)
+        .map(|content| key_lines(&content))
+        .unwrap_or_default()
+}
+
+/// Find the line of each top-level key of a YAML configuration, and of each variable of
+/// its `env` mapping (as `env.NAME`), starting at 1.
+///
+/// # Arguments
+/// * `content` - The configuration, as YAML
+pub(crate) fn key_lines(content: &str) -> HashMap<String, usize> {
+    let mut lines = HashMap::new();
+    let mut current_key = None;
+    let mut env_indent = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let key = trimmed
+            .split_once(':')
+            .map(|(key, _)| key.trim().trim_matches(['"', '\'']).to_string());
+        let indent = line.len() - trimmed.len();
+
+        if indent == 0 {
+            current_key.clone_from(&key);
+            env_indent = None;
+
+            if let Some(key) = key {
+                lines.entry(key).or_insert(index + 1);
+            }
+        } else if current_key.as_deref() == Some("env")
+            && *env_indent.get_or_insert(indent) == indent
+        {
+            if let Some(key) = key {
+                lines.entry(format!("env.{key}")).or_insert(index + 1);
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_lines() {
+        let lines = key_lines(
+            "# Settings\nshell: bash\nenv:\n  CI: \"true\"\n  # comment\n  'RUST_LOG': debug\n\n\"pre-commit\":\n  commands:\n    - env: true\n",
+        );
+
+        assert_eq!(lines["shell"], 2);
+        assert_eq!(lines["env"], 3);
+        assert_eq!(lines["env.CI"], 4);
+        assert_eq!(lines["env.RUST_LOG"], 6);
+        assert_eq!(lines["pre-commit"], 8);
+        assert_eq!(lines.len(), 5);
+    }
+}
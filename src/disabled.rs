@@ -0,0 +1,58 @@
+//! Marker turning all hooks off without uninstalling them (`hooksmith disable`).
+//!
+//! The marker is `.git/hooksmith-disabled`. While it exists, the installed hook scripts
+//! still call hooksmith, which runs nothing.
+
+use crate::{error::Result, git_related::get_git_path};
+use std::{fs, io, path::PathBuf};
+
+/// Name of the marker file inside the Git directory.
+const MARKER_FILE: &str = "hooksmith-disabled";
+
+/// Path of the marker file, whether it exists or not.
+///
+/// # Errors
+/// * If the current directory is not inside a Git repository
+pub fn marker_path() -> Result<PathBuf> {
+    Ok(get_git_path(MARKER_FILE)?)
+}
+
+/// Whether hooks are disabled. Outside a Git repository, they aren't.
+pub fn is_disabled() -> bool {
+    marker_path().is_ok_and(|path| path.exists())
+}
+
+/// Disable all hooks by creating the marker.
+///
+/// # Errors
+/// * If the current directory is not inside a Git repository
+/// * If the marker cannot be written
+///
+/// # Returns
+/// * `false` if hooks were already disabled
+pub fn disable() -> Result<bool> {
+    let path = marker_path()?;
+    if path.exists() {
+        return Ok(false);
+    }
+
+    fs::write(path, "Hooks disabled by `hooksmith disable`, remove this file or run `hooksmith enable` to turn them back on.\n")?;
+
+    Ok(true)
+}
+
+/// Enable hooks again by removing the marker.
+///
+/// # Errors
+/// * If the current directory is not inside a Git repository
+/// * If the marker cannot be removed
+///
+/// # Returns
+/// * `false` if hooks weren't disabled
+pub fn enable() -> Result<bool> {
+    match fs::remove_file(marker_path()?) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
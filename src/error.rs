@@ -28,8 +28,17 @@ pub enum ConfigError {
     #[error("Failed to parse config file: {0}")]
     Parse(#[from] serde_yaml::Error),
 
+    #[error("Failed to read the configuration embedded in {path}: {message}")]
+    Embedded { path: String, message: String },
+
     #[error("Config file not found at: {0}")]
     NotFound(String),
+
+    #[error("Unknown template: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Failed to serialize config: {0}")]
+    Serialize(String),
 }
 
 /// Errors related to Git operations.
@@ -43,6 +52,23 @@ pub enum GitError {
 
     #[error("Not a git repository")]
     NotGitRepo,
+
+    #[error("Invalid hooks directory: {0}")]
+    InvalidHooksDir(String),
+
+    #[error("Failed to compute changed files: {0}")]
+    ChangedFiles(String),
+
+    #[error("Failed to stash unstaged changes: {0}")]
+    Stash(String),
+
+    #[error(
+        "Failed to restore unstaged changes, they are kept in the stash (bring them back with `git stash pop` once resolved): {0}"
+    )]
+    StashRestore(String),
+
+    #[error("Failed to remove the files of hooks: {}", .0.join(", "))]
+    HookRemoval(Vec<String>),
 }
 
 /// Errors related to hook execution.
@@ -56,6 +82,26 @@ pub enum HookExecutionError {
 
     #[error("Hook not found: {0}")]
     HookNotFound(String),
+
+    #[error("Hook '{hook}' exceeded its {seconds}s time budget while running `{command}`")]
+    HookTimeout {
+        hook: String,
+        seconds: u64,
+        command: String,
+    },
+
+    #[error("Hook '{hook}' command `{command}` exceeded its {seconds}s timeout")]
+    CommandTimeout {
+        hook: String,
+        seconds: u64,
+        command: String,
+    },
+
+    #[error("Interrupted by signal {0}")]
+    Interrupted(i32),
+
+    #[error("Working directory not found: {0}")]
+    WorkingDirNotFound(String),
 }
 
 /// Errors related to validation operations.
@@ -66,6 +112,15 @@ pub enum ValidationError {
 
     #[error("Invalid command: {0}")]
     InvalidCommand(String),
+
+    #[error("{0} problem(s) found by verify")]
+    VerificationFailed(usize),
+
+    #[error("{0} hook(s) not managed by hooksmith")]
+    ForeignHooks(usize),
+
+    #[error("{0} setup problem(s) found by doctor")]
+    SetupProblems(usize),
 }
 
 /// Type alias for Result using `HooksmithError`
@@ -1,18 +1,233 @@
-use crate::error::GitError;
-use std::path::PathBuf;
+use crate::{error::GitError, utils::glob_match};
+use std::{
+    ffi::OsString,
+    fmt,
+    io::BufRead,
+    path::{Path, PathBuf},
+    process::Output,
+};
+
+/// Environment variable overriding the hooks directory for every operation.
+pub const HOOKS_DIR_ENV: &str = "HOOKSMITH_HOOKS_DIR";
+
+/// Where the hooks directory was resolved from, in order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HooksDirSource {
+    /// The `HOOKSMITH_HOOKS_DIR` environment variable
+    Environment,
+    /// Git's `core.hooksPath` setting
+    CoreHooksPath,
+    /// The default `hooks` directory inside the Git directory
+    GitDir,
+}
+
+impl HooksDirSource {
+    /// Whether the hooks directory was moved from the default `hooks` directory inside the
+    /// Git directory.
+    #[must_use]
+    pub fn is_custom(self) -> bool {
+        self != Self::GitDir
+    }
+}
+
+impl fmt::Display for HooksDirSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Environment => write!(f, "{HOOKS_DIR_ENV}"),
+            Self::CoreHooksPath => write!(f, "core.hooksPath"),
+            Self::GitDir => write!(f, "git rev-parse --git-path hooks"),
+        }
+    }
+}
 
 /// Get the path to the Git hooks directory.
 ///
 /// # Errors
 /// * If the `git` command fails to execute
+/// * If `HOOKSMITH_HOOKS_DIR` is set to an invalid directory
 ///
 /// # Returns
 /// * `PathBuf` - Path to the Git hooks directory
 pub fn get_git_hooks_path() -> Result<PathBuf, GitError> {
+    resolve_git_hooks_path().map(|(path, _)| path)
+}
+
+/// Resolve the Git hooks directory: `HOOKSMITH_HOOKS_DIR` if set, otherwise what Git uses,
+/// i.e. `core.hooksPath` if set, `.git/hooks` otherwise.
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If `HOOKSMITH_HOOKS_DIR` is empty or points to something other than a directory
+///
+/// # Returns
+/// * The path to the hooks directory, which may not exist yet, and where it came from
+pub fn resolve_git_hooks_path() -> Result<(PathBuf, HooksDirSource), GitError> {
+    if let Some(path) = hooks_dir_override(std::env::var_os(HOOKS_DIR_ENV)) {
+        return Ok((path?, HooksDirSource::Environment));
+    }
+
+    git_hooks_path_in(Path::new("."))
+}
+
+/// Resolve the hooks directory Git uses for the repository of a given directory:
+/// `core.hooksPath` if set, `.git/hooks` otherwise.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the directory is not inside a Git repository
+///
+/// # Returns
+/// * The path to the hooks directory, relative to `directory` unless Git gives it as an
+///   absolute path, and where it came from
+fn git_hooks_path_in(directory: &Path) -> Result<(PathBuf, HooksDirSource), GitError> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::NotGitRepo);
+    }
+
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let source = if has_core_hooks_path(directory) {
+        HooksDirSource::CoreHooksPath
+    } else {
+        HooksDirSource::GitDir
+    };
+
+    Ok((path, source))
+}
+
+/// Whether a directory lies outside of the working tree of the current repository, as a
+/// `core.hooksPath` shared by several repositories does. Hooks installed there aren't
+/// versioned with the repository, and run for the other repositories using it too.
+///
+/// # Arguments
+/// * `path` - The directory, absolute or relative to the current directory
+#[must_use]
+pub fn is_outside_working_tree(path: &Path) -> bool {
+    get_repo_root().is_ok_and(|root| is_outside(path, &root))
+}
+
+/// Whether a path lies outside of a directory, once both are made absolute and symbolic
+/// links are resolved where they exist.
+///
+/// # Arguments
+/// * `path` - The path, absolute or relative to the current directory
+/// * `root` - The directory
+fn is_outside(path: &Path, root: &Path) -> bool {
+    let resolve = |path: &Path| {
+        path.canonicalize()
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    !resolve(path).starts_with(resolve(root))
+}
+
+/// Validate the value of `HOOKSMITH_HOOKS_DIR`.
+///
+/// # Arguments
+/// * `value` - The value of the variable, if it is set
+///
+/// # Returns
+/// * `None` if the variable is not set, the directory or the reason it is invalid otherwise
+fn hooks_dir_override(value: Option<OsString>) -> Option<Result<PathBuf, GitError>> {
+    let path = PathBuf::from(value?);
+
+    if path.as_os_str().is_empty() {
+        return Some(Err(GitError::InvalidHooksDir(format!(
+            "{HOOKS_DIR_ENV} is empty"
+        ))));
+    }
+    if path.exists() && !path.is_dir() {
+        return Some(Err(GitError::InvalidHooksDir(format!(
+            "{HOOKS_DIR_ENV} points to '{}', which is not a directory",
+            path.display()
+        ))));
+    }
+
+    Some(Ok(path))
+}
+
+/// Whether Git's `core.hooksPath` setting is set for the repository of a given directory.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+fn has_core_hooks_path(directory: &Path) -> bool {
+    std::process::Command::new("git")
+        .args(["config", "--get", "core.hooksPath"])
+        .current_dir(directory)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Resolve a path inside the Git directory (e.g. `hooks` → `.git/hooks`).
+///
+/// # Arguments
+/// * `name` - The path relative to the Git directory
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the current directory is not inside a Git repository
+///
+/// # Returns
+/// * `PathBuf` - The resolved path, which may not exist yet
+pub fn get_git_path(name: &str) -> Result<PathBuf, GitError> {
+    get_git_path_in(Path::new("."), name)
+}
+
+/// Resolve a path inside the Git directory of the repository of a given directory, see
+/// `get_git_path`.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+/// * `name` - The path relative to the Git directory
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the directory is not inside a Git repository
+pub fn get_git_path_in(directory: &Path, name: &str) -> Result<PathBuf, GitError> {
     let output = std::process::Command::new("git")
         .arg("rev-parse")
         .arg("--git-path")
-        .arg("hooks")
+        .arg(name)
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::NotGitRepo);
+    }
+
+    // Git prints the path relative to the directory it runs in
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    Ok(directory.join(path))
+}
+
+/// Whether the current directory is inside a Git repository, bare repositories included.
+#[must_use]
+pub fn is_inside_git_repo() -> bool {
+    std::process::Command::new("git")
+        .args(["rev-parse", "--git-dir"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Get the root directory of the working tree.
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the current directory is not inside a working tree (e.g. in a bare repository)
+pub fn get_repo_root() -> Result<PathBuf, GitError> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
         .output()?;
 
     if !output.status.success() {
@@ -24,6 +239,542 @@ pub fn get_git_hooks_path() -> Result<PathBuf, GitError> {
     Ok(PathBuf::from(path))
 }
 
+/// Get the SHA of the commit `HEAD` points to, in the repository of a given directory.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+///
+/// # Returns
+/// * `Some(sha)` if the repository has a `HEAD` commit, `None` otherwise
+#[must_use]
+pub fn get_head_commit_in(directory: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .current_dir(directory)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Message of the stash entries made by `stash_unstaged_changes`, telling them apart from
+/// the user's own entries.
+pub const STASH_MESSAGE: &str = "hooksmith: unstaged changes";
+
+/// Set the unstaged changes to tracked files aside with `git stash push --keep-index`: the
+/// working tree is left with the staged changes only, while the index and untracked files
+/// are untouched.
+///
+/// # Arguments
+/// * `directory` - A directory inside the working tree
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * `GitError::Stash` if the changes cannot be stashed
+///
+/// # Returns
+/// * The stash commit holding the changes, `None` if there are no unstaged changes
+pub fn stash_unstaged_changes(directory: &Path) -> Result<Option<String>, GitError> {
+    // `git diff --quiet` exits with 1 when there are unstaged changes
+    if git_in(directory, &["diff", "--quiet"])?.status.success() {
+        return Ok(None);
+    }
+
+    let push = git_in(
+        directory,
+        &[
+            "stash",
+            "push",
+            "--keep-index",
+            "--quiet",
+            "--message",
+            STASH_MESSAGE,
+        ],
+    )?;
+    if !push.status.success() {
+        return Err(GitError::Stash(git_output_text(&push)));
+    }
+
+    let stash = git_in(
+        directory,
+        &["rev-parse", "--verify", "--quiet", "refs/stash"],
+    )?;
+    if !stash.status.success() {
+        return Err(GitError::Stash("no stash entry was created".to_string()));
+    }
+
+    Ok(Some(
+        String::from_utf8_lossy(&stash.stdout).trim().to_string(),
+    ))
+}
+
+/// Bring back changes set aside by `stash_unstaged_changes` with `git stash pop`, and drop
+/// their stash entry.
+///
+/// If tracked files are still as the stash left them, they are first reset, so that the
+/// index and the unstaged changes come back exactly as they were. Otherwise, e.g. when a
+/// hook reformatted files, the changes are merged with the modified files.
+///
+/// # Arguments
+/// * `directory` - A directory inside the working tree
+/// * `stash` - The stash commit returned by `stash_unstaged_changes`
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * `GitError::StashRestore` if the changes conflict with the working tree or their entry
+///   is gone; they are then left in the stash
+pub fn pop_stashed_changes(directory: &Path, stash: &str) -> Result<(), GitError> {
+    let Some(entry) = find_stash_entries(directory, |commit, _| commit == stash)?
+        .into_iter()
+        .next()
+    else {
+        return Err(GitError::StashRestore(format!(
+            "{stash} is no longer in the stash"
+        )));
+    };
+
+    let index = format!("{stash}^2");
+    let untouched = git_in(directory, &["diff", "--quiet", &index])?
+        .status
+        .success()
+        && git_in(directory, &["diff", "--cached", "--quiet", &index])?
+            .status
+            .success()
+        && get_head_commit_in(directory).is_some_and(|head| {
+            git_in(directory, &["rev-parse", &format!("{stash}^1")])
+                .is_ok_and(|parent| String::from_utf8_lossy(&parent.stdout).trim() == head)
+        });
+
+    let pop = if untouched {
+        let reset = git_in(directory, &["reset", "--quiet", "--hard"])?;
+        if !reset.status.success() {
+            return Err(GitError::StashRestore(format!(
+                "{entry}: {}",
+                git_output_text(&reset)
+            )));
+        }
+
+        git_in(directory, &["stash", "pop", "--quiet", "--index", &entry])?
+    } else {
+        git_in(directory, &["stash", "pop", "--quiet", &entry])?
+    };
+
+    if !pop.status.success() {
+        return Err(GitError::StashRestore(format!(
+            "{entry}: {}",
+            git_output_text(&pop)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Stash entries made by `stash_unstaged_changes` that are still in the stash, e.g. because
+/// they could not be restored.
+///
+/// # Arguments
+/// * `directory` - A directory inside the working tree
+///
+/// # Returns
+/// * The names of the entries, e.g. `stash@{1}`; empty if there are none or they cannot be
+///   listed
+#[must_use]
+pub fn leftover_stashes(directory: &Path) -> Vec<String> {
+    find_stash_entries(directory, |_, subject| subject.ends_with(STASH_MESSAGE)).unwrap_or_default()
+}
+
+/// Names of the stash entries matching a predicate on their commit and subject.
+///
+/// # Arguments
+/// * `directory` - A directory inside the working tree
+/// * `matches` - Whether to keep an entry, given its commit and its subject
+///
+/// # Errors
+/// * If the `git` command fails to execute
+fn find_stash_entries(
+    directory: &Path,
+    matches: impl Fn(&str, &str) -> bool,
+) -> Result<Vec<String>, GitError> {
+    let output = git_in(directory, &["stash", "list", "--format=%gd%x00%H%x00%gs"])?;
+
+    let entries = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            let (name, commit, subject) = (fields.next()?, fields.next()?, fields.next()?);
+            matches(commit, subject).then(|| name.to_string())
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Run a git command in the given directory.
+fn git_in(directory: &Path, args: &[&str]) -> Result<Output, GitError> {
+    Ok(std::process::Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?)
+}
+
+/// What a git command printed, on both outputs, for an error message.
+fn git_output_text(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+    .trim()
+    .to_string()
+}
+
+/// Get the files staged for the next commit that still exist once it's made, relative to
+/// the root of the working tree: renamed files under their new path, without deleted ones,
+/// see `git_diff_files_in`.
+///
+/// # Arguments
+/// * `filter` - Optional glob pattern the files must match, e.g. `*.rs`
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the current directory is not inside a Git repository
+pub fn get_staged_files(filter: Option<&str>) -> Result<Vec<PathBuf>, GitError> {
+    get_staged_files_in(Path::new("."), filter)
+}
+
+/// Get the files staged in the repository of a given directory, see `get_staged_files`.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+/// * `filter` - Optional glob pattern the files must match
+///
+/// # Errors
+/// * If the `git` command fails to execute
+/// * If the directory is not inside a Git repository
+fn get_staged_files_in(directory: &Path, filter: Option<&str>) -> Result<Vec<PathBuf>, GitError> {
+    let files = git_diff_files_in(directory, &["--cached"])?
+        .into_iter()
+        .filter(|file| filter.is_none_or(|pattern| glob_match(pattern, file)))
+        .map(PathBuf::from)
+        .collect();
+
+    Ok(files)
+}
+
+/// Run `git diff` with rename detection in the given directory and return the paths of
+/// the files that still exist once the changes are applied.
+///
+/// Renamed and copied files are reported under their new path, deleted files are left
+/// out since there's nothing left to check. Paths are read NUL-separated, so names
+/// containing newlines are kept intact.
+///
+/// # Arguments
+/// * `directory` - The directory to run `git diff` in
+/// * `args` - Additional arguments or revision ranges to pass to `git diff`
+///
+/// # Errors
+/// * If the `git diff` command fails
+pub(crate) fn git_diff_files_in(directory: &Path, args: &[&str]) -> Result<Vec<String>, GitError> {
+    let mut diff_args = vec!["diff", "--name-status", "-z", "-M"];
+    diff_args.extend(args);
+
+    git_name_status_in(directory, &diff_args)
+}
+
+/// Run a git command printing `--name-status -z` records in the given directory and
+/// return the paths of the files that still exist, see `parse_name_status`.
+///
+/// # Arguments
+/// * `directory` - The directory to run the command in
+/// * `args` - The arguments of the git command
+///
+/// # Errors
+/// * If the git command fails
+pub(crate) fn git_name_status_in(directory: &Path, args: &[&str]) -> Result<Vec<String>, GitError> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::ChangedFiles(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(parse_name_status(&output.stdout))
+}
+
+/// Parse the output of `git diff --name-status -z` into the paths of the files that
+/// still exist: the new path of renamed and copied files, and no deleted files.
+///
+/// # Arguments
+/// * `output` - Records of a status followed by one path, or two for renames and copies,
+///   all NUL-terminated
+fn parse_name_status(output: &[u8]) -> Vec<String> {
+    let mut fields = output
+        .split(|&byte| byte == 0)
+        .map(|field| String::from_utf8_lossy(field).into_owned());
+    let mut files = Vec::new();
+
+    while let Some(status) = fields.next() {
+        // Renames and copies carry a similarity score, e.g. `R100`
+        let Some(kind) = status.chars().next() else {
+            continue;
+        };
+        let path = if matches!(kind, 'R' | 'C') {
+            fields.nth(1)
+        } else {
+            fields.next()
+        };
+
+        match path {
+            Some(path) if kind != 'D' && !path.is_empty() => files.push(path),
+            _ => {}
+        }
+    }
+
+    files
+}
+
+/// A ref update being pushed, as given to the `pre-push` hook on its standard input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    /// The local ref being pushed, e.g. `refs/heads/main`, or `(delete)`
+    pub local_ref: String,
+    /// The commit being pushed, all zeros when the remote ref is deleted
+    pub local_sha: String,
+    /// The remote ref being updated
+    pub remote_ref: String,
+    /// The commit the remote ref points to, all zeros when it doesn't exist yet
+    pub remote_sha: String,
+}
+
+impl RefUpdate {
+    /// The revision range being pushed, as in Git's sample `pre-push` hook:
+    /// `<remote sha>..<local sha>` for an existing remote ref, the local commit alone (all
+    /// of its history) for a new one.
+    ///
+    /// # Returns
+    /// * `None` when the remote ref is deleted
+    #[must_use]
+    pub fn range(&self) -> Option<String> {
+        if self.is_deletion() {
+            None
+        } else if self.is_new_ref() {
+            Some(self.local_sha.clone())
+        } else {
+            Some(format!("{}..{}", self.remote_sha, self.local_sha))
+        }
+    }
+
+    /// Whether the remote ref is deleted, in which case nothing is pushed.
+    #[must_use]
+    pub fn is_deletion(&self) -> bool {
+        is_zero_sha(&self.local_sha)
+    }
+
+    /// Whether the remote ref doesn't exist yet, e.g. when pushing a new branch.
+    #[must_use]
+    pub fn is_new_ref(&self) -> bool {
+        is_zero_sha(&self.remote_sha)
+    }
+}
+
+impl fmt::Display for RefUpdate {
+    /// Format the update as a line of the `pre-push` standard input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.local_ref, self.local_sha, self.remote_ref, self.remote_sha
+        )
+    }
+}
+
+/// A ref update received by the server, as given to the `pre-receive` and `post-receive`
+/// hooks on their standard input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRef {
+    /// The commit the ref pointed to, all zeros when the ref is created
+    pub old_sha: String,
+    /// The commit the ref is updated to, all zeros when the ref is deleted
+    pub new_sha: String,
+    /// The ref being updated, e.g. `refs/heads/main`
+    pub ref_name: String,
+}
+
+impl ReceivedRef {
+    /// The revision range received: `<old sha>..<new sha>` for an existing ref, the new
+    /// commit alone (all of its history) for a new one.
+    ///
+    /// # Returns
+    /// * `None` when the ref is deleted
+    #[must_use]
+    pub fn range(&self) -> Option<String> {
+        if self.is_deletion() {
+            None
+        } else if self.is_new_ref() {
+            Some(self.new_sha.clone())
+        } else {
+            Some(format!("{}..{}", self.old_sha, self.new_sha))
+        }
+    }
+
+    /// Whether the ref is deleted.
+    #[must_use]
+    pub fn is_deletion(&self) -> bool {
+        is_zero_sha(&self.new_sha)
+    }
+
+    /// Whether the ref doesn't exist yet on the server.
+    #[must_use]
+    pub fn is_new_ref(&self) -> bool {
+        is_zero_sha(&self.old_sha)
+    }
+}
+
+impl fmt::Display for ReceivedRef {
+    /// Format the update as a line of the `pre-receive` standard input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.old_sha, self.new_sha, self.ref_name)
+    }
+}
+
+/// A commit rewritten by an amend or a rebase, as given to the `post-rewrite` hook on its
+/// standard input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenCommit {
+    /// The commit before the rewrite
+    pub old_sha: String,
+    /// The commit it was rewritten to
+    pub new_sha: String,
+    /// Extra information Git may append to the line, currently never set by Git itself
+    pub extra: Option<String>,
+}
+
+impl fmt::Display for RewrittenCommit {
+    /// Format the commit as a line of the `post-rewrite` standard input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.old_sha, self.new_sha)?;
+        if let Some(extra) = &self.extra {
+            write!(f, " {extra}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether an object name is the all-zeros one Git uses for a missing object.
+fn is_zero_sha(sha: &str) -> bool {
+    !sha.is_empty() && sha.bytes().all(|byte| byte == b'0')
+}
+
+/// Parse the ref updates given to the `pre-push` hook on its standard input, one per line:
+/// `<local ref> <local sha> <remote ref> <remote sha>`. Lines that don't follow this
+/// format, or whose object names aren't hexadecimal, are ignored.
+///
+/// # Arguments
+/// * `reader` - The standard input of the hook
+///
+/// # Returns
+/// * The ref updates, in the order Git gave them
+pub fn parse_pre_push_stdin(reader: impl BufRead) -> Vec<RefUpdate> {
+    reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [local_ref, local_sha, remote_ref, remote_sha] = fields[..] else {
+                return None;
+            };
+            if ![local_sha, remote_sha]
+                .iter()
+                .all(|sha| sha.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            {
+                return None;
+            }
+
+            Some(RefUpdate {
+                local_ref: local_ref.to_string(),
+                local_sha: local_sha.to_string(),
+                remote_ref: remote_ref.to_string(),
+                remote_sha: remote_sha.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse the ref updates given to the `pre-receive` and `post-receive` hooks on their
+/// standard input, one per line: `<old sha> <new sha> <ref name>`. Lines that don't follow
+/// this format, or whose object names aren't hexadecimal, are ignored.
+///
+/// # Arguments
+/// * `reader` - The standard input of the hook
+///
+/// # Returns
+/// * The ref updates, in the order Git gave them
+pub fn parse_receive_stdin(reader: impl BufRead) -> Vec<ReceivedRef> {
+    reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [old_sha, new_sha, ref_name] = fields[..] else {
+                return None;
+            };
+            if ![old_sha, new_sha]
+                .iter()
+                .all(|sha| sha.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            {
+                return None;
+            }
+
+            Some(ReceivedRef {
+                old_sha: old_sha.to_string(),
+                new_sha: new_sha.to_string(),
+                ref_name: ref_name.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse the rewritten commits given to the `post-rewrite` hook on its standard input, one
+/// per line: `<old sha> <new sha> [<extra info>]`. Lines that don't follow this format are
+/// ignored.
+///
+/// # Arguments
+/// * `reader` - The standard input of the hook
+///
+/// # Returns
+/// * The rewritten commits, in the order Git gave them
+pub fn parse_post_rewrite_stdin(reader: impl BufRead) -> Vec<RewrittenCommit> {
+    reader
+        .lines()
+        .map_while(std::result::Result::ok)
+        .filter_map(|line| {
+            let mut fields = line.trim_end().splitn(3, ' ');
+            let (old_sha, new_sha) = (fields.next()?, fields.next()?);
+            if ![old_sha, new_sha]
+                .iter()
+                .all(|sha| !sha.is_empty() && sha.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            {
+                return None;
+            }
+
+            Some(RewrittenCommit {
+                old_sha: old_sha.to_string(),
+                new_sha: new_sha.to_string(),
+                extra: fields.next().map(str::to_string),
+            })
+        })
+        .collect()
+}
+
 /// Check whether the current repository has a hooks directory.
 ///
 /// Looks up the hooks directory using `git rev-parse --git-path hooks` and
@@ -39,3 +790,242 @@ pub fn check_for_git_hooks() -> bool {
 
     git_hooks.is_some_and(|path| path.exists())
 }
+
+/// Select the Git-related environment variables, i.e. those starting with `GIT_`, such as
+/// the `GIT_DIR` and `GIT_INDEX_FILE` Git sets when it runs a hook.
+///
+/// # Arguments
+/// * `vars` - The environment, usually `std::env::vars_os()`
+///
+/// # Returns
+/// * The variables and their values, sorted by name. Values that aren't valid UTF-8 are
+///   converted lossily.
+pub(crate) fn git_environment(
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+) -> Vec<(String, String)> {
+    let mut variables = vars
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let key = key.into_string().ok()?;
+            key.starts_with("GIT_")
+                .then(|| (key, value.to_string_lossy().into_owned()))
+        })
+        .collect::<Vec<_>>();
+    variables.sort();
+
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_core_hooks_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+
+        git(&["init", "-q"]);
+        let (path, source) = git_hooks_path_in(dir.path()).unwrap();
+        assert_eq!(path, PathBuf::from(".git/hooks"));
+        assert_eq!(source, HooksDirSource::GitDir);
+        assert!(!source.is_custom());
+
+        git(&["config", "core.hooksPath", ".githooks"]);
+        let (path, source) = git_hooks_path_in(dir.path()).unwrap();
+        assert_eq!(path, PathBuf::from(".githooks"));
+        assert_eq!(source, HooksDirSource::CoreHooksPath);
+        assert!(source.is_custom());
+
+        let shared = tempfile::tempdir().unwrap();
+        git(&[
+            "config",
+            "core.hooksPath",
+            &shared.path().display().to_string(),
+        ]);
+        let (path, _) = git_hooks_path_in(dir.path()).unwrap();
+        assert_eq!(path, shared.path());
+        assert!(is_outside(&path, dir.path()));
+        assert!(!is_outside(&dir.path().join(".githooks"), dir.path()));
+    }
+
+    #[test]
+    fn test_parse_post_rewrite_stdin() {
+        let (a, b) = ("a".repeat(40), "b".repeat(40));
+        let stdin = format!("{a} {b}\n{b} {a} some extra info\n\n{a}\nnot a commit\n");
+
+        let rewrites = parse_post_rewrite_stdin(stdin.as_bytes());
+
+        assert_eq!(rewrites.len(), 2);
+        assert_eq!(rewrites[0].old_sha, a);
+        assert_eq!(rewrites[0].new_sha, b);
+        assert_eq!(rewrites[0].extra, None);
+        assert_eq!(rewrites[1].extra.as_deref(), Some("some extra info"));
+        assert_eq!(rewrites[1].to_string(), format!("{b} {a} some extra info"));
+    }
+
+    #[test]
+    fn test_parse_receive_stdin() {
+        let (a, b, zero) = ("a".repeat(40), "b".repeat(40), "0".repeat(40));
+        let stdin = format!(
+            "{b} {a} refs/heads/main\n{zero} {a} refs/heads/topic\n{b} {zero} refs/heads/old\n\nnot a ref\n"
+        );
+
+        let refs = parse_receive_stdin(stdin.as_bytes());
+
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].ref_name, "refs/heads/main");
+        assert_eq!(refs[0].range(), Some(format!("{b}..{a}")));
+        assert_eq!(refs[0].to_string(), format!("{b} {a} refs/heads/main"));
+        assert!(refs[1].is_new_ref());
+        assert_eq!(refs[1].range(), Some(a));
+        assert!(refs[2].is_deletion());
+        assert_eq!(refs[2].range(), None);
+    }
+
+    #[test]
+    fn test_parse_pre_push_stdin() {
+        let zero = "0".repeat(40);
+        let stdin = format!(
+            "refs/heads/main {a} refs/heads/main {b}\nrefs/heads/topic {a} refs/heads/topic {zero}\n(delete) {zero} refs/heads/old {b}\n\nnot a ref update\n",
+            a = "a".repeat(40),
+            b = "b".repeat(40),
+        );
+
+        let updates = parse_pre_push_stdin(stdin.as_bytes());
+
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0].local_ref, "refs/heads/main");
+        assert_eq!(updates[0].remote_sha, "b".repeat(40));
+        assert!(!updates[0].is_deletion() && !updates[0].is_new_ref());
+        assert_eq!(
+            updates[0].range(),
+            Some(format!("{}..{}", "b".repeat(40), "a".repeat(40)))
+        );
+        assert!(updates[1].is_new_ref());
+        assert_eq!(updates[1].range(), Some("a".repeat(40)));
+        assert!(updates[2].is_deletion());
+        assert_eq!(updates[2].range(), None);
+        assert_eq!(
+            updates[2].to_string(),
+            format!("(delete) {zero} refs/heads/old {}", "b".repeat(40))
+        );
+        // SHA-256 repositories use longer object names
+        assert!(parse_pre_push_stdin(
+            format!("(delete) {} x {}", "0".repeat(64), "c".repeat(64)).as_bytes()
+        )[0]
+        .is_deletion());
+        assert!(parse_pre_push_stdin(&b""[..]).is_empty());
+    }
+
+    #[test]
+    fn test_hooks_dir_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+
+        assert!(hooks_dir_override(None).is_none());
+        assert_eq!(
+            hooks_dir_override(Some(dir.path().into()))
+                .unwrap()
+                .unwrap(),
+            dir.path()
+        );
+        // Created on install, like the default hooks directory
+        assert!(hooks_dir_override(Some(dir.path().join("hooks").into()))
+            .unwrap()
+            .is_ok());
+        assert!(hooks_dir_override(Some(OsString::new())).unwrap().is_err());
+        assert!(hooks_dir_override(Some(file.into())).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_git_environment() {
+        let vars = [
+            ("PATH", "/usr/bin"),
+            ("GIT_INDEX_FILE", ".git/index"),
+            ("GIT_DIR", ".git"),
+            ("HOOKSMITH_GIT_DIR", "elsewhere"),
+        ]
+        .map(|(key, value)| (OsString::from(key), OsString::from(value)));
+
+        assert_eq!(
+            git_environment(vars),
+            vec![
+                ("GIT_DIR".to_string(), ".git".to_string()),
+                ("GIT_INDEX_FILE".to_string(), ".git/index".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_name_status() {
+        let output = b"M\0src/lib.rs\0R087\0old.rs\0new.rs\0D\0gone.rs\0C100\0a.rs\0b.rs\0A\0with\nnewline.txt\0";
+
+        assert_eq!(
+            parse_name_status(output),
+            vec!["src/lib.rs", "new.rs", "b.rs", "with\nnewline.txt"]
+        );
+        assert!(parse_name_status(b"").is_empty());
+    }
+
+    #[test]
+    fn test_get_staged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+
+        git(&["init", "-q"]);
+        std::fs::write(
+            dir.path().join("renamed.txt"),
+            "some content\nthat is kept\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("deleted.txt"), "removed\n").unwrap();
+        std::fs::write(dir.path().join("edited.txt"), "before\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "initial"]);
+
+        git(&["mv", "renamed.txt", "moved.txt"]);
+        git(&["rm", "-q", "deleted.txt"]);
+        std::fs::write(dir.path().join("edited.txt"), "after\n").unwrap();
+        std::fs::write(dir.path().join("new\nline.txt"), "added\n").unwrap();
+        git(&["add", "."]);
+
+        let mut files = git_diff_files_in(dir.path(), &["--cached"]).unwrap();
+        files.sort();
+
+        assert_eq!(files, vec!["edited.txt", "moved.txt", "new\nline.txt"]);
+
+        // The staged files are the same, filtered by the glob
+        let mut files = get_staged_files_in(dir.path(), Some("*.txt")).unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("edited.txt"),
+                PathBuf::from("moved.txt"),
+                PathBuf::from("new\nline.txt")
+            ]
+        );
+        assert!(get_staged_files_in(dir.path(), Some("*.rs"))
+            .unwrap()
+            .is_empty());
+    }
+}
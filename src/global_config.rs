@@ -0,0 +1,99 @@
+//! Per-user configuration holding personal defaults shared by every repository.
+//!
+//! The file lives in the user's configuration directory (e.g. `~/.config/hooksmith/config.yaml`
+//! on Linux) and only provides settings; hooks are always defined by the repository.
+
+use crate::error::{ConfigError, HooksmithError, Result};
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Name of the global configuration file inside the user's configuration directory.
+const GLOBAL_CONFIG_FILE: &str = "config.yaml";
+
+/// Settings read from the user's global configuration file.
+/// Every setting is overridden by the same setting in the repository configuration.
+#[derive(Debug, Default, Deserialize)]
+pub struct GlobalConfig {
+    /// Shell used to run commands
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Whether to run the shell as a login shell
+    #[serde(default)]
+    pub login_shell: Option<bool>,
+    /// Whether to color message prefixes
+    #[serde(default)]
+    pub color: Option<bool>,
+    /// Environment variables set for every command
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Whether to print a weekly reminder to update hooksmith and review the hooks
+    #[serde(default)]
+    pub reminder: Option<bool>,
+}
+
+/// Path of the user's global configuration file, whether it exists or not.
+///
+/// # Returns
+/// * `None` if the user's home directory cannot be determined
+#[must_use]
+pub fn global_config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "hooksmith").map(|dirs| dirs.config_dir().join(GLOBAL_CONFIG_FILE))
+}
+
+/// Read the user's global configuration file.
+///
+/// # Arguments
+/// * `path` - Path of the global configuration file
+///
+/// # Errors
+/// * If the file exists but cannot be read or parsed
+///
+/// # Returns
+/// * `None` if the file doesn't exist
+pub fn read_global_config(path: &Path) -> Result<Option<GlobalConfig>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // An empty file is a valid, empty configuration
+    if content.trim().is_empty() {
+        return Ok(Some(GlobalConfig::default()));
+    }
+
+    serde_yaml::from_str(&content)
+        .map(Some)
+        .map_err(|err| HooksmithError::Config(ConfigError::Parse(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_global_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(GLOBAL_CONFIG_FILE);
+
+        assert!(read_global_config(&path).unwrap().is_none());
+
+        fs::write(
+            &path,
+            "shell: bash\ncolor: true\nenv:\n  RUST_BACKTRACE: \"1\"\n",
+        )
+        .unwrap();
+        let global = read_global_config(&path).unwrap().unwrap();
+        assert_eq!(global.shell.as_deref(), Some("bash"));
+        assert_eq!(global.color, Some(true));
+        assert_eq!(global.env["RUST_BACKTRACE"], "1");
+
+        fs::write(&path, "shell: [bash\n").unwrap();
+        assert!(read_global_config(&path).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! Built-in documentation for the standard git hooks, used by `hooksmith explain` and the
+//! hook selection of `hooksmith init`.
+
+/// Description of a git hook.
+pub struct HookDoc {
+    /// The name of the hook
+    pub name: &'static str,
+    /// When git triggers the hook and what it is typically used for
+    pub description: &'static str,
+    /// The arguments (and standard input) git passes to the hook
+    pub arguments: &'static str,
+    /// Whether a non-zero exit status aborts the git operation
+    pub can_abort: bool,
+}
+
+impl HookDoc {
+    /// The first sentence of the description, without its final period, short enough to
+    /// follow the hook's name in a list.
+    #[must_use]
+    pub fn summary(&self) -> &'static str {
+        let description = self.description;
+        let end = description
+            .match_indices(". ")
+            .map(|(idx, _)| idx)
+            .find(|&idx| {
+                description[idx + 2..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_uppercase)
+            })
+            .unwrap_or(description.len());
+
+        description[..end].trim_end_matches('.')
+    }
+}
+
+/// Documentation for every hook in `GIT_HOOKS`, in the same order.
+pub const HOOK_DOCS: [HookDoc; 28] = [
+    HookDoc {
+        name: "applypatch-msg",
+        description: "Invoked by `git am` before a patch is applied, to check or edit the proposed commit message.",
+        arguments: "$1: path of the file holding the proposed commit message.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "pre-applypatch",
+        description: "Invoked by `git am` after the patch is applied to the working tree but before the commit is made.",
+        arguments: "None.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-applypatch",
+        description: "Invoked by `git am` after the patch is applied and committed, mainly for notifications.",
+        arguments: "None.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "pre-commit",
+        description: "Invoked by `git commit` before the commit message is requested, to inspect the snapshot about to be committed (formatting, linting, tests). Bypassed by `git commit --no-verify`.",
+        arguments: "None.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "pre-merge-commit",
+        description: "Invoked by `git merge` after a successful merge, before the merge commit is created. Bypassed by `--no-verify`.",
+        arguments: "None.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "prepare-commit-msg",
+        description: "Invoked by `git commit` after the default commit message is prepared and before the editor opens, to edit the message programmatically.",
+        arguments: "$1: path of the commit message file, $2: source of the message (message, template, merge, squash or commit), $3: commit SHA when amending.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "commit-msg",
+        description: "Invoked by `git commit` and `git merge` once the message is written, to validate or normalize the commit message. Bypassed by `--no-verify`.",
+        arguments: "$1: path of the file holding the commit message.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-commit",
+        description: "Invoked by `git commit` after the commit is made, mainly for notifications.",
+        arguments: "None.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "pre-rebase",
+        description: "Invoked by `git rebase` before anything is rebased, to prevent rebasing branches that shouldn't be.",
+        arguments: "$1: the upstream the series was forked from, $2: the branch being rebased (empty when rebasing the current branch).",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-checkout",
+        description: "Invoked by `git checkout`, `git switch` and `git clone` after the working tree is updated.",
+        arguments: "$1: previous HEAD, $2: new HEAD, $3: 1 for a branch checkout, 0 for a file checkout.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "post-merge",
+        description: "Invoked by `git merge` (and therefore `git pull`) after a successful merge.",
+        arguments: "$1: 1 if the merge was a squash merge, 0 otherwise.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "pre-push",
+        description: "Invoked by `git push` after the remote status is checked but before anything is pushed. Bypassed by `git push --no-verify`.",
+        arguments: "$1: remote name, $2: remote URL. Standard input: one `<local ref> <local sha> <remote ref> <remote sha>` line per ref to update.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "pre-receive",
+        description: "Server side: invoked by `git receive-pack` once before any ref is updated by a push.",
+        arguments: "Standard input: one `<old sha> <new sha> <ref name>` line per ref to update.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "update",
+        description: "Server side: invoked by `git receive-pack` once for each ref about to be updated by a push.",
+        arguments: "$1: ref name, $2: old SHA, $3: new SHA.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "proc-receive",
+        description: "Server side: invoked by `git receive-pack` to handle the commands matching `receive.procReceiveRefs` itself.",
+        arguments: "Standard input and output: a pkt-line protocol with `git receive-pack`.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-receive",
+        description: "Server side: invoked by `git receive-pack` once after all refs are updated, mainly for notifications and deployments.",
+        arguments: "Standard input: one `<old sha> <new sha> <ref name>` line per updated ref.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "post-update",
+        description: "Server side: invoked by `git receive-pack` once after all refs are updated, e.g. to run `git update-server-info`.",
+        arguments: "One argument per updated ref name.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "reference-transaction",
+        description: "Invoked by any git command that updates references, at each state of the reference transaction.",
+        arguments: "$1: transaction state (prepared, committed or aborted). Standard input: one `<old value> <new value> <ref name>` line per reference.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "push-to-checkout",
+        description: "Server side: invoked by `git receive-pack` when a push updates the checked-out branch of a non-bare repository with `receive.denyCurrentBranch=updateInstead`.",
+        arguments: "$1: the commit the branch is being updated to.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "pre-auto-gc",
+        description: "Invoked by `git gc --auto` before it decides to run, to prevent automatic garbage collection.",
+        arguments: "None.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-rewrite",
+        description: "Invoked by commands that rewrite commits (`git commit --amend`, `git rebase`).",
+        arguments: "$1: the rewriting command (amend or rebase). Standard input: one `<old sha> <new sha>` line per rewritten commit.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "sendemail-validate",
+        description: "Invoked by `git send-email` for each patch before it is sent.",
+        arguments: "$1: path of the file holding the email to be sent.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "fsmonitor-watchman",
+        description: "Invoked when `core.fsmonitor` points to it, to ask a filesystem monitor which files changed since a given time.",
+        arguments: "$1: protocol version, $2: timestamp or opaque token of the last query.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "p4-changelist",
+        description: "Invoked by `git-p4 submit` after the changelist message is edited, to validate it.",
+        arguments: "$1: path of the file holding the changelist text.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "p4-prepare-changelist",
+        description: "Invoked by `git-p4 submit` after the default changelist message is prepared and before the editor opens.",
+        arguments: "$1: path of the file holding the changelist text.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "p4-post-changelist",
+        description: "Invoked by `git-p4 submit` after the submission succeeded, mainly for notifications.",
+        arguments: "None.",
+        can_abort: false,
+    },
+    HookDoc {
+        name: "p4-pre-submit",
+        description: "Invoked by `git-p4 submit` before the submission starts.",
+        arguments: "None.",
+        can_abort: true,
+    },
+    HookDoc {
+        name: "post-index-change",
+        description: "Invoked when the index is written.",
+        arguments: "$1: 1 if the working directory was updated, $2: 1 if the skip-worktree bit changed.",
+        can_abort: false,
+    },
+];
+
+/// Find the documentation of a git hook.
+///
+/// # Arguments
+/// * `hook_name` - The name of the hook
+pub fn find_hook_doc(hook_name: &str) -> Option<&'static HookDoc> {
+    HOOK_DOCS.iter().find(|doc| doc.name == hook_name)
+}
+
+/// Suggest known hook names close to an unknown one.
+///
+/// # Arguments
+/// * `hook_name` - The unknown hook name
+///
+/// # Returns
+/// * The known hook names within a small edit distance, or containing the given name
+pub fn suggest_hook_names(hook_name: &str) -> Vec<&'static str> {
+    HOOK_DOCS
+        .iter()
+        .map(|doc| doc.name)
+        .filter(|name| {
+            edit_distance(name, hook_name) <= 3 || (hook_name.len() > 2 && name.contains(hook_name))
+        })
+        .collect()
+}
+
+/// Compute the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b_chars.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_hook_doc() {
+        let doc = find_hook_doc("pre-push").unwrap();
+        assert!(doc.can_abort);
+        assert!(doc.arguments.contains("remote name"));
+
+        assert!(find_hook_doc("pre-comit").is_none());
+    }
+
+    #[test]
+    fn test_hook_doc_summary() {
+        assert_eq!(
+            find_hook_doc("pre-push").unwrap().summary(),
+            "Invoked by `git push` after the remote status is checked but before anything is pushed"
+        );
+        // Abbreviations don't end the sentence
+        assert_eq!(
+            find_hook_doc("post-update").unwrap().summary(),
+            "Server side: invoked by `git receive-pack` once after all refs are updated, e.g. to run `git update-server-info`"
+        );
+    }
+
+    #[test]
+    fn test_suggest_hook_names() {
+        assert_eq!(suggest_hook_names("pre-comit"), vec!["pre-commit"]);
+        assert!(suggest_hook_names("receive").contains(&"pre-receive"));
+        assert!(suggest_hook_names("zzzzzzzzzzzz").is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! Data Git writes to the standard input of some hooks: the refs being pushed for
+//! `pre-push`, the refs received for `pre-receive` and `post-receive`, the rewritten
+//! commits for `post-rewrite`.
+//!
+//! When a hook's commands use it, the input is read once before they run, exposed through
+//! environment variables, and written back to the standard input of each command. The
+//! input of the server-side receive hooks is always read, see `HookInput::is_always_read`.
+
+use crate::{
+    git_related::{
+        parse_post_rewrite_stdin, parse_pre_push_stdin, parse_receive_stdin, ReceivedRef,
+        RefUpdate, RewrittenCommit,
+    },
+    placeholders::PUSH_RANGE_ENV,
+};
+use std::{
+    io::{BufRead, IsTerminal},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// Environment variable holding the ref updates given to a `pre-push` hook, one per line,
+/// as Git wrote them.
+pub const PUSH_UPDATES_ENV: &str = "HOOKSMITH_PUSH_UPDATES";
+
+/// Environment variable holding the ref updates received by a `pre-receive` or
+/// `post-receive` hook, one per line, as Git wrote them.
+pub const RECEIVED_REFS_ENV: &str = "HOOKSMITH_RECEIVED_REFS";
+
+/// Environment variable holding the commits rewritten before a `post-rewrite` hook, one
+/// `<old sha> <new sha>` pair per line, as Git wrote them.
+pub const REWRITES_ENV: &str = "HOOKSMITH_REWRITES";
+
+/// How long to wait for a hook's input. Git writes it as soon as the hook starts; a stdin
+/// left open by something else, such as a CI runner, must not block the hook.
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Hooks whose input is read whenever they run: their commands are typically all
+/// interested in it, and without a copy each, the first command would consume it.
+const ALWAYS_READ: [&str; 2] = ["pre-receive", "post-receive"];
+
+/// The parsed standard input of a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookInput {
+    /// The refs being pushed, for `pre-push`
+    RefUpdates(Vec<RefUpdate>),
+    /// The refs received by the server, for `pre-receive` and `post-receive`
+    ReceivedRefs(Vec<ReceivedRef>),
+    /// The commits rewritten by an amend or a rebase, for `post-rewrite`
+    Rewrites(Vec<RewrittenCommit>),
+}
+
+impl HookInput {
+    /// The placeholders and environment variables through which commands use the input of
+    /// a hook. The input is only read when a command mentions one of them.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook
+    pub fn names(hook_name: &str) -> &'static [&'static str] {
+        match hook_name {
+            "pre-push" => &["{push_range}", PUSH_RANGE_ENV, PUSH_UPDATES_ENV],
+            "pre-receive" | "post-receive" => &["{push_range}", PUSH_RANGE_ENV, RECEIVED_REFS_ENV],
+            "post-rewrite" => &[REWRITES_ENV],
+            _ => &[],
+        }
+    }
+
+    /// Whether the input of a hook is read even when no command mentions it, so that
+    /// every command gets it on its standard input.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook
+    pub fn is_always_read(hook_name: &str) -> bool {
+        ALWAYS_READ.contains(&hook_name)
+    }
+
+    /// Parse the input of a hook.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook
+    /// * `reader` - The standard input of the hook
+    ///
+    /// # Returns
+    /// * `None` if the hook takes no input, or the input holds nothing
+    pub fn parse(hook_name: &str, reader: impl BufRead) -> Option<Self> {
+        let input = match hook_name {
+            "pre-push" => Self::RefUpdates(parse_pre_push_stdin(reader)),
+            "pre-receive" | "post-receive" => Self::ReceivedRefs(parse_receive_stdin(reader)),
+            "post-rewrite" => Self::Rewrites(parse_post_rewrite_stdin(reader)),
+            _ => return None,
+        };
+
+        (!input.lines().is_empty()).then_some(input)
+    }
+
+    /// Read and parse the standard input of the current process, as given to a hook.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook
+    ///
+    /// # Returns
+    /// * `None` if the hook takes no input, or stdin is a terminal, holds nothing, or isn't
+    ///   closed within `READ_TIMEOUT`, e.g. when the hook is run by hand
+    pub fn read(hook_name: &str) -> Option<Self> {
+        if Self::names(hook_name).is_empty() || std::io::stdin().is_terminal() {
+            return None;
+        }
+
+        // A reader still blocked after the timeout is left behind, it ends with the process
+        let hook_name = hook_name.to_string();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(Self::parse(&hook_name, std::io::stdin().lock()));
+        });
+
+        receiver.recv_timeout(READ_TIMEOUT).ok().flatten()
+    }
+
+    /// The input as Git wrote it, one line per entry.
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            Self::RefUpdates(updates) => updates.iter().map(ToString::to_string).collect(),
+            Self::ReceivedRefs(refs) => refs.iter().map(ToString::to_string).collect(),
+            Self::Rewrites(rewrites) => rewrites.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// The environment variables exposing the input to the commands.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let lines = self.lines().join("\n");
+
+        match self {
+            Self::RefUpdates(updates) => {
+                let ranges = updates.iter().filter_map(RefUpdate::range);
+
+                vec![
+                    (PUSH_RANGE_ENV, ranges.collect::<Vec<_>>().join("\n")),
+                    (PUSH_UPDATES_ENV, lines),
+                ]
+            }
+            Self::ReceivedRefs(refs) => {
+                let ranges = refs.iter().filter_map(ReceivedRef::range);
+
+                vec![
+                    (PUSH_RANGE_ENV, ranges.collect::<Vec<_>>().join("\n")),
+                    (RECEIVED_REFS_ENV, lines),
+                ]
+            }
+            Self::Rewrites(_) => vec![(REWRITES_ENV, lines)],
+        }
+    }
+
+    /// The refs being pushed, for a `pre-push` hook.
+    pub fn ref_updates(&self) -> Option<&[RefUpdate]> {
+        match self {
+            Self::RefUpdates(updates) => Some(updates),
+            Self::ReceivedRefs(_) | Self::Rewrites(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_input() {
+        let (a, b) = ("a".repeat(40), "b".repeat(40));
+
+        let rewrites = format!("{a} {b}\n{b} {a} extra info\n");
+        let input = HookInput::parse("post-rewrite", rewrites.as_bytes()).unwrap();
+        assert_eq!(input.lines().len(), 2);
+        assert_eq!(
+            input.env(),
+            vec![(REWRITES_ENV, rewrites.trim_end().to_string())]
+        );
+        assert!(input.ref_updates().is_none());
+
+        let updates = format!("refs/heads/main {a} refs/heads/main {b}\n");
+        let input = HookInput::parse("pre-push", updates.as_bytes()).unwrap();
+        assert_eq!(input.env()[0], (PUSH_RANGE_ENV, format!("{b}..{a}")));
+        assert_eq!(input.ref_updates().unwrap().len(), 1);
+
+        let received = format!("{b} {a} refs/heads/main\n");
+        let input = HookInput::parse("pre-receive", received.as_bytes()).unwrap();
+        assert_eq!(
+            input.env(),
+            vec![
+                (PUSH_RANGE_ENV, format!("{b}..{a}")),
+                (RECEIVED_REFS_ENV, received.trim_end().to_string())
+            ]
+        );
+        assert!(HookInput::is_always_read("post-receive"));
+        assert!(!HookInput::is_always_read("pre-push"));
+
+        assert!(HookInput::parse("pre-push", &b"\n"[..]).is_none());
+        assert!(HookInput::parse("pre-commit", updates.as_bytes()).is_none());
+        assert!(HookInput::names("pre-commit").is_empty());
+    }
+}
@@ -1,17 +1,59 @@
 use crate::{
-    error::{ConfigError, HookExecutionError, Result, ValidationError},
-    git_related::{check_for_git_hooks, get_git_hooks_path},
+    api::{CommandEntry, HookEntry, HookList, HOOKS_SCHEMA_VERSION},
+    bench::BenchReport,
+    builtins::Builtin,
+    config_sources::{render_table, ConfigSources},
+    disabled,
+    error::{ConfigError, GitError, HookExecutionError, Result, ValidationError},
+    git_related::{
+        check_for_git_hooks, get_git_hooks_path, get_head_commit_in, get_repo_root,
+        get_staged_files, git_diff_files_in, git_environment, git_name_status_in,
+        is_inside_git_repo, is_outside_working_tree, leftover_stashes, resolve_git_hooks_path,
+        HooksDirSource, RefUpdate, HOOKS_DIR_ENV,
+    },
+    global_config::read_global_config,
+    hook_docs::{find_hook_doc, suggest_hook_names, HookDoc},
+    hook_input::HookInput,
+    manifest::embedded_config,
     my_clap_theme,
-    utils::{format_list, print_error, print_success, print_warning},
+    observer::{HookObserver, StdoutObserver},
+    output::{self, out, outln, Sink},
+    placeholders::{
+        expand_argument_placeholders, expand_staged_files, hook_argument_names,
+        interpolate_variables, required_variables, unknown_placeholders, unquoted_arguments,
+        STAGED_FILES,
+    },
+    pre_commit_import::import_pre_commit_config,
+    process::{self, SignalGuard},
+    run_report::{RunRecorder, RunReport},
+    run_state,
+    stash::{self, StashGuard},
+    task_runner::TaskRunner,
+    templates::{find_template, TEMPLATES},
+    utils::{
+        command_program, format_list, glob_match, is_glob_pattern, shell_quote, unbalanced_quote,
+        Reporter,
+    },
     HooksmithError,
 };
 
+use console::style;
 use dialoguer::{Confirm, MultiSelect};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
-    fs::{self},
-    path::Path,
-    process::{Command, ExitStatus},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
@@ -46,11 +88,205 @@ const GIT_HOOKS: [&str; 28] = [
     "post-index-change",
 ];
 
-/// Represents a command that can be either a simple string or a named command
-#[derive(Debug, Clone)]
+/// Hooks run by the server receiving a push rather than on a developer's machine. They run
+/// in the repository receiving the push, usually bare, so there is no working tree.
+const SERVER_HOOKS: [&str; 6] = [
+    "pre-receive",
+    "update",
+    "proc-receive",
+    "post-receive",
+    "post-update",
+    "push-to-checkout",
+];
+
+/// Hooks Git writes input to on their standard input, one line per entry. `proc-receive`
+/// isn't one of them: it talks a protocol with Git on its standard input and output.
+const INPUT_HOOKS: [&str; 5] = [
+    "pre-push",
+    "pre-receive",
+    "post-receive",
+    "reference-transaction",
+    "post-rewrite",
+];
+
+/// Position of a hook in Git's workflow, i.e. in `GIT_HOOKS`, which lists them in the order
+/// githooks(5) documents them: committing, then merging and checking out, then pushing and
+/// receiving. Unknown hooks come last.
+///
+/// # Arguments
+/// * `hook_name` - Name of the hook
+fn lifecycle_position(hook_name: &str) -> usize {
+    GIT_HOOKS
+        .iter()
+        .position(|name| *name == hook_name)
+        .unwrap_or(GIT_HOOKS.len())
+}
+
+/// Shell builtins and keywords commands commonly start with, which aren't programs to
+/// look for on `PATH`.
+const SHELL_BUILTINS: [&str; 29] = [
+    ".", "alias", "break", "case", "cd", "command", "continue", "echo", "eval", "exec", "exit",
+    "export", "false", "for", "if", "printf", "pwd", "read", "return", "set", "shift", "source",
+    "test", "trap", "true", "umask", "unset", "until", "while",
+];
+
+/// Whether a hook runs on the server receiving a push, see `SERVER_HOOKS`.
+fn is_server_hook(hook_name: &str) -> bool {
+    SERVER_HOOKS.contains(&hook_name)
+}
+
+/// Whether an installed hook file was written by hooksmith, by any version: its script
+/// runs `hooksmith run <hook>`. Unreadable files are considered foreign.
+///
+/// # Arguments
+/// * `path` - Path of the hook file
+/// * `hook_name` - Name of the hook, i.e. of the file
+fn is_managed_hook(path: &Path, hook_name: &str) -> bool {
+    fs::read_to_string(path)
+        .is_ok_and(|content| content.contains(&format!("hooksmith run {hook_name}")))
+}
+
+/// Which tool wrote an installed hook file, told by marker strings in its script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookOrigin {
+    Hooksmith,
+    Husky,
+    Lefthook,
+    /// The `pre-commit` framework (<https://pre-commit.com>), not the hook of that name
+    PreCommit,
+    /// A hand-written script, or one of a tool hooksmith doesn't know
+    Unknown,
+}
+
+impl HookOrigin {
+    /// Classify an installed hook file by its script. Hooksmith is checked first, as its
+    /// scripts may mention the other tools in commands.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook, i.e. of the file
+    /// * `content` - Script of the hook file
+    fn classify(hook_name: &str, content: &str) -> Self {
+        let lowercase = content.to_lowercase();
+
+        if content.contains(&format!("hooksmith run {hook_name}")) {
+            Self::Hooksmith
+        } else if lowercase.contains("husky") {
+            Self::Husky
+        } else if lowercase.contains("lefthook") {
+            Self::Lefthook
+        } else if lowercase.contains("file generated by pre-commit")
+            || lowercase.contains("pre-commit.com")
+        {
+            Self::PreCommit
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Classify the hook file at a given path, `None` if there is no readable file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the hook file
+    /// * `hook_name` - Name of the hook, i.e. of the file
+    fn of_file(path: &Path, hook_name: &str) -> Option<Self> {
+        fs::read_to_string(path)
+            .ok()
+            .map(|content| Self::classify(hook_name, &content))
+    }
+
+    /// Whether the hook was installed by another hook manager, which would stop managing
+    /// it if hooksmith replaced it.
+    fn is_other_manager(self) -> bool {
+        matches!(self, Self::Husky | Self::Lefthook | Self::PreCommit)
+    }
+}
+
+impl fmt::Display for HookOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hooksmith => "hooksmith",
+            Self::Husky => "husky",
+            Self::Lefthook => "lefthook",
+            Self::PreCommit => "pre-commit",
+            Self::Unknown => "an unknown tool",
+        })
+    }
+}
+
+/// Whether a hook file can be run by Git. Outside of Unix, every file can.
+///
+/// # Arguments
+/// * `path` - Path of the hook file
+fn is_executable_file(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// The hooks of a hooks directory that hooksmith didn't install, sorted by name. Sample
+/// hooks (`*.sample`) are left out.
+///
+/// # Arguments
+/// * `git_hooks_path` - Path to the git hooks directory
+fn foreign_hooks(git_hooks_path: &Path) -> Vec<String> {
+    let mut foreign = fs::read_dir(git_hooks_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|hook_name| {
+            !hook_name.ends_with(".sample")
+                && !is_managed_hook(&git_hooks_path.join(hook_name), hook_name)
+        })
+        .collect::<Vec<_>>();
+    foreign.sort();
+
+    foreign
+}
+
+/// Represents a command that can be either a simple string, a named command or a structured command
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HookCommand {
     pub name: Option<String>,
     pub command: String,
+    /// Exit codes treated as success, `[0]` when not set
+    pub success_codes: Option<Vec<i32>>,
+    /// Whether the command may expand hook arguments without quotes (no lint warning)
+    pub allow_unquoted: bool,
+    /// How many more times the command runs when it fails, `0` to run it once
+    pub retries: u32,
+    /// Seconds to wait before each retry
+    pub retry_delay: Option<u64>,
+    /// Exit codes the command is retried on, any failing one when not set
+    pub retry_on: Option<Vec<i32>>,
+    /// Directory the command runs in, relative to the root of the repository
+    pub working_dir: Option<String>,
+    /// Seconds the command may run before it is stopped, overriding its hook's
+    /// `command_timeout`
+    pub timeout: Option<u64>,
+    /// Whether the hook goes on when the command fails, for advisory checks: the failure
+    /// is reported, but doesn't fail the hook
+    pub allow_failure: bool,
+    /// Environment variables set for the command, overriding its hook's `env`
+    pub env: HashMap<String, String>,
+    /// Whether the command reads the hook's standard input: it then gets it in a parallel
+    /// group, and the input of hooks such as `pre-push` is replayed to it
+    pub stdin: bool,
+    /// Task runner the command delegates to, when written `just: <target>` or
+    /// `make: <target>`; `command` then holds the full invocation (e.g. `just test`)
+    pub task_runner: Option<TaskRunner>,
+    /// Check built into hooksmith the command runs instead of a shell command, when written
+    /// `builtin: <name>`; `command` then only describes it (e.g. `builtin: commit-format`)
+    pub builtin: Option<Builtin>,
 }
 
 impl HookCommand {
@@ -59,6 +295,18 @@ impl HookCommand {
         Self {
             name: None,
             command,
+            success_codes: None,
+            allow_unquoted: false,
+            retries: 0,
+            retry_delay: None,
+            retry_on: None,
+            working_dir: None,
+            timeout: None,
+            allow_failure: false,
+            env: HashMap::new(),
+            stdin: false,
+            task_runner: None,
+            builtin: None,
         }
     }
 
@@ -67,6 +315,254 @@ impl HookCommand {
         Self {
             name: Some(name),
             command,
+            success_codes: None,
+            allow_unquoted: false,
+            retries: 0,
+            retry_delay: None,
+            retry_on: None,
+            working_dir: None,
+            timeout: None,
+            allow_failure: false,
+            env: HashMap::new(),
+            stdin: false,
+            task_runner: None,
+            builtin: None,
+        }
+    }
+
+    /// Create a command running a check built into hooksmith
+    ///
+    /// # Arguments
+    /// * `builtin` - The check and its options
+    pub fn new_builtin(builtin: Builtin) -> Self {
+        Self {
+            command: format!("builtin: {}", builtin.name()),
+            builtin: Some(builtin),
+            ..Self::new_unnamed(String::new())
+        }
+    }
+
+    /// Create a command running a target of a task runner
+    ///
+    /// # Arguments
+    /// * `task_runner` - The task runner
+    /// * `target` - The target, optionally followed by arguments (e.g. `test --release`)
+    pub fn new_task(task_runner: TaskRunner, target: &str) -> Self {
+        Self {
+            task_runner: Some(task_runner),
+            ..Self::new_unnamed(task_runner.command(target))
+        }
+    }
+
+    /// Stable identifier of the command, used to key its results and recorded state: its
+    /// name, or a hash of the command when it has none (e.g. `cmd-3f1c0a9e2b7d4e65`).
+    /// Naming a command keeps its key stable when the command itself is edited.
+    #[must_use]
+    pub fn key(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("cmd-{:016x}", fnv1a_hash(&self.command)),
+        }
+    }
+
+    /// How the command is referred to in messages: its name, or the command itself.
+    fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+
+    /// Whether the given exit code counts as a success for this command.
+    ///
+    /// # Arguments
+    /// * `code` - The exit code of the command
+    fn is_success_code(&self, code: i32) -> bool {
+        self.success_codes
+            .as_ref()
+            .map_or(code == 0, |codes| codes.contains(&code))
+    }
+
+    /// Whether a failure with the given exit code is retried, attempts left aside.
+    ///
+    /// # Arguments
+    /// * `code` - The exit code of the failed command
+    fn is_retried_on(&self, code: i32) -> bool {
+        self.retry_on
+            .as_ref()
+            .is_none_or(|codes| codes.contains(&code))
+    }
+}
+
+/// 64-bit FNV-1a hash of a string. Unlike `DefaultHasher`, it is stable across Rust
+/// versions and platforms, so it can be used in files kept between runs.
+///
+/// # Arguments
+/// * `text` - The string to hash
+fn fnv1a_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Structured form of a command, a mapping with a `run` key and per-command options:
+///
+/// ```yaml
+/// - run: grep -rn TODO src
+///   name: todo-check
+///   success_codes: [0, 1]
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct StructuredCommand {
+    run: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    success_codes: Option<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_unquoted: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    retries: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_delay: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_on: Option<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_failure: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stdin: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// A command running a check built into hooksmith, with the options of the check.
+///
+/// ```yaml
+/// - builtin: commit-format
+///   name: message
+///   subject_length: 50
+/// ```
+#[derive(Deserialize, Serialize)]
+struct BuiltinCommand {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(flatten)]
+    builtin: Builtin,
+}
+
+impl From<BuiltinCommand> for HookCommand {
+    fn from(command: BuiltinCommand) -> Self {
+        Self {
+            name: command.name,
+            ..Self::new_builtin(command.builtin)
+        }
+    }
+}
+
+impl From<&HookCommand> for StructuredCommand {
+    fn from(command: &HookCommand) -> Self {
+        Self {
+            run: command.command.clone(),
+            name: command.name.clone(),
+            success_codes: command.success_codes.clone(),
+            allow_unquoted: command.allow_unquoted,
+            retries: command.retries,
+            retry_delay: command.retry_delay,
+            retry_on: command.retry_on.clone(),
+            working_dir: command.working_dir.clone(),
+            timeout: command.timeout,
+            allow_failure: command.allow_failure,
+            env: command.env.clone(),
+            stdin: command.stdin,
+        }
+    }
+}
+
+/// Serialize commands in the shortest form that reads back the same: a plain string,
+/// a `name: command` mapping, or the structured form when options are set.
+impl Serialize for HookCommand {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        if let Some(builtin) = &self.builtin {
+            return BuiltinCommand {
+                name: self.name.clone(),
+                builtin: builtin.clone(),
+            }
+            .serialize(serializer);
+        }
+
+        let has_options = self.success_codes.is_some()
+            || self.allow_unquoted
+            || self.retries > 0
+            || self.retry_delay.is_some()
+            || self.retry_on.is_some()
+            || self.working_dir.is_some()
+            || self.timeout.is_some()
+            || self.allow_failure
+            || !self.env.is_empty()
+            || self.stdin;
+
+        let task = self.task_runner.and_then(|runner| {
+            let target = self
+                .command
+                .strip_prefix(runner.program())?
+                .strip_prefix(' ')?;
+            Some((runner.program(), target))
+        });
+
+        match (&self.name, task) {
+            _ if has_options => StructuredCommand::from(self).serialize(serializer),
+            (None, Some((program, target))) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(program, target)?;
+                map.end()
+            }
+            (None, None) => serializer.serialize_str(&self.command),
+            // A command named `run`, `just` or `make` would read back as another form
+            (Some(name), _) if name == "run" || TaskRunner::from_key(name).is_some() => {
+                StructuredCommand::from(self).serialize(serializer)
+            }
+            (Some(name), _) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(name, &self.command)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl From<StructuredCommand> for HookCommand {
+    fn from(structured: StructuredCommand) -> Self {
+        Self {
+            name: structured.name,
+            command: structured.run,
+            success_codes: structured.success_codes,
+            allow_unquoted: structured.allow_unquoted,
+            retries: structured.retries,
+            retry_delay: structured.retry_delay,
+            retry_on: structured.retry_on,
+            working_dir: structured.working_dir,
+            timeout: structured.timeout,
+            allow_failure: structured.allow_failure,
+            env: structured.env,
+            stdin: structured.stdin,
+            task_runner: None,
+            builtin: None,
         }
     }
 }
@@ -100,6 +596,44 @@ where
                     Value::String(cmd) => {
                         commands.push(HookCommand::new_unnamed(cmd));
                     }
+                    // Handle built-in checks: builtin: commit-format, with their options
+                    Value::Mapping(map) if map.contains_key("builtin") => {
+                        let command: BuiltinCommand = serde_yaml::from_value(Value::Mapping(map))
+                            .map_err(A::Error::custom)?;
+                        commands.push(command.into());
+                    }
+                    // Handle structured commands: run: "grep ...", success_codes: [0, 1]
+                    Value::Mapping(map) if map.contains_key("run") => {
+                        let structured: StructuredCommand =
+                            serde_yaml::from_value(Value::Mapping(map))
+                                .map_err(A::Error::custom)?;
+                        commands.push(structured.into());
+                    }
+                    // Handle task runner targets: just: test, make: lint
+                    Value::Mapping(map)
+                        if map.len() == 1
+                            && map
+                                .keys()
+                                .next()
+                                .and_then(Value::as_str)
+                                .is_some_and(|key| TaskRunner::from_key(key).is_some()) =>
+                    {
+                        for (key, val) in map {
+                            let runner = key.as_str().and_then(TaskRunner::from_key);
+                            match (runner, val) {
+                                (Some(runner), Value::String(target))
+                                    if !target.trim().is_empty() =>
+                                {
+                                    commands.push(HookCommand::new_task(runner, target.trim()));
+                                }
+                                _ => {
+                                    return Err(A::Error::custom(
+                                        "Task runner commands must name a target, e.g. `just: test`",
+                                    ));
+                                }
+                            }
+                        }
+                    }
                     // Handle named commands: "clippy-linter": "cargo clippy ..."
                     Value::Mapping(map) => {
                         for (key, val) in map {
@@ -127,6 +661,26 @@ where
     deserializer.deserialize_seq(CommandsVisitor)
 }
 
+/// Custom deserializer for optional command groups, each group being a sequence of commands
+fn deserialize_optional_groups<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<Vec<HookCommand>>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let Some(groups) = Option::<Vec<serde_yaml::Value>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    groups
+        .into_iter()
+        .map(|group| deserialize_commands(group).map_err(D::Error::custom))
+        .collect::<std::result::Result<_, _>>()
+        .map(Some)
+}
+
 /// Custom deserializer for optional commands
 fn deserialize_optional_commands<'de, D>(
     deserializer: D,
@@ -164,1316 +718,9501 @@ where
 }
 
 /// Configuration structure for hooksmith.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 struct Config {
-    #[serde(flatten)]
-    hooks: std::collections::HashMap<String, Hook>,
+    /// Shell used to run commands (`sh` by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shell: Option<String>,
+    /// Whether to run the shell as a login shell (`-l`), so that it reads the user's profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login_shell: Option<bool>,
+    /// Whether to color message prefixes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<bool>,
+    /// Environment variables set for every command
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    env: HashMap<String, String>,
+    /// Program and arguments every command runs through, e.g. `mise exec --`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_command_prefix"
+    )]
+    command_prefix: Option<Vec<String>>,
+    /// Directories added in front of `PATH` for every command, relative to the repository
+    /// root, e.g. `node_modules/.bin`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    path_prepend: Vec<String>,
+    /// Directory of scripts named after Git hooks, relative to the configuration file,
+    /// each run after the inline commands of its hook, e.g. `.hooks`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scripts_dir: Option<String>,
+    #[serde(flatten, serialize_with = "serialize_sorted")]
+    hooks: HashMap<String, Hook>,
+}
+
+impl Config {
+    /// Render the configuration as YAML, with keys in a stable order.
+    ///
+    /// # Errors
+    /// * If the configuration cannot be serialized
+    fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).map_err(|e| ConfigError::Serialize(e.to_string()).into())
+    }
 }
 
 /// Path-scoped configuration for a hook.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 struct PathScopedConfig {
     #[serde(deserialize_with = "deserialize_commands")]
     commands: Vec<HookCommand>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     working_directory: Option<String>,
 }
 
 /// Hook structure for hooksmith.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 struct Hook {
-    #[serde(default)]
+    /// What the hook is for, shown by `list` and when selecting hooks to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[serde(deserialize_with = "deserialize_optional_commands")]
     commands: Option<Vec<HookCommand>>,
-    #[serde(default)]
-    paths: Option<std::collections::HashMap<String, PathScopedConfig>>, // path prefix -> config
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_sorted"
+    )]
+    paths: Option<HashMap<String, PathScopedConfig>>, // path prefix -> config
+    /// Total time budget for the hook's commands, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hook_timeout: Option<u64>,
+    /// Seconds each of the hook's commands may run before it is stopped, unless the
+    /// command sets its own `timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command_timeout: Option<u64>,
+    /// File mode creation mask applied to the hook's commands (Unix only)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_umask",
+        serialize_with = "serialize_umask"
+    )]
+    umask: Option<u32>,
+    /// Capture the output of each command and print it once the command finishes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    capture_output: bool,
+    /// Maximum number of bytes of captured output printed per command (implies `capture_output`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_output_bytes: Option<usize>,
+    /// Stash unstaged changes while the hook runs, so that it only sees what is staged
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stash_unstaged: bool,
+    /// Groups of commands run after `commands`, one group after the other; the commands
+    /// of a group run in parallel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_optional_groups")]
+    groups: Option<Vec<Vec<HookCommand>>>,
+    /// Glob patterns of the files the hook is concerned with; with `--only-changed-hooks`
+    /// the hook is skipped when none of the changed files match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<String>>,
+    /// Shell running the hook's commands, overriding the top-level `shell` and `--shell`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shell: Option<String>,
+    /// Program and arguments the hook's commands run through, overriding the top-level
+    /// `command_prefix`; empty to run the commands directly
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_command_prefix"
+    )]
+    command_prefix: Option<Vec<String>>,
+    /// Commands run once all of the hook's commands succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_optional_commands")]
+    on_success: Option<Vec<HookCommand>>,
+    /// Commands run once one of the hook's commands failed or the hook ran out of time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_optional_commands")]
+    on_failure: Option<Vec<HookCommand>>,
+    /// Don't warn about commands repeated in `commands` and `groups`, when running them
+    /// more than once is intended
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_duplicate_commands: bool,
+    /// Environment variables the hook only runs with: a value the variable must equal, `*`
+    /// for any value, `!<value>` for any other value and `!*` for the variable to be unset
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    when_env: HashMap<String, String>,
+    /// Environment variables set for the hook's commands, overriding the top-level `env`
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    env: HashMap<String, String>,
 }
 
-/// Timing information for a single command execution.
-#[derive(Debug, Clone)]
-pub struct CommandTiming {
-    pub command: String,
-    pub name: Option<String>,
-    pub duration: Duration,
+/// Serialize a map with its keys sorted, so that the output is stable.
+fn serialize_sorted<S, V>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter()
+        .collect::<std::collections::BTreeMap<_, _>>()
+        .serialize(serializer)
 }
 
-/// Timing information for a hook execution.
-#[derive(Debug, Clone)]
-pub struct HookTiming {
-    pub hook_name: String,
-    pub commands: Vec<CommandTiming>,
-    pub total_duration: Duration,
+/// Serialize an optional map with its keys sorted.
+fn serialize_optional_sorted<S, V>(
+    map: &Option<HashMap<String, V>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    match map {
+        Some(map) => serialize_sorted(map, serializer),
+        None => serializer.serialize_none(),
+    }
 }
 
-/// Collection of timing information for multiple hooks.
-#[derive(Debug, Clone)]
-pub struct TimingReport {
-    pub hooks: Vec<HookTiming>,
-    pub total_duration: Duration,
+/// Serialize `umask` as quoted octal digits (`"022"`).
+fn serialize_umask<S>(umask: &Option<u32>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match umask {
+        Some(mask) => serializer.serialize_str(&format!("{mask:03o}")),
+        None => serializer.serialize_none(),
+    }
 }
 
-/// Hooksmith structure for managing git hooks.
-pub struct Hooksmith {
-    config: Config,
-    dry_run: bool,
-    verbose: bool,
+/// Custom deserializer for `umask`, written as octal digits either quoted (`"022"`)
+/// or not (`022`).
+fn deserialize_umask<'de, D>(deserializer: D) -> std::result::Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde_yaml::Value;
+
+    let digits = match Value::deserialize(deserializer)? {
+        Value::Null => return Ok(None),
+        Value::String(digits) => digits,
+        Value::Number(number) => number.to_string(),
+        _ => {
+            return Err(D::Error::custom(
+                "umask must be an octal number such as 022",
+            ))
+        }
+    };
+
+    let digits = digits.trim_start_matches("0o");
+    match u32::from_str_radix(digits, 8) {
+        Ok(mask) if mask <= 0o777 => Ok(Some(mask)),
+        _ => Err(D::Error::custom(format!(
+            "invalid umask '{digits}', expected an octal number between 000 and 777"
+        ))),
+    }
 }
 
-impl Hooksmith {
-    /// Create a new instance of `Hooksmith` from a configuration file.
+/// Custom deserializer for `command_prefix`, written either as a string split on
+/// whitespace (`mise exec --`) or as a list of arguments (`[nix, develop, -c]`).
+fn deserialize_command_prefix<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde_yaml::Value;
+
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(prefix) => Ok(Some(
+            prefix.split_whitespace().map(str::to_string).collect(),
+        )),
+        Value::Sequence(args) => args
+            .into_iter()
+            .map(|arg| match arg {
+                Value::String(arg) => Ok(arg),
+                Value::Number(number) => Ok(number.to_string()),
+                _ => Err(D::Error::custom("command_prefix arguments must be strings")),
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Some),
+        _ => Err(D::Error::custom(
+            "command_prefix must be a string or a list of arguments",
+        )),
+    }
+}
+
+impl Hook {
+    /// Whether any of the given changed files matches the hook's `files` patterns. Hooks
+    /// without patterns are concerned with every change.
     ///
     /// # Arguments
-    /// * `config` - Path to the configuration file
-    /// * `dry_run` - Whether to run in dry run mode
-    /// * `verbose` - Whether to print verbose output
-    ///
-    /// # Errors
-    /// * If the configuration file cannot be read or parsed
-    pub fn new_from_config(config: &Path, dry_run: bool, verbose: bool) -> Result<Self> {
-        let config = Self::read_config(config)?;
+    /// * `changed_files` - The changed files, relative to the repository root
+    fn matches_changed_files(&self, changed_files: &[String]) -> bool {
+        self.files.as_ref().is_none_or(|patterns| {
+            changed_files
+                .iter()
+                .any(|file| patterns.iter().any(|pattern| glob_match(pattern, file)))
+        })
+    }
 
-        if dry_run {
-            println!("🔄 DRY RUN MODE - No commands will be executed\n");
-        }
+    /// Iterate over every command of the hook: path-scoped ones first, then global ones,
+    /// then grouped ones, then the `on_success` and `on_failure` handlers.
+    fn all_commands(&self) -> impl Iterator<Item = &HookCommand> {
+        self.paths
+            .iter()
+            .flat_map(|paths| paths.values())
+            .flat_map(|path_cfg| path_cfg.commands.iter())
+            .chain(self.commands.iter().flatten())
+            .chain(self.groups.iter().flatten().flatten())
+            .chain(self.on_success.iter().flatten())
+            .chain(self.on_failure.iter().flatten())
+    }
 
-        Ok(Self {
-            config,
-            dry_run,
-            verbose,
-        })
+    /// The commands of the hook in the order they run: path-based blocks, sorted by path,
+    /// then `commands`, then groups. `on_success` and `on_failure` are left out.
+    fn commands_in_run_order(&self) -> impl Iterator<Item = &HookCommand> {
+        self.paths
+            .iter()
+            .flat_map(|paths| paths.iter().collect::<BTreeMap<_, _>>().into_values())
+            .flat_map(|block| block.commands.iter())
+            .chain(self.commands.iter().flatten())
+            .chain(self.groups.iter().flatten().flatten())
     }
 
-    /// Check for hooks that are in config but not installed.
-    /// Iterates through hooks in the config and checks if they are installed.
-    /// Updates the `differences_found` flag and prints messages for missing hooks.
+    /// A copy of the hook without the commands a predicate selects, given their 1-based
+    /// position in run order (see `commands_in_run_order`).
     ///
     /// # Arguments
-    /// * `git_hooks_path` - Path to the git hooks directory
-    /// * `differences_found` - Mutable reference to track if differences were found
-    fn check_missing_hooks(&self, git_hooks_path: &Path, differences_found: &mut bool) {
-        for hook_name in self.config.hooks.keys() {
-            let hook_path = git_hooks_path.join(hook_name);
-            if !hook_path.exists() {
-                if !*differences_found {
-                    println!("\n❌ Differences found:");
-
-                    *differences_found = true;
-                }
+    /// * `is_removed` - Whether to remove the command at a given position
+    fn without_commands(&self, mut is_removed: impl FnMut(usize, &HookCommand) -> bool) -> Self {
+        let mut hook = self.clone();
+        let mut position = 0;
+        let mut keep = |hook_command: &HookCommand| {
+            position += 1;
+            !is_removed(position, hook_command)
+        };
 
-                println!("  - Hook '{hook_name}' is in config but not installed");
+        if let Some(paths) = &mut hook.paths {
+            for block in paths.iter_mut().collect::<BTreeMap<_, _>>().into_values() {
+                block.commands.retain(&mut keep);
             }
         }
+        if let Some(commands) = &mut hook.commands {
+            commands.retain(&mut keep);
+        }
+        for group in hook.groups.iter_mut().flatten() {
+            group.retain(&mut keep);
+        }
+
+        hook
     }
 
-    /// Check for hooks that are installed but not in config.
-    /// Scans the git hooks directory and checks if each hook is in the config.
-    /// Updates the `differences_found` flag and prints messages for extra hooks.
+    /// Whether a command uses the standard input Git gives the hook, through the
+    /// placeholders or variables exposing it (see `HookInput::names`), or the hook's input
+    /// is always read (see `HookInput::is_always_read`). Only then is the input read,
+    /// otherwise the commands inherit it untouched.
     ///
     /// # Arguments
-    /// * `git_hooks_path` - Path to the git hooks directory
-    /// * `differences_found` - Mutable reference to track if differences were found
+    /// * `hook_name` - The name of the hook
+    fn uses_input(&self, hook_name: &str) -> bool {
+        let names = HookInput::names(hook_name);
+
+        HookInput::is_always_read(hook_name)
+            || self.all_commands().any(|hook_command| {
+                hook_command.stdin || names.iter().any(|name| hook_command.command.contains(name))
+            })
+    }
+
+    /// The first condition of `when_env` the environment doesn't meet, described, in the
+    /// order of the variable names.
     ///
-    /// # Errors
-    /// * If there is an error reading the git hooks directory
-    fn check_extra_hooks(&self, git_hooks_path: &Path, differences_found: &mut bool) {
-        if let Ok(entries) = fs::read_dir(git_hooks_path) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if !file_type.is_file() {
-                        continue;
-                    }
+    /// # Arguments
+    /// * `lookup` - Value of an environment variable, `None` when it isn't set
+    ///
+    /// # Returns
+    /// * `None` if every condition is met, so that the hook runs
+    fn unmet_env_condition(&self, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let mut conditions = self.when_env.iter().collect::<Vec<_>>();
+        conditions.sort();
+
+        conditions.into_iter().find_map(|(name, expected)| {
+            let value = lookup(name);
+            match (expected.strip_prefix('!'), value) {
+                (Some("*"), Some(_)) => Some(format!("`{name}` is set")),
+                (Some("*"), None) => None,
+                (Some(excluded), Some(value)) if value == excluded => {
+                    Some(format!("`{name}` is `{value}`"))
+                }
+                (Some(_), _) => None,
+                (None, None) => Some(format!("`{name}` is not set")),
+                (None, Some(_)) if expected == "*" => None,
+                (None, Some(value)) if value != *expected => {
+                    Some(format!("`{name}` is `{value}`, not `{expected}`"))
+                }
+                (None, Some(_)) => None,
+            }
+        })
+    }
+}
 
-                    let hook_name = entry.file_name().to_string_lossy().to_string();
+/// Outcome of removing the files of several hooks.
+#[derive(Debug, Default)]
+struct HookRemoval {
+    /// Hooks whose file was removed (or would be, in dry run mode), sorted
+    removed: Vec<String>,
+    /// Hooks whose file couldn't be removed, and why
+    failed: Vec<(String, HooksmithError)>,
+}
 
-                    if hook_name.ends_with(".sample") {
-                        continue;
-                    }
+/// Outcome of refreshing the installed hook scripts, see `Hooksmith::upgrade_hooks`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct HookUpgrade {
+    /// Hooks whose script was rewritten (or would be, in dry run mode), sorted
+    upgraded: Vec<String>,
+    /// Hooks whose script already is the one `install` writes, sorted
+    current: Vec<String>,
+    /// Configured hooks whose installed script wasn't written by hooksmith, left alone, sorted
+    foreign: Vec<String>,
+}
 
-                    if !self.config.hooks.contains_key(&hook_name) {
-                        if !*differences_found {
-                            println!("\n❌ Differences found:");
+/// A configured hook as `Hooksmith::list_hooks` prints it.
+#[derive(Debug, PartialEq, Eq)]
+struct ListedHook {
+    name: String,
+    /// Whether hooksmith installed the hook, whether its script is current or not
+    installed: bool,
+    /// Whether the hook is installed and current, and if not, what to do about it
+    state: &'static str,
+    /// The hook's `description`, if any
+    description: Option<String>,
+    /// Commands in the order they run, `name: command` for named ones
+    commands: Vec<String>,
+}
 
-                            *differences_found = true;
-                        }
+/// A check of `Hooksmith::doctor` and its outcome.
+#[derive(Debug, PartialEq, Eq)]
+struct DoctorCheck {
+    status: CheckStatus,
+    message: String,
+}
 
-                        println!("  - Hook '{hook_name}' is installed but not in config");
+/// Outcome of a check of `Hooksmith::doctor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    /// Hooks still fire, but maybe not as expected
+    Warn,
+    /// Hooks can't fire, or hooksmith can't manage them
+    Fail,
+}
+
+impl DoctorCheck {
+    /// Create a check with the given outcome.
+    ///
+    /// # Arguments
+    /// * `status` - The outcome of the check
+    /// * `message` - What was found
+    fn new(status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DoctorCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let icon = match self.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+        };
+
+        write!(f, "{icon} {}", self.message)
+    }
+}
+
+/// Timing information for a single command execution.
+#[derive(Debug, Clone)]
+pub struct CommandTiming {
+    pub command: String,
+    pub name: Option<String>,
+    /// Stable identifier of the command, see `HookCommand::key`
+    pub key: String,
+    pub duration: Duration,
+    /// Whether the command failed, which only leaves the hook running for the commands
+    /// allowed to fail (`allow_failure`)
+    pub failed: bool,
+    /// Status code the command exited with, `None` when no process ran (built-in checks,
+    /// dry runs) or it was stopped by a timeout or a signal
+    pub exit_code: Option<i32>,
+}
+
+impl CommandTiming {
+    /// Create the timing of a command.
+    ///
+    /// # Arguments
+    /// * `hook_command` - The command
+    /// * `duration` - How long the command ran
+    fn new(hook_command: &HookCommand, duration: Duration) -> Self {
+        Self {
+            command: hook_command.command.clone(),
+            name: hook_command.name.clone(),
+            key: hook_command.key(),
+            duration,
+            failed: false,
+            exit_code: None,
+        }
+    }
+}
+
+/// Timing information for a hook execution.
+#[derive(Debug, Clone)]
+pub struct HookTiming {
+    pub hook_name: String,
+    pub commands: Vec<CommandTiming>,
+    pub total_duration: Duration,
+    /// What was intentionally not run, in the order it was skipped
+    pub skipped: Vec<Skipped>,
+}
+
+/// A hook or a block of commands that was intentionally not run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Skipped {
+    /// What was skipped: a hook name or a `paths:` block, in backticks
+    pub target: String,
+    /// Why it was skipped
+    pub reason: String,
+}
+
+/// Collection of timing information for multiple hooks.
+#[derive(Debug, Clone)]
+pub struct TimingReport {
+    pub hooks: Vec<HookTiming>,
+    pub total_duration: Duration,
+}
+
+/// Options of a run of hooks, overriding the process defaults and the configuration
+/// without editing it. Every option defaults to the behavior of a plain `hooksmith run`.
+///
+/// # Example
+/// ```
+/// use hooksmith::RunOptions;
+/// use std::time::Duration;
+///
+/// let options = RunOptions::new()
+///     .with_env("CI", "true")
+///     .with_extra_args(vec!["origin".to_string()])
+///     .with_timeout(Duration::from_secs(60));
+/// assert_eq!(options.env["CI"], "true");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunOptions {
+    /// Environment variables set for every command, on top of (and overriding) the
+    /// configuration's `env`
+    pub env: HashMap<String, String>,
+    /// Directory the commands run in instead of the current directory. The
+    /// `working_directory` of path-based blocks is resolved relative to it.
+    /// Git queries, such as detecting changed files, still use the current directory.
+    pub cwd: Option<PathBuf>,
+    /// Positional arguments exposed to the commands as `$1`, `$2`, ...
+    pub extra_args: Vec<String>,
+    /// Capture the output of each command, as if the hook set `capture_output`
+    pub capture: bool,
+    /// Total time budget of each hook, overriding its `hook_timeout`
+    pub timeout: Option<Duration>,
+    /// Maximum number of bytes of output printed per command, for hooks that capture
+    /// their output, overriding their `max_output_bytes`
+    pub max_output_bytes: Option<usize>,
+    /// Select the hooks to run interactively (`Hooksmith::run_hook` only)
+    pub interactive: bool,
+    /// Run every configured hook, in Git's workflow order (`Hooksmith::run_hook` only)
+    pub all: bool,
+    /// Configured hooks left out of the selection, however it was made
+    /// (`Hooksmith::run_hook` only)
+    pub except: Vec<String>,
+    /// Hooks, and commands as `<hook>:<index>` (1-based, in run order) or `<hook>:<name>`,
+    /// that are skipped wherever they run, on top of those listed in `HOOKSMITH_SKIP`
+    pub skip: Vec<String>,
+    /// Print a timing report once the hooks have run
+    pub profile: bool,
+    /// Only process the files changed since each hook's last successful run. Hooks without
+    /// a recorded run, or whose recorded commit no longer exists, run in full.
+    pub since_last_run: bool,
+    /// Stash unstaged changes while each hook runs, as if every hook set `stash_unstaged`
+    pub stash: bool,
+    /// Skip the hooks whose `files` patterns match none of the changed files. For
+    /// `pre-push`, the changed files are those of the ref updates read from stdin.
+    pub only_changed_hooks: bool,
+    /// Whether to print a `skipped: <reason>` line for each hook or block of commands that
+    /// is intentionally not run, `None` to print them in verbose mode only. Skips are
+    /// recorded in the `HookTiming` either way.
+    pub show_skipped: Option<bool>,
+    /// In dry-run mode, print the exact program, arguments, environment and directory
+    /// each command would be started with, instead of the human-readable preview
+    pub resolve: bool,
+    /// Run the hooks this many times and print the min/mean/max duration of each command
+    /// (`Hooksmith::run_hook` only). The commands really run, side effects included.
+    pub bench: Option<usize>,
+    /// File receiving a copy of the output of every command, while it is still printed as
+    /// it comes. The file is truncated when the run starts.
+    pub tee: Option<PathBuf>,
+    /// Print the Git-related environment variables (`GIT_*`) present when each hook starts,
+    /// as `HOOKSMITH_SHOW_GIT_ENV` does for the hooks Git triggers
+    pub show_git_env: bool,
+    /// Commands run once before the hooks, which don't run if one of them fails
+    /// (`Hooksmith::run_hook`, `run_hooks` and `run_hooks_with_timing`)
+    pub pre_run: Vec<String>,
+    /// Commands run once after the hooks, whether they succeeded or not
+    /// (`Hooksmith::run_hook`, `run_hooks` and `run_hooks_with_timing`)
+    pub post_run: Vec<String>,
+    /// Shell running the commands instead of the configured `shell`, unless their hook
+    /// sets its own
+    pub shell: Option<String>,
+    /// How many of the hooks run at the same time (`Hooksmith::run_hook` and `run_hooks`,
+    /// except with `profile` or `bench`); 0 and 1 run them one after the other. The output
+    /// of concurrent hooks is captured and printed per hook once it is done, and a failing
+    /// hook doesn't stop the others.
+    pub jobs: usize,
+}
+
+impl RunOptions {
+    /// Create options matching a plain `hooksmith run`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an environment variable for every command.
+    ///
+    /// # Arguments
+    /// * `key` - The name of the variable
+    /// * `value` - The value of the variable
+    #[must_use]
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the directory the commands run in.
+    ///
+    /// # Arguments
+    /// * `cwd` - The directory
+    #[must_use]
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Set the positional arguments exposed to the commands as `$1`, `$2`, ...
+    ///
+    /// # Arguments
+    /// * `extra_args` - The arguments
+    #[must_use]
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    /// Set whether the output of every command is captured.
+    ///
+    /// # Arguments
+    /// * `capture` - Whether to capture the output
+    #[must_use]
+    pub fn with_capture(mut self, capture: bool) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Set the total time budget of each hook.
+    ///
+    /// # Arguments
+    /// * `timeout` - The time budget
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Override the maximum number of bytes of output printed per command, for hooks that
+    /// capture their output.
+    ///
+    /// # Arguments
+    /// * `max_output_bytes` - The maximum, `None` to use each hook's own setting
+    #[must_use]
+    pub fn with_max_output_bytes(mut self, max_output_bytes: Option<usize>) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    /// Set whether the hooks to run are selected interactively.
+    ///
+    /// # Arguments
+    /// * `interactive` - Whether to prompt for the hooks
+    #[must_use]
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Set whether every configured hook runs.
+    ///
+    /// # Arguments
+    /// * `all` - Whether to run every hook
+    #[must_use]
+    pub fn with_all(mut self, all: bool) -> Self {
+        self.all = all;
+        self
+    }
+
+    /// Set the configured hooks left out of the selection.
+    ///
+    /// # Arguments
+    /// * `except` - Names of the hooks to leave out
+    #[must_use]
+    pub fn with_except(mut self, except: Vec<String>) -> Self {
+        self.except = except;
+        self
+    }
+
+    /// Set the hooks and commands that are skipped.
+    ///
+    /// # Arguments
+    /// * `skip` - Hook names, or commands as `<hook>:<index>` or `<hook>:<name>`
+    #[must_use]
+    pub fn with_skip(mut self, skip: Vec<String>) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Set whether a timing report is printed once the hooks have run.
+    ///
+    /// # Arguments
+    /// * `profile` - Whether to print the report
+    #[must_use]
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Set whether hooks only process the files changed since their last successful run.
+    ///
+    /// # Arguments
+    /// * `since_last_run` - Whether to run hooks incrementally
+    #[must_use]
+    pub fn with_since_last_run(mut self, since_last_run: bool) -> Self {
+        self.since_last_run = since_last_run;
+        self
+    }
+
+    /// Set whether hooks whose `files` patterns match none of the changed files are skipped.
+    ///
+    /// # Arguments
+    /// * `only_changed_hooks` - Whether to skip hooks unaffected by the changes
+    #[must_use]
+    pub fn with_only_changed_hooks(mut self, only_changed_hooks: bool) -> Self {
+        self.only_changed_hooks = only_changed_hooks;
+        self
+    }
+
+    /// Set the number of runs of a benchmark, `None` to run the hooks once.
+    ///
+    /// # Arguments
+    /// * `runs` - How many times the hooks run
+    #[must_use]
+    pub fn with_bench(mut self, runs: Option<usize>) -> Self {
+        self.bench = runs;
+        self
+    }
+
+    /// Set whether unstaged changes are stashed while each hook runs.
+    ///
+    /// # Arguments
+    /// * `stash` - Whether to stash unstaged changes
+    #[must_use]
+    pub fn with_stash(mut self, stash: bool) -> Self {
+        self.stash = stash;
+        self
+    }
+
+    /// Set whether skipped hooks and blocks of commands are printed.
+    ///
+    /// # Arguments
+    /// * `show_skipped` - Whether to print skips, `None` to print them in verbose mode only
+    #[must_use]
+    pub fn with_show_skipped(mut self, show_skipped: Option<bool>) -> Self {
+        self.show_skipped = show_skipped;
+        self
+    }
+
+    /// Set whether the Git-related environment variables are printed when each hook starts.
+    ///
+    /// # Arguments
+    /// * `show_git_env` - Whether to print the `GIT_*` variables
+    #[must_use]
+    pub fn with_show_git_env(mut self, show_git_env: bool) -> Self {
+        self.show_git_env = show_git_env;
+        self
+    }
+
+    /// Set the shell running the commands of hooks that don't set their own.
+    ///
+    /// # Arguments
+    /// * `shell` - The shell, optionally with its arguments (e.g. `pwsh -Command`), `None`
+    ///   for the configured one
+    #[must_use]
+    pub fn with_shell(mut self, shell: Option<String>) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Set how many hooks run at the same time.
+    ///
+    /// # Arguments
+    /// * `jobs` - The number of hooks run concurrently, 0 or 1 to run them in order
+    #[must_use]
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Set the commands run before the hooks.
+    ///
+    /// # Arguments
+    /// * `pre_run` - The commands, run in order by the shell
+    #[must_use]
+    pub fn with_pre_run(mut self, pre_run: Vec<String>) -> Self {
+        self.pre_run = pre_run;
+        self
+    }
+
+    /// Set the commands run after the hooks.
+    ///
+    /// # Arguments
+    /// * `post_run` - The commands, run in order by the shell
+    #[must_use]
+    pub fn with_post_run(mut self, post_run: Vec<String>) -> Self {
+        self.post_run = post_run;
+        self
+    }
+
+    /// Set the file receiving a copy of the output of every command.
+    ///
+    /// # Arguments
+    /// * `tee` - The log file, `None` to only print the output
+    #[must_use]
+    pub fn with_tee(mut self, tee: Option<PathBuf>) -> Self {
+        self.tee = tee;
+        self
+    }
+
+    /// Set whether dry runs print the exact invocation of each command.
+    ///
+    /// # Arguments
+    /// * `resolve` - Whether to print the resolved invocations
+    #[must_use]
+    pub fn with_resolve(mut self, resolve: bool) -> Self {
+        self.resolve = resolve;
+        self
+    }
+}
+
+/// State of the repository deciding which hooks fire for a Git event, see
+/// `Hooksmith::hooks_for_event`.
+///
+/// # Example
+/// ```
+/// use hooksmith::RunContext;
+///
+/// let context = RunContext::new().with_changed_files(vec!["src/main.rs".to_string()]);
+/// assert_eq!(context.changed_files.unwrap().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunContext {
+    /// Files changed by the operation, relative to the repository root (e.g. the staged
+    /// files for `pre-commit`), `None` when unknown. Unknown changes match every hook.
+    pub changed_files: Option<Vec<String>>,
+}
+
+impl RunContext {
+    /// Create a context where the changed files are unknown.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a context from the current repository: the staged files for `pre-commit`,
+    /// the commits ahead of the upstream for `pre-push`. Other hooks get unknown changes.
+    ///
+    /// # Arguments
+    /// * `event` - The Git hook the context is for
+    #[must_use]
+    pub fn detect(event: &str) -> Self {
+        Self {
+            changed_files: Hooksmith::detect_changed_files(event),
+        }
+    }
+
+    /// Set the files changed by the operation.
+    ///
+    /// # Arguments
+    /// * `changed_files` - The changed files, relative to the repository root
+    #[must_use]
+    pub fn with_changed_files(mut self, changed_files: Vec<String>) -> Self {
+        self.changed_files = Some(changed_files);
+        self
+    }
+}
+
+/// A command as handed to the operating system, after every setting of the configuration
+/// and of the run has been applied.
+#[derive(Debug, PartialEq, Eq)]
+struct ResolvedCommand {
+    /// The program, i.e. the shell
+    program: String,
+    /// The arguments of the program
+    args: Vec<String>,
+    /// Environment variables set on top of the inherited environment, by name
+    env: BTreeMap<String, String>,
+    /// Directory the command runs in, `None` for the current directory
+    cwd: Option<PathBuf>,
+}
+
+/// State of a single hook run, shared by the commands it executes.
+#[derive(Clone)]
+struct ExecutionContext<'a> {
+    /// Name of the hook being run
+    hook_name: &'a str,
+    /// Positional arguments exposed to the commands as `$1`, `$2`, ...
+    hook_args: &'a [String],
+    /// When the hook started running
+    started: Instant,
+    /// Total time budget of the hook (`hook_timeout`)
+    hook_timeout: Option<Duration>,
+    /// Time each command may run before it is stopped (`command_timeout`, or the command's
+    /// own `timeout`)
+    command_timeout: Option<Duration>,
+    /// Files changed since the hook's last successful run (`--since-last-run`), if known
+    changed_files: Option<Vec<String>>,
+    /// Input read from the standard input of the hook, replayed to each command
+    input: Option<HookInput>,
+    /// Whether the commands' standard input is closed instead of inherited from hooksmith,
+    /// for the commands of a parallel group that don't read it
+    null_stdin: bool,
+    /// File mode creation mask applied to the commands (`umask`)
+    umask: Option<u32>,
+    /// Program and arguments the commands run through, when the hook sets its own
+    command_prefix: Option<Vec<String>>,
+    /// Shell running the commands, when the hook sets its own
+    shell: Option<String>,
+    /// Environment variables of the hook, then of the running command (`env`), applied in
+    /// this order on top of the configuration's
+    env: Vec<HashMap<String, String>>,
+    /// Variables describing the hook's outcome, for its `on_success` and `on_failure` commands
+    outcome_env: Vec<(&'static str, String)>,
+    /// Directories added in front of `PATH` (`path_prepend`), resolved once per run
+    path_prepend: Vec<PathBuf>,
+    /// Maximum number of bytes of output kept per command, when the output is captured
+    max_output_bytes: Option<usize>,
+    /// What was intentionally not run so far
+    skipped: Vec<Skipped>,
+    /// Options of the run
+    options: &'a RunOptions,
+}
+
+impl<'a> ExecutionContext<'a> {
+    /// Start a new run of the given hook.
+    fn new(hook_name: &'a str, options: &'a RunOptions, hook: &Hook) -> Self {
+        Self {
+            hook_name,
+            hook_args: &options.extra_args,
+            started: Instant::now(),
+            hook_timeout: options
+                .timeout
+                .or_else(|| hook.hook_timeout.map(Duration::from_secs)),
+            command_timeout: hook.command_timeout.map(Duration::from_secs),
+            changed_files: None,
+            input: None,
+            null_stdin: false,
+            umask: hook.umask,
+            command_prefix: hook.command_prefix.clone(),
+            shell: hook.shell.clone(),
+            env: vec![hook.env.clone()],
+            outcome_env: Vec::new(),
+            path_prepend: Vec::new(),
+            max_output_bytes: None,
+            skipped: Vec::new(),
+            options,
+        }
+    }
+
+    /// The context a command runs in: the hook's, with the command's own `timeout` and
+    /// `env` when it sets them.
+    ///
+    /// # Arguments
+    /// * `hook_command` - The command
+    fn for_command(&self, hook_command: &HookCommand) -> Cow<'_, Self> {
+        if hook_command.timeout.is_none() && hook_command.env.is_empty() {
+            return Cow::Borrowed(self);
+        }
+
+        let mut env = self.env.clone();
+        env.push(hook_command.env.clone());
+        Cow::Owned(Self {
+            command_timeout: hook_command
+                .timeout
+                .map(Duration::from_secs)
+                .or(self.command_timeout),
+            env,
+            ..self.clone()
+        })
+    }
+
+    /// When the hook's time budget runs out, if it has one.
+    fn deadline(&self) -> Option<Instant> {
+        self.hook_timeout.map(|timeout| self.started + timeout)
+    }
+
+    /// Whether the hook's time budget has already run out.
+    fn is_out_of_time(&self) -> bool {
+        self.deadline()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Temporary commit message file, used to simulate the path git passes to `commit-msg` as `$1`.
+/// The file is removed when the value is dropped.
+pub struct CommitMessageFile {
+    file: tempfile::NamedTempFile,
+}
+
+impl CommitMessageFile {
+    /// Write the given message to a new file in the system temporary directory. The file
+    /// gets a random name and is created exclusively, readable by the current user only, so
+    /// that another user can't have it written elsewhere through a symlink.
+    ///
+    /// # Arguments
+    /// * `message` - The commit message to write
+    ///
+    /// # Errors
+    /// * If the file cannot be created or written
+    pub fn create(message: &str) -> Result<Self> {
+        let mut file = tempfile::Builder::new()
+            .prefix("hooksmith-COMMIT_EDITMSG-")
+            .tempfile()?;
+
+        writeln!(file, "{message}")?;
+
+        Ok(Self { file })
+    }
+
+    /// Path of the temporary commit message file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+/// Maximum number of bytes of captured output printed per command, unless configured.
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Shell used to run commands when the configuration doesn't set one, see `default_shell`.
+const DEFAULT_SHELL: &str = "sh";
+
+/// Shell used to run commands on Windows when the configuration doesn't set one and `sh`
+/// isn't available.
+const WINDOWS_FALLBACK_SHELL: &str = "cmd";
+
+/// The shell running commands when the configuration doesn't set one: `sh`, or on Windows
+/// `cmd` when there is no `sh` in `PATH`. Git for Windows puts its `sh` in the `PATH` of the
+/// hooks it runs, but a stock Windows installation doesn't have one.
+fn default_shell() -> &'static str {
+    if cfg!(windows) && !is_in_path(DEFAULT_SHELL) {
+        WINDOWS_FALLBACK_SHELL
+    } else {
+        DEFAULT_SHELL
+    }
+}
+
+/// Whether an executable program can be found in a directory of `PATH`, as the shell
+/// would, with or without the `.exe` extension.
+///
+/// # Arguments
+/// * `program` - Name of the program
+fn is_in_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| {
+            is_executable_file(&dir.join(program))
+                || is_executable_file(&dir.join(format!("{program}.exe")))
+        })
+    })
+}
+
+/// Environment variable holding the files changed since the hook's last successful run,
+/// one per line, when running with `--since-last-run`.
+const CHANGED_FILES_ENV: &str = "HOOKSMITH_CHANGED_FILES";
+
+/// Environment variable holding the outcome of a hook, `success` or `failure`, for its
+/// `on_success` and `on_failure` commands.
+const HOOK_OUTCOME_ENV: &str = "HOOKSMITH_HOOK_OUTCOME";
+
+/// Environment variable holding how long a hook's commands took, in milliseconds, for its
+/// `on_success` and `on_failure` commands.
+const HOOK_DURATION_ENV: &str = "HOOKSMITH_HOOK_DURATION_MS";
+
+/// Environment variable holding why a hook failed, for its `on_failure` commands.
+const HOOK_ERROR_ENV: &str = "HOOKSMITH_HOOK_ERROR";
+
+/// Environment variable holding the hooks whose `on_success` or `on_failure` commands are
+/// running, separated by `:`. Hooks they trigger, e.g. through `git commit`, are skipped
+/// when listed, so that a handler doesn't trigger its own hook again.
+const HANDLER_HOOKS_ENV: &str = "HOOKSMITH_HANDLER_HOOKS";
+
+/// Environment variable listing the hooks, and the commands as `<hook>:<index>` or
+/// `<hook>:<name>`, to skip, separated by commas, e.g. `pre-push,pre-commit:2`.
+const SKIP_ENV: &str = "HOOKSMITH_SKIP";
+
+/// Environment variable that, when set, prints the Git-related environment variables each
+/// hook starts with, for the hooks Git triggers, whose command line can't take `--git-env`.
+const SHOW_GIT_ENV_ENV: &str = "HOOKSMITH_SHOW_GIT_ENV";
+
+/// Environment variables Git may set for hooks, so they count as defined when validating
+/// the variables commands use.
+const GIT_HOOK_VARIABLES: [&str; 8] = [
+    "GIT_DIR",
+    "GIT_INDEX_FILE",
+    "GIT_WORK_TREE",
+    "GIT_PREFIX",
+    "GIT_EXEC_PATH",
+    "GIT_EDITOR",
+    "GIT_REFLOG_ACTION",
+    "GIT_PUSH_OPTION_COUNT",
+];
+
+/// Hooksmith structure for managing git hooks.
+pub struct Hooksmith {
+    config: Config,
+    /// Where the configuration came from, for `config --explain`
+    sources: ConfigSources,
+    dry_run: bool,
+    verbose: bool,
+    create_hooks_dir: bool,
+    /// Whether installing overwrites hooks hooksmith didn't write without backing them up
+    force: bool,
+    /// Whether installing keeps hooks hooksmith didn't write, as `<hook>.local` scripts
+    /// run before hooksmith
+    append: bool,
+    reporter: Reporter,
+    observer: Box<dyn HookObserver>,
+}
+
+impl Hooksmith {
+    /// Create a new instance of `Hooksmith` from a configuration file.
+    ///
+    /// # Arguments
+    /// * `config` - Path to the configuration file
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be read or parsed
+    pub fn new_from_config(config: &Path, dry_run: bool, verbose: bool) -> Result<Self> {
+        Self::new_from_config_with_reporter(config, dry_run, verbose, Reporter::default())
+    }
+
+    /// Create a new instance of `Hooksmith` from a configuration file, reporting
+    /// warnings and errors through the given reporter.
+    ///
+    /// # Arguments
+    /// * `config` - Path to the configuration file
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be read or parsed
+    pub fn new_from_config_with_reporter(
+        config: &Path,
+        dry_run: bool,
+        verbose: bool,
+        reporter: Reporter,
+    ) -> Result<Self> {
+        let config_path = config;
+        let config = Self::read_config(config_path)?;
+
+        Ok(Self::from_parsed_config(
+            config,
+            Some(config_path),
+            dry_run,
+            verbose,
+            reporter,
+        ))
+    }
+
+    /// Create a new instance of `Hooksmith` from a configuration file, only reading the
+    /// given hooks and the settings. The other Git hooks of the file are left out before it
+    /// is parsed, so that a mistake in one of them doesn't stop the selected hooks from
+    /// running (`run --no-validate-all`). When none of the hooks are in the file, it is read
+    /// in full, so that the usual error lists the available hooks.
+    ///
+    /// # Arguments
+    /// * `config` - Path to the configuration file
+    /// * `hook_names` - Names of the hooks to keep, or glob patterns matching them
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be read, or the settings or the kept hooks
+    ///   cannot be parsed
+    pub fn new_from_config_for_hooks(
+        config: &Path,
+        hook_names: &[String],
+        dry_run: bool,
+        verbose: bool,
+        reporter: Reporter,
+    ) -> Result<Self> {
+        let config_path = config;
+        let content = fs::read_to_string(config_path)?;
+        let value = match embedded_config(config_path, &content)? {
+            Some(value) => value,
+            None => serde_yaml::from_str(&content)
+                .map_err(|err| HooksmithError::Config(ConfigError::Parse(err)))?,
+        };
+        let config = Self::read_config_value_for_hooks(value, hook_names)?;
+
+        Ok(Self::from_parsed_config(
+            config,
+            Some(config_path),
+            dry_run,
+            verbose,
+            reporter,
+        ))
+    }
+
+    /// Create a new instance of `Hooksmith` from a configuration read from any reader,
+    /// e.g. the standard input, an embedded resource or a Git blob, without a file.
+    ///
+    /// # Arguments
+    /// * `reader` - Reader of the configuration, as YAML
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    ///
+    /// # Errors
+    /// * If the configuration cannot be read or parsed
+    pub fn from_reader<R: Read>(reader: R, dry_run: bool, verbose: bool) -> Result<Self> {
+        Self::from_reader_with_reporter(reader, dry_run, verbose, Reporter::default())
+    }
+
+    /// Create a new instance of `Hooksmith` from a configuration read from any reader,
+    /// reporting warnings and errors through the given reporter.
+    ///
+    /// # Arguments
+    /// * `reader` - Reader of the configuration, as YAML
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the configuration cannot be read or parsed
+    pub fn from_reader_with_reporter<R: Read>(
+        mut reader: R,
+        dry_run: bool,
+        verbose: bool,
+        reporter: Reporter,
+    ) -> Result<Self> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let config = Self::read_config_str(&content)?;
+
+        Ok(Self::from_parsed_config(
+            config, None, dry_run, verbose, reporter,
+        ))
+    }
+
+    /// Create a new instance of `Hooksmith` from a parsed configuration.
+    ///
+    /// # Arguments
+    /// * `config` - The parsed configuration
+    /// * `config_path` - Path the configuration was read from, if it was read from a file
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    fn from_parsed_config(
+        mut config: Config,
+        config_path: Option<&Path>,
+        dry_run: bool,
+        verbose: bool,
+        reporter: Reporter,
+    ) -> Self {
+        if dry_run {
+            outln!("🔄 DRY RUN MODE - No commands will be executed\n");
+        }
+
+        Self::merge_hook_scripts(&reporter, &mut config, config_path);
+        Self::warn_if_no_hooks(&reporter, &config, config_path);
+
+        let reporter = match config.color {
+            Some(color) => reporter.with_color(color),
+            None => reporter,
+        };
+
+        Self {
+            config,
+            sources: ConfigSources {
+                repository: config_path.map(Path::to_path_buf),
+                ..ConfigSources::default()
+            },
+            dry_run,
+            verbose,
+            create_hooks_dir: true,
+            force: false,
+            append: false,
+            reporter,
+            observer: Box::new(StdoutObserver),
+        }
+    }
+
+    /// Merge the user's global configuration under the repository configuration: settings
+    /// missing from the repository configuration are taken from the global one. Hooks are
+    /// only ever defined by the repository configuration.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the global configuration file, which may not exist
+    ///
+    /// # Errors
+    /// * If the global configuration file exists but cannot be read or parsed
+    pub fn with_global_config(mut self, path: &Path) -> Result<Self> {
+        let Some(global) = read_global_config(path)? else {
+            return Ok(self);
+        };
+        self.sources.global = Some(path.to_path_buf());
+
+        if self.config.shell.is_none() && global.shell.is_some() {
+            self.config.shell = global.shell;
+            self.sources.from_global.push("shell".to_string());
+        }
+
+        if self.config.login_shell.is_none() && global.login_shell.is_some() {
+            self.config.login_shell = global.login_shell;
+            self.sources.from_global.push("login_shell".to_string());
+        }
+
+        if self.config.color.is_none() {
+            if let Some(color) = global.color {
+                self.config.color = Some(color);
+                self.reporter = std::mem::take(&mut self.reporter).with_color(color);
+                self.sources.from_global.push("color".to_string());
+            }
+        }
+
+        for (key, value) in global.env {
+            if let std::collections::hash_map::Entry::Vacant(entry) = self.config.env.entry(key) {
+                self.sources
+                    .from_global
+                    .push(format!("env.{}", entry.key()));
+                entry.insert(value);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Render the configuration hooksmith acts on, after the global configuration is merged
+    /// and defaults are applied.
+    ///
+    /// # Arguments
+    /// * `json` - Whether to render JSON instead of YAML
+    ///
+    /// # Errors
+    /// * If the configuration cannot be serialized
+    pub fn effective_config(&self, json: bool) -> Result<String> {
+        let config = self.effective();
+
+        if json {
+            serde_json::to_string_pretty(&config)
+                .map_err(|e| ConfigError::Serialize(e.to_string()).into())
+        } else {
+            config.to_yaml()
+        }
+    }
+
+    /// Describe the configured hooks as JSON, for editors and other tools: whether each hook
+    /// is installed and enabled, and its commands. See the `api` module for the schema.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be located
+    /// * If the description cannot be serialized
+    pub fn hooks_json(&self) -> Result<String> {
+        let git_hooks_path = self.hooks_dir()?;
+        let enabled = !disabled::is_disabled();
+
+        let hooks = self
+            .config
+            .hooks
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, hook)| {
+                let commands = hook
+                    .commands_in_run_order()
+                    .map(|command| CommandEntry {
+                        name: command.name.as_deref(),
+                        run: &command.command,
+                    })
+                    .collect();
+
+                HookEntry {
+                    name,
+                    installed: git_hooks_path.join(name).exists(),
+                    enabled,
+                    description: hook.description.as_deref(),
+                    commands,
+                }
+            })
+            .collect();
+
+        let list = HookList {
+            schema_version: HOOKS_SCHEMA_VERSION,
+            hooks,
+        };
+
+        serde_json::to_string_pretty(&list)
+            .map_err(|e| ConfigError::Serialize(e.to_string()).into())
+    }
+
+    /// Describe where each setting and hook of the effective configuration comes from: the
+    /// repository configuration, the global configuration or hooksmith's defaults, with the
+    /// file and line setting it.
+    ///
+    /// # Arguments
+    /// * `json` - Whether to render JSON instead of a table
+    ///
+    /// # Errors
+    /// * If the description cannot be serialized
+    pub fn explain_config(&self, json: bool) -> Result<String> {
+        let config = &self.config;
+        let mut keys = vec![
+            ("shell".to_string(), config.shell.is_some()),
+            ("color".to_string(), config.color.is_some()),
+        ];
+        if config.login_shell.is_some() {
+            keys.push(("login_shell".to_string(), true));
+        }
+        if config.command_prefix.is_some() {
+            keys.push(("command_prefix".to_string(), true));
+        }
+        if !config.path_prepend.is_empty() {
+            keys.push(("path_prepend".to_string(), true));
+        }
+        if config.scripts_dir.is_some() {
+            keys.push(("scripts_dir".to_string(), true));
+        }
+        let mut env = config.env.keys().collect::<Vec<_>>();
+        env.sort();
+        keys.extend(env.into_iter().map(|name| (format!("env.{name}"), true)));
+        let mut hooks = config.hooks.keys().collect::<Vec<_>>();
+        hooks.sort();
+        keys.extend(hooks.into_iter().map(|name| (name.clone(), true)));
+
+        let sources = self.sources.explain(&keys);
+
+        if json {
+            serde_json::to_string_pretty(&sources)
+                .map_err(|e| ConfigError::Serialize(e.to_string()).into())
+        } else {
+            Ok(render_table(&sources))
+        }
+    }
+
+    /// Render the configuration hooksmith acts on as a readable tree: settings, then each
+    /// hook with its path-based blocks, commands and groups, in the order they run, and
+    /// their options annotated inline. Uses plain characters and colors according to the
+    /// reporter.
+    #[must_use]
+    pub fn config_tree(&self) -> String {
+        render_config_tree(
+            &self.effective(),
+            self.reporter.is_ascii(),
+            self.reporter.is_color(),
+        )
+    }
+
+    /// The configuration with defaults applied.
+    fn effective(&self) -> Config {
+        let mut config = self.config.clone();
+        config
+            .shell
+            .get_or_insert_with(|| default_shell().to_string());
+        config.color.get_or_insert(false);
+
+        config
+    }
+
+    /// Write the configuration to a file in canonical form: settings first, then hooks
+    /// sorted by name, each command in the shortest form that reads back the same.
+    /// Comments and formatting of the original file are not preserved.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path of the file to write
+    ///
+    /// # Errors
+    /// * If the configuration cannot be serialized or the file cannot be written
+    pub fn write_config(&self, config_path: &Path) -> Result<()> {
+        fs::write(config_path, self.config.to_yaml()?)?;
+
+        Ok(())
+    }
+
+    /// The reporter holding the number of warnings and errors reported so far.
+    #[must_use]
+    pub const fn reporter(&self) -> &Reporter {
+        &self.reporter
+    }
+
+    /// Set whether a missing hooks directory is created on install (the default).
+    /// When disabled, installing into a missing hooks directory fails instead.
+    ///
+    /// # Arguments
+    /// * `create_hooks_dir` - Whether to create the hooks directory if it doesn't exist
+    #[must_use]
+    pub fn with_create_hooks_dir(mut self, create_hooks_dir: bool) -> Self {
+        self.create_hooks_dir = create_hooks_dir;
+        self
+    }
+
+    /// Set whether installing overwrites hooks hooksmith didn't write, e.g. hand-written
+    /// scripts, instead of first renaming them to `<hook>.bak` (the default).
+    ///
+    /// # Arguments
+    /// * `force` - Whether to overwrite such hooks without a backup
+    #[must_use]
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Set whether installing keeps the hooks hooksmith didn't write, e.g. hand-written
+    /// scripts, by moving them to `<hook>.local`: the installed hook runs them before
+    /// hooksmith.
+    ///
+    /// # Arguments
+    /// * `append` - Whether to keep such hooks running
+    #[must_use]
+    pub fn with_append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Replace the observer notified while hooks run. The default observer prints progress
+    /// to stdout; see `HookObserver` for the callback contract.
+    ///
+    /// # Arguments
+    /// * `observer` - The observer to notify
+    pub fn set_observer(&mut self, observer: Box<dyn HookObserver>) {
+        self.observer = observer;
+    }
+
+    /// The observer notified while hooks run.
+    fn observer(&self) -> &dyn HookObserver {
+        self.observer.as_ref()
+    }
+
+    /// Warn when the configuration file parsed successfully but defines no hooks,
+    /// so that an empty config doesn't silently look like a successful no-op.
+    ///
+    /// # Arguments
+    /// * `reporter` - The reporter used to emit the warning
+    /// * `config` - Parsed configuration
+    /// * `config_path` - Path the configuration was read from, if it was read from a file
+    ///
+    /// # Returns
+    /// * `true` if the warning was emitted
+    fn warn_if_no_hooks(reporter: &Reporter, config: &Config, config_path: Option<&Path>) -> bool {
+        if !config.hooks.is_empty() {
+            return false;
+        }
+
+        let source = match config_path {
+            Some(path) => format!("configuration file '{}'", path.display()),
+            None => "configuration".to_string(),
+        };
+        reporter.warning(
+            "No hooks defined",
+            &format!(
+                "The {source} was parsed but defines no hooks.\n\nAdd at least one hook (e.g. `pre-commit:` with a `commands:` list), or run `hooksmith init`."
+            ),
+        );
+
+        true
+    }
+
+    /// The directory of `scripts_dir`, relative to the directory of the configuration file,
+    /// or to the current directory when the configuration wasn't read from a file.
+    ///
+    /// # Arguments
+    /// * `config` - Parsed configuration
+    /// * `config_path` - Path the configuration was read from, if it was read from a file
+    fn scripts_dir_path(config: &Config, config_path: Option<&Path>) -> Option<PathBuf> {
+        let dir = config.scripts_dir.as_deref()?;
+        let base = config_path
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        Some(base.join(dir))
+    }
+
+    /// Add the scripts of `scripts_dir` to the hooks they are named after: each one runs
+    /// once the inline `commands` and `groups` of its hook are done, with the hook's
+    /// arguments. Hooks only defined by a script are created. Files that aren't named
+    /// after a Git hook are left out, `validate_hooks` warns about them.
+    ///
+    /// # Arguments
+    /// * `reporter` - The reporter used to warn about a missing directory
+    /// * `config` - Parsed configuration, updated in place
+    /// * `config_path` - Path the configuration was read from, if it was read from a file
+    fn merge_hook_scripts(reporter: &Reporter, config: &mut Config, config_path: Option<&Path>) {
+        let Some(dir) = Self::scripts_dir_path(config, config_path) else {
+            return;
+        };
+
+        let Ok(entries) = fs::read_dir(&dir) else {
+            reporter.warning(
+                "Scripts directory not found",
+                &format!(
+                    "The `scripts_dir` '{}' doesn't exist or can't be read, no scripts were added to the hooks.",
+                    dir.display()
+                ),
+            );
+            return;
+        };
+
+        let mut scripts = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| GIT_HOOKS.contains(&name.as_str()))
+            .collect::<Vec<_>>();
+        scripts.sort();
+
+        for hook_name in scripts {
+            let script = dir.join(&hook_name);
+            let command = HookCommand::new_named(
+                format!("script {}", script.display()),
+                format!("{} \"$@\"", shell_quote(&script.to_string_lossy())),
+            );
+
+            let hook = config.hooks.entry(hook_name).or_default();
+            match &mut hook.groups {
+                Some(groups) if !groups.is_empty() => groups.push(vec![command]),
+                _ => hook.commands.get_or_insert_with(Vec::new).push(command),
+            }
+        }
+    }
+
+    /// Check for hooks that are in config but not installed.
+    /// Iterates through hooks in the config and checks if they are installed.
+    /// Updates the `differences_found` flag and prints messages for missing hooks.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `differences_found` - Mutable reference to track if differences were found
+    fn check_missing_hooks(&self, git_hooks_path: &Path, differences_found: &mut bool) {
+        for hook_name in self.config.hooks.keys() {
+            let hook_path = git_hooks_path.join(hook_name);
+            if !hook_path.exists() {
+                if !*differences_found {
+                    outln!("\n❌ Differences found:");
+
+                    *differences_found = true;
+                }
+
+                outln!("  - Hook '{hook_name}' is in config but not installed");
+            }
+        }
+    }
+
+    /// Check for hooks that are installed but not in config.
+    /// Scans the git hooks directory and checks if each hook is in the config.
+    /// Updates the `differences_found` flag and prints messages for extra hooks.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `differences_found` - Mutable reference to track if differences were found
+    ///
+    /// # Errors
+    /// * If there is an error reading the git hooks directory
+    fn check_extra_hooks(&self, git_hooks_path: &Path, differences_found: &mut bool) {
+        if let Ok(entries) = fs::read_dir(git_hooks_path) {
+            for entry in entries.flatten() {
+                if let Ok(file_type) = entry.file_type() {
+                    if !file_type.is_file() {
+                        continue;
+                    }
+
+                    let hook_name = entry.file_name().to_string_lossy().to_string();
+
+                    if hook_name.ends_with(".sample") {
+                        continue;
+                    }
+
+                    if !self.config.hooks.contains_key(&hook_name) {
+                        if !*differences_found {
+                            outln!("\n❌ Differences found:");
+
+                            *differences_found = true;
+                        }
+
+                        if is_managed_hook(&entry.path(), &hook_name) {
+                            outln!("  - Hook '{hook_name}' is installed but not in config");
+                        } else {
+                            outln!(
+                                "  - Hook '{hook_name}' is installed but not in config, and was not installed by hooksmith"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolve the Git hooks directory, printing where it came from in verbose mode.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be resolved, or `HOOKSMITH_HOOKS_DIR` is invalid
+    fn hooks_dir(&self) -> Result<PathBuf> {
+        self.hooks_dir_with_source().map(|(path, _)| path)
+    }
+
+    /// Resolve the Git hooks directory and where it came from, printing both in verbose
+    /// mode.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be resolved, or `HOOKSMITH_HOOKS_DIR` is invalid
+    fn hooks_dir_with_source(&self) -> Result<(PathBuf, HooksDirSource)> {
+        let (path, source) = resolve_git_hooks_path()?;
+
+        if self.verbose {
+            if source.is_custom() {
+                outln!(
+                    "📁 Hooks directory: {} (from {source}, instead of the default .git/hooks)",
+                    path.display()
+                );
+            } else {
+                outln!("📁 Hooks directory: {} (from {source})", path.display());
+            }
+        }
+
+        Ok((path, source))
+    }
+
+    /// Compare installed hooks with the configuration file.
+    ///
+    /// # Errors
+    /// * If there is an error reading the git hooks directory.
+    pub fn compare_hooks(&self) -> Result<()> {
+        let git_hooks_path = self.hooks_dir()?;
+        let mut differences_found = false;
+
+        if self.verbose {
+            outln!("🔍 Comparing installed hooks with configuration file...");
+        }
+
+        if disabled::is_disabled() {
+            outln!("⏸️  Hooks are disabled: installed hooks run nothing until `hooksmith enable`");
+        }
+
+        // Check for hooks in config but not installed
+        self.check_missing_hooks(&git_hooks_path, &mut differences_found);
+
+        // Check for installed hooks not in config
+        self.check_extra_hooks(&git_hooks_path, &mut differences_found);
+
+        if !differences_found {
+            outln!("✅ All hooks match the configuration file");
+        }
+
+        Ok(())
+    }
+
+    /// Fail if the hooks directory holds hooks hooksmith didn't install, for repositories
+    /// where hooksmith must manage every hook (`compare --fail-on-foreign`). Sample hooks
+    /// don't count.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be located
+    /// * `ValidationError::ForeignHooks` if any foreign hook was found
+    pub fn check_foreign_hooks(&self) -> Result<()> {
+        let foreign = foreign_hooks(&self.hooks_dir()?);
+
+        if foreign.is_empty() {
+            return Ok(());
+        }
+
+        self.reporter.error(
+            "Foreign hooks found",
+            &format!(
+                "These hooks were not installed by hooksmith:\n{}",
+                format_list(&foreign)
+            ),
+            "Move their commands to the configuration and run `hooksmith install`, or remove them.",
+        );
+
+        Err(ValidationError::ForeignHooks(foreign.len()).into())
+    }
+
+    /// Check that the repository matches the configuration, for CI: the configuration is
+    /// valid, every configured hook is installed with the content `install` writes, and the
+    /// scripts referenced by commands exist. Every problem is reported at once.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be located
+    /// * `ValidationError::VerificationFailed` if any problem was found
+    pub fn verify(&self) -> Result<()> {
+        let git_hooks_path = self.hooks_dir()?;
+        let root = std::env::current_dir()?;
+
+        if self.verbose {
+            outln!("🔍 Verifying hooks against the configuration file...");
+        }
+
+        let mut problems = self.verification_problems(&git_hooks_path, &root);
+        if disabled::is_disabled() {
+            problems.push(
+                "Hooks are disabled by `hooksmith disable`, run `hooksmith enable` to turn them back on"
+                    .to_string(),
+            );
+        }
+
+        if problems.is_empty() {
+            outln!(
+                "✅ {} hook(s) valid, installed and up to date",
+                self.config.hooks.len()
+            );
+
+            return Ok(());
+        }
+
+        self.reporter.error(
+            "Verification failed",
+            &format!(
+                "{} problem(s) found:\n{}",
+                problems.len(),
+                format_list(&problems)
+            ),
+            "Run `hooksmith install` to install or update the hooks, and fix the configuration for the other problems.",
+        );
+
+        Err(ValidationError::VerificationFailed(problems.len()).into())
+    }
+
+    /// Collect the problems reported by `verify`.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `root` - Directory the commands run from, to resolve the scripts they reference
+    ///
+    /// # Returns
+    /// * A description of each problem, in a stable order
+    fn verification_problems(&self, git_hooks_path: &Path, root: &Path) -> Vec<String> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut problems = Vec::new();
+
+        for hook_name in &hook_names {
+            if !GIT_HOOKS.contains(&hook_name.as_str()) {
+                problems.push(format!("'{hook_name}' is not a Git hook"));
+            }
+        }
+        problems.extend(self.unknown_placeholder_usages());
+
+        for hook_name in &hook_names {
+            let hook_path = git_hooks_path.join(hook_name);
+            match Self::installed_hook_content_in(git_hooks_path, hook_name) {
+                Ok(None) | Err(_) => problems.push(format!("Hook '{hook_name}' is not installed")),
+                Ok(Some(content)) if content != Self::hook_content_at(&hook_path, hook_name) => {
+                    problems.push(format!(
+                        "Hook '{hook_name}' is installed but out of date, or was not installed by hooksmith"
+                    ));
+                }
+                Ok(_) => {}
+            }
+        }
+
+        for hook_name in &hook_names {
+            let hook = &self.config.hooks[*hook_name];
+            let mut scoped_commands: Vec<(Option<&str>, &HookCommand)> = hook
+                .paths
+                .iter()
+                .flat_map(|paths| paths.values())
+                .flat_map(|path_cfg| {
+                    path_cfg
+                        .commands
+                        .iter()
+                        .map(|command| (path_cfg.working_directory.as_deref(), command))
+                })
+                .collect();
+            scoped_commands.extend(
+                hook.commands
+                    .iter()
+                    .flatten()
+                    .chain(hook.groups.iter().flatten().flatten())
+                    .map(|command| (None, command)),
+            );
+
+            for (working_directory, hook_command) in scoped_commands {
+                let Some(script) = referenced_script(&hook_command.command) else {
+                    continue;
+                };
+
+                let directory = hook_command
+                    .working_dir
+                    .as_deref()
+                    .or(working_directory)
+                    .map_or_else(|| root.to_path_buf(), |dir| root.join(dir));
+                let path = directory.join(script);
+                if !path.is_file() {
+                    problems.push(format!(
+                        "'{hook_name}' command `{}` references `{script}`, which does not exist",
+                        hook_command.command
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Creates the git hooks directory if it doesn't exist.
+    /// Handles both normal and dry run modes.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    ///
+    /// # Errors
+    /// * If the directory doesn't exist and automatic creation is disabled
+    /// * If the directory cannot be created
+    fn ensure_hooks_directory(&self, git_hooks_path: &Path) -> Result<()> {
+        if !git_hooks_path.exists() {
+            if !self.create_hooks_dir {
+                self.reporter.error(
+                    "Hooks directory not found",
+                    &format!(
+                        "The hooks directory '{}' does not exist and automatic creation is disabled.",
+                        git_hooks_path.display()
+                    ),
+                    "Create the directory (or fix core.hooksPath), or install without --no-create-dir.",
+                );
+
+                return Err(GitError::HooksDirNotFound.into());
+            }
+
+            if self.dry_run {
+                outln!("🪝 Skipping creation of .git/hooks directory in dry run mode");
+            } else {
+                if self.verbose {
+                    outln!("  - Creating .git/hooks directory...");
+                }
+                fs::create_dir_all(git_hooks_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates configuration content for a specific hook type
+    ///
+    /// # Arguments
+    /// * `hook` - The name of the hook to generate configuration for
+    ///
+    /// # Returns
+    /// * `String` - The generated configuration content for the hook
+    fn generate_hook_config(hook: &str) -> String {
+        let mut config = String::new();
+        config.push_str(hook);
+        config.push_str(":\n");
+        config.push_str("  commands:\n");
+
+        // Add hook-specific default commands and comments
+        let (echo_msg, examples) = match hook {
+            "pre-commit" => (
+                "Running pre-commit checks...",
+                vec![
+                    "# Add your pre-commit commands here",
+                    "# Examples:",
+                    "# - cargo fmt --all -- --check",
+                    "# - cargo clippy -- --deny warnings",
+                ],
+            ),
+            "pre-push" => (
+                "Running pre-push checks...",
+                vec![
+                    "# Add your pre-push commands here",
+                    "# Examples:",
+                    "# - cargo test",
+                    "# - cargo build --release",
+                ],
+            ),
+            "commit-msg" => (
+                "Validating commit message...",
+                vec![
+                    "# Add your commit message validation here",
+                    "# Example:",
+                    "# - ./scripts/validate-commit-msg.sh $1",
+                ],
+            ),
+            "post-commit" => (
+                "Post-commit actions...",
+                vec!["# Add your post-commit commands here"],
+            ),
+            _ => (
+                &format!("Running {hook} hook...")[..],
+                vec!["# Add your commands here"],
+            ),
+        };
+
+        config.push_str(&format!("    - echo \"{echo_msg}\"\n")[..]);
+
+        for example in examples {
+            config.push_str(&format!("    {example}\n")[..]);
+        }
+
+        config.push('\n');
+
+        config
+    }
+
+    /// Print a description of a standard git hook: when git triggers it, the arguments
+    /// it receives and whether a non-zero exit aborts the git operation.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook to explain
+    ///
+    /// # Errors
+    /// * If the hook is not a standard git hook
+    pub fn explain_hook(hook_name: &str) -> Result<()> {
+        Self::explain_hook_with_reporter(hook_name, &Reporter::default())
+    }
+
+    /// Print a description of a standard git hook, reporting an unknown hook through the
+    /// given reporter. See `explain_hook`.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the git hook to explain
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the hook is not a standard git hook
+    pub fn explain_hook_with_reporter(hook_name: &str, reporter: &Reporter) -> Result<()> {
+        let Some(doc) = find_hook_doc(hook_name) else {
+            let suggestions = suggest_hook_names(hook_name);
+            let suggestion = if suggestions.is_empty() {
+                format!("Known Git hooks:\n{}", format_list(&GIT_HOOKS))
+            } else {
+                format!("Did you mean:\n{}", format_list(&suggestions))
+            };
+
+            reporter.error(
+                "Unknown hook",
+                &format!("'{hook_name}' is not a standard Git hook."),
+                &suggestion,
+            );
+
+            return Err(ValidationError::InvalidHookName(hook_name.to_string()).into());
+        };
+
+        outln!("📖 {}\n", doc.name);
+        outln!("{}\n", doc.description);
+        outln!("Arguments: {}", doc.arguments);
+
+        let placeholders = hook_argument_names(hook_name);
+        if !placeholders.is_empty() {
+            let placeholders = placeholders
+                .iter()
+                .map(|name| format!("{{{name}}}"))
+                .collect::<Vec<_>>();
+            outln!("Placeholders: {}", placeholders.join(", "));
+        }
+
+        if doc.can_abort {
+            outln!("Non-zero exit: aborts the operation");
+        } else {
+            outln!("Non-zero exit: does not affect the outcome of the operation");
+        }
+
+        Ok(())
+    }
+
+    /// Remove the markers recorded by `--since-last-run`, so that the next runs process all files.
+    ///
+    /// # Errors
+    /// * If the current directory is not inside a Git repository
+    /// * If a marker cannot be removed
+    pub fn clear_run_markers() -> Result<()> {
+        let cleared = run_state::clear_markers(Path::new("."))?;
+
+        if cleared.is_empty() {
+            outln!("No recorded hook runs to clear");
+        } else {
+            outln!("🧹 Cleared the last successful run of:");
+            outln!("{}", format_list(&cleared));
+        }
+
+        Ok(())
+    }
+
+    /// Turn all hooks off without uninstalling them: until `enable_hooks`, hook runs print a
+    /// notice and run nothing.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Whether to only print what would be done
+    ///
+    /// # Errors
+    /// * If the current directory is not inside a Git repository
+    /// * If the marker cannot be written
+    pub fn disable_hooks(dry_run: bool) -> Result<()> {
+        let path = disabled::marker_path()?;
+
+        if dry_run {
+            outln!("🚧 Dry run: Would create {}", path.display());
+        } else if disabled::disable()? {
+            outln!(
+                "⏸️  Hooks disabled ({} created), run `hooksmith enable` to turn them back on",
+                path.display()
+            );
+        } else {
+            outln!("Hooks are already disabled ({} exists)", path.display());
+        }
+
+        Ok(())
+    }
+
+    /// Turn hooks back on after `disable_hooks`.
+    ///
+    /// # Arguments
+    /// * `dry_run` - Whether to only print what would be done
+    ///
+    /// # Errors
+    /// * If the current directory is not inside a Git repository
+    /// * If the marker cannot be removed
+    pub fn enable_hooks(dry_run: bool) -> Result<()> {
+        let path = disabled::marker_path()?;
+
+        if dry_run {
+            outln!("🚧 Dry run: Would remove {}", path.display());
+        } else if disabled::enable()? {
+            outln!("▶️  Hooks enabled ({} removed)", path.display());
+        } else {
+            outln!("Hooks are not disabled");
+        }
+
+        Ok(())
+    }
+
+    /// Initialize hooksmith configuration interactively.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path where the configuration file will be created
+    /// * `append` - Whether to add the selected hooks to an existing configuration file
+    ///   instead of overwriting it. Hooks it already defines are left unchanged.
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    ///
+    /// # Errors
+    /// * If the user cancels the selection
+    /// * If the existing configuration file cannot be read or parsed (with `append`)
+    /// * If there's an error writing the configuration file
+    pub fn init_interactive(
+        config_path: &Path,
+        append: bool,
+        dry_run: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        if dry_run {
+            outln!("🔄 DRY RUN MODE - No files will be created\n");
+        }
+
+        if verbose {
+            outln!("🚀 Initializing hooksmith configuration...");
+        }
+
+        let append = append && config_path.exists();
+        let existing_config = if append {
+            Some(Self::read_config(config_path)?)
+        } else {
+            if !Self::confirm_overwrite(config_path, dry_run)? {
+                return Ok(());
+            }
+
+            None
+        };
+
+        // Get all available Git hooks
+        let hook_options: Vec<String> = GIT_HOOKS.iter().map(|&s| s.to_string()).collect();
+        let items = hook_options
+            .iter()
+            .map(|hook| selection_item(hook, find_hook_doc(hook).map(HookDoc::summary)))
+            .collect::<Vec<_>>();
+
+        // Interactive hook selection
+        let selections = MultiSelect::with_theme(&my_clap_theme::ColorfulTheme::default())
+            .with_prompt("Select hooks to configure (Space to select, Enter to confirm)")
+            .items(&items)
+            .interact()
+            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+        if selections.is_empty() {
+            outln!("❌ No hooks selected. Configuration file not created.");
+            return Ok(());
+        }
+
+        let selected_hooks: Vec<String> = selections
+            .into_iter()
+            .map(|i| hook_options[i].clone())
+            .collect();
+
+        if verbose {
+            outln!("📝 Selected hooks: {}", selected_hooks.join(", "));
+        }
+
+        let Some(existing_config) = existing_config else {
+            // Create configuration content
+            let config_content: String = selected_hooks
+                .iter()
+                .map(|hook| Self::generate_hook_config(hook))
+                .collect();
+
+            return Self::write_initial_config(config_path, &config_content, dry_run);
+        };
+
+        let (existing_hooks, new_hooks): (Vec<String>, Vec<String>) = selected_hooks
+            .into_iter()
+            .partition(|hook| existing_config.hooks.contains_key(hook));
+
+        if !existing_hooks.is_empty() {
+            Reporter::default().warning(
+                "Hooks left unchanged",
+                &format!(
+                    "The following hooks are already configured in '{}' and were not modified:\n{}",
+                    config_path.display(),
+                    format_list(&existing_hooks)
+                ),
+            );
+        }
+
+        if new_hooks.is_empty() {
+            outln!("❌ No new hooks selected. Configuration file not modified.");
+            return Ok(());
+        }
+
+        let config_content =
+            Self::append_hook_configs(&fs::read_to_string(config_path)?, &new_hooks);
+
+        if dry_run {
+            outln!(
+                "🔍 Would add {} to configuration file '{}', resulting in:",
+                new_hooks.join(", "),
+                config_path.display()
+            );
+            outln!("{config_content}");
+        } else {
+            fs::write(config_path, config_content)?;
+            outln!(
+                "✅ Added {} to configuration file '{}'",
+                new_hooks.join(", "),
+                config_path.display()
+            );
+            outln!("🚀 Run 'hooksmith install' to install the new hooks.");
+        }
+
+        Ok(())
+    }
+
+    /// Append the default configuration of new hooks to an existing configuration,
+    /// keeping its content (including comments and formatting) untouched.
+    ///
+    /// # Arguments
+    /// * `existing` - Content of the existing configuration file
+    /// * `hooks` - Hooks to add, none of which is already configured
+    fn append_hook_configs(existing: &str, hooks: &[String]) -> String {
+        let mut config = existing.to_string();
+
+        if !config.is_empty() && !config.ends_with('\n') {
+            config.push('\n');
+        }
+        if !config.trim().is_empty() && !config.ends_with("\n\n") {
+            config.push('\n');
+        }
+
+        for hook in hooks {
+            config.push_str(&Self::generate_hook_config(hook));
+        }
+
+        config
+    }
+
+    /// Comment out the blocks of the given top-level keys of a configuration, keeping the
+    /// rest of its content (including comments and formatting) untouched. A block spans
+    /// from its key to the next top-level line.
+    ///
+    /// # Arguments
+    /// * `content` - Content of the configuration file
+    /// * `hook_names` - Top-level keys whose blocks are commented out
+    ///
+    /// # Returns
+    /// * The new content, and the keys that were found in it
+    fn comment_out_hooks(content: &str, hook_names: &[String]) -> (String, Vec<String>) {
+        let mut pruned = String::with_capacity(content.len());
+        let mut found = Vec::new();
+        let mut in_pruned_block = false;
+
+        for line in content.split_inclusive('\n') {
+            let is_top_level = !line.starts_with([' ', '\t', '#', '\n', '\r']);
+
+            if is_top_level {
+                let key = line
+                    .split_once(':')
+                    .map(|(key, _)| key.trim().trim_matches(['"', '\'']));
+                in_pruned_block = key.is_some_and(|key| hook_names.iter().any(|name| name == key));
+
+                if let Some(key) = key.filter(|_| in_pruned_block) {
+                    pruned.push_str(&format!(
+                        "# Commented out by `hooksmith prune-config`: '{key}' is not a Git hook\n"
+                    ));
+                    found.push(key.to_string());
+                }
+            }
+
+            if in_pruned_block && !line.trim().is_empty() {
+                pruned.push_str("# ");
+            }
+            pruned.push_str(line);
+        }
+
+        (pruned, found)
+    }
+
+    /// Initialize hooksmith configuration from a built-in template.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path where the configuration file should be created
+    /// * `template_name` - Name of the template (e.g. `rust`, `node`)
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    ///
+    /// # Errors
+    /// * If the template doesn't exist
+    /// * If the configuration file cannot be written
+    pub fn init_from_template(
+        config_path: &Path,
+        template_name: &str,
+        dry_run: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        Self::init_from_template_with_reporter(
+            config_path,
+            template_name,
+            dry_run,
+            verbose,
+            &Reporter::default(),
+        )
+    }
+
+    /// Initialize hooksmith configuration from a built-in template, reporting an unknown
+    /// template through the given reporter. See `init_from_template`.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path where the configuration file should be created
+    /// * `template_name` - Name of the template (e.g. `rust`, `node`)
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the template doesn't exist
+    /// * If the configuration file cannot be written
+    pub fn init_from_template_with_reporter(
+        config_path: &Path,
+        template_name: &str,
+        dry_run: bool,
+        verbose: bool,
+        reporter: &Reporter,
+    ) -> Result<()> {
+        let Some(template) = find_template(template_name) else {
+            let templates = TEMPLATES
+                .iter()
+                .map(|template| format!("{}: {}", template.name, template.description))
+                .collect::<Vec<_>>();
+
+            reporter.error(
+                "Unknown template",
+                &format!("'{template_name}' is not a built-in template."),
+                &format!("Available templates:\n{}", format_list(&templates)),
+            );
+
+            return Err(ConfigError::UnknownTemplate(template_name.to_string()).into());
+        };
+
+        if dry_run {
+            outln!("🔄 DRY RUN MODE - No files will be created\n");
+        }
+
+        if verbose {
+            outln!(
+                "🚀 Initializing hooksmith configuration from the '{}' template...",
+                template.name
+            );
+        }
+
+        if !Self::confirm_overwrite(config_path, dry_run)? {
+            return Ok(());
+        }
+
+        Self::write_initial_config(config_path, template.content, dry_run)
+    }
+
+    /// Create a hooksmith configuration from a `.pre-commit-config.yaml` of the pre-commit
+    /// framework, see `pre_commit_import` for how it is converted. What can't be converted
+    /// is commented out in the configuration and listed in a warning.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path where the configuration file should be created
+    /// * `from` - Path of the pre-commit configuration file
+    /// * `dry_run` - Whether to run in dry run mode
+    /// * `verbose` - Whether to print verbose output
+    /// * `reporter` - The reporter used for user-facing messages
+    ///
+    /// # Errors
+    /// * If the pre-commit configuration cannot be read or parsed
+    /// * If the configuration file cannot be written
+    pub fn import_pre_commit_config(
+        config_path: &Path,
+        from: &Path,
+        dry_run: bool,
+        verbose: bool,
+        reporter: &Reporter,
+    ) -> Result<()> {
+        let content = fs::read_to_string(from).map_err(ConfigError::Io)?;
+        let imported = import_pre_commit_config(&content, &from.display().to_string())
+            .map_err(ConfigError::Parse)?;
+
+        if dry_run {
+            outln!("🔄 DRY RUN MODE - No files will be created\n");
+        }
+
+        if verbose {
+            outln!(
+                "🚀 Importing the pre-commit configuration from {}...",
+                from.display()
+            );
+        }
+
+        if !imported.unsupported.is_empty() {
+            reporter.warning(
+                "Settings not imported",
+                &format!(
+                    "hooksmith has no equivalent for these settings, they are commented out in the configuration:\n{}",
+                    format_list(&imported.unsupported)
+                ),
+            );
+        }
+
+        if !Self::confirm_overwrite(config_path, dry_run)? {
+            return Ok(());
+        }
+
+        Self::write_initial_config(config_path, &imported.content, dry_run)
+    }
+
+    /// Ask whether an existing configuration file should be overwritten.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path of the configuration file
+    /// * `dry_run` - Whether to run in dry run mode (nothing is overwritten, so nothing is asked)
+    ///
+    /// # Errors
+    /// * If the prompt fails
+    ///
+    /// # Returns
+    /// * `true` if initialization can proceed
+    fn confirm_overwrite(config_path: &Path, dry_run: bool) -> Result<bool> {
+        if !config_path.exists() || dry_run {
+            return Ok(true);
+        }
+
+        let overwrite = Confirm::with_theme(&my_clap_theme::ColorfulTheme::default())
+            .with_prompt(format!(
+                "Configuration file '{}' already exists. Overwrite?",
+                config_path.display()
+            ))
+            .default(false)
+            .interact()
+            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+        if !overwrite {
+            outln!("❌ Initialization cancelled");
+        }
+
+        Ok(overwrite)
+    }
+
+    /// Write a new configuration file, or print it in dry run mode.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path of the configuration file
+    /// * `config_content` - Content of the configuration file
+    /// * `dry_run` - Whether to run in dry run mode
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be written
+    fn write_initial_config(config_path: &Path, config_content: &str, dry_run: bool) -> Result<()> {
+        if dry_run {
+            outln!(
+                "🔍 Would create configuration file '{}' with content:",
+                config_path.display()
+            );
+            outln!("{config_content}");
+        } else {
+            fs::write(config_path, config_content)?;
+            outln!(
+                "✅ Configuration file '{}' created successfully!",
+                config_path.display()
+            );
+            outln!("📝 You can now edit the file to customize your hook commands.");
+            outln!("🚀 Run 'hooksmith install' to install the configured hooks.");
+        }
+
+        Ok(())
+    }
+
+    /// The script `hooksmith install` writes for a hook, to compare with what is installed
+    /// (see `installed_hook_content`). A hook `install --append` chained to an existing
+    /// script gets a different one, see `generate_hook_content`.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook
+    #[must_use]
+    pub fn expected_hook_content(hook_name: &str) -> String {
+        Self::generate_hook_content(hook_name, false)
+    }
+
+    /// The script `hooksmith install` writes at a path: it runs the `<hook>.local` script
+    /// first when one sits next to it, i.e. when `install --append` moved the hook that was
+    /// there.
+    ///
+    /// # Arguments
+    /// * `hook_path` - Path of the hook file
+    /// * `hook_name` - Name of the hook
+    fn hook_content_at(hook_path: &Path, hook_name: &str) -> String {
+        Self::generate_hook_content(hook_name, local_hook_path(hook_path).is_file())
+    }
+
+    /// Read the installed script of a hook, from the hooks directory Git uses (or
+    /// `HOOKSMITH_HOOKS_DIR`).
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be resolved
+    /// * If the hook file exists but cannot be read
+    ///
+    /// # Returns
+    /// * The content of the script, `None` if the hook is not installed
+    ///
+    /// # Example
+    /// ```no_run
+    /// use hooksmith::Hooksmith;
+    /// use std::path::Path;
+    ///
+    /// let hs = Hooksmith::new_from_config(Path::new("hooksmith.yaml"), false, false)?;
+    /// let up_to_date = hs.installed_hook_content("pre-commit")?.as_deref()
+    ///     == Some(Hooksmith::expected_hook_content("pre-commit").as_str());
+    /// # Ok::<(), hooksmith::HooksmithError>(())
+    /// ```
+    pub fn installed_hook_content(&self, hook_name: &str) -> Result<Option<String>> {
+        Self::installed_hook_content_in(&get_git_hooks_path()?, hook_name)
+    }
+
+    /// Read the installed script of a hook from the given hooks directory, see
+    /// `installed_hook_content`.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `hook_name` - Name of the hook
+    ///
+    /// # Errors
+    /// * If the hook file exists but cannot be read
+    fn installed_hook_content_in(git_hooks_path: &Path, hook_name: &str) -> Result<Option<String>> {
+        match fs::read_to_string(git_hooks_path.join(hook_name)) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Generates the hook script content.
+    /// Creates a shell script that checks for hooksmith and runs the specified hook,
+    /// forwarding the arguments Git passes to the hook (e.g. the message file of
+    /// `commit-msg`), which commands receive as `$1`, `$2`, ...
+    ///
+    /// Scripts of server-side hooks don't install hooksmith with `cargo install` when it is
+    /// missing, which would happen on the server while a push waits; they reject it with a
+    /// message instead.
+    ///
+    /// A chained script first runs the `<hook>.local` script next to it, the hook that was
+    /// there before `install --append`, and stops with its status if it fails.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to create content for
+    /// * `chained` - Whether to run the `<hook>.local` script first
+    fn generate_hook_content(hook_name: &str, chained: bool) -> String {
+        let local_hook = if chained {
+            Self::local_hook_content(hook_name)
+        } else {
+            ""
+        };
+
+        if is_server_hook(hook_name) {
+            return format!(
+                "#!/bin/sh\n
+{local_hook}    if hooksmith -h >/dev/null 2>&1
+    then
+      exec hooksmith run {hook_name} -- \"$@\"
+    else
+      echo \"hooksmith is not installed on this server, cannot run the {hook_name} hook\" >&2
+      exit 1
+    fi"
+            );
+        }
+
+        format!(
+            "#!/bin/sh\n
+{local_hook}    if hooksmith -h >/dev/null 2>&1
+    then
+      exec hooksmith run {hook_name} -- \"$@\"
+    else
+      cargo install hooksmith
+      exec hooksmith run {hook_name} -- \"$@\"
+    fi"
+        )
+    }
+
+    /// The part of a chained hook script running the `<hook>.local` script next to it.
+    /// For hooks Git gives input on stdin, the input is saved to a temporary file first,
+    /// so that both the local script and hooksmith read all of it.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook
+    fn local_hook_content(hook_name: &str) -> &'static str {
+        if !INPUT_HOOKS.contains(&hook_name) {
+            return "    if [ -x \"$0.local\" ]
+    then
+      \"$0.local\" \"$@\" || exit $?
+    fi
+";
+        }
+
+        "    if [ -x \"$0.local\" ]
+    then
+      input=$(mktemp) || exit 1
+      cat >\"$input\"
+      exec 3<\"$input\" <\"$input\"
+      rm -f \"$input\"
+      \"$0.local\" \"$@\" || exit $?
+      exec <&3 3<&-
+    fi
+"
+    }
+
+    /// Writes the hook file and sets appropriate permissions.
+    /// Handles both normal and dry run modes.
+    ///
+    /// An existing hook hooksmith didn't write is renamed to `<hook>.bak` first, unless
+    /// `force` is set, so that installing doesn't lose a hand-written script. A hook of
+    /// another hook manager (husky, lefthook, pre-commit) is only replaced once the user
+    /// confirms it, and left in place when there is no terminal to ask on.
+    ///
+    /// # Arguments
+    /// * `hook_path` - Path where the hook file should be written
+    /// * `hook_name` - Name of the hook being installed
+    ///
+    /// # Errors
+    /// * If the prompt fails
+    /// * If an existing hook cannot be backed up
+    /// * If the file cannot be written
+    /// * If permissions cannot be set
+    ///
+    /// # Returns
+    /// * `false` if an existing hook was left in place
+    fn write_hook_file(&self, hook_path: &Path, hook_name: &str) -> Result<bool> {
+        let mut origin = HookOrigin::of_file(hook_path, hook_name);
+        let mut exists = hook_path.is_file();
+
+        if self.append && exists && origin != Some(HookOrigin::Hooksmith) {
+            self.keep_as_local_hook(hook_path, hook_name)?;
+            origin = None;
+            exists = false;
+        }
+
+        if let Some(origin) = origin.filter(|origin| !self.force && origin.is_other_manager()) {
+            if self.dry_run {
+                outln!("🪝 Would ask before replacing the {hook_name} hook installed by {origin}");
+            } else if !self.confirm_replace_hook(hook_name, origin)? {
+                return Ok(false);
+            }
+        }
+
+        let backup_path = (!self.force && exists && origin != Some(HookOrigin::Hooksmith))
+            .then(|| backup_path(hook_path));
+
+        if self.dry_run {
+            if let Some(backup_path) = &backup_path {
+                outln!(
+                    "🪝 Would back up the existing {hook_name} hook to {}",
+                    backup_path.display()
+                );
+            }
+            outln!("🪝 Skipping installation of {hook_name} hook in dry run mode");
+            return Ok(true);
+        }
+
+        if let Some(backup_path) = backup_path {
+            fs::rename(hook_path, &backup_path)?;
+            self.reporter.warning(
+                "Existing hook backed up",
+                &format!(
+                    "The {hook_name} hook wasn't installed by hooksmith, it was moved to {} before installing. Use `hooksmith install --force` to overwrite such hooks instead.",
+                    backup_path.display()
+                ),
+            );
+        }
+
+        if self.verbose {
+            outln!("  - Installing {hook_name} file...");
+        }
+
+        write_executable(hook_path, &Self::hook_content_at(hook_path, hook_name))?;
+
+        Ok(true)
+    }
+
+    /// Move a hook hooksmith didn't write to `<hook>.local`, which the installed hook runs
+    /// before hooksmith. An earlier `<hook>.local` is backed up to `<hook>.local.bak`.
+    ///
+    /// # Arguments
+    /// * `hook_path` - Path of the hook file
+    /// * `hook_name` - Name of the hook
+    ///
+    /// # Errors
+    /// * If a file cannot be moved
+    fn keep_as_local_hook(&self, hook_path: &Path, hook_name: &str) -> Result<()> {
+        let local_path = local_hook_path(hook_path);
+
+        if self.dry_run {
+            outln!(
+                "🪝 Would move the existing {hook_name} hook to {}, to run it before hooksmith",
+                local_path.display()
+            );
+            return Ok(());
+        }
+
+        if local_path.exists() {
+            let backup_path = backup_path(&local_path);
+            fs::rename(&local_path, &backup_path)?;
+            self.reporter.warning(
+                "Existing local hook backed up",
+                &format!(
+                    "{} was moved to {} to make room for the current {hook_name} hook.",
+                    local_path.display(),
+                    backup_path.display()
+                ),
+            );
+        }
+
+        fs::rename(hook_path, &local_path)?;
+        outln!(
+            "🔗 Moved the existing {hook_name} hook to {}, it runs before hooksmith",
+            local_path.display()
+        );
+
+        Ok(())
+    }
+
+    /// Ask whether a hook installed by another hook manager should be replaced. Without a
+    /// terminal to ask on, it isn't, with a warning.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook
+    /// * `origin` - The hook manager that installed it
+    ///
+    /// # Errors
+    /// * If the prompt fails
+    ///
+    /// # Returns
+    /// * `true` if the hook can be replaced
+    fn confirm_replace_hook(&self, hook_name: &str, origin: HookOrigin) -> Result<bool> {
+        if !std::io::stdin().is_terminal() {
+            self.reporter.warning(
+                &format!("{hook_name} hook managed by {origin}"),
+                &format!(
+                    "The {hook_name} hook was installed by {origin}, it was left in place. Run `hooksmith install` in a terminal to be asked, or `hooksmith install --force` to replace it."
+                ),
+            );
+            return Ok(false);
+        }
+
+        let replace = Confirm::with_theme(&my_clap_theme::ColorfulTheme::default())
+            .with_prompt(format!(
+                "The {hook_name} hook was installed by {origin}. Replace it with hooksmith's?"
+            ))
+            .default(false)
+            .interact()
+            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+        if !replace {
+            outln!("⏭️  Left the {origin} {hook_name} hook in place");
+        }
+
+        Ok(replace)
+    }
+
+    /// Install a single, given hook.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to install
+    ///
+    /// # Errors
+    /// * If the `.git/hooks` directory cannot be created
+    /// * If the hook cannot be installed/given permission
+    pub fn install_hook(&self, hook_name: &str) -> Result<()> {
+        self.install_hook_file(hook_name).map(|_| ())
+    }
+
+    /// Install a single, given hook, unless the user chose to leave the hook another hook
+    /// manager installed in place.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to install
+    ///
+    /// # Errors
+    /// * If the `.git/hooks` directory cannot be created
+    /// * If the hook cannot be installed/given permission
+    ///
+    /// # Returns
+    /// * `false` if the existing hook was left in place
+    fn install_hook_file(&self, hook_name: &str) -> Result<bool> {
+        if self.verbose && !self.dry_run {
+            outln!("🪝 Installing {hook_name} hook...");
+        }
+
+        let git_hooks_path = get_git_hooks_path()?;
+        self.ensure_hooks_directory(&git_hooks_path)?;
+
+        let hook_path = git_hooks_path.join(hook_name);
+        if !self.write_hook_file(&hook_path, hook_name)? {
+            return Ok(false);
+        }
+
+        if self.verbose {
+            outln!("  ✅ Installed {hook_name} file");
+        }
+
+        Ok(true)
+    }
+
+    /// Install all hooks.
+    ///
+    /// # Errors
+    /// * If the `.git/hooks` directory cannot be created
+    ///
+    /// # Arguments
+    /// * `config` - Parsed configuration file
+    pub fn install_hooks(&self) -> Result<()> {
+        self.validate_hooks()?;
+
+        let (git_hooks_path, source) = self.hooks_dir_with_source()?;
+
+        if source == HooksDirSource::CoreHooksPath && is_outside_working_tree(&git_hooks_path) {
+            self.reporter.warning(
+                "Hooks directory outside the repository",
+                &format!(
+                    "`core.hooksPath` points to {}, outside of the repository: the installed hooks aren't versioned with it, and also run for any other repository using this directory.",
+                    git_hooks_path.display()
+                ),
+            );
+        }
+
+        if !check_for_git_hooks() {
+            self.ensure_hooks_directory(&git_hooks_path)?;
+        }
+
+        if self.verbose {
+            outln!("🪝 Installing hooks...");
+        }
+
+        self.warn_about_server_hooks();
+
+        let mut left_in_place = 0;
+        for hook_name in self.config.hooks.keys() {
+            if !self.install_hook_file(hook_name)? {
+                left_in_place += 1;
+            }
+        }
+
+        if !self.dry_run {
+            let installed = self.config.hooks.len() - left_in_place;
+            if left_in_place == 0 {
+                outln!("Installed {installed} hook(s) successfully.");
+            } else {
+                outln!(
+                    "Installed {installed} hook(s) successfully, left {left_in_place} in place."
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the configured hooks, whether each one is installed, and their commands in the
+    /// order they run, for a quick inventory without opening the configuration.
+    ///
+    /// # Arguments
+    /// * `installed_only` - Whether to leave out the hooks hooksmith hasn't installed
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be resolved
+    /// * If an installed script cannot be read
+    pub fn list_hooks(&self, installed_only: bool) -> Result<()> {
+        let git_hooks_path = self.hooks_dir()?;
+
+        if disabled::is_disabled() {
+            outln!("⏸️  Hooks are disabled: installed hooks run nothing until `hooksmith enable`");
+        }
+
+        let listing = self.hook_listing_in(&git_hooks_path, installed_only)?;
+        for hook in &listing {
+            outln!("{} ({})", hook.name, hook.state);
+            if let Some(description) = &hook.description {
+                outln!("  {}", style(description).dim());
+            }
+            if !hook.commands.is_empty() {
+                outln!("{}", format_list(&hook.commands));
+            }
+        }
+
+        let installed = listing.iter().filter(|hook| hook.installed).count();
+        self.reporter.success(
+            "Hooks listed",
+            &format!(
+                "{} hook(s) configured, {installed} installed",
+                self.config.hooks.len()
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Describe each configured hook of the given hooks directory, sorted by name, see
+    /// `list_hooks`.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `installed_only` - Whether to leave out the hooks hooksmith hasn't installed
+    ///
+    /// # Errors
+    /// * If an installed script cannot be read
+    fn hook_listing_in(
+        &self,
+        git_hooks_path: &Path,
+        installed_only: bool,
+    ) -> Result<Vec<ListedHook>> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut listing = Vec::new();
+        for hook_name in hook_names {
+            // As `upgrade` tells them apart: scripts hooksmith wrote, current or not, and
+            // scripts it didn't write
+            let hook_path = git_hooks_path.join(hook_name);
+            let (installed, state) =
+                match Self::installed_hook_content_in(git_hooks_path, hook_name)? {
+                    Some(content) if content == Self::hook_content_at(&hook_path, hook_name) => {
+                        (true, "installed")
+                    }
+                    Some(_) if is_managed_hook(&hook_path, hook_name) => {
+                        (true, "installed, outdated: run `hooksmith upgrade`")
+                    }
+                    Some(_) => (false, "not installed, another script is in place"),
+                    None => (false, "not installed"),
+                };
+            if installed_only && !installed {
+                continue;
+            }
+
+            let commands = self.config.hooks[hook_name]
+                .commands_in_run_order()
+                .map(|command| match &command.name {
+                    Some(name) => format!("{name}: {}", command.command),
+                    None => command.command.clone(),
+                })
+                .collect();
+            listing.push(ListedHook {
+                name: hook_name.clone(),
+                installed,
+                state,
+                description: self.config.hooks[hook_name].description.clone(),
+                commands,
+            });
+        }
+
+        Ok(listing)
+    }
+
+    /// Diagnose why hooks may not fire, printing a checklist: whether the current directory
+    /// is inside a Git repository, where the hooks directory is and whether it is
+    /// overridden, whether it is writable, whether each configured hook is a Git hook and
+    /// is installed as an executable script, and whether hooks are disabled.
+    ///
+    /// # Errors
+    /// * `ValidationError::SetupProblems` if a check failed: hooks can't fire, or hooksmith
+    ///   can't install them
+    pub fn doctor(&self) -> Result<()> {
+        let mut checks = Vec::new();
+
+        if is_inside_git_repo() {
+            checks.push(DoctorCheck::new(
+                CheckStatus::Pass,
+                "Inside a Git repository",
+            ));
+
+            match resolve_git_hooks_path() {
+                Ok((path, source)) => {
+                    checks.push(Self::hooks_dir_check(&path, source));
+                    checks.extend(Self::hooks_dir_writable_check(&path));
+                    checks.extend(self.hook_file_checks(&path));
+                }
+                Err(e) => checks.push(DoctorCheck::new(
+                    CheckStatus::Fail,
+                    format!("The hooks directory can't be located: {e}"),
+                )),
+            }
+        } else {
+            checks.push(DoctorCheck::new(
+                CheckStatus::Fail,
+                "Not inside a Git repository: `cd` into one, or create one with `git init`",
+            ));
+        }
+
+        if disabled::is_disabled() {
+            checks.push(DoctorCheck::new(
+                CheckStatus::Warn,
+                "Hooks are disabled by `hooksmith disable`: run `hooksmith enable` to turn them back on",
+            ));
+        }
+
+        for check in &checks {
+            outln!("{check}");
+        }
+
+        let count = |status| checks.iter().filter(|check| check.status == status).count();
+        let (warnings, failures) = (count(CheckStatus::Warn), count(CheckStatus::Fail));
+        if failures > 0 {
+            self.reporter.error(
+                "Setup problems found",
+                &format!("{failures} check(s) failed, {warnings} warning(s)"),
+                "Fix the problems marked ❌, then run `hooksmith doctor` again.",
+            );
+
+            return Err(ValidationError::SetupProblems(failures).into());
+        }
+
+        self.reporter.success(
+            "Setup checked",
+            &format!(
+                "{} check(s) passed, {warnings} warning(s)",
+                checks.len() - warnings
+            ),
+        );
+
+        Ok(())
+    }
+
+    /// Report where the hooks directory is, warning when it isn't the default one.
+    ///
+    /// # Arguments
+    /// * `path` - The resolved hooks directory
+    /// * `source` - Where it was resolved from
+    fn hooks_dir_check(path: &Path, source: HooksDirSource) -> DoctorCheck {
+        match source {
+            HooksDirSource::GitDir => DoctorCheck::new(
+                CheckStatus::Pass,
+                format!("Hooks directory: {}", path.display()),
+            ),
+            HooksDirSource::CoreHooksPath => DoctorCheck::new(
+                CheckStatus::Warn,
+                format!(
+                    "`core.hooksPath` is set: Git runs the hooks of {} only",
+                    path.display()
+                ),
+            ),
+            HooksDirSource::Environment => DoctorCheck::new(
+                CheckStatus::Warn,
+                format!(
+                    "{HOOKS_DIR_ENV} is set: hooksmith manages the hooks of {}, which Git only runs if `core.hooksPath` points to it",
+                    path.display()
+                ),
+            ),
+        }
+    }
+
+    /// Check that hooks can be installed in the hooks directory, by creating a file in it.
+    /// A missing directory is created by `install`, so it is only a warning.
+    ///
+    /// # Arguments
+    /// * `path` - The hooks directory
+    fn hooks_dir_writable_check(path: &Path) -> Option<DoctorCheck> {
+        if !path.is_dir() {
+            return Some(DoctorCheck::new(
+                CheckStatus::Warn,
+                "The hooks directory doesn't exist yet: `hooksmith install` creates it",
+            ));
+        }
+
+        let probe = path.join(format!(".hooksmith-doctor-{}", std::process::id()));
+        match fs::write(&probe, "") {
+            Ok(()) => {
+                let _ = fs::remove_file(&probe);
+                None
+            }
+            Err(e) => Some(DoctorCheck::new(
+                CheckStatus::Fail,
+                format!("The hooks directory is not writable: {e}"),
+            )),
+        }
+    }
+
+    /// Check each configured hook, sorted by name: whether it is a Git hook, and whether
+    /// it is installed as an executable script written by hooksmith.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    fn hook_file_checks(&self, git_hooks_path: &Path) -> Vec<DoctorCheck> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut checks = Vec::new();
+        for hook_name in hook_names {
+            if !GIT_HOOKS.contains(&hook_name.as_str()) {
+                let suggestion = suggest_hook_names(hook_name)
+                    .first()
+                    .map(|hook| format!(" (did you mean '{hook}'?)"))
+                    .unwrap_or_default();
+                checks.push(DoctorCheck::new(
+                    CheckStatus::Fail,
+                    format!("'{hook_name}' is not a Git hook, Git never runs it{suggestion}"),
+                ));
+                continue;
+            }
+
+            let path = git_hooks_path.join(hook_name);
+            let check = match Self::installed_hook_content_in(git_hooks_path, hook_name) {
+                Ok(None) => DoctorCheck::new(
+                    CheckStatus::Warn,
+                    format!("'{hook_name}' is not installed: run `hooksmith install`"),
+                ),
+                Err(e) => DoctorCheck::new(
+                    CheckStatus::Fail,
+                    format!("'{hook_name}' can't be read: {e}"),
+                ),
+                Ok(Some(_)) if !is_executable_file(&path) => DoctorCheck::new(
+                    CheckStatus::Fail,
+                    format!(
+                        "'{hook_name}' is installed but not executable, so Git skips it: run `chmod +x {}`",
+                        path.display()
+                    ),
+                ),
+                Ok(Some(content)) if content == Self::hook_content_at(&path, hook_name) => {
+                    DoctorCheck::new(CheckStatus::Pass, format!("'{hook_name}' is installed"))
+                }
+                Ok(Some(_)) if is_managed_hook(&path, hook_name) => DoctorCheck::new(
+                    CheckStatus::Warn,
+                    format!("'{hook_name}' is installed but outdated: run `hooksmith upgrade`"),
+                ),
+                Ok(Some(_)) => DoctorCheck::new(
+                    CheckStatus::Warn,
+                    format!(
+                        "'{hook_name}' is not installed, another script is in place: run `hooksmith install` to replace it"
+                    ),
+                ),
+            };
+            checks.push(check);
+        }
+
+        checks
+    }
+
+    /// Rewrite the installed scripts of the configured hooks that are out of date, e.g. after
+    /// upgrading hooksmith, and report which ones were upgraded and which were already
+    /// current. Hooks that aren't installed, hooks missing from the configuration and
+    /// scripts not written by hooksmith are left alone. In dry run mode, only reports.
+    ///
+    /// # Errors
+    /// * If the hooks directory cannot be resolved
+    /// * If an installed script cannot be read or rewritten
+    pub fn upgrade_hooks(&self) -> Result<()> {
+        let upgrade = self.upgrade_hooks_in(&self.hooks_dir()?)?;
+
+        let verb = if self.dry_run {
+            "Would upgrade"
+        } else {
+            "Upgraded"
+        };
+        for hook_name in &upgrade.upgraded {
+            outln!("  ⬆️  {verb} '{hook_name}'");
+        }
+        for hook_name in &upgrade.current {
+            outln!("  ✅ '{hook_name}' is up to date");
+        }
+
+        if !upgrade.foreign.is_empty() {
+            self.reporter.warning(
+                "Hooks not installed by hooksmith",
+                &format!(
+                    "These hooks are configured but their installed scripts weren't written by hooksmith, so they were left alone:\n{}\n\nRun `hooksmith install` to replace them.",
+                    format_list(&upgrade.foreign)
+                ),
+            );
+        }
+
+        outln!(
+            "{} hook(s) {}, {} already up to date",
+            upgrade.upgraded.len(),
+            if self.dry_run {
+                "would be upgraded"
+            } else {
+                "upgraded"
+            },
+            upgrade.current.len()
+        );
+
+        Ok(())
+    }
+
+    /// Rewrite the out of date scripts of the configured hooks in the given hooks directory,
+    /// see `upgrade_hooks`.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    ///
+    /// # Errors
+    /// * If an installed script cannot be read or rewritten
+    fn upgrade_hooks_in(&self, git_hooks_path: &Path) -> Result<HookUpgrade> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut upgrade = HookUpgrade::default();
+        for hook_name in hook_names {
+            let Some(content) = Self::installed_hook_content_in(git_hooks_path, hook_name)? else {
+                continue;
+            };
+
+            let hook_path = git_hooks_path.join(hook_name);
+            let expected = Self::hook_content_at(&hook_path, hook_name);
+            if content == expected {
+                upgrade.current.push(hook_name.clone());
+            } else if is_managed_hook(&hook_path, hook_name) {
+                if !self.dry_run {
+                    write_executable(&hook_path, &expected)?;
+                }
+                upgrade.upgraded.push(hook_name.clone());
+            } else {
+                upgrade.foreign.push(hook_name.clone());
+            }
+        }
+
+        Ok(upgrade)
+    }
+
+    /// Write the hook scripts `install` would write to another directory, for review, e.g.
+    /// with `diff -r <dir> .git/hooks`. The hooks directory itself is never written to.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory to write the scripts to, created if needed
+    ///
+    /// # Errors
+    /// * If the configuration is invalid (see `validate_hooks`)
+    /// * If `dir` is, or is inside, the hooks directory
+    /// * If the scripts cannot be written
+    pub fn emit_hooks(&self, dir: &Path) -> Result<()> {
+        self.validate_hooks()?;
+
+        // The hooks directory may not be resolvable, e.g. outside a repository: then there is
+        // nothing to protect, nor hooks to chain to
+        let hooks_dir = resolve_git_hooks_path().ok().map(|(path, _)| path);
+        if let Some(hooks_dir) = &hooks_dir {
+            if absolute_path(dir).starts_with(absolute_path(hooks_dir)) {
+                self.reporter.error(
+                    "Cannot emit into the hooks directory",
+                    &format!(
+                        "'{}' is inside the hooks directory '{}', which dry runs never write to.",
+                        dir.display(),
+                        hooks_dir.display()
+                    ),
+                    "Pass a directory outside the hooks directory to --emit-to.",
+                );
+
+                return Err(GitError::InvalidHooksDir(dir.display().to_string()).into());
+            }
+        }
+
+        fs::create_dir_all(dir)?;
+
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        for hook_name in &hook_names {
+            let path = dir.join(hook_name);
+            let content = hooks_dir.as_ref().map_or_else(
+                || Self::expected_hook_content(hook_name),
+                |hooks_dir| Self::hook_content_at(&hooks_dir.join(hook_name), hook_name),
+            );
+            write_executable(&path, &content)?;
+
+            if self.verbose {
+                outln!("  - Wrote {}", path.display());
+            }
+        }
+
+        outln!(
+            "📝 Wrote {} hook script(s) to {} instead of installing them",
+            hook_names.len(),
+            dir.display()
+        );
+
+        Ok(())
+    }
+
+    /// Warn that the configured server-side hooks need hooksmith installed on the server,
+    /// since their scripts don't fall back to `cargo install`.
+    fn warn_about_server_hooks(&self) {
+        let mut server_hooks = self
+            .config
+            .hooks
+            .keys()
+            .filter(|hook_name| is_server_hook(hook_name))
+            .collect::<Vec<_>>();
+        if server_hooks.is_empty() {
+            return;
+        }
+        server_hooks.sort();
+
+        self.reporter.warning(
+            "Server-side hooks",
+            &format!(
+                "The following hooks run on the server receiving pushes:\n{}\n\nInstall hooksmith on the server beforehand: unlike client hooks, their scripts don't run `cargo install hooksmith` when it is missing, and reject the push instead.",
+                format_list(&server_hooks)
+            ),
+        );
+    }
+
+    /// Executes a single command and handles its output
+    ///
+    /// # Arguments
+    /// * `context` - The hook run the command belongs to
+    /// * `hook_command` - The command to execute
+    /// * `working_directory` - Optional directory to run the command in
+    ///
+    /// # Errors
+    /// * `HookExecutionError::CommandFailed` if the command fails or cannot be started
+    /// * `HookExecutionError::HookTimeout` if the hook's time budget runs out
+    /// * `HookExecutionError::CommandTimeout` if the command runs longer than its timeout
+    ///
+    /// # Returns
+    /// * The status code the command exited with, `None` when no process ran (built-in
+    ///   checks, commands whose `{staged_files}` match no file)
+    fn execute_single_command(
+        &self,
+        context: &ExecutionContext,
+        hook_command: &HookCommand,
+        working_directory: Option<&Path>,
+    ) -> Result<Option<i32>> {
+        let hook_name = context.hook_name;
+
+        if self.verbose && !self.dry_run {
+            let display = if let Some(name) = &hook_command.name {
+                format!("{} ({})", name, hook_command.command)
+            } else {
+                hook_command.command.clone()
+            };
+            outln!(
+                "  - Running command: {display} (shell: {})",
+                self.shell(context)
+            );
+        }
+
+        if let Some(runner) = hook_command.task_runner {
+            // Behind a prefix, the runner may only be available in the environment it sets up
+            let prefixed = !self.command_prefix(context).is_empty();
+            let is_installed =
+                runner.is_installed() || context.path_prepend.iter().any(|dir| runner.is_in(dir));
+            if !self.dry_run && !prefixed && !is_installed {
+                let program = runner.program();
+                self.report_command_failure(
+                    hook_command,
+                    "Task runner not found",
+                    &format!(
+                        "Hook '{hook_name}' runs `{}`, but `{program}` is not installed or not in PATH",
+                        hook_command.command
+                    ),
+                    &format!("Install `{program}`, or replace the `{program}:` command with the commands it runs."),
+                );
+
+                // The status code a shell exits with for a command it cannot find
+                return Err(HookExecutionError::CommandFailed(127).into());
+            }
+        }
+
+        if let Some(builtin) = &hook_command.builtin {
+            return self
+                .run_builtin(context, hook_command, builtin)
+                .map(|()| None);
+        }
+
+        let command_dir = match &hook_command.working_dir {
+            Some(dir) => Some(self.command_working_dir(context, hook_command, dir)?),
+            None => None,
+        };
+        let working_directory = command_dir.as_deref().or(working_directory);
+
+        let command_context = context.for_command(hook_command);
+        let context = command_context.as_ref();
+
+        let command = expand_argument_placeholders(hook_name, &hook_command.command);
+        let Some(command) = expand_staged_files(&command, get_staged_files)? else {
+            if self.verbose {
+                outln!(
+                    "⏭️  skipped `{}`: no staged file matches",
+                    hook_command.label()
+                );
+            }
+            return Ok(None);
+        };
+        let started = Instant::now();
+        let mut attempt = 0;
+        let result = loop {
+            let result = self.execute_command(hook_command, &command, working_directory, context);
+
+            // Whatever the command did, it was stopped by the interrupt
+            if let Some(signal) = process::pending_signal() {
+                return Err(HookExecutionError::Interrupted(signal).into());
+            }
+
+            // Failures are retried, unlike timeouts and commands that can't start
+            let retried_code = match &result {
+                Ok(Some(status)) => status.code().filter(|code| {
+                    !hook_command.is_success_code(*code) && hook_command.is_retried_on(*code)
+                }),
+                _ => None,
+            };
+            match retried_code {
+                Some(code) if attempt < hook_command.retries => {
+                    attempt += 1;
+                    outln!(
+                        "🔁 Hook '{hook_name}' command `{}` failed with status code {code}, retrying ({attempt}/{})",
+                        hook_command.label(),
+                        hook_command.retries
+                    );
+                    if let Some(seconds) = hook_command.retry_delay {
+                        thread::sleep(Duration::from_secs(seconds));
+                    }
+                }
+                _ => break result,
+            }
+        };
+
+        match result {
+            Ok(Some(status))
+                if status
+                    .code()
+                    .is_some_and(|code| hook_command.is_success_code(code)) =>
+            {
+                if self.verbose && !self.dry_run {
+                    outln!(
+                        "\n  ✅ Command completed successfully (done in {})",
+                        Self::format_duration(&started.elapsed())
+                    );
+                }
+
+                Ok(status.code())
+            }
+            Ok(Some(status)) => {
+                let code = status.code().unwrap_or(1);
+                self.report_command_failure(
+                    hook_command,
+                    "Command failed",
+                    &format!(
+                        "Hook '{hook_name}' command `{}` failed with status code {code}",
+                        hook_command.label()
+                    ),
+                    "Please check your command and try again.",
+                );
+
+                // A zero status can fail when `success_codes` excludes it, still fail the hook
+                Err(HookExecutionError::CommandFailed(if code == 0 { 1 } else { code }).into())
+            }
+            Ok(None) if context.is_out_of_time() => {
+                Err(self.hook_timeout_error(context, hook_command))
+            }
+            Ok(None) => Err(self.command_timeout_error(context, hook_command)),
+            Err(e) => {
+                self.report_command_failure(
+                    hook_command,
+                    "Failed to execute command",
+                    &format!(
+                        "Hook '{hook_name}' command `{}` could not be started: {e}",
+                        hook_command.label()
+                    ),
+                    "Please ensure the command exists and is executable.",
+                );
+
+                Err(HookExecutionError::CommandFailed(1).into())
+            }
+        }
+    }
+
+    /// Run a check built into hooksmith, reporting the problems it finds.
+    ///
+    /// # Arguments
+    /// * `context` - The hook run the check belongs to
+    /// * `hook_command` - The command running the check
+    /// * `builtin` - The check
+    ///
+    /// # Errors
+    /// * `HookExecutionError::CommandFailed` if the check fails or its input can't be read
+    fn run_builtin(
+        &self,
+        context: &ExecutionContext,
+        hook_command: &HookCommand,
+        builtin: &Builtin,
+    ) -> Result<()> {
+        let hook_name = context.hook_name;
+
+        if self.dry_run {
+            outln!("🔍 Would run the built-in check `{}`", builtin.name());
+            return Ok(());
+        }
+
+        match builtin.check(context.hook_args) {
+            Ok(violations) if violations.is_empty() => Ok(()),
+            Ok(violations) => {
+                let file = context.hook_args.first().map_or("", String::as_str);
+                self.report_command_failure(
+                    hook_command,
+                    "Check failed",
+                    &format!(
+                        "Hook '{hook_name}' command `{}` found {} problem(s):\n{}",
+                        hook_command.label(),
+                        violations.len(),
+                        format_list(
+                            &violations
+                                .iter()
+                                .map(|violation| format!("{file}:{violation}"))
+                                .collect::<Vec<_>>()
+                        )
+                    ),
+                    &format!(
+                        "Fix them, or adjust the options of `builtin: {}` in the configuration.",
+                        builtin.name()
+                    ),
+                );
+
+                Err(HookExecutionError::CommandFailed(1).into())
+            }
+            Err(e) => {
+                self.report_command_failure(
+                    hook_command,
+                    "Failed to run check",
+                    &format!(
+                        "Hook '{hook_name}' command `{}` could not run: {e}",
+                        hook_command.label()
+                    ),
+                    "Please check the hook the command is configured for.",
+                );
+
+                Err(HookExecutionError::CommandFailed(1).into())
+            }
+        }
+    }
+
+    /// Report that a hook ran out of its time budget and build the matching error.
+    ///
+    /// # Arguments
+    /// * `context` - The hook run that timed out
+    /// * `hook_command` - The command that was running (or about to run) when the budget ran out
+    fn hook_timeout_error(
+        &self,
+        context: &ExecutionContext,
+        hook_command: &HookCommand,
+    ) -> HooksmithError {
+        let hook_name = context.hook_name;
+        let seconds = context.hook_timeout.map_or(0, |timeout| timeout.as_secs());
+        let command = hook_command.label().to_string();
+
+        self.reporter.error(
+            "Hook timed out",
+            &format!(
+                "Hook '{hook_name}' exceeded its {seconds}s time budget while running `{command}`"
+            ),
+            "Speed up the hook's commands or raise its `hook_timeout`.",
+        );
+
+        HookExecutionError::HookTimeout {
+            hook: hook_name.to_string(),
+            seconds,
+            command,
+        }
+        .into()
+    }
+
+    /// Report that a command ran longer than its timeout and build the matching error.
+    ///
+    /// # Arguments
+    /// * `context` - The hook run the command belongs to
+    /// * `hook_command` - The command that was stopped
+    fn command_timeout_error(
+        &self,
+        context: &ExecutionContext,
+        hook_command: &HookCommand,
+    ) -> HooksmithError {
+        let hook_name = context.hook_name;
+        let seconds = context
+            .command_timeout
+            .map_or(0, |timeout| timeout.as_secs());
+        let command = hook_command.label().to_string();
+
+        self.report_command_failure(
+            hook_command,
+            "Command timed out",
+            &format!(
+                "Hook '{hook_name}' command `{command}` was stopped after running for {seconds}s"
+            ),
+            "Speed up the command, or raise its `timeout` or the hook's `command_timeout`.",
+        );
+
+        HookExecutionError::CommandTimeout {
+            hook: hook_name.to_string(),
+            seconds,
+            command,
+        }
+        .into()
+    }
+
+    /// Get a list of available hooks from the configuration.
+    #[must_use]
+    pub fn get_available_hooks(&self) -> Vec<String> {
+        self.config.hooks.keys().cloned().collect()
+    }
+
+    /// The configured hooks that would fire for a Git event, after hooks are turned off by
+    /// `hooksmith disable`, while the event's own `on_success` or `on_failure` commands
+    /// run, or by `files` patterns matching none of the changed files (as with
+    /// `--only-changed-hooks`). Nothing runs.
+    ///
+    /// # Arguments
+    /// * `event` - The Git hook being triggered, e.g. `pre-commit`
+    /// * `context` - The state of the repository, see `RunContext::detect`
+    ///
+    /// # Returns
+    /// * The names of the hooks that would run, empty when none would
+    ///
+    /// # Example
+    /// ```no_run
+    /// use hooksmith::{Hooksmith, RunContext};
+    /// use std::path::Path;
+    ///
+    /// let hs = Hooksmith::new_from_config(Path::new("hooksmith.yaml"), false, false)?;
+    /// for hook in hs.hooks_for_event("pre-commit", &RunContext::detect("pre-commit")) {
+    ///     println!("{hook} would run");
+    /// }
+    /// # Ok::<(), hooksmith::HooksmithError>(())
+    /// ```
+    #[must_use]
+    pub fn hooks_for_event(&self, event: &str, context: &RunContext) -> Vec<String> {
+        let Some((name, hook)) = self.config.hooks.get_key_value(event) else {
+            return Vec::new();
+        };
+
+        let fires = !disabled::is_disabled()
+            && !is_running_handler_of(name)
+            && context
+                .changed_files
+                .as_ref()
+                .is_none_or(|files| hook.matches_changed_files(files));
+
+        if fires {
+            vec![name.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Handle the "hook not found error"
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook being executed
+    ///
+    /// # Errors
+    /// * If the hook is not found in the configuration.
+    fn handle_hook_not_found(&self, hook_name: &str) -> Result<()> {
+        let formatted_hooks = format_list(&self.config.hooks.keys().collect::<Vec<_>>());
+
+        self.reporter.error(
+            "Hook not found",
+            &format!("No commands defined for hook '{hook_name}'"),
+            &format!(
+                "Available hooks:\n{formatted_hooks}\n\nPlease check your configuration file."
+            ),
+        );
+
+        Err(HookExecutionError::HookNotFound(hook_name.to_string()).into())
+    }
+
+    /// Runs multiple hooks with timing information.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Vector of hook names to run
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    /// * If any hook is not found in the configuration
+    pub fn run_hooks_with_timing(&self, hook_names: &[String], options: &RunOptions) -> Result<()> {
+        self.start_output_log(options)?;
+
+        self.run_around_hooks(options, || {
+            let start_time = Instant::now();
+            let mut hook_timings = Vec::new();
+            let total_hooks = hook_names.len();
+
+            for (hook_idx, hook_name) in hook_names.iter().enumerate() {
+                self.observer()
+                    .on_hook_start(hook_name, hook_idx, total_hooks);
+                let hook_start = Instant::now();
+                let hook_timing = self.run_hook_internal_with_timing(hook_name, options)?;
+                let hook_duration = hook_start.elapsed();
+
+                // Update the hook timing with the actual total duration
+                let mut updated_timing = hook_timing;
+                updated_timing.total_duration = hook_duration;
+                hook_timings.push(updated_timing);
+            }
+
+            let total_duration = start_time.elapsed();
+
+            let timing_report = TimingReport {
+                hooks: hook_timings,
+                total_duration,
+            };
+
+            Self::print_timing_report(self, &timing_report);
+            Ok(())
+        })
+    }
+
+    /// Runs multiple hooks by executing their commands.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Vector of hook names to run
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    /// * If any hook is not found in the configuration
+    pub fn run_hooks(&self, hook_names: &[String], options: &RunOptions) -> Result<()> {
+        self.start_output_log(options)?;
+
+        self.run_around_hooks(options, || {
+            let jobs = self.concurrent_jobs(hook_names, options);
+            if jobs > 1 {
+                return self.run_hooks_concurrently(hook_names, options, jobs);
+            }
+
+            let total_hooks = hook_names.len();
+            for (hook_idx, hook_name) in hook_names.iter().enumerate() {
+                self.observer()
+                    .on_hook_start(hook_name, hook_idx, total_hooks);
+                self.run_hook_internal(hook_name, options)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// How many of the hooks can run at the same time. Dry runs, and hooks stashing
+    /// unstaged changes (which would race on the working tree), run one after the other.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Names of the hooks to run
+    /// * `options` - Options of the run
+    fn concurrent_jobs(&self, hook_names: &[String], options: &RunOptions) -> usize {
+        let jobs = options.jobs.min(hook_names.len());
+        if jobs <= 1 || self.dry_run {
+            return 1;
+        }
+
+        let stashes = options.stash
+            || hook_names.iter().any(|hook_name| {
+                self.config
+                    .hooks
+                    .get(hook_name)
+                    .is_some_and(|hook| hook.stash_unstaged)
+            });
+        if stashes {
+            self.reporter.warning(
+                "Hooks run one at a time",
+                "Stashing unstaged changes can't be shared by concurrent hooks, --jobs is ignored.",
+            );
+            return 1;
+        }
+
+        jobs
+    }
+
+    /// Run hooks on `jobs` threads, each picking the next hook to run once its previous one
+    /// is done. The output of each hook, its progress and messages included, is held until
+    /// the hook is done, so that hooks don't interleave it. Every hook runs even when another
+    /// one fails, and all of them stop on an interrupt.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Names of the hooks to run
+    /// * `options` - Options of the run
+    /// * `jobs` - How many hooks run at the same time
+    ///
+    /// # Errors
+    /// * The error of the first failing hook, in selection order
+    fn run_hooks_concurrently(
+        &self,
+        hook_names: &[String],
+        options: &RunOptions,
+        jobs: usize,
+    ) -> Result<()> {
+        let options = RunOptions {
+            capture: true,
+            max_output_bytes: Some(options.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES)),
+            ..options.clone()
+        };
+        let total_hooks = hook_names.len();
+        let next_hook = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(total_hooks));
+        let sink = output::current();
+        // A single guard for all the hooks: an interrupt stops every one of them
+        let signals = SignalGuard::install();
+
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| {
+                    let _sink = output::redirect(sink.clone());
+
+                    loop {
+                        let hook_idx = next_hook.fetch_add(1, Ordering::SeqCst);
+                        let Some(hook_name) = hook_names.get(hook_idx) else {
+                            break;
+                        };
+
+                        let result = match signals.take_signal() {
+                            Some(signal) => Err(HookExecutionError::Interrupted(signal).into()),
+                            None => {
+                                let buffer = output::Buffer::new();
+                                let result = {
+                                    let _buffer = output::redirect(Sink::Buffer(buffer.clone()));
+
+                                    self.observer()
+                                        .on_hook_start(hook_name, hook_idx, total_hooks);
+                                    self.run_hook_internal(hook_name, &options)
+                                };
+                                buffer.flush();
+
+                                result
+                            }
+                        };
+                        results
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .push((hook_idx, result));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+        results.sort_by_key(|(hook_idx, _)| *hook_idx);
+
+        results.into_iter().try_for_each(|(_, result)| result)
+    }
+
+    /// Run the one-off commands of the options around hooks: `pre_run` before them, and
+    /// `post_run` after them whatever their outcome. The hooks don't run when a `pre_run`
+    /// command fails.
+    ///
+    /// # Arguments
+    /// * `options` - Options of the run
+    /// * `run_hooks` - Runs the hooks
+    ///
+    /// # Errors
+    /// * The error of the hooks, else of the first failing `pre_run` or `post_run` command
+    fn run_around_hooks(
+        &self,
+        options: &RunOptions,
+        run_hooks: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let no_hook = Hook::default();
+
+        for command in &options.pre_run {
+            self.run_one_off_command(command, "pre-run", &no_hook, options)?;
+        }
+
+        let result = run_hooks();
+
+        let mut post_run = Ok(());
+        for command in &options.post_run {
+            post_run = self.run_one_off_command(command, "post-run", &no_hook, options);
+            if post_run.is_err() {
+                break;
+            }
+        }
+
+        result.and(post_run)
+    }
+
+    /// Empty the log file of the run (`RunOptions::tee`), creating it if needed, so that it
+    /// only holds the output of this run. Commands then append to it.
+    ///
+    /// # Arguments
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If the log file cannot be created
+    fn start_output_log(&self, options: &RunOptions) -> Result<()> {
+        match &options.tee {
+            Some(path) if !self.dry_run => {
+                File::create(path)?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Internal method to run a single hook
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to run
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    /// * If the hook is not found in the configuration
+    fn run_hook_internal(&self, hook_name: &str, options: &RunOptions) -> Result<()> {
+        self.run_hook_internal_with_timing(hook_name, options)
+            .map(|_| ())
+    }
+
+    /// Run a single hook with explicit overrides of the environment, working directory,
+    /// arguments, output capture and time budget, without editing the configuration.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to run
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If a command cannot be executed or fails
+    /// * If the hook is not found in the configuration
+    ///
+    /// # Returns
+    /// * The timing of the hook and of its commands
+    ///
+    /// # Example
+    /// ```no_run
+    /// use hooksmith::{Hooksmith, RunOptions};
+    /// use std::path::Path;
+    ///
+    /// let hs = Hooksmith::new_from_config(Path::new("hooksmith.yaml"), false, false)?;
+    /// let options = RunOptions::new()
+    ///     .with_env("CI", "true")
+    ///     .with_cwd("crates/api")
+    ///     .with_capture(true);
+    /// let timing = hs.run_hook_with("pre-commit", options)?;
+    /// # Ok::<(), hooksmith::HooksmithError>(())
+    /// ```
+    pub fn run_hook_with(&self, hook_name: &str, options: RunOptions) -> Result<HookTiming> {
+        self.start_output_log(&options)?;
+        self.observer().on_hook_start(hook_name, 0, 1);
+
+        self.run_hook_internal_with_timing(hook_name, &options)
+    }
+
+    /// Set up the run of a hook: its time budget, `umask`, command prefix, `path_prepend`
+    /// and whether the output of its commands is captured.
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to run
+    /// * `options` - Options of the run
+    /// * `hook` - The hook's configuration
+    fn execution_context<'a>(
+        &self,
+        hook_name: &'a str,
+        options: &'a RunOptions,
+        hook: &Hook,
+    ) -> ExecutionContext<'a> {
+        let mut context = ExecutionContext::new(hook_name, options, hook);
+        context.path_prepend = self.path_prepend_dirs();
+
+        if options.capture || hook.capture_output || hook.max_output_bytes.is_some() {
+            context.max_output_bytes = Some(
+                options
+                    .max_output_bytes
+                    .or(hook.max_output_bytes)
+                    .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES),
+            );
+        }
+
+        if cfg!(not(unix)) && context.umask.is_some() {
+            self.reporter.warning(
+                "umask ignored",
+                &format!("Hook '{hook_name}' sets a `umask`, which is only supported on Unix."),
+            );
+        }
+
+        context
+    }
+
+    /// Run a one-off command the way the commands of a hook run: through the configured
+    /// shell and `command_prefix`, with `env` and `path_prepend`, and with the options'
+    /// timeout, capture, log file and working directory (`hooksmith exec`). This helps
+    /// debugging commands that work in a terminal but not in a hook.
+    ///
+    /// # Arguments
+    /// * `command` - The command line, run by the shell
+    /// * `hook_name` - Hook whose settings apply (`hook_timeout`, `umask`, `command_prefix`,
+    ///   capture), `None` for the top-level settings only
+    /// * `options` - Options of the run; `extra_args` become `$1`, `$2`, ...
+    ///
+    /// # Errors
+    /// * If the hook is not found in the configuration
+    /// * `HookExecutionError::CommandFailed` if the command fails, times out or can't start
+    ///
+    /// # Example
+    /// ```no_run
+    /// use hooksmith::{Hooksmith, RunOptions};
+    /// use std::path::Path;
+    ///
+    /// let hs = Hooksmith::new_from_config(Path::new("hooksmith.yaml"), false, false)?;
+    /// hs.exec_command("cargo fmt --check", Some("pre-commit"), &RunOptions::new())?;
+    /// # Ok::<(), hooksmith::HooksmithError>(())
+    /// ```
+    pub fn exec_command(
+        &self,
+        command: &str,
+        hook_name: Option<&str>,
+        options: &RunOptions,
+    ) -> Result<()> {
+        let default_hook = Hook::default();
+        let hook = match hook_name {
+            Some(name) => match self.config.hooks.get(name) {
+                Some(hook) => hook,
+                None => return self.handle_hook_not_found(name),
+            },
+            None => &default_hook,
+        };
+
+        self.start_output_log(options)?;
+
+        self.run_one_off_command(command, hook_name.unwrap_or("exec"), hook, options)
+    }
+
+    /// Run a command that isn't part of a hook, with the settings of the given one.
+    ///
+    /// # Arguments
+    /// * `command` - The command line, run by the shell
+    /// * `label` - What the command is reported as, in place of a hook name
+    /// * `hook` - Hook whose settings apply
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * `HookExecutionError::CommandFailed` if the command fails or can't start
+    /// * `HookExecutionError::HookTimeout` if it runs out of time
+    fn run_one_off_command(
+        &self,
+        command: &str,
+        label: &str,
+        hook: &Hook,
+        options: &RunOptions,
+    ) -> Result<()> {
+        let context = self.execution_context(label, options, hook);
+        let _signals = SignalGuard::install();
+
+        self.execute_single_command(
+            &context,
+            &HookCommand::new_unnamed(command.to_string()),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Internal method to run a single hook with timing information
+    ///
+    /// # Arguments
+    /// * `hook_name` - Name of the hook to run
+    /// * `options` - Options of the run
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    /// * If the hook is not found in the configuration
+    fn run_hook_internal_with_timing(
+        &self,
+        hook_name: &str,
+        options: &RunOptions,
+    ) -> Result<HookTiming> {
+        let Some(hook) = self.config.hooks.get(hook_name) else {
+            self.handle_hook_not_found(hook_name)?;
+            // This should never be reached due to the error above
+            return Ok(HookTiming {
+                hook_name: hook_name.to_string(),
+                commands: Vec::new(),
+                total_duration: Duration::from_secs(0),
+                skipped: Vec::new(),
+            });
+        };
+
+        if disabled::is_disabled() {
+            outln!("⏸️  Hooks are disabled, `{hook_name}` was not run (`hooksmith enable` turns them back on)");
+
+            let mut context = ExecutionContext::new(hook_name, options, hook);
+            self.skip(&mut context, format!("`{hook_name}`"), "hooks are disabled");
+            let hook_timing = HookTiming {
+                hook_name: hook_name.to_string(),
+                commands: Vec::new(),
+                total_duration: context.started.elapsed(),
+                skipped: context.skipped,
+            };
+            self.observer().on_hook_finish(&hook_timing);
+
+            return Ok(hook_timing);
+        }
+
+        let skip_list = skip_list(options);
+        if let Some((_, source)) = skip_list.iter().find(|(target, _)| target == hook_name) {
+            outln!("⏭️  `{hook_name}` skipped: listed in {source}");
+
+            let mut context = ExecutionContext::new(hook_name, options, hook);
+            context.skipped.push(Skipped {
+                target: format!("`{hook_name}`"),
+                reason: format!("listed in {source}"),
+            });
+            let hook_timing = HookTiming {
+                hook_name: hook_name.to_string(),
+                commands: Vec::new(),
+                total_duration: context.started.elapsed(),
+                skipped: context.skipped,
+            };
+            self.observer().on_hook_finish(&hook_timing);
+
+            return Ok(hook_timing);
+        }
+
+        let mut skipped_commands = Vec::new();
+        let hook = &hook.without_commands(|position, hook_command| {
+            let source = skip_list.iter().find_map(|(target, source)| {
+                let (name, command) = target.split_once(':')?;
+                let is_listed = name == hook_name
+                    && (command == position.to_string()
+                        || hook_command.name.as_deref() == Some(command));
+                is_listed.then_some(*source)
+            });
+            if let Some(source) = source {
+                outln!(
+                    "⏭️  `{hook_name}:{position}` (`{}`) skipped: listed in {source}",
+                    hook_command.label()
+                );
+                skipped_commands.push(Skipped {
+                    target: format!("`{hook_name}:{position}`"),
+                    reason: format!("listed in {source}"),
+                });
+            }
+
+            source.is_some()
+        });
+
+        if self.verbose && !self.dry_run {
+            outln!("📋 Running Hook: {hook_name}");
+        }
+
+        if options.show_git_env || std::env::var_os(SHOW_GIT_ENV_ENV).is_some() {
+            Self::print_git_environment(hook_name);
+        }
+
+        let mut context = self.execution_context(hook_name, options, hook);
+        context.skipped.extend(skipped_commands);
+        let mut command_timings = Vec::new();
+
+        // Variables set for the run take precedence over the environment hooksmith runs in
+        let unmet_env = hook.unmet_env_condition(|name| {
+            options
+                .env
+                .get(name)
+                .cloned()
+                .or_else(|| std::env::var(name).ok())
+        });
+        if let Some(reason) = unmet_env {
+            outln!("⏭️  `{hook_name}` skipped: {reason} (`when_env`)");
+
+            context.skipped.push(Skipped {
+                target: format!("`{hook_name}`"),
+                reason: format!("{reason} (`when_env`)"),
+            });
+            let hook_timing = HookTiming {
+                hook_name: hook_name.to_string(),
+                commands: Vec::new(),
+                total_duration: context.started.elapsed(),
+                skipped: context.skipped,
+            };
+            self.observer().on_hook_finish(&hook_timing);
+
+            return Ok(hook_timing);
+        }
+
+        if options.only_changed_hooks || hook.uses_input(hook_name) {
+            context.input = HookInput::read(hook_name);
+        }
+
+        if options.since_last_run {
+            context.changed_files = Self::files_changed_since_last_run(Path::new("."), hook_name);
+        }
+
+        let skip_reason = if is_running_handler_of(hook_name) {
+            Some("it was triggered by its own `on_success` or `on_failure` commands")
+        } else if context.changed_files.as_ref().is_some_and(Vec::is_empty) {
+            Some("no files changed since its last successful run")
+        } else if options.only_changed_hooks && !Self::is_affected_by_changes(&mut context, hook) {
+            Some("none of the changed files match its `files` patterns")
+        } else {
+            None
+        };
+
+        if let Some(reason) = skip_reason {
+            self.skip(&mut context, format!("`{hook_name}`"), reason);
+
+            let hook_timing = HookTiming {
+                hook_name: hook_name.to_string(),
+                commands: Vec::new(),
+                total_duration: context.started.elapsed(),
+                skipped: context.skipped,
+            };
+            self.observer().on_hook_finish(&hook_timing);
+
+            return Ok(hook_timing);
+        }
+
+        // On an interrupt, the running commands are stopped and the hook fails once the
+        // unstaged changes are restored
+        let signals = SignalGuard::install();
+
+        let stash = (hook.stash_unstaged || options.stash) && !self.dry_run;
+        if stash && is_server_hook(hook_name) {
+            self.reporter.warning(
+                "Stashing skipped",
+                &format!(
+                    "Hook '{hook_name}' runs on the server, which has no working tree to stash unstaged changes from."
+                ),
+            );
+        }
+
+        let stashed = if stash && !is_server_hook(hook_name) {
+            self.stash_unstaged_changes()?
+        } else {
+            None
+        };
+
+        // Restore explicitly to report failures; should running the commands panic, the
+        // guard restores the changes when dropped
+        let result = self.run_hook_commands(&mut context, hook);
+
+        if let Some(stashed) = stashed {
+            self.restore_unstaged_changes(stashed)?;
+        }
+        if let Some(signal) = signals.take_signal() {
+            return Err(HookExecutionError::Interrupted(signal).into());
+        }
+        self.run_outcome_handlers(&context, hook, result.as_ref().err());
+        command_timings.extend(result?);
+
+        let total_commands = command_timings.len();
+
+        if self.dry_run {
+            outln!("🏁 Dry run completed. {total_commands} command(s) would be executed",);
+        } else if options.since_last_run {
+            self.record_successful_run(Path::new("."), hook_name);
+        }
+        if self.verbose && !self.dry_run {
+            outln!(
+                "⏱️  `{hook_name}`: {total_commands} command(s) done in {}",
+                Self::format_duration(&context.started.elapsed())
+            );
+        }
+
+        let hook_timing = HookTiming {
+            hook_name: hook_name.to_string(),
+            commands: command_timings,
+            total_duration: context.started.elapsed(),
+            skipped: context.skipped,
+        };
+        self.observer().on_hook_finish(&hook_timing);
+
+        Ok(hook_timing)
+    }
+
+    /// Record that a hook or a block of commands was intentionally not run, and print it
+    /// if skips are shown (`RunOptions::show_skipped`, or verbose mode by default).
+    ///
+    /// # Arguments
+    /// * `context` - The run the skip belongs to
+    /// * `target` - What was skipped
+    /// * `reason` - Why it was skipped
+    fn skip(&self, context: &mut ExecutionContext, target: String, reason: &str) {
+        if context.options.show_skipped.unwrap_or(self.verbose) {
+            outln!("⏭️  skipped {target}: {reason}");
+        }
+
+        context.skipped.push(Skipped {
+            target,
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Run the path-scoped commands of a hook, then its global commands.
+    /// Returns timing information for each command executed.
+    fn run_hook_commands(
+        &self,
+        context: &mut ExecutionContext,
+        hook: &Hook,
+    ) -> Result<Vec<CommandTiming>> {
+        let mut timings = self.run_path_scoped_commands(context, hook)?;
+        timings.extend(self.run_global_commands(context, hook)?);
+        timings.extend(self.run_command_groups(context, hook)?);
+
+        Ok(timings)
+    }
+
+    /// Run the `on_success` or `on_failure` commands of a hook, depending on the outcome of
+    /// its commands. They get the outcome through environment variables, aren't bound by the
+    /// hook's time budget, and their failures are reported as warnings without changing the
+    /// outcome of the hook.
+    ///
+    /// # Arguments
+    /// * `context` - The execution context of the hook
+    /// * `hook` - The hook
+    /// * `error` - Why the hook's commands failed, `None` if they succeeded
+    fn run_outcome_handlers(
+        &self,
+        context: &ExecutionContext,
+        hook: &Hook,
+        error: Option<&HooksmithError>,
+    ) {
+        let (handler, commands) = match error {
+            None => ("on_success", &hook.on_success),
+            Some(_) => ("on_failure", &hook.on_failure),
+        };
+        let Some(commands) = commands else {
+            return;
+        };
+
+        let hook_name = context.hook_name;
+        let mut outcome_env = vec![
+            (
+                HOOK_OUTCOME_ENV,
+                if error.is_none() {
+                    "success"
+                } else {
+                    "failure"
+                }
+                .to_string(),
+            ),
+            (
+                HOOK_DURATION_ENV,
+                context.started.elapsed().as_millis().to_string(),
+            ),
+            (HANDLER_HOOKS_ENV, handler_hooks_with(hook_name)),
+        ];
+        if let Some(error) = error {
+            outcome_env.push((HOOK_ERROR_ENV, error.to_string()));
+        }
+        let handler_context = ExecutionContext {
+            hook_timeout: None,
+            command_timeout: None,
+            outcome_env,
+            ..context.clone()
+        };
+
+        for hook_command in commands {
+            outln!("↪️  {handler}: `{}`", hook_command.label());
+
+            let command = expand_argument_placeholders(hook_name, &hook_command.command);
+            let failure = match self.execute_command(hook_command, &command, None, &handler_context)
+            {
+                Ok(Some(status))
+                    if status
+                        .code()
+                        .is_some_and(|code| hook_command.is_success_code(code)) =>
+                {
+                    continue
+                }
+                Ok(Some(status)) => {
+                    format!("failed with status code {}", status.code().unwrap_or(1))
+                }
+                // Handlers have no time budget
+                Ok(None) => continue,
+                Err(e) => format!("could not be started: {e}"),
+            };
+            self.reporter.warning(
+                "Handler failed",
+                &format!(
+                    "Hook '{hook_name}' {handler} command `{}` {failure}; the outcome of the hook is unchanged.",
+                    hook_command.label()
+                ),
+            );
+        }
+    }
+
+    /// Stash the unstaged changes before running a hook, warning about changes left over
+    /// by a run that could not restore them.
+    ///
+    /// # Errors
+    /// * If the changes cannot be stashed
+    ///
+    /// # Returns
+    /// * `None` if there was nothing to stash
+    fn stash_unstaged_changes(&self) -> Result<Option<StashGuard<'_>>> {
+        for leftover in leftover_stashes(Path::new(".")) {
+            self.reporter.warning(
+                "Unrestored stash",
+                &format!(
+                    "Unstaged changes stashed by a previous run are still in {leftover}.\nBring them back with `git stash pop {leftover}`."
+                ),
+            );
+        }
+
+        let stashed = stash::stash_unstaged(Path::new("."), &self.reporter)?;
+
+        if let Some(stashed) = &stashed {
+            outln!(
+                "📦 Stashed unstaged changes (stash commit {})",
+                stashed.commit()
+            );
+        }
+
+        Ok(stashed)
+    }
+
+    /// Restore the unstaged changes stashed before running a hook.
+    ///
+    /// # Errors
+    /// * If the changes conflict with the files modified by the hook; they are then kept in
+    ///   the stash
+    fn restore_unstaged_changes(&self, stashed: StashGuard<'_>) -> Result<()> {
+        stashed.restore()?;
+        outln!("📦 Restored unstaged changes");
+
+        Ok(())
+    }
+
+    /// Execute a list of commands with an optional working directory override.
+    /// Returns timing information for each command executed (zero durations in dry-run).
+    fn run_commands_for_scope(
+        &self,
+        context: &ExecutionContext,
+        commands: &[HookCommand],
+        working_directory_override: Option<&str>,
+    ) -> Result<Vec<CommandTiming>> {
+        let mut timings = Vec::new();
+        let total_commands = commands.len();
+
+        if self.dry_run {
+            for (idx, hook_command) in commands.iter().enumerate() {
+                if context.options.resolve {
+                    let command =
+                        expand_argument_placeholders(context.hook_name, &hook_command.command);
+                    let command = expand_staged_files(&command, get_staged_files)
+                        .ok()
+                        .flatten()
+                        .unwrap_or(command);
+                    let resolved = self.resolve_command(
+                        &command,
+                        working_directory_override.map(Path::new),
+                        &context.for_command(hook_command),
+                    );
+                    print_resolved_command(&resolved, context.umask, idx, total_commands);
+                } else if working_directory_override.is_some() {
+                    handle_dry_run_with_dir(
+                        hook_command,
+                        idx,
+                        total_commands,
+                        working_directory_override,
+                        context.hook_args,
+                    );
+                } else {
+                    handle_dry_run(hook_command, idx, total_commands, context.hook_args);
+                }
+                // For dry run, we still add timing entries with zero duration
+                timings.push(CommandTiming::new(hook_command, Duration::from_secs(0)));
+            }
+            return Ok(timings);
+        }
+
+        let working_directory = working_directory_override.map(Path::new);
+        for (idx, hook_command) in commands.iter().enumerate() {
+            if let Some(signal) = process::pending_signal() {
+                return Err(HookExecutionError::Interrupted(signal).into());
+            }
+            if context.is_out_of_time() {
+                return Err(self.hook_timeout_error(context, hook_command));
+            }
+
+            self.observer()
+                .on_command_start(context.hook_name, hook_command, idx, total_commands);
+
+            let start_time = Instant::now();
+            let result = self.execute_single_command(context, hook_command, working_directory);
+            let mut timing = CommandTiming::new(hook_command, start_time.elapsed());
+
+            match result {
+                Ok(exit_code) => timing.exit_code = exit_code,
+                Err(e) => {
+                    self.observer()
+                        .on_command_finish(context.hook_name, hook_command, Err(&e));
+                    if !Self::is_allowed_failure(hook_command, &e) {
+                        return Err(e);
+                    }
+
+                    timings.push(CommandTiming {
+                        failed: true,
+                        exit_code: failure_exit_code(&e),
+                        ..timing
+                    });
+                    continue;
+                }
+            }
+
+            self.observer()
+                .on_command_finish(context.hook_name, hook_command, Ok(&timing));
+            timings.push(timing);
+        }
+
+        Ok(timings)
+    }
+
+    /// Whether the hook goes on after a command failed, because the command sets
+    /// `allow_failure`. The failure was already reported as a note, see
+    /// `report_command_failure`. Interrupts and the hook running out of time stop the hook
+    /// regardless.
+    ///
+    /// # Arguments
+    /// * `hook_command` - The command that failed
+    /// * `error` - Why it failed
+    fn is_allowed_failure(hook_command: &HookCommand, error: &HooksmithError) -> bool {
+        let is_command_failure = matches!(
+            error,
+            HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(_)
+                    | HookExecutionError::CommandTimeout { .. }
+                    | HookExecutionError::WorkingDirNotFound(_)
+            )
+        );
+
+        hook_command.allow_failure && is_command_failure
+    }
+
+    /// Report why a command failed: as an error, or as a note when the command sets
+    /// `allow_failure`, since the hook goes on and the failure isn't a problem then.
+    ///
+    /// # Arguments
+    /// * `hook_command` - The command that failed
+    /// * `title` - The title of the error
+    /// * `details` - What went wrong
+    /// * `suggestion` - How to fix it, left out of notes
+    fn report_command_failure(
+        &self,
+        hook_command: &HookCommand,
+        title: &str,
+        details: &str,
+        suggestion: &str,
+    ) {
+        if hook_command.allow_failure {
+            self.reporter.note(
+                "Failure allowed",
+                &format!("{details}\n\nThe command sets `allow_failure`, so the hook goes on."),
+            );
+        } else {
+            self.reporter.error(title, details, suggestion);
+        }
+    }
+
+    /// Execute global commands for a hook, if any, and return timing information.
+    fn run_global_commands(
+        &self,
+        context: &ExecutionContext,
+        hook: &Hook,
+    ) -> Result<Vec<CommandTiming>> {
+        match &hook.commands {
+            Some(commands) => self.run_commands_for_scope(context, commands, None),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Execute the command groups of a hook, if any, one group after the other.
+    /// Returns timing information for each command executed.
+    fn run_command_groups(
+        &self,
+        context: &ExecutionContext,
+        hook: &Hook,
+    ) -> Result<Vec<CommandTiming>> {
+        let Some(groups) = &hook.groups else {
+            return Ok(Vec::new());
+        };
+
+        if self.verbose || self.dry_run {
+            print_group_plan(groups);
+        }
+
+        // Parallel commands would interleave their output, so it is always captured
+        let parallel_context = ExecutionContext {
+            max_output_bytes: Some(
+                context
+                    .max_output_bytes
+                    .or(context.options.max_output_bytes)
+                    .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES),
+            ),
+            ..context.clone()
+        };
+
+        let mut timings = Vec::new();
+        for group in groups {
+            if self.dry_run || group.len() < 2 {
+                timings.extend(self.run_commands_for_scope(context, group, None)?);
+            } else {
+                timings.extend(self.run_parallel_group(&parallel_context, group)?);
+            }
+        }
+
+        Ok(timings)
+    }
+
+    /// Execute the commands of a group in parallel and wait for all of them, even if one
+    /// fails. Observer notifications are serialized, so they never overlap.
+    ///
+    /// Only one command can read hooksmith's standard input: the first one setting `stdin`.
+    /// The standard input of the others is closed, unless the hook's input is replayed to
+    /// each command.
+    ///
+    /// # Errors
+    /// * The error of the first failing command, in group order
+    fn run_parallel_group(
+        &self,
+        context: &ExecutionContext,
+        group: &[HookCommand],
+    ) -> Result<Vec<CommandTiming>> {
+        let total_commands = group.len();
+        let notifications = Mutex::new(());
+        let stdin_reader = group.iter().position(|hook_command| hook_command.stdin);
+        let sink = output::current();
+
+        let results: Vec<Result<CommandTiming>> = thread::scope(|scope| {
+            let handles: Vec<_> = group
+                .iter()
+                .enumerate()
+                .map(|(idx, hook_command)| {
+                    let notifications = &notifications;
+                    let sink = sink.clone();
+
+                    scope.spawn(move || {
+                        let _sink = output::redirect(sink);
+                        if let Some(signal) = process::pending_signal() {
+                            return Err(HookExecutionError::Interrupted(signal).into());
+                        }
+                        if context.is_out_of_time() {
+                            return Err(self.hook_timeout_error(context, hook_command));
+                        }
+
+                        {
+                            let _lock = notifications.lock();
+                            self.observer().on_command_start(
+                                context.hook_name,
+                                hook_command,
+                                idx,
+                                total_commands,
+                            );
+                        }
+
+                        let command_context = ExecutionContext {
+                            null_stdin: stdin_reader != Some(idx),
+                            ..context.clone()
+                        };
+                        let start_time = Instant::now();
+                        let result =
+                            self.execute_single_command(&command_context, hook_command, None);
+                        let timing = CommandTiming::new(hook_command, start_time.elapsed());
+
+                        let _lock = notifications.lock();
+                        match result {
+                            Ok(exit_code) => {
+                                let timing = CommandTiming {
+                                    exit_code,
+                                    ..timing
+                                };
+                                self.observer().on_command_finish(
+                                    context.hook_name,
+                                    hook_command,
+                                    Ok(&timing),
+                                );
+                                Ok(timing)
+                            }
+                            Err(e) => {
+                                self.observer().on_command_finish(
+                                    context.hook_name,
+                                    hook_command,
+                                    Err(&e),
+                                );
+                                if Self::is_allowed_failure(hook_command, &e) {
+                                    Ok(CommandTiming {
+                                        failed: true,
+                                        exit_code: failure_exit_code(&e),
+                                        ..timing
+                                    })
+                                } else {
+                                    Err(e)
+                                }
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+
+        results.into_iter().collect()
+    }
+
+    /// Execute path-scoped commands that match changed files for the hook.
+    /// Returns timing information for commands executed.
+    fn run_path_scoped_commands(
+        &self,
+        context: &mut ExecutionContext,
+        hook: &Hook,
+    ) -> Result<Vec<CommandTiming>> {
+        let Some(paths_map) = &hook.paths else {
+            return Ok(Vec::new());
+        };
+
+        let changed_files = match &context.changed_files {
+            Some(files) => Some(files.clone()),
+            None => Self::detect_changed_files(context.hook_name),
+        };
+
+        let mut timings = Vec::new();
+        for (path_prefix, path_cfg) in paths_map {
+            let Some(changed_files) = &changed_files else {
+                self.skip(
+                    context,
+                    format!("`paths: {path_prefix}`"),
+                    &format!(
+                        "changed files can't be detected for `{}`",
+                        context.hook_name
+                    ),
+                );
+                continue;
+            };
+
+            let has_match = changed_files.iter().any(|f| f.starts_with(path_prefix));
+            if !has_match {
+                self.skip(
+                    context,
+                    format!("`paths: {path_prefix}`"),
+                    "no changed file matches",
+                );
+                continue;
+            }
+
+            let mut command_timings = self.run_commands_for_scope(
+                context,
+                &path_cfg.commands,
+                path_cfg.working_directory.as_deref(),
+            )?;
+            timings.append(&mut command_timings);
+        }
+
+        Ok(timings)
+    }
+
+    /// Expand glob patterns (e.g. `pre-*`) against the configured hook names.
+    /// Exact names are kept as given; results are deduplicated, keeping the first occurrence.
+    ///
+    /// # Arguments
+    /// * `names` - Hook names or glob patterns
+    ///
+    /// # Errors
+    /// * If a pattern matches no configured hook
+    fn resolve_hook_patterns(&self, names: &[String]) -> Result<Vec<String>> {
+        let mut configured_hooks = self.get_available_hooks();
+        configured_hooks.sort();
+
+        let mut resolved: Vec<String> = Vec::new();
+
+        for name in names {
+            let matches = if is_glob_pattern(name) {
+                let matches = configured_hooks
+                    .iter()
+                    .filter(|hook_name| glob_match(name, hook_name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                if matches.is_empty() {
+                    self.reporter.error(
+                        "No matching hooks",
+                        &format!("Pattern '{name}' doesn't match any configured hook"),
+                        &format!(
+                            "Available hooks:\n{}\n\nPlease check your pattern.",
+                            format_list(&configured_hooks)
+                        ),
+                    );
+
+                    return Err(HookExecutionError::HookNotFound(name.clone()).into());
+                }
+
+                matches
+            } else {
+                vec![name.clone()]
+            };
+
+            for hook_name in matches {
+                if !resolved.contains(&hook_name) {
+                    resolved.push(hook_name);
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Leave hooks out of a selection (`--except`).
+    ///
+    /// # Arguments
+    /// * `hooks` - The selected hooks
+    /// * `except` - Names of the hooks to leave out
+    ///
+    /// # Errors
+    /// * If a hook to leave out isn't configured, which is likely a typo
+    /// * If every selected hook is left out
+    fn exclude_hooks(&self, hooks: Vec<String>, except: &[String]) -> Result<Vec<String>> {
+        if except.is_empty() {
+            return Ok(hooks);
+        }
+
+        let unknown = except
+            .iter()
+            .filter(|name| !self.config.hooks.contains_key(*name))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            let mut configured_hooks = self.get_available_hooks();
+            configured_hooks.sort();
+
+            self.reporter.error(
+                "Unknown hooks to exclude",
+                &format!(
+                    "These hooks can't be excluded since they aren't configured:\n{}",
+                    format_list(&unknown)
+                ),
+                &format!(
+                    "Available hooks:\n{}\n\nPlease check the names given to `--except`.",
+                    format_list(&configured_hooks)
+                ),
+            );
+
+            return Err(HookExecutionError::HookNotFound(
+                unknown
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .into());
+        }
+
+        let hooks = hooks
+            .into_iter()
+            .filter(|hook| !except.contains(hook))
+            .collect::<Vec<_>>();
+        if hooks.is_empty() {
+            return Err(HookExecutionError::HookNotFound(
+                "Every selected hook is excluded".to_string(),
+            )
+            .into());
+        }
+
+        Ok(hooks)
+    }
+
+    /// Runs hooks either interactively or from provided names.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Optional vector of hook names or glob patterns (e.g. `pre-*`) to run. If None, and `options.interactive` is true, will prompt for selection, or with `options.all`, every hook runs, in Git's workflow order (`pre-commit` before `commit-msg` before `pre-push`).
+    /// * `options` - Options of the run, e.g. interactive selection, profiling, or the arguments forwarded to every command as `$1`, `$2`, ...
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    /// * If hook selection fails
+    /// * If any hook is not found in the configuration
+    pub fn run_hook(&self, hook_names: Option<&[String]>, options: &RunOptions) -> Result<()> {
+        let hooks = if options.interactive {
+            self.select_hooks_interactively()?
+        } else if let Some(names) = hook_names {
+            if names.is_empty() {
+                return Err(
+                    HookExecutionError::HookNotFound("No hooks specified".to_string()).into(),
+                );
+            }
+
+            self.resolve_hook_patterns(names)?
+        } else if options.all {
+            let mut hooks = self.get_available_hooks();
+            hooks.sort_by_key(|hook_name| (lifecycle_position(hook_name), hook_name.clone()));
+            hooks
+        } else {
+            return Err(HookExecutionError::HookNotFound(
+                "No hook specified and interactive mode is disabled".to_string(),
+            )
+            .into());
+        };
+        let hooks = self.exclude_hooks(hooks, &options.except)?;
+
+        if self.verbose {
+            outln!("📋 Selected hooks: {}", hooks.join(", "));
+        }
+
+        if let Some(runs) = options.bench {
+            self.bench_hooks(&hooks, options, runs)
+        } else if options.profile {
+            self.run_hooks_with_timing(&hooks, options)
+        } else {
+            self.run_hooks(&hooks, options)
+        }
+    }
+
+    /// Run hooks and return their results instead of printing them, e.g. from a `build.rs`
+    /// script or a custom tool. The output of every command is captured into the report.
+    ///
+    /// # Arguments
+    /// * `names` - Names of the hooks, or glob patterns (e.g. `pre-*`), to run
+    ///
+    /// # Errors
+    /// * If any hook is not found in the configuration
+    /// * If a command cannot be executed, or the run is interrupted
+    ///
+    /// # Returns
+    /// * The results of the hooks that ran. A failing command doesn't make this fail: the
+    ///   report has `success: false`, and the failing command its exit code and error.
+    pub fn run_hook_collect(&mut self, names: &[String]) -> Result<RunReport> {
+        self.run_hook_collect_with_options(Some(names), &RunOptions::new())
+    }
+
+    /// Run hooks as `run_hook` does, and return their results instead of printing them. The
+    /// output of every command is captured into the report, and nothing is printed, not even
+    /// warnings or skipped hooks. The observer set with `set_observer` isn't notified while
+    /// the hooks run.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Names of the hooks, or glob patterns, to run, as for `run_hook`
+    /// * `options` - Options of the run; the output is captured whatever `capture` says
+    ///
+    /// # Errors
+    /// * If hook selection fails
+    /// * If any hook is not found in the configuration
+    /// * If a command cannot be executed, or the run is interrupted
+    ///
+    /// # Returns
+    /// * The results of the hooks that ran, with `success: false` if a command failed or
+    ///   exceeded its timeout
+    pub fn run_hook_collect_with_options(
+        &mut self,
+        hook_names: Option<&[String]>,
+        options: &RunOptions,
+    ) -> Result<RunReport> {
+        let recorder = RunRecorder::new();
+        let options = RunOptions {
+            capture: true,
+            ..options.clone()
+        };
+
+        let previous = std::mem::replace(&mut self.observer, recorder.observer(None));
+        let result = {
+            let _silence = output::redirect(Sink::Discard);
+            self.run_hook(hook_names, &options)
+        };
+        self.observer = previous;
+
+        let mut report = recorder.report();
+        report.success &= result.is_ok();
+        match result {
+            Ok(())
+            | Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(_)
+                | HookExecutionError::HookTimeout { .. }
+                | HookExecutionError::CommandTimeout { .. },
+            )) => Ok(report),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Run hooks several times and print the min/mean/max duration of each command across
+    /// the runs, then the slowest commands. The benchmark stops at the first failing run.
+    ///
+    /// # Arguments
+    /// * `hook_names` - Names of the hooks to run
+    /// * `options` - Options of each run
+    /// * `runs` - How many times the hooks run
+    ///
+    /// # Errors
+    /// * If `runs` is zero
+    /// * If a command cannot be executed or fails
+    /// * If any hook is not found in the configuration
+    fn bench_hooks(&self, hook_names: &[String], options: &RunOptions, runs: usize) -> Result<()> {
+        if runs == 0 {
+            return Err(HookExecutionError::HookNotFound(
+                "A benchmark needs at least one run".to_string(),
+            )
+            .into());
+        }
+
+        outln!(
+            "⏱️  Benchmarking {} over {runs} run(s). The commands really run each time, side effects included.\n",
+            hook_names.join(", ")
+        );
+
+        self.start_output_log(options)?;
+
+        self.run_around_hooks(options, || {
+            let mut report = BenchReport::new();
+            for _ in 0..runs {
+                let started = Instant::now();
+                let mut hook_timings = Vec::new();
+
+                for (hook_idx, hook_name) in hook_names.iter().enumerate() {
+                    self.observer()
+                        .on_hook_start(hook_name, hook_idx, hook_names.len());
+                    hook_timings.push(self.run_hook_internal_with_timing(hook_name, options)?);
+                }
+
+                report.record_run(&hook_timings, started.elapsed());
+            }
+
+            out!("{}", report.render());
+
+            Ok(())
+        })
+    }
+
+    /// Removes the installed file of a configured hook, if there is one.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook to remove.
+    ///
+    /// # Returns
+    /// * `true` if the hook file was removed (or would be, in dry run mode), `false` if no file was found.
+    ///
+    /// # Errors
+    /// * If the git hooks directory cannot be resolved or the file cannot be removed.
+    fn remove_hook_file(&self, hook_name: &str) -> Result<bool> {
+        self.remove_hook_file_in(&get_git_hooks_path()?, hook_name)
+    }
+
+    /// Remove the file of a hook from the given hooks directory, see `remove_hook_file`.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `hook_name` - The name of the hook to remove
+    ///
+    /// # Errors
+    /// * If the file cannot be removed
+    fn remove_hook_file_in(&self, git_hooks_path: &Path, hook_name: &str) -> Result<bool> {
+        if self.verbose && !self.dry_run {
+            outln!("🗑️ Uninstalling hook: {hook_name}");
+        }
+
+        let hook_path = git_hooks_path.join(hook_name);
+
+        if !hook_path.exists() {
+            return Ok(false);
+        }
+
+        if self.dry_run {
+            outln!(
+                "  🚧 Dry run: Would remove hook file: {}",
+                hook_path.display()
+            );
+        } else {
+            fs::remove_file(&hook_path)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Uninstalls a single, given hook by removing its file.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook to run.
+    ///
+    /// # Errors
+    /// * Errors if the command fails to remove the file.
+    pub fn uninstall_given_hook(&self, hook_name: &str) -> Result<()> {
+        if self.config.hooks.contains_key(hook_name) {
+            if !self.remove_hook_file(hook_name)? {
+                outln!("  ⚠️ No hook file found for {hook_name}");
+            }
+        } else {
+            let possible_hooks = self.config.hooks.keys().collect::<Vec<_>>();
+            eprintln!("No file found for hook '{hook_name}'");
+            eprintln!("Possible hooks: {possible_hooks:?}");
+
+            return Err(ValidationError::InvalidHookName(hook_name.to_string()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Uninstalls the given hooks, expanding glob patterns (e.g. `pre-*`) against the
+    /// configured hooks. When a pattern matches several hooks, asks for confirmation first,
+    /// unless `assume_yes` is set or in dry run mode.
+    ///
+    /// # Arguments
+    /// * `names` - Hook names or glob patterns
+    /// * `assume_yes` - Whether to uninstall without asking for confirmation
+    ///
+    /// # Errors
+    /// * If a pattern matches no configured hook
+    /// * If a name is not a configured hook
+    /// * If the confirmation prompt fails
+    /// * If a hook file cannot be removed
+    pub fn uninstall_given_hooks(&self, names: &[String], assume_yes: bool) -> Result<()> {
+        let hook_names = self.resolve_hook_patterns(names)?;
+
+        let expands_to_several = names.iter().any(|name| {
+            is_glob_pattern(name)
+                && hook_names
+                    .iter()
+                    .filter(|hook_name| glob_match(name, hook_name))
+                    .count()
+                    > 1
+        });
+
+        if expands_to_several && !assume_yes && !self.dry_run {
+            let confirmed = Confirm::with_theme(&my_clap_theme::ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Uninstall {} hooks ({})?",
+                    hook_names.len(),
+                    hook_names.join(", ")
+                ))
+                .default(false)
+                .interact()
+                .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+            if !confirmed {
+                outln!("❌ Uninstallation cancelled");
+                return Ok(());
+            }
+        }
+
+        for hook_name in &hook_names {
+            self.uninstall_given_hook(hook_name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Uninstalls all configured hooks and reports which ones were actually removed.
+    /// Hooks without an installed file are skipped and left out of the result. A file that
+    /// can't be removed doesn't stop the others from being removed.
+    ///
+    /// # Errors
+    /// * If the git hooks directory cannot be resolved
+    /// * If the files of some hooks cannot be removed, once all the others are
+    ///
+    /// # Returns
+    /// * `Vec<String>` - Sorted names of the hooks whose files were removed (or would be, in dry run mode)
+    pub fn uninstall_all(&self) -> Result<Vec<String>> {
+        let removal = self.remove_hook_files(&get_git_hooks_path()?);
+        self.report_removal_failures(&removal.failed)?;
+
+        Ok(removal.removed)
+    }
+
+    /// Uninstalls all hooks by removing their files.
+    ///
+    /// # Errors
+    /// * If the git hooks directory cannot be resolved
+    /// * If the files of some hooks cannot be removed, once all the others are
+    pub fn uninstall_hooks(&self) -> Result<()> {
+        if self.verbose && !self.dry_run {
+            outln!("🗑️ Uninstalling all hooks");
+        }
+
+        let removal = self.remove_hook_files(&get_git_hooks_path()?);
+
+        let mut not_installed = self
+            .config
+            .hooks
+            .keys()
+            .filter(|hook_name| {
+                !removal.removed.contains(hook_name)
+                    && !removal
+                        .failed
+                        .iter()
+                        .any(|(failed, _)| failed == *hook_name)
+            })
+            .collect::<Vec<_>>();
+        not_installed.sort();
+
+        for hook_name in not_installed {
+            outln!("  ⚠️ No hook file found for {hook_name}");
+        }
+
+        if self.verbose && !self.dry_run {
+            outln!(
+                "🏁 Uninstallation completed: {} hooks removed",
+                removal.removed.len()
+            );
+        }
+
+        self.report_removal_failures(&removal.failed)
+    }
+
+    /// Remove the files of all configured hooks from the given hooks directory, going on
+    /// when one of them can't be removed.
+    ///
+    /// # Arguments
+    /// * `git_hooks_path` - Path to the git hooks directory
+    fn remove_hook_files(&self, git_hooks_path: &Path) -> HookRemoval {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut removal = HookRemoval::default();
+        for hook_name in hook_names {
+            match self.remove_hook_file_in(git_hooks_path, hook_name) {
+                Ok(true) => removal.removed.push(hook_name.clone()),
+                Ok(false) => {}
+                Err(e) => removal.failed.push((hook_name.clone(), e)),
+            }
+        }
+
+        removal
+    }
+
+    /// Report the hooks whose files couldn't be removed.
+    ///
+    /// # Arguments
+    /// * `failed` - The hooks and why their file couldn't be removed
+    ///
+    /// # Errors
+    /// * `GitError::HookRemoval` listing the hooks, if there are any
+    fn report_removal_failures(&self, failed: &[(String, HooksmithError)]) -> Result<()> {
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        let details = failed
+            .iter()
+            .map(|(hook_name, e)| format!("{hook_name}: {e}"))
+            .collect::<Vec<_>>();
+        self.reporter.error(
+            "Some hooks could not be uninstalled",
+            &format!(
+                "The files of the following hooks could not be removed, the other hooks were uninstalled:\n{}",
+                format_list(&details)
+            ),
+            "Check the permissions of the hooks directory and its files, then run `hooksmith uninstall` again.",
+        );
+
+        let hook_names = failed.iter().map(|(hook_name, _)| hook_name.clone());
+        Err(GitError::HookRemoval(hook_names.collect()).into())
+    }
+
+    /// Validate that hooks in the configuration file are standard Git hooks.
+    ///
+    /// # Errors
+    /// None, I just return Ok(()) to aggregate all calls in a `match` statement in the main function.
+    pub fn validate_hooks(&self) -> Result<()> {
+        if self.verbose {
+            outln!("🔍 Validating hooks in configuration file...");
+        }
+
+        let mut invalid_hooks = Vec::new();
+        let mut valid_hooks = 0;
+
+        for hook_name in self.config.hooks.keys() {
+            if GIT_HOOKS.contains(&hook_name.as_str()) {
+                valid_hooks += 1;
+                if self.verbose {
+                    outln!("  ✅ Hook '{hook_name}' is valid");
+                }
+            } else {
+                invalid_hooks.push(hook_name.clone());
+            }
+        }
+
+        if invalid_hooks.is_empty() {
+            if self.verbose {
+                self.reporter.success(
+                    "All hooks are valid",
+                    &format!("Found {valid_hooks} valid Git hooks in your configuration."),
+                );
+            }
+        } else {
+            self.reporter.warning(
+                "Invalid hooks detected",
+                &format!(
+                    "The following hooks are not recognized by Git:\n{}\n\nPlease use only valid Git hook names in your configuration.",
+                    format_list(&invalid_hooks)
+                ),
+            );
+        }
+
+        self.lint_unquoted_arguments();
+        self.lint_duplicate_names();
+        self.lint_duplicate_commands();
+        self.lint_scripts_dir();
+        self.lint_missing_programs();
+
+        self.validate_commands()?;
+        self.validate_placeholders()
+    }
+
+    /// Comment out the hooks of the configuration file whose names aren't Git hooks, after
+    /// listing them and asking for confirmation, unless `assume_yes` is set. The original
+    /// file is kept next to it with a `.bak` extension. In dry run mode, only lists them.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path of the configuration file the hooks were read from
+    /// * `assume_yes` - Whether to rewrite the file without asking for confirmation
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be read, backed up or written
+    /// * If an invalid hook can't be found in the file, e.g. when it comes from an alias
+    /// * If the confirmation prompt fails
+    ///
+    /// # Returns
+    /// * The names of the hooks commented out (or that would be, in dry run mode)
+    pub fn prune_config(&self, config_path: &Path, assume_yes: bool) -> Result<Vec<String>> {
+        let mut invalid_hooks = self
+            .config
+            .hooks
+            .keys()
+            .filter(|name| !GIT_HOOKS.contains(&name.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        invalid_hooks.sort();
+
+        if invalid_hooks.is_empty() {
+            outln!("✅ Every hook of the configuration is a Git hook, nothing to prune");
+            return Ok(invalid_hooks);
+        }
+
+        let content = fs::read_to_string(config_path)?;
+        let (pruned, found) = Self::comment_out_hooks(&content, &invalid_hooks);
+        let missing = invalid_hooks
+            .iter()
+            .filter(|name| !found.contains(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            self.reporter.error(
+                "Can't prune the configuration",
+                &format!(
+                    "These hooks aren't top-level keys of {}:\n{}",
+                    config_path.display(),
+                    format_list(&missing)
+                ),
+                "Remove them from the configuration by hand.",
+            );
+
+            return Err(ValidationError::InvalidHookName(missing.join(", ")).into());
+        }
+
+        outln!("Hooks not recognized by Git:");
+        for name in &invalid_hooks {
+            match suggest_hook_names(name).as_slice() {
+                [] => outln!("  - {name}"),
+                suggestions => outln!("  - {name} (did you mean {}?)", suggestions.join(", ")),
+            }
+        }
+
+        if self.dry_run {
+            outln!(
+                "\n🚧 Dry run: Would comment them out in {}",
+                config_path.display()
+            );
+            return Ok(invalid_hooks);
+        }
+
+        if !assume_yes {
+            let confirmed = Confirm::with_theme(&my_clap_theme::ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Comment out {} hook(s) in {}?",
+                    invalid_hooks.len(),
+                    config_path.display()
+                ))
+                .default(false)
+                .interact()
+                .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+            if !confirmed {
+                outln!("❌ Pruning cancelled");
+                return Ok(Vec::new());
+            }
+        }
+
+        let mut backup_path = config_path.as_os_str().to_owned();
+        backup_path.push(".bak");
+        let backup_path = PathBuf::from(backup_path);
+
+        fs::copy(config_path, &backup_path)?;
+        fs::write(config_path, pruned)?;
+
+        outln!(
+            "🧹 Commented out {} hook(s) in {} (original kept in {})",
+            invalid_hooks.len(),
+            config_path.display(),
+            backup_path.display()
+        );
+
+        Ok(invalid_hooks)
+    }
+
+    /// Warn about commands sharing a name within a hook, since their names are used as keys
+    /// of their results and recorded state.
+    ///
+    /// # Returns
+    /// * `true` if a warning was emitted
+    fn lint_duplicate_names(&self) -> bool {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut duplicates = Vec::new();
+        for hook_name in hook_names {
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for name in self.config.hooks[hook_name]
+                .all_commands()
+                .filter_map(|command| command.name.as_deref())
+            {
+                *counts.entry(name).or_default() += 1;
+            }
+
+            duplicates.extend(counts.into_iter().filter(|(_, count)| *count > 1).map(
+                |(name, count)| format!("'{name}' is used by {count} commands of '{hook_name}'"),
+            ));
+        }
+
+        if duplicates.is_empty() {
+            return false;
+        }
+
+        self.reporter.warning(
+            "Duplicate command names",
+            &format!(
+                "Command names identify results and recorded state, so they should be unique within a hook:\n{}\n\nRename the commands so that each name is used once.",
+                format_list(&duplicates)
+            ),
+        );
+
+        true
+    }
+
+    /// Warn about commands repeated within the `commands` and `groups` of a hook, which is
+    /// usually a copy-paste mistake. Path-based blocks and handlers are left out, as are
+    /// hooks setting `allow_duplicate_commands`.
+    ///
+    /// # Returns
+    /// * `true` if a warning was emitted
+    fn lint_duplicate_commands(&self) -> bool {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut duplicates = Vec::new();
+        for hook_name in hook_names {
+            let hook = &self.config.hooks[hook_name];
+            if hook.allow_duplicate_commands {
+                continue;
+            }
+
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for command in hook
+                .commands
+                .iter()
+                .flatten()
+                .chain(hook.groups.iter().flatten().flatten())
+            {
+                *counts.entry(command.command.as_str()).or_default() += 1;
+            }
+
+            duplicates.extend(counts.into_iter().filter(|(_, count)| *count > 1).map(
+                |(command, count)| format!("`{command}` appears {count} times in '{hook_name}'"),
+            ));
+        }
+
+        if duplicates.is_empty() {
+            return false;
+        }
+
+        self.reporter.warning(
+            "Duplicate commands",
+            &format!(
+                "These commands run more than once in the same hook, which is usually a copy-paste mistake:\n{}\n\nRemove the extra copies, or set `allow_duplicate_commands: true` on the hook if they are intended.",
+                format_list(&duplicates)
+            ),
+        );
+
+        true
+    }
+
+    /// Warn about the commands running a program that isn't on `PATH`, e.g. a misspelled
+    /// `carg fmt`. Only a warning: the program may be installed by the time the hook runs.
+    /// Paths, shell builtins, commands the shell expands and commands run through a
+    /// `command_prefix` (e.g. in a container) aren't checked.
+    ///
+    /// # Returns
+    /// * `true` if a warning was emitted
+    fn lint_missing_programs(&self) -> bool {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut missing = Vec::new();
+        for hook_name in hook_names {
+            let hook = &self.config.hooks[hook_name];
+            let prefixed = hook
+                .command_prefix
+                .as_deref()
+                .or(self.config.command_prefix.as_deref())
+                .is_some_and(|prefix| !prefix.is_empty());
+            if prefixed {
+                continue;
+            }
+
+            for hook_command in hook.all_commands() {
+                let Some(program) = command_program(&hook_command.command) else {
+                    continue;
+                };
+
+                if hook_command.builtin.is_none()
+                    && !program.contains('/')
+                    && !SHELL_BUILTINS.contains(&program)
+                    && !is_in_path(program)
+                {
+                    missing.push(format!(
+                        "`{program}` in '{hook_name}' command `{}`",
+                        hook_command.command
+                    ));
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            return false;
+        }
+
+        self.reporter.warning(
+            "Programs not found",
+            &format!(
+                "The following programs are not on PATH, the commands running them will fail unless they are installed:\n{}",
+                format_list(&missing)
+            ),
+        );
+
+        true
+    }
+
+    /// Warn about the files of `scripts_dir` that won't run: those not named after a Git
+    /// hook (hidden files aside), and on Unix those that aren't executable.
+    ///
+    /// # Returns
+    /// * `true` if a warning was emitted
+    fn lint_scripts_dir(&self) -> bool {
+        let Some(dir) = Self::scripts_dir_path(&self.config, self.sources.repository.as_deref())
+        else {
+            return false;
+        };
+
+        let mut problems = Vec::new();
+        let mut entries = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(std::fs::DirEntry::file_name);
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if !GIT_HOOKS.contains(&name.as_str()) {
+                let suggestion = suggest_hook_names(&name)
+                    .first()
+                    .map(|hook| format!(" (did you mean '{hook}'?)"))
+                    .unwrap_or_default();
+                problems.push(format!("'{name}' is not a Git hook name{suggestion}"));
+                continue;
+            }
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                if entry
+                    .metadata()
+                    .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 == 0)
+                {
+                    problems.push(format!("'{name}' is not executable"));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return false;
+        }
+
+        self.reporter.warning(
+            "Scripts that won't run",
+            &format!(
+                "These files of the scripts directory '{}' won't run:\n{}\n\nName scripts after the Git hook they run for, and make them executable (`chmod +x`).",
+                dir.display(),
+                format_list(&problems)
+            ),
+        );
+
+        true
+    }
+
+    /// Warn about commands expanding hook arguments without quotes, which breaks on paths
+    /// containing spaces. Commands containing `# noqa` or setting `allow_unquoted` are skipped.
+    ///
+    /// # Returns
+    /// * `true` if a warning was emitted
+    fn lint_unquoted_arguments(&self) -> bool {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut unquoted = Vec::new();
+        for hook_name in hook_names {
+            for hook_command in self.config.hooks[hook_name].all_commands() {
+                if hook_command.allow_unquoted {
+                    continue;
+                }
+
+                for expansion in unquoted_arguments(hook_name, &hook_command.command) {
+                    unquoted.push(format!(
+                        "{expansion} in '{hook_name}' command `{}`",
+                        hook_command.command
+                    ));
+                }
+            }
+        }
+
+        if unquoted.is_empty() {
+            return false;
+        }
+
+        self.reporter.warning(
+            "Unquoted hook arguments",
+            &format!(
+                "The following hook arguments are expanded without quotes, which breaks on paths containing spaces:\n{}\n\nWrap them in double quotes (e.g. \"$1\" or \"{{msg_file}}\"), or set `allow_unquoted: true` on the command to silence this warning.",
+                format_list(&unquoted)
+            ),
+        );
+
+        true
+    }
+
+    /// Validate that commands can be run by the shell: they aren't empty, and close their
+    /// quotes.
+    ///
+    /// # Errors
+    /// * If a command is empty or leaves a quote open.
+    fn validate_commands(&self) -> Result<()> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut invalid = Vec::new();
+        for hook_name in hook_names {
+            for hook_command in self.config.hooks[hook_name].all_commands() {
+                let command = &hook_command.command;
+                if command.trim().is_empty() {
+                    invalid.push(format!("empty command in '{hook_name}'"));
+                } else if let Some(quote) = unbalanced_quote(command) {
+                    invalid.push(format!(
+                        "unclosed {quote} in '{hook_name}' command `{command}`"
+                    ));
+                }
+            }
+        }
+
+        if invalid.is_empty() {
+            return Ok(());
+        }
+
+        self.reporter.error(
+            "Invalid commands",
+            &format!(
+                "The following commands can't be run by the shell:\n{}",
+                format_list(&invalid)
+            ),
+            "Remove the empty commands, and close the quotes (or escape them with a backslash).",
+        );
+
+        Err(ValidationError::InvalidCommand(invalid.join(", ")).into())
+    }
+
+    /// Validate that the placeholders used in commands are known for their hook.
+    ///
+    /// # Errors
+    /// * If a command uses a placeholder that isn't available for its hook.
+    fn validate_placeholders(&self) -> Result<()> {
+        let unknown = self.unknown_placeholder_usages();
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        self.reporter.error(
+            "Unknown placeholders",
+            &format!(
+                "The following placeholders are not available for their hook:\n{}",
+                format_list(&unknown)
+            ),
+            "Check the placeholders supported by each hook in the documentation, or use `$1`, `$2`, ... instead.",
+        );
+
+        Err(ValidationError::InvalidCommand(unknown.join(", ")).into())
+    }
+
+    /// Describe each use of a placeholder that isn't available for its hook.
+    fn unknown_placeholder_usages(&self) -> Vec<String> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut unknown = Vec::new();
+        for hook_name in hook_names {
+            let available = hook_argument_names(hook_name)
+                .iter()
+                .chain([&STAGED_FILES])
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            for hook_command in self.config.hooks[hook_name].all_commands() {
+                for placeholder in unknown_placeholders(hook_name, &hook_command.command) {
+                    unknown.push(format!(
+                        "{{{placeholder}}} in '{hook_name}' command `{}` (available: {available})",
+                        hook_command.command
+                    ));
+                }
+            }
+        }
+
+        unknown
+    }
+
+    /// Check that the environment variables commands require (`${NAME}` without a default)
+    /// are defined: by the `env` setting, the current environment, hooksmith or Git.
+    /// Unlike `validate_hooks`, the result depends on the environment hooksmith runs in, so
+    /// installing hooks doesn't check it.
+    ///
+    /// # Errors
+    /// * If a command requires an undefined variable
+    pub fn validate_variables(&self) -> Result<()> {
+        let undefined = self.undefined_variable_usages();
+
+        if undefined.is_empty() {
+            return Ok(());
+        }
+
+        self.reporter.error(
+            "Undefined variables",
+            &format!(
+                "The following variables are neither set in `env` nor in the environment:\n{}",
+                format_list(&undefined)
+            ),
+            "Set them in `env`, or write `${NAME:-default}` for variables that are optional.",
+        );
+
+        Err(ValidationError::InvalidCommand(undefined.join(", ")).into())
+    }
+
+    /// Describe each use of an undefined variable, see `validate_variables`.
+    fn undefined_variable_usages(&self) -> Vec<String> {
+        let mut hook_names = self.config.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        let mut undefined = Vec::new();
+        for hook_name in hook_names {
+            let hook = &self.config.hooks[hook_name];
+            let is_defined = |name: &str, hook_command: &HookCommand| {
+                self.config.env.contains_key(name)
+                    || hook.env.contains_key(name)
+                    || hook_command.env.contains_key(name)
+                    || std::env::var_os(name).is_some()
+                    || [
+                        CHANGED_FILES_ENV,
+                        HOOK_OUTCOME_ENV,
+                        HOOK_DURATION_ENV,
+                        HOOK_ERROR_ENV,
+                    ]
+                    .contains(&name)
+                    || HookInput::names(hook_name).contains(&name)
+                    || GIT_HOOK_VARIABLES.contains(&name)
+            };
+
+            for hook_command in hook.all_commands() {
+                for name in required_variables(&hook_command.command) {
+                    if !is_defined(&name, hook_command) {
+                        undefined.push(format!(
+                            "${{{name}}} in '{hook_name}' command `{}`",
+                            hook_command.command
+                        ));
                     }
                 }
             }
         }
+
+        undefined
+    }
+
+    /// Validate hooks configuration before installation.
+    ///
+    /// # Errors
+    /// * If any invalid hook names are found.
+    pub fn validate_hooks_for_install(&self) -> Result<()> {
+        if self.verbose {
+            outln!("🔍 Validating hooks before installation...");
+        }
+
+        let mut invalid_hooks = Vec::new();
+        for hook_name in self.config.hooks.keys() {
+            if !GIT_HOOKS.contains(&hook_name.as_str()) {
+                invalid_hooks.push(hook_name.clone());
+            }
+        }
+
+        if !invalid_hooks.is_empty() {
+            let error_message = format!(
+                "Invalid hook names detected\n\nThe following hooks are not recognized by Git:\n{}\n\nPlease check your configuration file and use only valid Git hook names.",
+                format_list(&invalid_hooks)
+            );
+
+            return Err(ValidationError::InvalidHookName(error_message).into());
+        }
+
+        self.validate_commands()?;
+        self.validate_placeholders()
+    }
+
+    /// Executes a command.
+    ///
+    /// # Arguments
+    /// * `hook_command` - The configured command, given to the observer with its output.
+    /// * `command` - The command to execute, with its placeholders expanded.
+    /// * `working_directory` - Optional directory to run the command in.
+    /// * `context` - The hook run the command belongs to.
+    ///
+    /// # Errors
+    /// * If a command cannot be executed
+    ///
+    /// # Returns
+    /// * `Some(status)` once the command exits, `None` if it was stopped because the hook ran out of time
+    fn execute_command(
+        &self,
+        hook_command: &HookCommand,
+        command: &str,
+        working_directory: Option<&Path>,
+        context: &ExecutionContext,
+    ) -> Result<Option<ExitStatus>> {
+        if self.dry_run {
+            match self
+                .resolve_command(command, working_directory, context)
+                .cwd
+            {
+                Some(dir) => outln!("🔍 Would execute: {command} (in {})", dir.display()),
+                None => outln!("🔍 Would execute: {command}"),
+            }
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::process::ExitStatusExt;
+
+                Ok(Some(ExitStatusExt::from_raw(0)))
+            }
+            #[cfg(windows)]
+            {
+                use std::os::windows::process::ExitStatusExt;
+
+                Ok(Some(ExitStatusExt::from_raw(0)))
+            }
+        } else {
+            let resolved = self.resolve_command(command, working_directory, context);
+            let mut cmd = Command::new(&resolved.program);
+            add_program_args(&mut cmd, &resolved);
+            cmd.envs(&resolved.env);
+            if let Some(dir) = &resolved.cwd {
+                cmd.current_dir(dir);
+            }
+            #[cfg(unix)]
+            if let Some(mask) = context.umask {
+                process::set_umask(&mut cmd, mask);
+            }
+
+            let log = match &context.options.tee {
+                Some(path) => Some(Arc::new(Mutex::new(
+                    OpenOptions::new().create(true).append(true).open(path)?,
+                ))),
+                None => None,
+            };
+            if context.max_output_bytes.is_some() || log.is_some() {
+                cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+            } else if output::is_stdout_redirected() {
+                cmd.stdout(std::io::stderr());
+            }
+            if context.input.is_some() {
+                cmd.stdin(Stdio::piped());
+            } else if context.null_stdin {
+                cmd.stdin(Stdio::null());
+            }
+
+            // The command stops at its own timeout, or earlier when the hook runs out of time
+            let command_deadline = context
+                .command_timeout
+                .map(|timeout| Instant::now() + timeout);
+            let deadline = match (context.deadline(), command_deadline) {
+                (Some(hook), Some(command)) => Some(hook.min(command)),
+                (hook, command) => hook.or(command),
+            };
+            // In their own process group, commands are stopped along with everything they
+            // started, when they run out of time or hooksmith is interrupted. Commands
+            // inheriting the terminal stay in the group of hooksmith instead, so that they
+            // get `Ctrl-C` and can read from it, unless they may run out of time: they are
+            // then given the terminal while they run.
+            let has_terminal =
+                context.input.is_none() && !context.null_stdin && process::owns_terminal();
+            let _terminal = if deadline.is_some() || !has_terminal {
+                process::isolate_process_group(&mut cmd, has_terminal)
+            } else {
+                None
+            };
+
+            let mut child = cmd.spawn()?;
+            // Captured output is printed once the command finishes, otherwise a log gets a
+            // copy of the output while it is printed
+            let capture = context
+                .max_output_bytes
+                .map(|max_bytes| process::capture_output(&mut child, max_bytes, log.as_ref()));
+            let tee = match (&capture, &log) {
+                (None, Some(log)) => Some(process::tee_output(&mut child, log)),
+                _ => None,
+            };
+            // Only once the output is read, so that the command can't block on it
+            if let (Some(input), Some(stdin)) = (&context.input, child.stdin.take()) {
+                let lines = input.lines().into_iter().map(|line| line + "\n");
+                process::write_input(stdin, lines.collect::<String>().into_bytes());
+            }
+
+            let status = process::wait_until(&mut child, deadline)?;
+
+            if let Some(capture) = capture {
+                self.observer().on_command_output(
+                    context.hook_name,
+                    hook_command,
+                    &capture.finish(),
+                );
+            }
+            if let Some(tee) = tee {
+                tee.finish();
+            }
+
+            Ok(status)
+        }
+    }
+
+    /// Build the program, arguments, environment and directory a command runs with.
+    ///
+    /// # Arguments
+    /// * `command` - Command to execute, with its placeholders expanded
+    /// * `working_directory` - Optional directory to run the command in
+    /// * `context` - The hook run the command belongs to
+    fn resolve_command(
+        &self,
+        command: &str,
+        working_directory: Option<&Path>,
+        context: &ExecutionContext,
+    ) -> ResolvedCommand {
+        // `[<prefix>...] sh [-l] -c <command> <$0> <$1> ...`: the first argument after the command
+        // becomes `$0`. The prefix wraps the shell, so it applies to the whole command line
+        // without quoting it again. Other shells would read the arguments as part of the
+        // command, so they only get the command.
+        let (mut shell, is_posix) =
+            shell_invocation(&self.shell(context), self.config.login_shell == Some(true));
+        let (program, mut args) = match self.command_prefix(context).split_first() {
+            Some((program, prefix_args)) => {
+                let mut args = prefix_args.to_vec();
+                args.append(&mut shell);
+                (program.clone(), args)
+            }
+            None => (shell.remove(0), shell),
+        };
+        args.push(command.to_string());
+        if is_posix {
+            args.push("hooksmith".to_string());
+            args.extend(context.hook_args.iter().cloned());
+        }
+
+        // Later settings override earlier ones, as with successive `Command::envs` calls.
+        // Configured values can refer to the variables set before them, or to the
+        // environment hooksmith runs in.
+        let mut env = BTreeMap::new();
+        for layer in std::iter::once(&self.config.env).chain(&context.env) {
+            let values = layer
+                .iter()
+                .map(|(key, value)| {
+                    let lookup =
+                        |name: &str| env.get(name).cloned().or_else(|| std::env::var(name).ok());
+                    (key.clone(), interpolate_variables(value, lookup))
+                })
+                .collect::<Vec<_>>();
+            env.extend(values);
+        }
+        env.extend(context.options.env.clone());
+        if let Some(files) = &context.changed_files {
+            env.insert(CHANGED_FILES_ENV.to_string(), files.join("\n"));
+        }
+        if let Some(input) = &context.input {
+            env.extend(
+                input
+                    .env()
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value)),
+            );
+        }
+        env.extend(
+            context
+                .outcome_env
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), value.clone())),
+        );
+        if !context.path_prepend.is_empty() {
+            // In front of the `PATH` the command would get otherwise, `env` included
+            let path = env
+                .get("PATH")
+                .map(OsString::from)
+                .or_else(|| std::env::var_os("PATH"))
+                .unwrap_or_default();
+            let dirs = context
+                .path_prepend
+                .iter()
+                .cloned()
+                .chain(std::env::split_paths(&path));
+
+            if let Ok(path) = std::env::join_paths(dirs) {
+                env.insert("PATH".to_string(), path.to_string_lossy().into_owned());
+            }
+        }
+
+        let cwd = match (context.options.cwd.as_deref(), working_directory) {
+            (Some(cwd), Some(dir)) => Some(cwd.join(dir)),
+            (cwd, dir) => cwd.or(dir).map(Path::to_path_buf),
+        };
+
+        ResolvedCommand {
+            program,
+            args,
+            env,
+            cwd,
+        }
+    }
+
+    /// The program and arguments a hook's commands run through: the hook's own
+    /// `command_prefix`, or else the top-level one.
+    ///
+    /// # Arguments
+    /// * `context` - The hook run the commands belong to
+    fn command_prefix<'c>(&'c self, context: &'c ExecutionContext) -> &'c [String] {
+        context
+            .command_prefix
+            .as_deref()
+            .or(self.config.command_prefix.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Print the Git-related environment variables the commands of a hook inherit.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The hook about to run
+    fn print_git_environment(hook_name: &str) {
+        let variables = git_environment(std::env::vars_os());
+
+        if variables.is_empty() {
+            outln!("🔧 Git environment of `{hook_name}`: no GIT_* variables are set");
+            return;
+        }
+
+        outln!("🔧 Git environment of `{hook_name}`:");
+        for (key, value) in variables {
+            outln!("   {key}={value}");
+        }
+    }
+
+    /// The directories of `path_prepend`, relative to the root of the working tree, or to
+    /// the current directory outside of one (e.g. in server hooks).
+    fn path_prepend_dirs(&self) -> Vec<PathBuf> {
+        if self.config.path_prepend.is_empty() {
+            return Vec::new();
+        }
+
+        let root = repo_root_or_current_dir();
+
+        self.config
+            .path_prepend
+            .iter()
+            .map(|dir| root.join(dir))
+            .collect()
+    }
+
+    /// The shell running the commands of a hook: the hook's own, the one of the run
+    /// (`--shell`), the configured one, or `sh`.
+    ///
+    /// # Arguments
+    /// * `context` - The context of the hook
+    fn shell(&self, context: &ExecutionContext) -> String {
+        context
+            .shell
+            .clone()
+            .or_else(|| context.options.shell.clone())
+            .or_else(|| self.config.shell.clone())
+            .unwrap_or_else(|| default_shell().to_string())
+    }
+
+    /// The directory a command's `working_dir` points to, relative to the root of the
+    /// working tree, or to the current directory outside of one.
+    ///
+    /// # Arguments
+    /// * `context` - The context of the hook running the command
+    /// * `hook_command` - The command
+    /// * `dir` - The command's `working_dir`
+    ///
+    /// # Errors
+    /// * `HookExecutionError::WorkingDirNotFound` if the directory doesn't exist
+    fn command_working_dir(
+        &self,
+        context: &ExecutionContext,
+        hook_command: &HookCommand,
+        dir: &str,
+    ) -> Result<PathBuf> {
+        let path = repo_root_or_current_dir().join(dir);
+
+        if !path.is_dir() {
+            self.report_command_failure(
+                hook_command,
+                "Working directory not found",
+                &format!(
+                    "Hook '{}' command `{}` runs in '{dir}', but '{}' is not a directory",
+                    context.hook_name,
+                    hook_command.label(),
+                    path.display()
+                ),
+                "Fix the command's `working_dir`, relative to the root of the repository.",
+            );
+
+            return Err(HookExecutionError::WorkingDirNotFound(path.display().to_string()).into());
+        }
+
+        Ok(path)
+    }
+
+    /// Read the configuration file and parse it into a Config struct.
+    ///
+    /// # Arguments
+    /// * `config_path` - Path to the configuration file
+    ///
+    /// # Errors
+    /// * If the configuration file cannot be read or parsed
+    ///
+    /// # Returns
+    /// * `Config` - Parsed configuration file
+    fn read_config(config_path: &Path) -> Result<Config> {
+        let config_string = fs::read_to_string(config_path)?;
+
+        match embedded_config(config_path, &config_string)? {
+            Some(value) => serde_yaml::from_value(value)
+                .map_err(|err| HooksmithError::Config(ConfigError::Parse(err))),
+            None => Self::read_config_str(&config_string),
+        }
+    }
+
+    /// Parse a configuration, whatever it was read from.
+    ///
+    /// # Arguments
+    /// * `content` - The configuration, as YAML
+    ///
+    /// # Errors
+    /// * If the configuration cannot be parsed
+    fn read_config_str(content: &str) -> Result<Config> {
+        match serde_yaml::from_str(content) {
+            Ok(config) => Ok(config),
+            Err(err) => Err(HooksmithError::Config(ConfigError::Parse(err))),
+        }
+    }
+
+    /// Parse the settings of a configuration and the given hooks only, see
+    /// `new_from_config_for_hooks`. Keys that aren't Git hooks are always kept.
+    ///
+    /// # Arguments
+    /// * `value` - The configuration, as a YAML value
+    /// * `hook_names` - Names of the hooks to keep, or glob patterns matching them
+    ///
+    /// # Errors
+    /// * If the configuration cannot be parsed
+    fn read_config_value_for_hooks(
+        mut value: serde_yaml::Value,
+        hook_names: &[String],
+    ) -> Result<Config> {
+        let is_selected = |key: &str| {
+            hook_names.iter().any(|name| {
+                if is_glob_pattern(name) {
+                    glob_match(name, key)
+                } else {
+                    name == key
+                }
+            })
+        };
+
+        if let Some(mapping) = value.as_mapping_mut() {
+            let has_selected = mapping
+                .keys()
+                .filter_map(serde_yaml::Value::as_str)
+                .any(is_selected);
+
+            if has_selected {
+                mapping.retain(|key, _| {
+                    key.as_str()
+                        .is_none_or(|key| !GIT_HOOKS.contains(&key) || is_selected(key))
+                });
+            }
+        }
+
+        serde_yaml::from_value(value).map_err(|err| HooksmithError::Config(ConfigError::Parse(err)))
     }
 
-    /// Compare installed hooks with the configuration file.
+    /// Select hooks interactively using `dialoguer`.
     ///
     /// # Errors
-    /// * If there is an error reading the git hooks directory.
-    pub fn compare_hooks(&self) -> Result<()> {
-        let git_hooks_path = get_git_hooks_path()?;
-        let mut differences_found = false;
+    /// * If the user cancels the selection, or an error occurs during selection
+    /// * If the selection is empty
+    ///
+    /// # Returns
+    /// * `Vec<String>` - Selected hooks
+    fn select_hooks_interactively(&self) -> Result<Vec<String>> {
+        let hooks = self.get_available_hooks();
 
-        if self.verbose {
-            println!("🔍 Comparing installed hooks with configuration file...");
+        if hooks.is_empty() {
+            return Err(HookExecutionError::HookNotFound(
+                "No hooks available in configuration".to_string(),
+            )
+            .into());
+        }
+
+        let items = hooks
+            .iter()
+            .map(|hook_name| {
+                let description = self.config.hooks[hook_name].description.as_deref();
+                selection_item(hook_name, description)
+            })
+            .collect::<Vec<_>>();
+
+        let selections = MultiSelect::with_theme(&my_clap_theme::ColorfulTheme::default())
+            .with_prompt("Select hooks to run (Space to select, Enter to confirm)")
+            .items(&items)
+            .interact()
+            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+
+        if selections.is_empty() {
+            return Err(HookExecutionError::HookNotFound("No hooks selected".to_string()).into());
+        }
+
+        Ok(selections.into_iter().map(|i| hooks[i].clone()).collect())
+    }
+}
+
+/// A hook as an item of an interactive selection: its name, followed by its description
+/// when it has one.
+///
+/// # Arguments
+/// * `hook_name` - Name of the hook
+/// * `description` - What the hook is for
+fn selection_item(hook_name: &str, description: Option<&str>) -> String {
+    match description {
+        Some(description) => format!("{hook_name} {}", style(format!("- {description}")).dim()),
+        None => hook_name.to_string(),
+    }
+}
+
+/// Whether the `on_success` or `on_failure` commands of the given hook are running, in
+/// which case they triggered it again, see `HANDLER_HOOKS_ENV`.
+fn is_running_handler_of(hook_name: &str) -> bool {
+    std::env::var(HANDLER_HOOKS_ENV)
+        .is_ok_and(|hooks| hooks.split(':').any(|running| running == hook_name))
+}
+
+/// The value of `HANDLER_HOOKS_ENV` for the handlers of the given hook: the hooks whose
+/// handlers are already running, then this one.
+fn handler_hooks_with(hook_name: &str) -> String {
+    match std::env::var(HANDLER_HOOKS_ENV) {
+        Ok(hooks) if !hooks.is_empty() => format!("{hooks}:{hook_name}"),
+        _ => hook_name.to_string(),
+    }
+}
+
+/// Write an executable file: a hook script.
+///
+/// # Errors
+/// * If the file cannot be written or its permissions cannot be set
+fn write_executable(path: &Path, content: &str) -> std::io::Result<()> {
+    fs::write(path, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_mode(0o755);
+        fs::set_permissions(path, permissions)?;
+    }
+
+    Ok(())
+}
+
+/// The path of the `<hook>.local` script of a hook, which `install --append` moves an
+/// existing hook to.
+///
+/// # Arguments
+/// * `hook_path` - Path of the hook file
+fn local_hook_path(hook_path: &Path) -> PathBuf {
+    let mut local_path = hook_path.as_os_str().to_owned();
+    local_path.push(".local");
+    PathBuf::from(local_path)
+}
+
+/// A path to back up a file to: the file's path with a `.bak` extension, or `.bak.1`,
+/// `.bak.2`, ... when earlier backups exist, so that none of them is overwritten.
+///
+/// # Arguments
+/// * `path` - Path of the file to back up
+fn backup_path(path: &Path) -> PathBuf {
+    let with_suffix = |suffix: &str| {
+        let mut backup_path = path.as_os_str().to_owned();
+        backup_path.push(suffix);
+        PathBuf::from(backup_path)
+    };
+
+    std::iter::once(with_suffix(".bak"))
+        .chain((1..).map(|n| with_suffix(&format!(".bak.{n}"))))
+        .find(|backup_path| !backup_path.exists())
+        .unwrap_or_else(|| with_suffix(".bak"))
+}
+
+/// The absolute form of a path, resolving symbolic links when it exists, so that two
+/// spellings of the same directory compare equal.
+fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The program and arguments running a command with the given shell, the command to be
+/// appended. A shell without arguments gets the option running a command (`bash` runs
+/// `bash -c <command>`, `pwsh` runs `pwsh -Command <command>`, `cmd` runs
+/// `cmd /C <command>`), one with arguments is used as it is.
+///
+/// # Arguments
+/// * `shell` - The shell, optionally followed by its arguments
+/// * `login` - Whether to start a login shell (`-l`, right after the program), for POSIX
+///   shells
+///
+/// # Returns
+/// * The program and its arguments, and whether the shell is a POSIX shell, which takes
+///   `$0`, `$1`, ... after the command
+fn shell_invocation(shell: &str, login: bool) -> (Vec<String>, bool) {
+    let mut words = shell.split_whitespace().map(ToString::to_string);
+    let mut invocation = vec![words.next().unwrap_or_else(|| default_shell().to_string())];
+
+    // Windows paths included, whatever the platform
+    let program = invocation[0]
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let (command_option, is_posix) = match program.trim_end_matches(".exe") {
+        "cmd" => ("/C", false),
+        "powershell" | "pwsh" => ("-Command", false),
+        _ => ("-c", true),
+    };
+
+    if login && is_posix {
+        invocation.push("-l".to_string());
+    }
+
+    let args = words.collect::<Vec<_>>();
+    if args.is_empty() {
+        invocation.push(command_option.to_string());
+    } else {
+        invocation.extend(args);
+    }
+
+    (invocation, is_posix)
+}
+
+/// Add the arguments of a resolved command to the process running it. `cmd` doesn't parse
+/// its command line like other Windows programs, so it gets its arguments as they are,
+/// instead of quoted for them.
+///
+/// # Arguments
+/// * `cmd` - The process
+/// * `resolved` - The resolved command
+fn add_program_args(cmd: &mut Command, resolved: &ResolvedCommand) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+
+        let program = resolved.program.to_lowercase();
+        if program == "cmd" || program.ends_with("cmd.exe") {
+            for arg in &resolved.args {
+                cmd.raw_arg(arg);
+            }
+            return;
+        }
+    }
+
+    cmd.args(&resolved.args);
+}
+
+/// The root of the working tree, or the current directory outside of one (e.g. in server
+/// hooks), which `path_prepend` and the commands' `working_dir` are relative to.
+fn repo_root_or_current_dir() -> PathBuf {
+    get_repo_root()
+        .ok()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+/// Handles the dry run output for a command
+fn handle_dry_run(
+    hook_command: &HookCommand,
+    idx: usize,
+    total_commands: usize,
+    hook_args: &[String],
+) {
+    let current_dir = std::env::current_dir();
+
+    outln!("Step {} of {}:", idx + 1, total_commands);
+    if let Some(name) = &hook_command.name {
+        outln!("  Command: {} ({})", name, hook_command.command);
+    } else {
+        outln!("  Command: {}", hook_command.command);
+    }
+
+    if !hook_args.is_empty() {
+        outln!("  Arguments: {}", hook_args.join(" "));
+    }
+
+    if let Some(dir) = &hook_command.working_dir {
+        outln!(
+            "  Working directory: {}",
+            repo_root_or_current_dir().join(dir).display()
+        );
+    } else if let Ok(dir) = current_dir {
+        outln!("  Working directory: {}", dir.display());
+    }
+
+    outln!();
+}
+
+/// Handles dry run output for a command with an explicit working directory
+fn handle_dry_run_with_dir(
+    hook_command: &HookCommand,
+    idx: usize,
+    total_commands: usize,
+    working_directory: Option<&str>,
+    hook_args: &[String],
+) {
+    outln!("Step {} of {}:", idx + 1, total_commands);
+    if let Some(name) = &hook_command.name {
+        outln!("  Command: {} ({})", name, hook_command.command);
+    } else {
+        outln!("  Command: {}", hook_command.command);
+    }
+
+    if !hook_args.is_empty() {
+        outln!("  Arguments: {}", hook_args.join(" "));
+    }
+
+    if let Some(dir) = &hook_command.working_dir {
+        outln!(
+            "  Working directory: {}",
+            repo_root_or_current_dir().join(dir).display()
+        );
+    } else if let Some(dir) = working_directory {
+        outln!("  Working directory (override): {dir}");
+    } else if let Ok(dir) = std::env::current_dir() {
+        outln!("  Working directory: {}", dir.display());
+    }
+
+    outln!();
+}
+
+/// Characters drawing the branches of a tree.
+struct TreeGlyphs {
+    /// Before a child followed by siblings
+    branch: &'static str,
+    /// Before the last child
+    last: &'static str,
+    /// Under a child followed by siblings
+    pipe: &'static str,
+    /// Under the last child
+    blank: &'static str,
+}
+
+const UNICODE_GLYPHS: TreeGlyphs = TreeGlyphs {
+    branch: "├── ",
+    last: "└── ",
+    pipe: "│   ",
+    blank: "    ",
+};
+
+const ASCII_GLYPHS: TreeGlyphs = TreeGlyphs {
+    branch: "|-- ",
+    last: "`-- ",
+    pipe: "|   ",
+    blank: "    ",
+};
+
+/// A node of a rendered tree.
+struct TreeNode {
+    label: String,
+    children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Create a node.
+    fn new(label: String, children: Vec<TreeNode>) -> Self {
+        Self { label, children }
+    }
+
+    /// Render the children of the node, one per line, below a line with the given prefix.
+    fn render_children(&self, glyphs: &TreeGlyphs, prefix: &str, output: &mut String) {
+        for (idx, child) in self.children.iter().enumerate() {
+            let is_last = idx + 1 == self.children.len();
+            let (branch, indent) = if is_last {
+                (glyphs.last, glyphs.blank)
+            } else {
+                (glyphs.branch, glyphs.pipe)
+            };
+
+            output.push_str(prefix);
+            output.push_str(branch);
+            output.push_str(&child.label);
+            output.push('\n');
+            child.render_children(glyphs, &format!("{prefix}{indent}"), output);
+        }
+    }
+}
+
+/// Format environment variables as `KEY=value` pairs sorted by name, for the tree.
+///
+/// # Arguments
+/// * `env` - The variables
+fn format_env(env: &HashMap<String, String>) -> String {
+    env.iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Render a configuration as a tree, see `Hooksmith::config_tree`.
+///
+/// # Arguments
+/// * `config` - The configuration
+/// * `ascii` - Whether to draw the tree with plain characters
+/// * `color` - Whether to highlight hook names and dim annotations
+fn render_config_tree(config: &Config, ascii: bool, color: bool) -> String {
+    let annotate = |label: String, annotations: &[String]| {
+        if annotations.is_empty() {
+            return label;
+        }
+
+        let annotations = format!("[{}]", annotations.join(", "));
+        if color {
+            format!("{label}  {}", style(annotations).dim())
+        } else {
+            format!("{label}  {annotations}")
+        }
+    };
+    let command_node = |command: &HookCommand| {
+        let label = match &command.name {
+            Some(name) => format!("{name}: {}", command.command),
+            None => command.command.clone(),
+        };
+        let mut annotations = Vec::new();
+        if let Some(runner) = command.task_runner {
+            annotations.push(format!("{} target", runner.program()));
+        }
+        if let Some(codes) = &command.success_codes {
+            let codes = codes.iter().map(ToString::to_string).collect::<Vec<_>>();
+            annotations.push(format!("success codes: {}", codes.join(" ")));
+        }
+        if command.allow_unquoted {
+            annotations.push("unquoted arguments allowed".to_string());
+        }
+        if let Some(dir) = &command.working_dir {
+            annotations.push(format!("in {dir}"));
+        }
+        if let Some(timeout) = command.timeout {
+            annotations.push(format!("timeout: {timeout}s"));
+        }
+        if command.allow_failure {
+            annotations.push("allowed to fail".to_string());
+        }
+        if !command.env.is_empty() {
+            annotations.push(format!("env: {}", format_env(&command.env)));
+        }
+        if command.stdin {
+            annotations.push("reads stdin".to_string());
+        }
+
+        TreeNode::new(annotate(label, &annotations), Vec::new())
+    };
+    let commands_node =
+        |commands: &[HookCommand]| commands.iter().map(&command_node).collect::<Vec<_>>();
+
+    let mut settings = vec![format!(
+        "shell: {}",
+        config.shell.as_deref().unwrap_or_else(|| default_shell())
+    )];
+    if config.login_shell == Some(true) {
+        settings.push("login shell".to_string());
+    }
+    if let Some(color) = config.color {
+        settings.push(format!("color: {color}"));
+    }
+    if let Some(prefix) = &config.command_prefix {
+        settings.push(format!("command prefix: {}", prefix.join(" ")));
+    }
+    if !config.path_prepend.is_empty() {
+        settings.push(format!("path prepend: {}", config.path_prepend.join(", ")));
+    }
+    if let Some(dir) = &config.scripts_dir {
+        settings.push(format!("scripts dir: {dir}"));
+    }
+    if !config.env.is_empty() {
+        settings.push(format!("env: {}", format_env(&config.env)));
+    }
+
+    let mut hook_names = config.hooks.keys().collect::<Vec<_>>();
+    hook_names.sort();
+
+    let hooks = hook_names
+        .into_iter()
+        .map(|hook_name| {
+            let hook = &config.hooks[hook_name];
+            let mut annotations = Vec::new();
+            if let Some(timeout) = hook.hook_timeout {
+                annotations.push(format!("timeout: {timeout}s"));
+            }
+            if let Some(timeout) = hook.command_timeout {
+                annotations.push(format!("command timeout: {timeout}s"));
+            }
+            if let Some(mask) = hook.umask {
+                annotations.push(format!("umask: {mask:03o}"));
+            }
+            match hook.max_output_bytes {
+                Some(max_bytes) => annotations.push(format!("captured, max {max_bytes} bytes")),
+                None if hook.capture_output => annotations.push("captured".to_string()),
+                None => {}
+            }
+            if hook.stash_unstaged {
+                annotations.push("stashes unstaged changes".to_string());
+            }
+            if let Some(files) = &hook.files {
+                annotations.push(format!("files: {}", files.join(" ")));
+            }
+            if !hook.when_env.is_empty() {
+                let conditions = hook
+                    .when_env
+                    .iter()
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .map(|(name, value)| format!("{name}={value}"))
+                    .collect::<Vec<_>>();
+                annotations.push(format!("when env: {}", conditions.join(" ")));
+            }
+            if !hook.env.is_empty() {
+                annotations.push(format!("env: {}", format_env(&hook.env)));
+            }
+            if let Some(shell) = &hook.shell {
+                annotations.push(format!("shell: {shell}"));
+            }
+            match hook.command_prefix.as_deref() {
+                Some([]) => annotations.push("no command prefix".to_string()),
+                Some(prefix) => annotations.push(format!("command prefix: {}", prefix.join(" "))),
+                None => {}
+            }
+
+            // In the order they run
+            let mut children = Vec::new();
+            if let Some(paths) = &hook.paths {
+                let blocks = paths
+                    .iter()
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .map(|(path, block)| {
+                        let annotations = block
+                            .working_directory
+                            .iter()
+                            .map(|dir| format!("working directory: {dir}"))
+                            .collect::<Vec<_>>();
+
+                        TreeNode::new(
+                            annotate(path.clone(), &annotations),
+                            commands_node(&block.commands),
+                        )
+                    })
+                    .collect();
+                children.push(TreeNode::new("paths".to_string(), blocks));
+            }
+            if let Some(commands) = &hook.commands {
+                children.push(TreeNode::new(
+                    "commands".to_string(),
+                    commands_node(commands),
+                ));
+            }
+            if let Some(groups) = &hook.groups {
+                let groups = groups
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, group)| {
+                        let label = if group.len() > 1 {
+                            format!("group {} (parallel)", idx + 1)
+                        } else {
+                            format!("group {}", idx + 1)
+                        };
+
+                        TreeNode::new(label, commands_node(group))
+                    })
+                    .collect();
+                children.push(TreeNode::new("groups".to_string(), groups));
+            }
+            for (handler, commands) in [
+                ("on_success", &hook.on_success),
+                ("on_failure", &hook.on_failure),
+            ] {
+                if let Some(commands) = commands {
+                    children.push(TreeNode::new(handler.to_string(), commands_node(commands)));
+                }
+            }
+
+            let label = if color {
+                style(hook_name).bold().to_string()
+            } else {
+                hook_name.clone()
+            };
+
+            TreeNode::new(annotate(label, &annotations), children)
+        })
+        .collect();
+
+    let glyphs = if ascii {
+        &ASCII_GLYPHS
+    } else {
+        &UNICODE_GLYPHS
+    };
+    let mut output = format!("Settings:\n{}\nHooks:\n", format_list(&settings));
+    TreeNode::new(String::new(), hooks).render_children(glyphs, "", &mut output);
+
+    output
+}
+
+/// Interpreters whose first argument is checked as a script by `verify`.
+const SCRIPT_INTERPRETERS: [&str; 9] = [
+    "sh", "bash", "zsh", "python", "python3", "node", "ruby", "perl", "pwsh",
+];
+
+/// The script a command runs, if it starts with a relative path to one (`./check.sh`,
+/// `scripts/lint`) or an interpreter followed by one (`bash lint.sh`). Programs
+/// looked up in `PATH` and words using shell expansions are not scripts.
+///
+/// # Arguments
+/// * `command` - The command
+fn referenced_script(command: &str) -> Option<&str> {
+    let mut words = command.split_whitespace();
+    let first = words.next()?;
+    // The argument of an interpreter is a file even without a `/`, unless it is an option
+    let (candidate, is_script) = if SCRIPT_INTERPRETERS.contains(&first) {
+        let argument = words.next()?;
+        (argument, !argument.starts_with('-'))
+    } else {
+        (first, first.contains('/'))
+    };
+
+    let is_plain = !candidate.contains(|c: char| "$`'\"*?[{~;&|<>()".contains(c));
+
+    (is_script && is_plain && !candidate.starts_with('/')).then_some(candidate)
+}
+
+/// Print the exact invocation of a command for `--dry-run --resolve`, along with a shell
+/// line reproducing it.
+///
+/// # Arguments
+/// * `resolved` - The resolved command
+/// * `umask` - The umask the command would run with, if the hook sets one
+/// * `idx` - Index of the command
+/// * `total_commands` - Number of commands in the scope
+fn print_resolved_command(
+    resolved: &ResolvedCommand,
+    umask: Option<u32>,
+    idx: usize,
+    total_commands: usize,
+) {
+    let argv: Vec<&str> = std::iter::once(resolved.program.as_str())
+        .chain(resolved.args.iter().map(String::as_str))
+        .collect();
+    // Only variables whose value differs from the inherited environment
+    let env: Vec<(&String, &String)> = resolved
+        .env
+        .iter()
+        .filter(|(key, value)| std::env::var(key).ok().as_ref() != Some(*value))
+        .collect();
+    let cwd = resolved
+        .cwd
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+
+    outln!("Step {} of {}:", idx + 1, total_commands);
+    let quoted: Vec<String> = argv
+        .iter()
+        .map(|arg| serde_json::to_string(arg).unwrap_or_default())
+        .collect();
+    outln!("  argv: [{}]", quoted.join(", "));
+    if env.is_empty() {
+        outln!("  env: (inherited)");
+    } else {
+        outln!("  env:");
+        for (key, value) in &env {
+            outln!("    {key}={}", shell_quote(value));
+        }
+    }
+    outln!("  cwd: {}", cwd.display());
+    if let Some(mask) = umask {
+        outln!("  umask: {mask:03o}");
+    }
+
+    let mut line = format!("cd {} && ", shell_quote(&cwd.to_string_lossy()));
+    if !env.is_empty() {
+        line.push_str("env ");
+        for (key, value) in &env {
+            line.push_str(&format!("{key}={} ", shell_quote(value)));
+        }
+    }
+    line.push_str(
+        &argv
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    outln!("  reproduce: {line}");
+    outln!();
+}
+
+/// The status code of a command that failed, if it exited on its own.
+///
+/// # Arguments
+/// * `error` - Why the command failed
+fn failure_exit_code(error: &HooksmithError) -> Option<i32> {
+    match error {
+        HooksmithError::HookExecution(HookExecutionError::CommandFailed(code)) => Some(*code),
+        _ => None,
+    }
+}
+
+/// The hooks and commands to skip, from `--skip` then `HOOKSMITH_SKIP`, each with where it
+/// was listed.
+///
+/// # Arguments
+/// * `options` - Options of the run
+fn skip_list(options: &RunOptions) -> Vec<(String, &'static str)> {
+    let from_env = std::env::var(SKIP_ENV).unwrap_or_default();
+
+    options
+        .skip
+        .iter()
+        .map(|target| (target.trim().to_string(), "`--skip`"))
+        .chain(
+            from_env
+                .split(',')
+                .map(|target| (target.trim().to_string(), "`HOOKSMITH_SKIP`")),
+        )
+        .filter(|(target, _)| !target.is_empty())
+        .collect()
+}
+
+/// Print the order in which command groups run, for verbose and dry-run output.
+///
+/// # Arguments
+/// * `groups` - The command groups of a hook
+fn print_group_plan(groups: &[Vec<HookCommand>]) {
+    outln!("🧩 Group plan:");
+
+    for (idx, group) in groups.iter().enumerate() {
+        let commands = group
+            .iter()
+            .map(|command| command.name.as_deref().unwrap_or(&command.command))
+            .collect::<Vec<_>>();
+
+        if commands.len() > 1 {
+            outln!("  {}. {} (parallel)", idx + 1, commands.join(" | "));
+        } else {
+            outln!("  {}. {}", idx + 1, commands.join(""));
+        }
+    }
+}
+
+impl Hooksmith {
+    /// Detect changed files for a given hook when possible.
+    ///
+    /// Behavior by hook name:
+    /// - `pre-commit`: Returns the list of staged files using `git diff --name-only --cached`.
+    /// - `pre-push`: Attempts to diff against the configured upstream with `@{u}..HEAD`.
+    ///   If no upstream is configured or that diff fails, falls back to `HEAD~1..HEAD`.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The hook to compute changed files for.
+    ///
+    /// # Returns
+    /// * `Some(Vec<String>)` when detection succeeds (the vector may be empty if no files changed).
+    /// * `None` if the hook is not supported or if detection fails (e.g., not a Git repo, no upstream, or the git command fails).
+    ///
+    /// # Notes
+    /// This helper is best-effort and never returns an error. Callers should treat `None`
+    /// as "path-scoped execution not applicable" and continue with global commands.
+    fn detect_changed_files(hook_name: &str) -> Option<Vec<String>> {
+        match hook_name {
+            "pre-commit" => Self::git_diff_name_only(&["--cached"]).ok(),
+            "pre-push" => {
+                // Try to diff against upstream; fall back to last commit range
+                if let Ok(files) = Self::git_diff_upstream_range() {
+                    Some(files)
+                } else {
+                    Self::git_diff_name_only(&["HEAD~1..HEAD"]).ok()
+                }
+            }
+            _ => None,
         }
+    }
 
-        // Check for hooks in config but not installed
-        self.check_missing_hooks(&git_hooks_path, &mut differences_found);
-
-        // Check for installed hooks not in config
-        self.check_extra_hooks(&git_hooks_path, &mut differences_found);
+    /// Whether any of the changed files matches the `files` patterns of a hook, for
+    /// `--only-changed-hooks`. Hooks without patterns, and hooks whose changed files
+    /// can't be detected, are always affected. The detected files are recorded in the
+    /// context, so that path-based blocks and commands see the same ones.
+    ///
+    /// # Arguments
+    /// * `context` - The execution context of the hook
+    /// * `hook` - The hook
+    fn is_affected_by_changes(context: &mut ExecutionContext, hook: &Hook) -> bool {
+        if hook.files.is_none() {
+            return true;
+        }
 
-        if !differences_found {
-            println!("✅ All hooks match the configuration file");
+        if context.changed_files.is_none() {
+            context.changed_files = context
+                .input
+                .as_ref()
+                .and_then(HookInput::ref_updates)
+                .and_then(Self::files_in_push_range)
+                .or_else(|| Self::detect_changed_files(context.hook_name));
         }
 
-        Ok(())
+        context
+            .changed_files
+            .as_ref()
+            .is_none_or(|files| hook.matches_changed_files(files))
     }
 
-    /// Creates the git hooks directory if it doesn't exist.
-    /// Handles both normal and dry run modes.
+    /// Compute the files changed by the ref updates of a `pre-push` hook.
+    ///
+    /// For a ref that already exists on the remote, these are the files that differ
+    /// between the remote and the local commit. For a new ref, they are the files touched
+    /// by the commits that aren't on any remote-tracking branch. Deleted refs push nothing.
     ///
     /// # Arguments
-    /// * `git_hooks_path` - Path to the git hooks directory
+    /// * `updates` - The ref updates being pushed
     ///
-    /// # Errors
-    /// * If the directory cannot be created
-    fn ensure_hooks_directory(&self, git_hooks_path: &Path) -> Result<()> {
-        if !git_hooks_path.exists() {
-            if self.dry_run {
-                println!("🪝 Skipping creation of .git/hooks directory in dry run mode");
+    /// # Returns
+    /// * `Some(Vec<String>)` with the changed files (possibly empty).
+    /// * `None` if a range can't be diffed (e.g. the remote commit isn't available locally).
+    fn files_in_push_range(updates: &[RefUpdate]) -> Option<Vec<String>> {
+        let mut files = Vec::new();
+        for update in updates.iter().filter(|update| !update.is_deletion()) {
+            let range_files = if update.is_new_ref() {
+                git_name_status_in(
+                    Path::new("."),
+                    &[
+                        "log",
+                        "--format=",
+                        "--name-status",
+                        "-z",
+                        "-M",
+                        &update.local_sha,
+                        "--not",
+                        "--remotes",
+                    ],
+                )
             } else {
-                if self.verbose {
-                    println!("  - Creating .git/hooks directory...");
-                }
-                fs::create_dir_all(git_hooks_path)?;
-            }
+                git_diff_files_in(Path::new("."), &[&update.remote_sha, &update.local_sha])
+            };
+
+            files.extend(range_files.ok()?);
         }
-        Ok(())
+
+        files.sort();
+        files.dedup();
+
+        Some(files)
     }
 
-    /// Generates configuration content for a specific hook type
+    /// Compute the files changed since the last successful run of a hook, from the commit
+    /// recorded in its marker to the current working tree.
     ///
     /// # Arguments
-    /// * `hook` - The name of the hook to generate configuration for
+    /// * `directory` - A directory inside the repository.
+    /// * `hook_name` - The hook to compute changed files for.
     ///
     /// # Returns
-    /// * `String` - The generated configuration content for the hook
-    fn generate_hook_config(hook: &str) -> String {
-        let mut config = String::new();
-        config.push_str(hook);
-        config.push_str(":\n");
-        config.push_str("  commands:\n");
+    /// * `Some(Vec<String>)` with the changed files (possibly empty).
+    /// * `None` if the hook has no marker or the diff fails, meaning the hook should run in full.
+    fn files_changed_since_last_run(directory: &Path, hook_name: &str) -> Option<Vec<String>> {
+        let marker = run_state::read_marker(directory, hook_name)?;
 
-        // Add hook-specific default commands and comments
-        let (echo_msg, examples) = match hook {
-            "pre-commit" => (
-                "Running pre-commit checks...",
-                vec![
-                    "# Add your pre-commit commands here",
-                    "# Examples:",
-                    "# - cargo fmt --all -- --check",
-                    "# - cargo clippy -- --deny warnings",
-                ],
-            ),
-            "pre-push" => (
-                "Running pre-push checks...",
-                vec![
-                    "# Add your pre-push commands here",
-                    "# Examples:",
-                    "# - cargo test",
-                    "# - cargo build --release",
-                ],
-            ),
-            "commit-msg" => (
-                "Validating commit message...",
-                vec![
-                    "# Add your commit message validation here",
-                    "# Example:",
-                    "# - ./scripts/validate-commit-msg.sh $1",
-                ],
-            ),
-            "post-commit" => (
-                "Post-commit actions...",
-                vec!["# Add your post-commit commands here"],
-            ),
-            _ => (
-                &format!("Running {hook} hook...")[..],
-                vec!["# Add your commands here"],
-            ),
-        };
+        git_diff_files_in(directory, &[&marker]).ok()
+    }
 
-        config.push_str(&format!("    - echo \"{echo_msg}\"\n")[..]);
+    /// Record the current commit as the last successful run of a hook.
+    /// Failing to record it only means the next run processes more files, so it's a warning.
+    ///
+    /// # Arguments
+    /// * `directory` - A directory inside the repository.
+    /// * `hook_name` - The hook that succeeded.
+    fn record_successful_run(&self, directory: &Path, hook_name: &str) {
+        let Some(head) = get_head_commit_in(directory) else {
+            return;
+        };
 
-        for example in examples {
-            config.push_str(&format!("    {example}\n")[..]);
+        if let Err(e) = run_state::write_marker(directory, hook_name, &head) {
+            self.reporter.warning(
+                "Failed to record hook run",
+                &format!("Could not record the last successful run of '{hook_name}': {e}"),
+            );
         }
-
-        config.push('\n');
-
-        config
     }
 
-    /// Initialize hooksmith configuration interactively.
+    /// Compute the list of files changed relative to the configured upstream branch.
     ///
-    /// # Arguments
-    /// * `config_path` - Path where the configuration file will be created
-    /// * `dry_run` - Whether to run in dry run mode
-    /// * `verbose` - Whether to print verbose output
+    /// Attempts to diff `@{u}..HEAD` if an upstream is configured. If no upstream is
+    /// configured, returns an error so callers can fall back to an alternative range.
     ///
     /// # Errors
-    /// * If the user cancels the selection
-    /// * If there's an error writing the configuration file
-    pub fn init_interactive(config_path: &Path, dry_run: bool, verbose: bool) -> Result<()> {
-        if dry_run {
-            println!("🔄 DRY RUN MODE - No files will be created\n");
-        }
-
-        if verbose {
-            println!("🚀 Initializing hooksmith configuration...");
-        }
-
-        // Check if config file already exists
-        if config_path.exists() && !dry_run {
-            let overwrite = Confirm::with_theme(&my_clap_theme::ColorfulTheme::default())
-                .with_prompt(format!(
-                    "Configuration file '{}' already exists. Overwrite?",
-                    config_path.display()
-                ))
-                .default(false)
-                .interact()
-                .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+    /// * If no upstream is configured or if running the underlying `git` command fails.
+    fn git_diff_upstream_range() -> Result<Vec<String>> {
+        // Check if upstream exists; if so, diff against it
+        let upstream_check = Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+            .output();
 
-            if !overwrite {
-                println!("❌ Initialization cancelled");
-                return Ok(());
+        if let Ok(output) = upstream_check {
+            if output.status.success() {
+                return Self::git_diff_name_only(&["@{u}..HEAD"]);
             }
         }
 
-        // Get all available Git hooks
-        let hook_options: Vec<String> = GIT_HOOKS.iter().map(|&s| s.to_string()).collect();
-
-        // Interactive hook selection
-        let selections = MultiSelect::with_theme(&my_clap_theme::ColorfulTheme::default())
-            .with_prompt("Select hooks to configure (Space to select, Enter to confirm)")
-            .items(&hook_options)
-            .interact()
-            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
-
-        if selections.is_empty() {
-            println!("❌ No hooks selected. Configuration file not created.");
-            return Ok(());
-        }
-
-        let selected_hooks: Vec<String> = selections
-            .into_iter()
-            .map(|i| hook_options[i].clone())
-            .collect();
-
-        if verbose {
-            println!("📝 Selected hooks: {}", selected_hooks.join(", "));
-        }
-
-        // Create configuration content
-        let config_content: String = selected_hooks
-            .iter()
-            .map(|hook| Self::generate_hook_config(hook))
-            .collect();
-
-        // Write configuration file
-        if dry_run {
-            println!(
-                "🔍 Would create configuration file '{}' with content:",
-                config_path.display()
-            );
-            println!("{config_content}");
-        } else {
-            fs::write(config_path, config_content)?;
-            println!(
-                "✅ Configuration file '{}' created successfully!",
-                config_path.display()
-            );
-            println!("📝 You can now edit the file to customize your hook commands.");
-            println!("🚀 Run 'hooksmith install' to install the configured hooks.");
-        }
-
-        Ok(())
+        Err(HookExecutionError::HookNotFound("No upstream configured".to_string()).into())
     }
 
-    /// Generates the hook script content.
-    /// Creates a shell script that checks for hooksmith and runs the specified hook.
+    /// Run `git diff` with the provided arguments in the current directory and return the
+    /// changed file paths, see `git_diff_files_in`.
     ///
     /// # Arguments
-    /// * `hook_name` - Name of the hook to create content for
-    fn generate_hook_content(hook_name: &str) -> String {
-        format!(
-            "#!/bin/sh\n
-    if hooksmith -h >/dev/null 2>&1
-    then
-      exec hooksmith run {hook_name}
-    else
-      cargo install hooksmith
-      exec hooksmith run {hook_name}
-    fi"
-        )
+    /// * `args` - Additional arguments or revision ranges to pass to `git diff`.
+    ///
+    /// # Errors
+    /// * If the underlying `git diff` command fails.
+    fn git_diff_name_only(args: &[&str]) -> Result<Vec<String>> {
+        Ok(git_diff_files_in(Path::new("."), args)?)
     }
 
-    /// Writes the hook file and sets appropriate permissions.
-    /// Handles both normal and dry run modes.
+    /// Print a formatted timing report showing execution times for hooks and commands.
     ///
     /// # Arguments
-    /// * `hook_path` - Path where the hook file should be written
-    /// * `hook_name` - Name of the hook being installed
-    /// * `content` - Content to write to the hook file
-    ///
-    /// # Errors
-    /// * If the file cannot be written
-    /// * If permissions cannot be set
-    fn write_hook_file(&self, hook_path: &Path, hook_name: &str, content: &str) -> Result<()> {
-        if self.dry_run {
-            println!("🪝 Skipping installation of {hook_name} hook in dry run mode");
-            return Ok(());
+    /// * `timing_report` - The timing report to print
+    fn print_timing_report(_: &Self, timing_report: &TimingReport) {
+        if timing_report.hooks.is_empty() {
+            return;
         }
 
-        fs::write(hook_path, content)?;
+        outln!("\n⏱️  Hook execution summary:");
 
-        if self.verbose {
-            println!("  - Installing {hook_name} file...");
-        }
+        for hook_timing in &timing_report.hooks {
+            if hook_timing.commands.is_empty() {
+                continue;
+            }
 
-        // Linux only
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
+            outln!(
+                "  Hook '{}' ({})",
+                hook_timing.hook_name,
+                Self::format_duration(&hook_timing.total_duration)
+            );
 
-            let mut permissions = fs::metadata(hook_path)?.permissions();
-            permissions.set_mode(0o755);
-            fs::set_permissions(hook_path, permissions)?;
+            for command_timing in &hook_timing.commands {
+                // Use command name if available, otherwise use the command itself
+                let display_command = if let Some(name) = &command_timing.name {
+                    if name.len() > 60 {
+                        format!("{}...", &name[..57])
+                    } else {
+                        name.clone()
+                    }
+                } else if command_timing.command.len() > 60 {
+                    format!("{}...", &command_timing.command[..57])
+                } else {
+                    command_timing.command.clone()
+                };
 
-            if self.verbose {
-                println!("  - Setting file permissions...");
+                outln!(
+                    "    {}: {}",
+                    display_command,
+                    Self::format_duration(&command_timing.duration)
+                );
             }
         }
 
-        Ok(())
+        outln!(
+            "  Total: {}",
+            Self::format_duration(&timing_report.total_duration)
+        );
     }
 
-    /// Install a single, given hook.
+    /// Format a duration for display in the timing report.
     ///
     /// # Arguments
-    /// * `hook_name` - Name of the hook to install
+    /// * `duration` - The duration to format
     ///
-    /// # Errors
-    /// * If the `.git/hooks` directory cannot be created
-    /// * If the hook cannot be installed/given permission
-    pub fn install_hook(&self, hook_name: &str) -> Result<()> {
-        if self.verbose && !self.dry_run {
-            println!("🪝 Installing {hook_name} hook...");
+    /// # Returns
+    /// A formatted string representation of the duration
+    pub(crate) fn format_duration(duration: &Duration) -> String {
+        let total_millis = duration.as_millis();
+
+        if total_millis >= 1000 {
+            format!("{:.1}s", duration.as_secs_f64())
+        } else {
+            format!("{total_millis}ms")
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hook_input::PUSH_UPDATES_ENV, placeholders::PUSH_RANGE_ENV};
+
+    /// Write `config` to `hooksmith.yaml` in `dir` and return the path of the file.
+    fn write_config(dir: &Path, config: &str) -> PathBuf {
+        let config_path = dir.join("hooksmith.yaml");
+        fs::write(&config_path, config).unwrap();
+        config_path
+    }
+
+    /// A Hooksmith reading `config`, written to `hooksmith.yaml` in `dir`.
+    fn hooksmith_in(dir: &Path, config: &str) -> Hooksmith {
+        Hooksmith::new_from_config(&write_config(dir, config), false, false).unwrap()
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let yaml = "shell: bash\npre-commit:\n  commands:\n    - cargo fmt --check\n";
+
+        let hs = Hooksmith::from_reader(yaml.as_bytes(), false, false).unwrap();
+        assert_eq!(hs.get_available_hooks(), vec!["pre-commit"]);
+        assert_eq!(hs.config.shell.as_deref(), Some("bash"));
+        assert_eq!(hs.reporter().warning_count(), 0);
+
+        // Same rules as for a file
+        let hs = Hooksmith::from_reader(&b""[..], false, false).unwrap();
+        assert_eq!(hs.reporter().warning_count(), 1);
+        assert!(matches!(
+            Hooksmith::from_reader(&b"pre-commit: [\n"[..], false, false),
+            Err(HooksmithError::Config(ConfigError::Parse(_)))
+        ));
+    }
+
+    #[test]
+    fn test_config_in_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo_path = dir.path().join("Cargo.toml");
+        fs::write(
+            &cargo_path,
+            "[package]\nname = \"demo\"\n\n[package.metadata.hooksmith]\nshell = \"bash\"\n\n[package.metadata.hooksmith.pre-commit]\ncommands = [\"cargo fmt --check\", { run = \"cargo test\", name = \"test\" }]\n\n[package.metadata.hooksmith.pre-push]\ncommands = [\"cargo test\"]\n",
+        )
+        .unwrap();
+        let package_path = dir.path().join("package.json");
+        fs::write(
+            &package_path,
+            r#"{"name": "demo", "hooksmith": {"env": {"CI": "1"}, "pre-commit": {"commands": ["npm test"]}}}"#,
+        )
+        .unwrap();
+
+        let hs = Hooksmith::new_from_config(&cargo_path, false, false).unwrap();
+        assert_eq!(hs.config.shell.as_deref(), Some("bash"));
+        let commands = hs.config.hooks["pre-commit"].commands.as_ref().unwrap();
+        assert_eq!(commands[1].name.as_deref(), Some("test"));
+        let hs = Hooksmith::new_from_config_for_hooks(
+            &cargo_path,
+            &["pre-push".to_string()],
+            false,
+            false,
+            Reporter::default(),
+        )
+        .unwrap();
+        assert_eq!(hs.get_available_hooks(), vec!["pre-push"]);
+
+        let hs = Hooksmith::new_from_config(&package_path, false, false).unwrap();
+        assert_eq!(hs.config.env["CI"], "1");
+        assert_eq!(hs.get_available_hooks(), vec!["pre-commit"]);
+
+        fs::write(&package_path, r#"{"name": "demo"}"#).unwrap();
+        assert!(matches!(
+            Hooksmith::new_from_config(&package_path, false, false),
+            Err(HooksmithError::Config(ConfigError::Embedded { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_command_keys() {
+        let named = HookCommand::new_named("fmt".to_string(), "cargo fmt".to_string());
+        let unnamed = HookCommand::new_unnamed("cargo fmt".to_string());
+        assert_eq!(named.key(), "fmt");
+        // The hash is part of recorded state, so it must not change between versions
+        assert_eq!(unnamed.key(), "cmd-aa6c3db7d7e67ff8");
+        assert_ne!(
+            HookCommand::new_unnamed("cargo fmt ".to_string()).key(),
+            unnamed.key()
+        );
 
-        let git_hooks_path = get_git_hooks_path()?;
-        self.ensure_hooks_directory(&git_hooks_path)?;
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - lint: cargo clippy\n    - \"true\"\n  groups:\n    - - lint: typos\npre-push:\n  commands:\n    - lint: cargo clippy\n");
 
-        let hook_path = git_hooks_path.join(hook_name);
-        let hook_content = Self::generate_hook_content(hook_name);
-        self.write_hook_file(&hook_path, hook_name, &hook_content)?;
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(hs.lint_duplicate_names());
 
-        if self.verbose {
-            println!("  ✅ Installed {hook_name} file");
-        }
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - lint: \"true\"\npre-push:\n  commands:\n    - lint: \"true\"\n");
+        assert!(!hs.lint_duplicate_names());
+        let timing = hs
+            .run_hook_internal_with_timing("pre-push", &RunOptions::default())
+            .unwrap();
+        assert_eq!(timing.commands[0].key, "lint");
+    }
 
-        Ok(())
+    #[test]
+    fn test_global_config_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let global_path = dir.path().join("config.yaml");
+        let config_path = write_config(
+            dir.path(),
+            "shell: sh\nenv:\n  LEVEL: repo\npre-commit:\n  commands: [\"true\"]\n",
+        );
+        fs::write(
+            &global_path,
+            "shell: bash\ncolor: false\nenv:\n  LEVEL: global\n  EDITOR: vi\n",
+        )
+        .unwrap();
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false)
+            .unwrap()
+            .with_global_config(&global_path)
+            .unwrap();
+
+        assert_eq!(hs.config.shell.as_deref(), Some("sh"));
+        assert_eq!(hs.config.color, Some(false));
+        assert_eq!(hs.config.env["LEVEL"], "repo");
+        assert_eq!(hs.config.env["EDITOR"], "vi");
+        assert_eq!(hs.get_available_hooks(), vec!["pre-commit".to_string()]);
+
+        // A missing global configuration leaves the repository configuration untouched
+        let hs = Hooksmith::new_from_config(&config_path, false, false)
+            .unwrap()
+            .with_global_config(&dir.path().join("missing.yaml"))
+            .unwrap();
+        assert_eq!(hs.config.env.len(), 1);
     }
 
-    /// Install all hooks.
-    ///
-    /// # Errors
-    /// * If the `.git/hooks` directory cannot be created
-    ///
-    /// # Arguments
-    /// * `config` - Parsed configuration file
-    pub fn install_hooks(&self) -> Result<()> {
-        self.validate_hooks()?;
+    #[test]
+    fn test_append_hook_configs() {
+        let existing = "# Team hooks\npre-commit:\n  commands:\n    - cargo fmt --all -- --check";
+        let config = Hooksmith::append_hook_configs(existing, &["pre-push".to_string()]);
 
-        let git_hooks_path = get_git_hooks_path()?;
+        assert!(config.starts_with(existing));
+        assert!(config.contains("--check\n\npre-push:\n  commands:\n"));
 
-        if !check_for_git_hooks() {
-            fs::create_dir_all(&git_hooks_path)?;
-        }
+        let parsed: Config = serde_yaml::from_str(&config).unwrap();
+        assert_eq!(parsed.hooks.len(), 2);
+        assert_eq!(
+            parsed.hooks["pre-commit"].commands.as_ref().unwrap()[0].command,
+            "cargo fmt --all -- --check"
+        );
 
-        if self.verbose {
-            println!("🪝 Installing hooks...");
-        }
+        let config = Hooksmith::append_hook_configs("", &["pre-push".to_string()]);
+        assert!(config.starts_with("pre-push:\n"));
+    }
 
-        for hook_name in self.config.hooks.keys() {
-            self.install_hook(hook_name)?;
-        }
+    #[test]
+    fn test_empty_config_warns() {
+        let dir = tempfile::tempdir().unwrap();
 
-        if !self.dry_run {
-            println!(
-                "Installed {} hook(s) successfully.",
-                self.config.hooks.len()
-            );
+        for content in ["", "# only a comment\n", "{}\n"] {
+            let hs = hooksmith_in(dir.path(), content);
+            assert!(hs.get_available_hooks().is_empty());
+            assert_eq!(hs.reporter().warning_count(), 1);
         }
 
-        Ok(())
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - echo ok\n");
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(!Hooksmith::warn_if_no_hooks(
+            hs.reporter(),
+            &hs.config,
+            Some(&config_path)
+        ));
+        assert_eq!(hs.reporter().warning_count(), 0);
     }
 
-    /// Executes a single command and handles its output
-    ///
-    /// # Arguments
-    /// * `hook_command` - The command to execute
-    /// * `hook_name` - The name of the hook being executed
-    fn execute_single_command(
-        &self,
-        hook_command: &HookCommand,
-        hook_name: &str,
-        working_directory: Option<&Path>,
-    ) {
-        if self.verbose && !self.dry_run {
-            let display = if let Some(name) = &hook_command.name {
-                format!("{} ({})", name, hook_command.command)
-            } else {
-                hook_command.command.clone()
-            };
-            println!("  - Running command: {display}");
-        }
-
-        match self.execute_command(&hook_command.command, working_directory) {
-            Ok(status) if status.success() => {
-                if self.verbose && !self.dry_run {
-                    println!("\n  ✅ Command completed successfully");
-                }
-            }
-            Ok(status) => {
-                let code = status.code().unwrap_or(1);
-                print_error(
-                    "Command failed",
-                    &format!("Hook '{hook_name}' command failed with status code {code}"),
-                    "Please check your command and try again.",
-                );
-
-                std::process::exit(code);
-            }
-            Err(e) => {
-                print_error(
-                    "Failed to execute command",
-                    &format!("Error: {e}"),
-                    "Please ensure the command exists and is executable.",
-                );
-
-                std::process::exit(1);
-            }
-        }
+    #[test]
+    fn test_write_config_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "# Team hooks\nshell: bash\nenv:\n  B: \"2\"\n  A: \"1\"\npre-commit:\n  hook_timeout: 30\n  umask: 022\n  commands:\n    - cargo fmt --all -- --check\n    - clippy: cargo clippy\n    - run: named run\n    - run: grep -q x $1\n      name: grep\n      success_codes: [0, 1]\n      allow_unquoted: true\n    - just: test --release\n    - run: make lint\n      name: make\n  paths:\n    web/:\n      working_directory: web\n      commands: [npm test]\n    src/:\n      commands: [cargo test]\ncommit-msg:\n  commands:\n    - run: \"true\"\n      name: run\n");
+        let written_path = dir.path().join("written.yaml");
+        hs.write_config(&written_path).unwrap();
+
+        let written = fs::read_to_string(&written_path).unwrap();
+        let reread = Hooksmith::read_config(&written_path).unwrap();
+        assert_eq!(reread, hs.config);
+
+        // Writing is deterministic and stable
+        Hooksmith::new_from_config(&written_path, false, false)
+            .unwrap()
+            .write_config(&written_path)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&written_path).unwrap(), written);
+        assert!(written.find("commit-msg:").unwrap() < written.find("pre-commit:").unwrap());
+        assert!(written.find("src/:").unwrap() < written.find("web/:").unwrap());
     }
 
-    /// Get a list of available hooks from the configuration.
-    #[must_use]
-    pub fn get_available_hooks(&self) -> Vec<String> {
-        self.config.hooks.keys().cloned().collect()
+    #[test]
+    fn test_effective_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-push:\n  umask: \"027\"\n  commands:\n    - cargo test\npre-commit:\n  commands:\n    - fmt: cargo fmt --check\n    - run: grep -q x\n      success_codes: [0, 1]\n");
+        let yaml = hs.effective_config(false).unwrap();
+
+        assert!(yaml.starts_with("shell: sh\ncolor: false\npre-commit:"));
+        assert!(yaml.contains("  - fmt: cargo fmt --check\n"));
+        assert!(yaml.contains("  - run: grep -q x\n    success_codes:\n"));
+        assert!(yaml.contains("umask: '027'"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&hs.effective_config(true).unwrap()).unwrap();
+        assert_eq!(json["shell"], "sh");
+        assert_eq!(json["pre-push"]["commands"][0], "cargo test");
     }
 
-    /// Handle the "hook not found error"
-    ///
-    /// # Arguments
-    /// * `hook_name` - The name of the hook being executed
-    ///
-    /// # Errors
-    /// * If the hook is not found in the configuration.
-    fn handle_hook_not_found(&self, hook_name: &str) -> Result<()> {
-        let formatted_hooks = format_list(&self.config.hooks.keys().collect::<Vec<_>>());
+    #[test]
+    fn test_explain_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let global_path = dir.path().join("global.yaml");
+        let config_path = write_config(
+            dir.path(),
+            "env:\n  CI: \"true\"\n\npre-commit:\n  commands:\n    - cargo test\n",
+        );
+        fs::write(
+            &global_path,
+            "shell: bash\nenv:\n  CI: \"false\"\n  EDITOR: vim\n",
+        )
+        .unwrap();
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false)
+            .unwrap()
+            .with_global_config(&global_path)
+            .unwrap();
+        let sources: serde_json::Value =
+            serde_json::from_str(&hs.explain_config(true).unwrap()).unwrap();
+        let source = |key: &str| {
+            let source = sources
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|source| source["key"] == key)
+                .unwrap();
+            (
+                source["origin"].as_str().unwrap().to_string(),
+                source["file"].as_str().map(ToString::to_string),
+                source["line"].as_u64(),
+            )
+        };
+        let config_file = Some(config_path.display().to_string());
+        let global_file = Some(global_path.display().to_string());
 
-        print_error(
-            "Hook not found",
-            &format!("No commands defined for hook '{hook_name}'"),
-            &format!(
-                "Available hooks:\n{formatted_hooks}\n\nPlease check your configuration file."
-            ),
+        assert_eq!(
+            source("shell"),
+            ("global".to_string(), global_file.clone(), Some(1))
+        );
+        assert_eq!(source("color"), ("default".to_string(), None, None));
+        assert_eq!(
+            source("env.CI"),
+            ("repository".to_string(), config_file.clone(), Some(2))
+        );
+        assert_eq!(
+            source("env.EDITOR"),
+            ("global".to_string(), global_file, Some(4))
+        );
+        assert_eq!(
+            source("pre-commit"),
+            ("repository".to_string(), config_file, Some(4))
         );
 
-        Err(HookExecutionError::HookNotFound(hook_name.to_string()).into())
+        let table = hs.explain_config(false).unwrap();
+        assert!(table.contains(&format!(
+            "pre-commit  repository  {}:4\n",
+            config_path.display()
+        )));
+        assert!(table.contains("color       default\n"));
     }
 
-    /// Runs multiple hooks with timing information.
-    ///
-    /// # Arguments
-    /// * `hook_names` - Vector of hook names to run
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    /// * If any hook is not found in the configuration
-    pub fn run_hooks_with_timing(&self, hook_names: &[String]) -> Result<()> {
-        let start_time = Instant::now();
-        let mut hook_timings = Vec::new();
-        let total_hooks = hook_names.len();
+    #[test]
+    fn test_config_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "env:\n  CI: \"1\"\npre-push:\n  hook_timeout: 60\n  stash_unstaged: true\n  groups:\n    - - cargo build\n    - - unit: cargo test\n      - just: lint\npre-commit:\n  paths:\n    web/:\n      working_directory: web\n      commands: [npm test]\n  commands:\n    - run: grep -q x\n      success_codes: [0, 1]\n");
+        let config = hs.effective();
+
+        assert_eq!(
+            render_config_tree(&config, false, false),
+            "Settings:
+  - shell: sh
+  - color: false
+  - env: CI=1
+Hooks:
+├── pre-commit
+│   ├── paths
+│   │   └── web/  [working directory: web]
+│   │       └── npm test
+│   └── commands
+│       └── grep -q x  [success codes: 0 1]
+└── pre-push  [timeout: 60s, stashes unstaged changes]
+    └── groups
+        ├── group 1
+        │   └── cargo build
+        └── group 2 (parallel)
+            ├── unit: cargo test
+            └── just lint  [just target]
+"
+        );
 
-        for (hook_idx, hook_name) in hook_names.iter().enumerate() {
-            if true {
-                println!(
-                    "running `{hook_name}`, {}/{total_hooks} steps:",
-                    hook_idx + 1
-                );
-            }
-            let hook_start = Instant::now();
-            let hook_timing = self.run_hook_internal_with_timing(hook_name)?;
-            let hook_duration = hook_start.elapsed();
+        let ascii = render_config_tree(&config, true, false);
+        assert!(ascii.contains("|-- pre-commit\n|   |-- paths\n"));
+        assert!(ascii.contains("`-- pre-push"));
+    }
+
+    #[test]
+    fn test_hook_origin() {
+        let fixtures = [
+            (
+                Hooksmith::generate_hook_content("pre-commit", false),
+                HookOrigin::Hooksmith,
+            ),
+            (
+                "#!/usr/bin/env sh\n. \"$(dirname -- \"$0\")/_/husky.sh\"\n\nnpx lint-staged\n"
+                    .to_string(),
+                HookOrigin::Husky,
+            ),
+            (
+                "#!/bin/sh\n\nif [ \"$LEFTHOOK_VERBOSE\" = \"1\" ]; then\n  set -x\nfi\n\ncall_lefthook run \"pre-commit\" \"$@\"\n"
+                    .to_string(),
+                HookOrigin::Lefthook,
+            ),
+            (
+                "#!/usr/bin/env bash\n# File generated by pre-commit: https://pre-commit.com\n# ID: 138fd403232d2ddd5efb44317e38bf03\nexec python -mpre_commit hook-impl --hook-type=pre-commit\n"
+                    .to_string(),
+                HookOrigin::PreCommit,
+            ),
+            (
+                "#!/bin/sh\ncargo test\n".to_string(),
+                HookOrigin::Unknown,
+            ),
+        ];
 
-            // Update the hook timing with the actual total duration
-            let mut updated_timing = hook_timing;
-            updated_timing.total_duration = hook_duration;
-            hook_timings.push(updated_timing);
+        for (content, origin) in fixtures {
+            assert_eq!(HookOrigin::classify("pre-commit", &content), origin);
+        }
+        assert!(HookOrigin::Husky.is_other_manager());
+        assert!(!HookOrigin::Unknown.is_other_manager());
+
+        // Without a terminal to ask on, another manager's hook is left in place
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - \"true\"\n");
+        let hook_path = dir.path().join("pre-commit");
+        let lefthook = "#!/bin/sh\ncall_lefthook run \"pre-commit\" \"$@\"\n";
+        fs::write(&hook_path, lefthook).unwrap();
+        let content = Hooksmith::generate_hook_content("pre-commit", false);
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        if !std::io::stdin().is_terminal() {
+            assert!(!hs.write_hook_file(&hook_path, "pre-commit").unwrap());
+            assert_eq!(fs::read_to_string(&hook_path).unwrap(), lefthook);
+            assert_eq!(hs.reporter().warning_count(), 1);
         }
 
-        let total_duration = start_time.elapsed();
+        let hs = hs.with_force(true);
+        hs.write_hook_file(&hook_path, "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook_path).unwrap(), content);
+    }
 
-        let timing_report = TimingReport {
-            hooks: hook_timings,
-            total_duration,
+    #[test]
+    fn test_new_from_config_for_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "env:\n  GREETING: hello\npre-commit:\n  commands:\n    - printf '%s' \"$GREETING\" > out.txt\npre-push:\n  umask: 999\n  commands:\n    - echo push\n");
+        let for_hooks = |names: &[&str]| {
+            let names = names.iter().map(ToString::to_string).collect::<Vec<_>>();
+            Hooksmith::new_from_config_for_hooks(
+                &config_path,
+                &names,
+                false,
+                false,
+                Reporter::default(),
+            )
         };
 
-        Self::print_timing_report(self, &timing_report);
-        Ok(())
+        // The invalid `umask` of `pre-push` stops the whole configuration from loading...
+        assert!(Hooksmith::new_from_config(&config_path, false, false).is_err());
+
+        // ...unless only the settings and `pre-commit` are read
+        let hs = for_hooks(&["pre-commit"]).unwrap();
+        assert_eq!(hs.get_available_hooks(), vec!["pre-commit".to_string()]);
+        hs.run_hook_with("pre-commit", RunOptions::new().with_cwd(dir.path()))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hello"
+        );
+
+        assert!(for_hooks(&["pre-*"]).is_err());
+        // Without any of the hooks, the configuration is read in full
+        assert!(for_hooks(&["post-merge"]).is_err());
     }
 
-    /// Runs multiple hooks by executing their commands.
-    ///
-    /// # Arguments
-    /// * `hook_names` - Vector of hook names to run
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    /// * If any hook is not found in the configuration
-    pub fn run_hooks(&self, hook_names: &[String]) -> Result<()> {
-        let total_hooks = hook_names.len();
-        for (hook_idx, hook_name) in hook_names.iter().enumerate() {
-            if true {
-                println!(
-                    "running `{hook_name}`, {}/{total_hooks} steps:",
-                    hook_idx + 1
-                );
+    #[test]
+    fn test_scripts_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let scripts = dir.path().join(".hooks");
+        fs::create_dir(&scripts).unwrap();
+        fs::write(scripts.join("pre-commit"), "#!/bin/sh\nexit 0\n").unwrap();
+        fs::write(scripts.join("commit-msg"), "#!/bin/sh\nexit 0\n").unwrap();
+        let hs = hooksmith_in(dir.path(), "scripts_dir: .hooks\npre-commit:\n  commands:\n    - cargo fmt\npre-push:\n  groups:\n    - [cargo test]\n");
+        let hooks = &hs.config.hooks;
+        // Inline commands run first, then the script
+        let pre_commit = hooks["pre-commit"].commands.as_ref().unwrap();
+        assert_eq!(pre_commit.len(), 2);
+        assert_eq!(pre_commit[0].command, "cargo fmt");
+        assert!(pre_commit[1].command.ends_with("/.hooks/pre-commit \"$@\""));
+        // Hooks only defined by a script are created
+        assert_eq!(hooks["commit-msg"].commands.as_ref().unwrap().len(), 1);
+        assert!(hooks["pre-push"].commands.is_none());
+        assert_eq!(hooks["pre-push"].groups.as_ref().unwrap().len(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            for hook in ["pre-commit", "commit-msg"] {
+                fs::set_permissions(scripts.join(hook), fs::Permissions::from_mode(0o755)).unwrap();
             }
-            self.run_hook_internal(hook_name)?;
         }
-        Ok(())
+        assert!(!hs.lint_scripts_dir());
+
+        fs::write(scripts.join("precommit"), "").unwrap();
+        fs::write(scripts.join(".gitignore"), "").unwrap();
+        assert!(hs.lint_scripts_dir());
+        assert_eq!(hs.reporter().warning_count(), 1);
     }
 
-    /// Internal method to run a single hook
-    ///
-    /// # Arguments
-    /// * `hook_name` - Name of the hook to run
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    /// * If the hook is not found in the configuration
-    fn run_hook_internal(&self, hook_name: &str) -> Result<()> {
-        let Some(hook) = self.config.hooks.get(hook_name) else {
-            return self.handle_hook_not_found(hook_name);
-        };
+    #[test]
+    fn test_validate_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(
+            dir.path(),
+            "pre-commit:\n  commands:\n    - \"  \"\n    - echo \"unclosed\n    - sh -c 'exit 0'\n",
+        );
 
-        if self.verbose && !self.dry_run {
-            println!("📋 Running Hook: {hook_name}");
-        }
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let error = hs.validate_hooks_for_install().unwrap_err();
+        assert!(matches!(
+            error,
+            HooksmithError::Validation(ValidationError::InvalidCommand(ref invalid))
+                if invalid == "empty command in 'pre-commit', unclosed \" in 'pre-commit' command `echo \"unclosed`"
+        ));
+        assert_eq!(hs.reporter().error_count(), 1);
+
+        // Programs missing from PATH are only a warning, unless run through a prefix
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - RUST_LOG=debug hooksmith-no-such-program --check\n    - cd src && ./lint.sh\npre-push:\n  command_prefix: docker run image\n  commands:\n    - hooksmith-no-such-program\n");
+        hs.validate_hooks_for_install().unwrap();
+        assert!(hs.lint_missing_programs());
+        assert_eq!(hs.reporter().warning_count(), 1);
+        assert!(is_in_path("sh"));
+    }
+
+    #[test]
+    fn test_validate_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "env:\n  DEPLOY_ENV: staging\npre-commit:\n  commands:\n    - ./deploy.sh ${DEPLOY_ENV} ${PATH} ${GIT_INDEX_FILE}\n    - ./lint.sh ${HOOKSMITH_TEST_OPTIONAL:-all}\npre-push:\n  commands:\n    - git log ${HOOKSMITH_PUSH_RANGE}\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(hs.validate_variables().is_ok());
+
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - ./deploy.sh ${HOOKSMITH_TEST_UNDEFINED}\n    - git log ${HOOKSMITH_PUSH_RANGE}\n");
+        assert_eq!(
+            hs.undefined_variable_usages(),
+            vec![
+                "${HOOKSMITH_TEST_UNDEFINED} in 'pre-commit' command `./deploy.sh ${HOOKSMITH_TEST_UNDEFINED}`",
+                // Only set for pre-push
+                "${HOOKSMITH_PUSH_RANGE} in 'pre-commit' command `git log ${HOOKSMITH_PUSH_RANGE}`",
+            ]
+        );
+        assert!(hs.validate_variables().is_err());
+        // Installing doesn't depend on the environment
+        assert!(hs.validate_hooks().is_ok());
+    }
 
-        let executed_commands_count = self.run_path_scoped_commands(hook_name, hook)
-            + self.run_global_commands(hook_name, hook);
+    #[test]
+    fn test_lint_duplicate_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-push:\n  commands:\n    - cargo test\n    - cargo clippy\n  groups:\n    - - tests: cargo test\npre-commit:\n  paths:\n    web/:\n      commands: [npm test]\n    api/:\n      commands: [npm test]\n  commands:\n    - cargo fmt\n");
 
-        if self.dry_run {
-            println!(
-                "🏁 Dry run completed. {executed_commands_count} command(s) would be executed",
-            );
-        }
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(hs.lint_duplicate_commands());
+        assert_eq!(hs.reporter().warning_count(), 1);
 
-        Ok(())
+        // The same command in several path-based blocks runs in different directories
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  paths:\n    web/:\n      commands: [npm test]\n    api/:\n      commands: [npm test]\npre-push:\n  allow_duplicate_commands: true\n  commands:\n    - ./flaky-check.sh\n    - ./flaky-check.sh\n");
+        assert!(!hs.lint_duplicate_commands());
     }
 
-    /// Internal method to run a single hook with timing information
-    ///
-    /// # Arguments
-    /// * `hook_name` - Name of the hook to run
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    /// * If the hook is not found in the configuration
-    fn run_hook_internal_with_timing(&self, hook_name: &str) -> Result<HookTiming> {
-        let Some(hook) = self.config.hooks.get(hook_name) else {
-            self.handle_hook_not_found(hook_name)?;
-            // This should never be reached due to the error above
-            return Ok(HookTiming {
-                hook_name: hook_name.to_string(),
-                commands: Vec::new(),
-                total_duration: Duration::from_secs(0),
-            });
-        };
+    #[test]
+    fn test_lint_unquoted_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "commit-msg:\n  commands:\n    - ./check.sh \"{msg_file}\"\n    - \"cat $1 # noqa\"\n    - run: wc -l $1\n      allow_unquoted: true\n");
 
-        if self.verbose && !self.dry_run {
-            println!("📋 Running Hook: {hook_name}");
-        }
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(!hs.lint_unquoted_arguments());
+        assert!(hs.validate_hooks().is_ok());
+        assert_eq!(hs.reporter().warning_count(), 0);
 
-        let mut command_timings = Vec::new();
+        let hs = hooksmith_in(
+            dir.path(),
+            "commit-msg:\n  commands:\n    - ./check.sh {msg_file}\n",
+        );
+        assert!(hs.validate_hooks().is_ok());
+        assert_eq!(hs.reporter().warning_count(), 1);
+    }
 
-        // Run path-scoped commands with timing
-        let path_timings = self.run_path_scoped_commands_with_timing(hook_name, hook);
-        command_timings.extend(path_timings);
+    #[test]
+    fn test_verification_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir(&hooks_dir).unwrap();
+        fs::create_dir(dir.path().join("scripts")).unwrap();
+        fs::write(dir.path().join("scripts/lint.sh"), "").unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - ./scripts/lint.sh\n    - cargo fmt\n    - \"$HOME/bin/check\"\npre-push:\n  commands:\n    - bash scripts/missing.sh\n  paths:\n    web/:\n      working_directory: web\n      commands: [./run-tests]\ncommit-msg:\n  commands:\n    - check {sha}\n");
+        fs::write(
+            hooks_dir.join("pre-commit"),
+            Hooksmith::generate_hook_content("pre-commit", false),
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("pre-push"), "#!/bin/sh\nexit 0\n").unwrap();
+
+        let problems = hs.verification_problems(&hooks_dir, dir.path());
+        assert_eq!(problems.len(), 5, "{problems:#?}");
+        assert!(problems[0].starts_with("{sha} in 'commit-msg' command"));
+        assert_eq!(problems[1], "Hook 'commit-msg' is not installed");
+        assert!(problems[2].starts_with("Hook 'pre-push' is installed but out of date"));
+        assert!(problems[3..]
+            .iter()
+            .any(|problem| problem.contains("`scripts/missing.sh`")));
+        assert!(problems[3..]
+            .iter()
+            .any(|problem| problem.contains("`./run-tests`")));
 
-        // Run global commands with timing
-        let global_timings = self.run_global_commands_with_timing(hook_name, hook);
-        command_timings.extend(global_timings);
+        assert_eq!(referenced_script("./check.sh \"$1\""), Some("./check.sh"));
+        assert_eq!(referenced_script("python3 -m pytest"), None);
+        assert_eq!(referenced_script("cargo test"), None);
+        assert_eq!(referenced_script("/usr/bin/env true"), None);
+    }
 
-        let total_commands = command_timings.len();
+    #[test]
+    fn test_prune_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "# Checks\npre-comit:\n  commands:\n    - cargo fmt --check\n\n\"pre-push\":\n  commands:\n    # slow\n    - cargo test\nlint:\n  commands: [cargo clippy]\n";
+        let config_path = write_config(dir.path(), original);
 
-        if self.dry_run {
-            println!("🏁 Dry run completed. {total_commands} command(s) would be executed",);
-        }
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let pruned = hs.prune_config(&config_path, true).unwrap();
 
-        Ok(HookTiming {
-            hook_name: hook_name.to_string(),
-            commands: command_timings,
-            total_duration: Duration::from_secs(0), // Will be updated by caller
-        })
+        assert_eq!(pruned, vec!["lint".to_string(), "pre-comit".to_string()]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("hooksmith.yaml.bak")).unwrap(),
+            original
+        );
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "# Checks\n# Commented out by `hooksmith prune-config`: 'pre-comit' is not a Git hook\n# pre-comit:\n#   commands:\n#     - cargo fmt --check\n\n\"pre-push\":\n  commands:\n    # slow\n    - cargo test\n# Commented out by `hooksmith prune-config`: 'lint' is not a Git hook\n# lint:\n#   commands: [cargo clippy]\n"
+        );
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert_eq!(hs.get_available_hooks(), vec!["pre-push".to_string()]);
+        assert!(hs.prune_config(&config_path, true).unwrap().is_empty());
     }
 
-    /// Execute a list of commands with an optional working directory override.
-    /// Returns the number of commands executed (or that would be executed in dry-run).
-    fn run_commands_for_scope(
-        &self,
-        hook_name: &str,
-        commands: &[HookCommand],
-        working_directory_override: Option<&str>,
-    ) -> usize {
-        let total_commands = commands.len();
+    #[test]
+    fn test_every_git_hook_is_documented() {
+        let documented = crate::hook_docs::HOOK_DOCS
+            .iter()
+            .map(|doc| doc.name)
+            .collect::<Vec<_>>();
 
-        if self.dry_run {
-            for (idx, hook_command) in commands.iter().enumerate() {
-                if working_directory_override.is_some() {
-                    handle_dry_run_with_dir(
-                        hook_command,
-                        idx,
-                        total_commands,
-                        working_directory_override,
-                    );
-                } else {
-                    handle_dry_run(hook_command, idx, total_commands);
-                }
-            }
-            return total_commands;
-        }
+        assert_eq!(documented, GIT_HOOKS);
+        assert!(Hooksmith::explain_hook("pre-commit").is_ok());
+        assert!(Hooksmith::explain_hook("pre-comit").is_err());
+    }
 
-        let working_directory = working_directory_override.map(Path::new);
-        for (idx, hook_command) in commands.iter().enumerate() {
-            if true {
-                let display = hook_command
-                    .name
-                    .as_deref()
-                    .unwrap_or(&hook_command.command);
-                println!("  running `{display}` {}/{total_commands}", idx + 1);
-            }
-            self.execute_single_command(hook_command, hook_name, working_directory);
+    #[test]
+    fn test_server_hooks() {
+        // Only hooks chained to a `<hook>.local` script run it
+        assert!(!Hooksmith::expected_hook_content("pre-commit").contains(".local"));
+        assert!(Hooksmith::generate_hook_content("pre-commit", true).contains("\"$0.local\""));
+
+        let update = Hooksmith::generate_hook_content("update", false);
+        assert!(update.contains("exec hooksmith run update -- \"$@\""));
+        assert!(!update.contains("cargo install"));
+        assert!(Hooksmith::generate_hook_content("pre-commit", false).contains("cargo install"));
+        assert!(Hooksmith::generate_hook_content("commit-msg", false)
+            .contains("exec hooksmith run commit-msg -- \"$@\""));
+
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(
+            dir.path(),
+            "pre-receive:\n  stash_unstaged: true\n  commands:\n    - \"true\"\n",
+        );
+        let hook = &hs.config.hooks["pre-receive"];
+        // The received refs are read even though no command mentions them
+        assert!(hook.uses_input("pre-receive"));
+
+        // There is no working tree to stash from
+        hs.run_hook_with("pre-receive", RunOptions::new()).unwrap();
+        assert_eq!(hs.reporter().warning_count(), 1);
+
+        // Every command gets the received refs on its standard input
+        let stdin = format!("{} {} refs/heads/main\n", "b".repeat(40), "a".repeat(40));
+        let options = RunOptions::new();
+        let mut context = ExecutionContext::new("pre-receive", &options, hook);
+        context.input = HookInput::parse("pre-receive", stdin.as_bytes());
+        for output in ["first", "second"] {
+            let output = dir.path().join(output);
+            let command = format!("cat > {}", output.display());
+            let status = hs
+                .execute_command(
+                    &HookCommand::new_unnamed(command.clone()),
+                    &command,
+                    None,
+                    &context,
+                )
+                .unwrap()
+                .unwrap();
+            assert!(status.success());
+            assert_eq!(fs::read_to_string(&output).unwrap(), stdin);
         }
+    }
+
+    #[test]
+    fn test_installed_hook_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path();
+
+        assert_eq!(
+            Hooksmith::installed_hook_content_in(hooks_dir, "pre-commit").unwrap(),
+            None
+        );
+
+        let expected = Hooksmith::expected_hook_content("pre-commit");
+        assert!(expected.contains("exec hooksmith run pre-commit"));
+        fs::write(hooks_dir.join("pre-commit"), &expected).unwrap();
+        fs::write(hooks_dir.join("pre-push"), "#!/bin/sh\nmake lint\n").unwrap();
 
-        total_commands
+        assert_eq!(
+            Hooksmith::installed_hook_content_in(hooks_dir, "pre-commit").unwrap(),
+            Some(expected)
+        );
+        assert_ne!(
+            Hooksmith::installed_hook_content_in(hooks_dir, "pre-push").unwrap(),
+            Some(Hooksmith::expected_hook_content("pre-push"))
+        );
+        // A directory in place of the hook can't be read
+        fs::create_dir(hooks_dir.join("commit-msg")).unwrap();
+        assert!(Hooksmith::installed_hook_content_in(hooks_dir, "commit-msg").is_err());
     }
 
-    /// Execute a list of commands with timing information.
-    /// Returns timing information for each command executed.
-    fn run_commands_for_scope_with_timing(
-        &self,
-        hook_name: &str,
-        commands: &[HookCommand],
-        working_directory_override: Option<&str>,
-    ) -> Vec<CommandTiming> {
-        let mut timings = Vec::new();
-        let total_commands = commands.len();
+    #[test]
+    fn test_emit_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(
+            dir.path(),
+            "pre-commit:\n  commands:\n    - cargo fmt\npre-push:\n  commands:\n    - cargo test\n",
+        );
 
-        if self.dry_run {
-            for (idx, hook_command) in commands.iter().enumerate() {
-                if working_directory_override.is_some() {
-                    handle_dry_run_with_dir(
-                        hook_command,
-                        idx,
-                        total_commands,
-                        working_directory_override,
-                    );
-                } else {
-                    handle_dry_run(hook_command, idx, total_commands);
-                }
-                // For dry run, we still add timing entries with zero duration
-                timings.push(CommandTiming {
-                    command: hook_command.command.clone(),
-                    name: hook_command.name.clone(),
-                    duration: Duration::from_secs(0),
-                });
-            }
-            return timings;
-        }
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        let review = dir.path().join("review");
+        hs.emit_hooks(&review).unwrap();
 
-        let working_directory = working_directory_override.map(Path::new);
-        for (idx, hook_command) in commands.iter().enumerate() {
-            if true {
-                let display = hook_command
-                    .name
-                    .as_deref()
-                    .unwrap_or(&hook_command.command);
-                println!("  running `{display}` {}/{total_commands}", idx + 1);
-            }
-            let start_time = Instant::now();
-            self.execute_single_command(hook_command, hook_name, working_directory);
-            let duration = start_time.elapsed();
+        for hook_name in ["pre-commit", "pre-push"] {
+            let path = review.join(hook_name);
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                Hooksmith::generate_hook_content(hook_name, false)
+            );
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
 
-            timings.push(CommandTiming {
-                command: hook_command.command.clone(),
-                name: hook_command.name.clone(),
-                duration,
-            });
+                let mode = fs::metadata(&path).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o755);
+            }
         }
 
-        timings
+        // The hooks directory and its subdirectories are refused before anything is written
+        let hooks_dir = get_git_hooks_path().unwrap();
+        let inside = hooks_dir.join("hooksmith-review");
+        assert!(hs.emit_hooks(&hooks_dir).is_err());
+        assert!(hs.emit_hooks(&inside).is_err());
+        assert!(!inside.exists());
     }
 
-    /// Execute global commands for a hook, if any, and return how many were executed.
-    fn run_global_commands(&self, hook_name: &str, hook: &Hook) -> usize {
-        match &hook.commands {
-            Some(commands) => self.run_commands_for_scope(hook_name, commands, None),
-            None => 0,
-        }
-    }
+    #[test]
+    fn test_install_backs_up_foreign_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - \"true\"\n");
+        let hook_path = dir.path().join("pre-commit");
+        let content = Hooksmith::generate_hook_content("pre-commit", false);
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        fs::write(&hook_path, "#!/bin/sh\nmake lint\n").unwrap();
+        hs.write_hook_file(&hook_path, "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook_path).unwrap(), content);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("pre-commit.bak")).unwrap(),
+            "#!/bin/sh\nmake lint\n"
+        );
+        assert_eq!(hs.reporter().warning_count(), 1);
+
+        // Reinstalling over a hooksmith hook doesn't back it up
+        hs.write_hook_file(&hook_path, "pre-commit").unwrap();
+        assert!(!dir.path().join("pre-commit.bak.1").exists());
+
+        // An earlier backup isn't overwritten
+        fs::write(&hook_path, "#!/bin/sh\nmake test\n").unwrap();
+        hs.write_hook_file(&hook_path, "pre-commit").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("pre-commit.bak.1")).unwrap(),
+            "#!/bin/sh\nmake test\n"
+        );
 
-    /// Execute global commands for a hook with timing, if any, and return timing information.
-    fn run_global_commands_with_timing(&self, hook_name: &str, hook: &Hook) -> Vec<CommandTiming> {
-        match &hook.commands {
-            Some(commands) => self.run_commands_for_scope_with_timing(hook_name, commands, None),
-            None => Vec::new(),
-        }
+        let hs = hs.with_force(true);
+        fs::write(&hook_path, "#!/bin/sh\nmake check\n").unwrap();
+        hs.write_hook_file(&hook_path, "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook_path).unwrap(), content);
+        assert!(!dir.path().join("pre-commit.bak.2").exists());
     }
 
-    /// Execute path-scoped commands that match changed files for the hook.
-    /// Returns the number of commands executed.
-    fn run_path_scoped_commands(&self, hook_name: &str, hook: &Hook) -> usize {
-        let Some(paths_map) = &hook.paths else {
-            return 0;
-        };
+    #[cfg(unix)]
+    #[test]
+    fn test_install_appends_to_existing_hooks() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-push:\n  commands:\n    - \"true\"\n");
+        let log = dir.path().join("log");
+        // Stands in for hooksmith, recording its arguments and input
+        let bin = dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        write_executable(
+            &bin.join("hooksmith"),
+            &format!(
+                "#!/bin/sh\n[ \"$1\" = -h ] && exit 0\necho \"hooksmith $* $(cat)\" >> {}\n",
+                log.display()
+            ),
+        )
+        .unwrap();
 
-        let Some(changed_files) = Self::detect_changed_files(hook_name) else {
-            return 0;
+        let hook_path = dir.path().join("pre-push");
+        write_executable(
+            &hook_path,
+            &format!(
+                "#!/bin/sh\necho \"local $* $(cat)\" >> {}\nexit \"$LOCAL_STATUS\"\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false)
+            .unwrap()
+            .with_append(true);
+        assert!(hs.write_hook_file(&hook_path, "pre-push").unwrap());
+        assert!(dir.path().join("pre-push.local").exists());
+        let content = fs::read_to_string(&hook_path).unwrap();
+        assert_eq!(content, Hooksmith::generate_hook_content("pre-push", true));
+        assert!(content.contains("$0.local"));
+
+        // Reinstalling keeps running the local hook
+        assert!(hs.write_hook_file(&hook_path, "pre-push").unwrap());
+        assert_eq!(fs::read_to_string(&hook_path).unwrap(), content);
+        assert!(!dir.path().join("pre-push.local.bak").exists());
+
+        let run_hook = |local_status: &str| {
+            let mut child = Command::new(&hook_path)
+                .arg("origin")
+                .env(
+                    "PATH",
+                    format!("{}:{}", bin.display(), std::env::var("PATH").unwrap()),
+                )
+                .env("LOCAL_STATUS", local_status)
+                .stdin(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(b"refs/heads/main")
+                .unwrap();
+            child.wait().unwrap().code()
         };
 
-        let mut executed = 0usize;
-        for (path_prefix, path_cfg) in paths_map {
-            let has_match = changed_files.iter().any(|f| f.starts_with(path_prefix));
-            if !has_match {
-                continue;
-            }
+        // The local hook runs first, and both get the input of the hook
+        assert_eq!(run_hook("0"), Some(0));
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "local origin refs/heads/main\nhooksmith run pre-push -- origin refs/heads/main\n"
+        );
 
-            executed += self.run_commands_for_scope(
-                hook_name,
-                &path_cfg.commands,
-                path_cfg.working_directory.as_deref(),
-            );
-        }
+        // A failing local hook stops the hook with its status
+        fs::remove_file(&log).unwrap();
+        assert_eq!(run_hook("3"), Some(3));
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "local origin refs/heads/main\n"
+        );
+    }
+
+    #[test]
+    fn test_foreign_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path();
+
+        fs::write(
+            hooks_dir.join("pre-commit"),
+            Hooksmith::generate_hook_content("pre-commit", false),
+        )
+        .unwrap();
+        fs::write(
+            hooks_dir.join("update"),
+            Hooksmith::generate_hook_content("update", false),
+        )
+        .unwrap();
+        // Scripts of older versions still run `hooksmith run <hook>`
+        fs::write(
+            hooks_dir.join("pre-push"),
+            "#!/bin/sh\nhooksmith run pre-push\n",
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("pre-rebase.sample"), "#!/bin/sh\n").unwrap();
+        assert!(foreign_hooks(hooks_dir).is_empty());
+
+        fs::write(hooks_dir.join("post-merge"), "#!/bin/sh\nnpm install\n").unwrap();
+        // Running hooksmith for another hook doesn't make it managed
+        fs::write(
+            hooks_dir.join("commit-msg"),
+            "#!/bin/sh\nhooksmith run pre-commit\n",
+        )
+        .unwrap();
+        fs::create_dir(hooks_dir.join("lib")).unwrap();
 
-        executed
+        assert_eq!(foreign_hooks(hooks_dir), vec!["commit-msg", "post-merge"]);
     }
 
-    /// Execute path-scoped commands that match changed files for the hook with timing.
-    /// Returns timing information for commands executed.
-    fn run_path_scoped_commands_with_timing(
-        &self,
-        hook_name: &str,
-        hook: &Hook,
-    ) -> Vec<CommandTiming> {
-        let Some(paths_map) = &hook.paths else {
-            return Vec::new();
-        };
+    #[test]
+    fn test_upgrade_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir(&hooks_dir).unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands: [cargo fmt]\npre-push:\n  commands: [cargo test]\ncommit-msg:\n  commands: [\"true\"]\npost-merge:\n  commands: [\"true\"]\n");
+
+        // An older wrapper, the current one, a foreign script, and a managed hook that is
+        // no longer configured; `post-merge` isn't installed
+        let stale = "#!/bin/sh\nhooksmith run pre-commit\n";
+        fs::write(hooks_dir.join("pre-commit"), stale).unwrap();
+        fs::write(
+            hooks_dir.join("pre-push"),
+            Hooksmith::expected_hook_content("pre-push"),
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("commit-msg"), "#!/bin/sh\nnpx commitlint\n").unwrap();
+        fs::write(
+            hooks_dir.join("post-checkout"),
+            "#!/bin/sh\nhooksmith run post-checkout\n",
+        )
+        .unwrap();
 
-        let Some(changed_files) = Self::detect_changed_files(hook_name) else {
-            return Vec::new();
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        let expected = HookUpgrade {
+            upgraded: vec!["pre-commit".to_string()],
+            current: vec!["pre-push".to_string()],
+            foreign: vec!["commit-msg".to_string()],
         };
+        assert_eq!(hs.upgrade_hooks_in(&hooks_dir).unwrap(), expected);
+        // Dry runs don't write
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre-commit")).unwrap(),
+            stale
+        );
 
-        let mut timings = Vec::new();
-        for (path_prefix, path_cfg) in paths_map {
-            let has_match = changed_files.iter().any(|f| f.starts_with(path_prefix));
-            if !has_match {
-                continue;
-            }
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert_eq!(hs.upgrade_hooks_in(&hooks_dir).unwrap(), expected);
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre-commit")).unwrap(),
+            Hooksmith::expected_hook_content("pre-commit")
+        );
+        assert!(!hooks_dir.join("post-merge").exists());
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("post-checkout")).unwrap(),
+            "#!/bin/sh\nhooksmith run post-checkout\n"
+        );
 
-            let mut command_timings = self.run_commands_for_scope_with_timing(
-                hook_name,
-                &path_cfg.commands,
-                path_cfg.working_directory.as_deref(),
-            );
-            timings.append(&mut command_timings);
+        let upgrade = hs.upgrade_hooks_in(&hooks_dir).unwrap();
+        assert!(upgrade.upgraded.is_empty());
+        assert_eq!(upgrade.current, vec!["pre-commit", "pre-push"]);
+    }
+
+    #[test]
+    fn test_uninstall_continues_after_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "commit-msg:\n  commands:\n    - \"true\"\npre-commit:\n  commands:\n    - \"true\"\npre-push:\n  commands:\n    - \"true\"\npost-merge:\n  commands:\n    - \"true\"\n");
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir(&hooks_dir).unwrap();
+        for hook_name in ["commit-msg", "pre-commit", "pre-push"] {
+            fs::write(hooks_dir.join(hook_name), "#!/bin/sh\n").unwrap();
         }
+        // A file that can't be removed: permissions don't stop root, a directory does
+        fs::remove_file(hooks_dir.join("pre-commit")).unwrap();
+        fs::create_dir(hooks_dir.join("pre-commit")).unwrap();
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let removal = hs.remove_hook_files(&hooks_dir);
+
+        // The hooks after the failing one are removed too
+        assert_eq!(removal.removed, vec!["commit-msg", "pre-push"]);
+        assert!(!hooks_dir.join("commit-msg").exists());
+        assert!(!hooks_dir.join("pre-push").exists());
+        assert_eq!(removal.failed.len(), 1);
+        assert_eq!(removal.failed[0].0, "pre-commit");
+
+        let error = hs.report_removal_failures(&removal.failed).unwrap_err();
+        assert!(matches!(
+            error,
+            HooksmithError::Git(GitError::HookRemoval(ref hooks)) if hooks == &["pre-commit"]
+        ));
+        assert_eq!(hs.reporter().error_count(), 1);
+    }
+
+    #[test]
+    fn test_resolve_hook_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands: [\"true\"]\npre-push:\n  commands: [\"true\"]\npost-merge:\n  commands: [\"true\"]\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
 
-        timings
+        assert_eq!(
+            hs.resolve_hook_patterns(&names(&["pre-*"])).unwrap(),
+            names(&["pre-commit", "pre-push"])
+        );
+        assert_eq!(
+            hs.resolve_hook_patterns(&names(&["post-merge", "pre-push", "p*"]))
+                .unwrap(),
+            names(&["post-merge", "pre-push", "pre-commit"])
+        );
+        assert!(hs.resolve_hook_patterns(&names(&["commit-*"])).is_err());
+
+        // In dry run mode, nothing is removed and no confirmation is asked
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        assert!(hs.uninstall_given_hooks(&names(&["pre-*"]), false).is_ok());
+        assert!(hs
+            .uninstall_given_hooks(&names(&["post-merge", "commit-*"]), true)
+            .is_err());
+        assert!(hs
+            .uninstall_given_hooks(&names(&["commit-msg"]), true)
+            .is_err());
     }
 
-    /// Runs hooks either interactively or from provided names.
-    ///
-    /// # Arguments
-    /// * `hook_names` - Optional vector of hook names to run. If None, and interactive is true, will prompt for selection.
-    /// * `interactive` - Whether to use interactive selection when `hook_names` is None.
-    /// * `profile` - Whether to enable performance profiling and show timing information.
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    /// * If hook selection fails
-    /// * If any hook is not found in the configuration
-    pub fn run_hook(
-        &self,
-        hook_names: Option<&[String]>,
-        interactive: bool,
-        profile: bool,
-    ) -> Result<()> {
-        if interactive {
-            let selected_hooks = self.select_hooks_interactively()?;
-            if profile {
-                self.run_hooks_with_timing(&selected_hooks)
-            } else {
-                self.run_hooks(&selected_hooks)
-            }
-        } else if let Some(names) = hook_names {
-            if names.is_empty() {
-                return Err(
-                    HookExecutionError::HookNotFound("No hooks specified".to_string()).into(),
-                );
-            }
+    #[test]
+    fn test_hook_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir(&hooks_dir).unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  description: Formatting and tests\n  commands:\n    - fmt: cargo fmt\n  paths:\n    web/:\n      commands: [npm test]\npre-push:\n  commands: [cargo test]\ncommit-msg:\n  commands: [\"true\"]\npost-merge:\n  groups:\n    - [a, b]\n");
+        fs::write(
+            hooks_dir.join("pre-commit"),
+            Hooksmith::expected_hook_content("pre-commit"),
+        )
+        .unwrap();
+        fs::write(
+            hooks_dir.join("pre-push"),
+            "#!/bin/sh\nhooksmith run pre-push\n",
+        )
+        .unwrap();
+        fs::write(hooks_dir.join("commit-msg"), "#!/bin/sh\nnpx commitlint\n").unwrap();
 
-            // remove duplicate hooks
-            let unique_hooks = names
-                .iter()
-                .cloned()
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect::<Vec<_>>();
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let listing = hs.hook_listing_in(&hooks_dir, false).unwrap();
+        let states = listing
+            .iter()
+            .map(|hook| (hook.name.as_str(), hook.installed, hook.state))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            [
+                (
+                    "commit-msg",
+                    false,
+                    "not installed, another script is in place"
+                ),
+                ("post-merge", false, "not installed"),
+                ("pre-commit", true, "installed"),
+                (
+                    "pre-push",
+                    true,
+                    "installed, outdated: run `hooksmith upgrade`"
+                ),
+            ]
+        );
+        assert_eq!(listing[1].commands, ["a", "b"]);
+        assert_eq!(listing[2].commands, ["npm test", "fmt: cargo fmt"]);
+        assert_eq!(
+            listing[2].description.as_deref(),
+            Some("Formatting and tests")
+        );
+        assert_eq!(listing[3].description, None);
+
+        let installed = hs.hook_listing_in(&hooks_dir, true).unwrap();
+        assert_eq!(installed, listing[2..]);
+    }
+
+    #[test]
+    fn test_hooks_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-push:\n  description: Runs the tests before pushing\n  commands:\n    - cargo test\npre-commit:\n  paths:\n    src/:\n      commands:\n        - lint: cargo clippy\n  commands:\n    - fmt: cargo fmt --check\n");
+        let json: serde_json::Value = serde_json::from_str(&hs.hooks_json().unwrap()).unwrap();
+
+        assert_eq!(json["schema_version"], HOOKS_SCHEMA_VERSION);
+        let hooks = json["hooks"].as_array().unwrap();
+        assert_eq!(hooks.len(), 2);
+
+        let pre_commit = &hooks[0];
+        assert_eq!(pre_commit["name"], "pre-commit");
+        assert!(pre_commit["installed"].is_boolean());
+        assert!(pre_commit["enabled"].is_boolean());
+        assert!(pre_commit["description"].is_null());
+        assert_eq!(hooks[1]["description"], "Runs the tests before pushing");
+        assert_eq!(
+            pre_commit["commands"],
+            serde_json::json!([
+                {"name": "lint", "run": "cargo clippy"},
+                {"name": "fmt", "run": "cargo fmt --check"},
+            ])
+        );
+        assert_eq!(
+            hooks[1]["commands"],
+            serde_json::json!([{"name": null, "run": "cargo test"}])
+        );
+    }
 
-            if profile {
-                self.run_hooks_with_timing(&unique_hooks)
+    #[test]
+    fn test_doctor_checks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir(&hooks_dir).unwrap();
+        let config_path = write_config(dir.path(), "pre-comit:\n  commands: [cargo fmt]\npre-commit:\n  commands: [cargo fmt]\npre-push:\n  commands: [cargo test]\ncommit-msg:\n  commands: [\"true\"]\n");
+        for hook_name in ["pre-commit", "pre-push"] {
+            let path = hooks_dir.join(hook_name);
+            fs::write(&path, Hooksmith::expected_hook_content(hook_name)).unwrap();
+            let mode = if hook_name == "pre-commit" {
+                0o755
             } else {
-                self.run_hooks(&unique_hooks)
-            }
-        } else {
-            Err(HookExecutionError::HookNotFound(
-                "No hook specified and interactive mode is disabled".to_string(),
-            )
-            .into())
+                0o644
+            };
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
         }
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let checks = hs.hook_file_checks(&hooks_dir);
+        let statuses = checks.iter().map(|check| check.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                CheckStatus::Warn,
+                CheckStatus::Fail,
+                CheckStatus::Pass,
+                CheckStatus::Fail
+            ]
+        );
+        assert_eq!(
+            checks[0].to_string(),
+            "⚠️  'commit-msg' is not installed: run `hooksmith install`"
+        );
+        assert!(checks[1].message.contains("did you mean 'pre-commit'?"));
+        assert!(checks[3].message.contains("not executable"));
+
+        assert_eq!(Hooksmith::hooks_dir_writable_check(&hooks_dir), None);
+        assert_eq!(
+            Hooksmith::hooks_dir_writable_check(&dir.path().join("missing"))
+                .unwrap()
+                .status,
+            CheckStatus::Warn
+        );
+        assert_eq!(
+            Hooksmith::hooks_dir_check(&hooks_dir, HooksDirSource::CoreHooksPath).status,
+            CheckStatus::Warn
+        );
     }
 
-    /// Uninstalls a single, given hook by removing its file.
-    ///
-    /// # Arguments
-    /// * `hook_name` - The name of the hook to run.
-    ///
-    /// # Errors
-    /// * Errors if the command fails to remove the file.
-    pub fn uninstall_given_hook(&self, hook_name: &str) -> Result<()> {
-        if self.config.hooks.contains_key(hook_name) {
-            if self.verbose && !self.dry_run {
-                println!("🗑️ Uninstalling hook: {hook_name}");
-            }
+    #[test]
+    fn test_run_options_builder() {
+        let options = RunOptions::new()
+            .with_env("CI", "true")
+            .with_extra_args(vec!["origin".to_string()])
+            .with_profile(true)
+            .with_stash(true)
+            .with_max_output_bytes(Some(64))
+            .with_show_skipped(Some(true));
+
+        assert_eq!(options.env["CI"], "true");
+        assert_eq!(options.extra_args, vec!["origin".to_string()]);
+        assert!(options.profile && options.stash);
+        assert!(!options.interactive && !options.since_last_run && !options.capture);
+        assert_eq!(options.max_output_bytes, Some(64));
+        assert_eq!(options.show_skipped, Some(true));
+        assert_eq!(options.cwd, None);
+        assert_eq!(options.timeout, None);
+        assert_eq!(RunOptions::new(), RunOptions::default());
+    }
 
-            let git_hooks_path = get_git_hooks_path()?;
-            let hook_path = git_hooks_path.join(hook_name);
+    #[test]
+    fn test_run_hook_with_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "env:\n  GREETING: hello\n  TARGET: config\npre-commit:\n  commands:\n    - printf '%s %s %s' \"$GREETING\" \"$TARGET\" \"$1\" > out.txt\n");
+        let options = RunOptions::new()
+            .with_env("TARGET", "options")
+            .with_cwd(dir.path())
+            .with_extra_args(vec!["arg".to_string()])
+            .with_capture(true)
+            .with_timeout(Duration::from_secs(10));
+        let timing = hs.run_hook_with("pre-commit", options).unwrap();
+
+        assert_eq!(timing.commands.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hello options arg"
+        );
+    }
 
-            if hook_path.exists() {
-                if self.dry_run {
-                    println!(
-                        "  🚧 Dry run: Would remove hook file: {}",
-                        hook_path.display()
-                    );
-                } else {
-                    fs::remove_file(&hook_path)?;
-                }
-            } else {
-                println!("  ⚠️ No hook file found for {hook_name}");
-            }
-        } else {
-            let possible_hooks = self.config.hooks.keys().collect::<Vec<_>>();
-            eprintln!("No file found for hook '{hook_name}'");
-            eprintln!("Possible hooks: {possible_hooks:?}");
+    #[test]
+    fn test_run_hook_with_built_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - printf '%s %s,' \"$MODE\" \"$1\" >> out.txt\npre-push:\n  commands:\n    - printf 'push %s,' \"$1\" >> out.txt\n");
+        let options = RunOptions::new()
+            .with_env("MODE", "fast")
+            .with_cwd(dir.path())
+            .with_extra_args(vec!["arg".to_string()])
+            .with_profile(true);
+        hs.run_hook(Some(&["pre-*".to_string()]), &options).unwrap();
+
+        let output = fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert!(output.contains("fast arg,"));
+        assert!(output.contains("push arg,"));
+
+        // Without names, hooks are only selected in interactive mode
+        assert!(hs.run_hook(None, &RunOptions::default()).is_err());
+    }
 
-            return Err(ValidationError::InvalidHookName(hook_name.to_string()).into());
-        }
+    #[test]
+    fn test_run_all_except() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - printf 'pre-commit,' >> out.txt\npre-push:\n  commands:\n    - printf 'pre-push,' >> out.txt\npost-merge:\n  commands:\n    - printf 'post-merge,' >> out.txt\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let options = RunOptions::new()
+            .with_cwd(dir.path())
+            .with_all(true)
+            .with_except(vec!["pre-push".to_string()]);
+        hs.run_hook(None, &options).unwrap();
+
+        // In Git's workflow order
+        assert_eq!(
+            fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "pre-commit,post-merge,"
+        );
 
-        Ok(())
+        // The run stops at the first failing hook
+        fs::write(
+            &config_path,
+            "pre-commit:\n  commands:\n    - exit 1\npost-merge:\n  commands:\n    - printf 'post-merge,' >> failed.txt\n",
+        )
+        .unwrap();
+        let failing = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(failing
+            .run_hook(None, &RunOptions::new().with_cwd(dir.path()).with_all(true))
+            .is_err());
+        assert!(!dir.path().join("failed.txt").exists());
+
+        // Excluded hooks must be configured, and something must be left to run
+        let options = options.with_except(vec!["pre-recieve".to_string()]);
+        assert!(hs.run_hook(None, &options).is_err());
+        let options = RunOptions::new().with_except(vec!["pre-commit".to_string()]);
+        assert!(hs
+            .run_hook(Some(&["pre-commit".to_string()]), &options)
+            .is_err());
     }
 
-    /// Uninstalls all hooks by removing their files.
-    ///
-    /// # Errors
-    /// * If there is an error uninstalling a hook.
-    pub fn uninstall_hooks(&self) -> Result<()> {
-        if self.verbose && !self.dry_run {
-            println!("🗑️ Uninstalling all hooks");
-        }
+    #[test]
+    fn test_hooks_for_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  files: [\"*.rs\", \"Cargo.toml\"]\n  commands:\n    - cargo fmt --check\npre-push:\n  commands:\n    - cargo test\n");
 
-        for hook_name in self.config.hooks.keys() {
-            self.uninstall_given_hook(hook_name)?;
-        }
+        let files = |files: &[&str]| {
+            RunContext::new().with_changed_files(files.iter().map(ToString::to_string).collect())
+        };
+        let pre_commit = vec!["pre-commit".to_string()];
 
-        if self.verbose && !self.dry_run {
-            println!(
-                "🏁 Uninstallation completed: {} hooks removed",
-                self.config.hooks.len()
-            );
-        }
+        // Unknown changes, matching and non-matching files
+        assert_eq!(
+            hs.hooks_for_event("pre-commit", &RunContext::new()),
+            pre_commit
+        );
+        assert_eq!(
+            hs.hooks_for_event("pre-commit", &files(&["src/lib.rs"])),
+            pre_commit
+        );
+        assert_eq!(
+            hs.hooks_for_event("pre-commit", &files(&["README.md", "Cargo.toml"])),
+            pre_commit
+        );
+        assert!(hs
+            .hooks_for_event("pre-commit", &files(&["README.md"]))
+            .is_empty());
+        assert!(hs.hooks_for_event("pre-commit", &files(&[])).is_empty());
+
+        // Without patterns, any change fires the hook
+        assert_eq!(
+            hs.hooks_for_event("pre-push", &files(&[])),
+            vec!["pre-push".to_string()]
+        );
 
-        Ok(())
+        // Unconfigured events fire nothing
+        assert!(hs
+            .hooks_for_event("commit-msg", &RunContext::new())
+            .is_empty());
     }
 
-    /// Validate that hooks in the configuration file are standard Git hooks.
-    ///
-    /// # Errors
-    /// None, I just return Ok(()) to aggregate all calls in a `match` statement in the main function.
-    pub fn validate_hooks(&self) -> Result<()> {
-        if self.verbose {
-            println!("🔍 Validating hooks in configuration file...");
-        }
+    #[test]
+    fn test_only_changed_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-push:\n  files: [\"*.rs\", Cargo.toml]\n  commands:\n    - cargo test\npre-commit:\n  commands:\n    - cargo fmt\n");
+        let options = RunOptions::new().with_only_changed_hooks(true);
+        let affected = |hook_name: &str, files: &[&str]| {
+            let hook = &hs.config.hooks[hook_name];
+            let mut context = ExecutionContext::new(hook_name, &options, hook);
+            context.changed_files = Some(files.iter().map(ToString::to_string).collect());
+
+            Hooksmith::is_affected_by_changes(&mut context, hook)
+        };
 
-        let mut invalid_hooks = Vec::new();
-        let mut valid_hooks = 0;
+        assert!(affected("pre-push", &["src/main.rs", "README.md"]));
+        assert!(affected("pre-push", &["Cargo.toml"]));
+        assert!(!affected("pre-push", &["README.md", "Cargo.lock"]));
+        assert!(!affected("pre-push", &[]));
+        // Hooks without `files` patterns always run
+        assert!(affected("pre-commit", &["README.md"]));
+    }
 
-        for hook_name in self.config.hooks.keys() {
-            if GIT_HOOKS.contains(&hook_name.as_str()) {
-                valid_hooks += 1;
-                if self.verbose {
-                    println!("  ✅ Hook '{hook_name}' is valid");
-                }
-            } else {
-                invalid_hooks.push(hook_name.clone());
-            }
-        }
+    #[test]
+    fn test_since_last_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
 
-        if invalid_hooks.is_empty() {
-            if self.verbose {
-                print_success(
-                    "All hooks are valid",
-                    &format!("Found {valid_hooks} valid Git hooks in your configuration."),
-                );
-            }
-        } else {
-            print_warning(
-                "Invalid hooks detected",
-                &format!(
-                    "The following hooks are not recognized by Git:\n{}\n\nPlease use only valid Git hook names in your configuration.",
-                    format_list(&invalid_hooks)
-                ),
-            );
-        }
+        git(&["init", "-q"]);
+        fs::write(dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "initial"]);
 
-        Ok(())
+        let hs = Hooksmith::from_reader(
+            "pre-commit:\n  commands:\n    - cargo fmt\n".as_bytes(),
+            false,
+            false,
+        )
+        .unwrap();
+        let changed = || Hooksmith::files_changed_since_last_run(dir.path(), "pre-commit");
+
+        // Never run: the hook runs in full
+        assert_eq!(changed(), None);
+
+        // Nothing changed since the last successful run: the hook is skipped
+        hs.record_successful_run(dir.path(), "pre-commit");
+        assert_eq!(changed(), Some(Vec::new()));
+        assert_eq!(
+            Hooksmith::files_changed_since_last_run(dir.path(), "pre-push"),
+            None
+        );
+
+        // Edited and committed files are picked up until the next successful run
+        fs::write(dir.path().join("lib.rs"), "fn b() {}\n").unwrap();
+        assert_eq!(changed(), Some(vec!["lib.rs".to_string()]));
+        git(&["commit", "-qam", "edit"]);
+        assert_eq!(changed(), Some(vec!["lib.rs".to_string()]));
+        hs.record_successful_run(dir.path(), "pre-commit");
+        assert_eq!(changed(), Some(Vec::new()));
+
+        // `hooksmith cache clear` makes the next run process everything again
+        assert_eq!(
+            run_state::clear_markers(dir.path()).unwrap(),
+            vec!["pre-commit".to_string()]
+        );
+        assert_eq!(changed(), None);
+        assert!(run_state::clear_markers(dir.path()).unwrap().is_empty());
     }
 
-    /// Validate hooks configuration before installation.
-    ///
-    /// # Errors
-    /// * If any invalid hook names are found.
-    pub fn validate_hooks_for_install(&self) -> Result<()> {
-        if self.verbose {
-            println!("🔍 Validating hooks before installation...");
-        }
+    #[test]
+    fn test_skip() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - exit 1\n    - run: exit 2\n      name: lint\n    - \"true\"\n  groups:\n    - - exit 3\npre-push:\n  commands:\n    - exit 4\n");
+        let options = RunOptions::new().with_skip(vec![
+            "pre-push".to_string(),
+            "pre-commit:1".to_string(),
+            "pre-commit:lint".to_string(),
+            "pre-commit:4".to_string(),
+        ]);
+
+        let timing = hs
+            .run_hook_internal_with_timing("pre-commit", &options)
+            .unwrap();
+        let run = timing
+            .commands
+            .iter()
+            .map(|timing| timing.command.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(run, ["true"]);
+        let skipped = timing
+            .skipped
+            .iter()
+            .map(|skipped| skipped.target.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped,
+            ["`pre-commit:1`", "`pre-commit:2`", "`pre-commit:4`"]
+        );
 
-        let mut invalid_hooks = Vec::new();
-        for hook_name in self.config.hooks.keys() {
-            if !GIT_HOOKS.contains(&hook_name.as_str()) {
-                invalid_hooks.push(hook_name.clone());
-            }
-        }
+        let timing = hs
+            .run_hook_internal_with_timing("pre-push", &options)
+            .unwrap();
+        assert!(timing.commands.is_empty());
+        assert_eq!(timing.skipped[0].reason, "listed in `--skip`");
+    }
 
-        if !invalid_hooks.is_empty() {
-            let error_message = format!(
-                "Invalid hook names detected\n\nThe following hooks are not recognized by Git:\n{}\n\nPlease check your configuration file and use only valid Git hook names.",
-                format_list(&invalid_hooks)
-            );
+    #[test]
+    fn test_skipped_blocks_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "post-merge:\n  paths:\n    src/:\n      commands:\n        - exit 1\n  commands:\n    - \"true\"\n");
+        let options = RunOptions::new().with_show_skipped(Some(false));
+        let timing = hs
+            .run_hook_internal_with_timing("post-merge", &options)
+            .unwrap();
+
+        // Changed files are only detected for pre-commit and pre-push
+        assert_eq!(timing.commands.len(), 1);
+        assert_eq!(
+            timing.skipped,
+            vec![Skipped {
+                target: "`paths: src/`".to_string(),
+                reason: "changed files can't be detected for `post-merge`".to_string(),
+            }]
+        );
+    }
 
-            return Err(ValidationError::InvalidHookName(error_message).into());
+    #[test]
+    fn test_run_hooks_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        // `pre-commit` only succeeds if `post-merge` runs while it waits
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - for i in $(seq 50); do [ -f merged ] && break; sleep 0.1; done; [ -f merged ]\npost-merge:\n  commands:\n    - touch merged\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let options = RunOptions::new()
+            .with_cwd(dir.path())
+            .with_all(true)
+            .with_jobs(2);
+        hs.run_hook(None, &options).unwrap();
+
+        // A failing hook doesn't stop the others, and its error is returned
+        fs::write(
+            &config_path,
+            "pre-commit:\n  commands:\n    - exit 3\npost-merge:\n  commands:\n    - sleep 0.2; touch done\n",
+        )
+        .unwrap();
+        let failing = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert!(matches!(
+            failing.run_hook(None, &options),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(3)
+            ))
+        ));
+        assert!(dir.path().join("done").exists());
+    }
+
+    #[test]
+    fn test_run_hook_collect() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(
+            dir.path(),
+            "pre-commit:\n  commands:\n    - echo checked\n    - lint: echo oops >&2; exit 3\n",
+        );
+
+        /// Observer counting its notifications.
+        struct Counter(Arc<AtomicUsize>);
+
+        impl HookObserver for Counter {
+            fn on_hook_start(&self, _: &str, _: usize, _: usize) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
         }
 
-        Ok(())
+        let mut hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let notified = Arc::new(AtomicUsize::new(0));
+        hs.set_observer(Box::new(Counter(Arc::clone(&notified))));
+        let options = RunOptions::new().with_cwd(dir.path());
+        let hook_names = ["pre-commit".to_string()];
+
+        // A failing command is part of the report, not an error
+        let report = hs
+            .run_hook_collect_with_options(Some(&hook_names), &options)
+            .unwrap();
+        assert!(!report.success);
+        assert_eq!(report.hooks.len(), 1);
+        let commands = &report.hooks[0].commands;
+        assert!(commands[0].success);
+        assert_eq!(commands[0].stdout.as_deref(), Some("checked\n"));
+        assert_eq!(commands[1].name.as_deref(), Some("lint"));
+        assert_eq!(commands[1].exit_code, Some(3));
+        assert_eq!(commands[1].stderr.as_deref(), Some("oops\n"));
+
+        // A hook that doesn't exist is
+        assert!(hs
+            .run_hook_collect_with_options(Some(&["pre-push".to_string()]), &options)
+            .is_err());
+
+        // The observer is left out of collected runs, and notified again afterwards
+        assert_eq!(notified.load(Ordering::SeqCst), 0);
+        let _ = hs.run_hook(Some(&hook_names), &options.clone().with_capture(true));
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
     }
 
-    /// Executes a command.
-    ///
-    /// # Arguments
-    /// * `command` - The command to execute.
-    ///
-    /// # Errors
-    /// * If a command cannot be executed
-    fn execute_command(
-        &self,
-        command: &str,
-        working_directory: Option<&Path>,
-    ) -> Result<ExitStatus> {
-        if self.dry_run {
-            println!("🔍 Would execute: {command}");
+    #[test]
+    fn test_observer_notifications() {
+        use std::sync::{Arc, Mutex};
 
-            #[cfg(unix)]
-            {
-                use std::os::unix::process::ExitStatusExt;
+        #[derive(Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
 
-                Ok(ExitStatusExt::from_raw(0))
+        impl HookObserver for Recorder {
+            fn on_hook_start(&self, hook_name: &str, index: usize, total: usize) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("hook start {hook_name} {index}/{total}"));
+            }
+
+            fn on_command_start(&self, _: &str, command: &HookCommand, index: usize, _: usize) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("command start {} {index}", command.command));
             }
-            #[cfg(windows)]
-            {
-                use std::os::windows::process::ExitStatusExt;
 
-                Ok(ExitStatusExt::from_raw(0))
+            fn on_command_finish(
+                &self,
+                _: &str,
+                command: &HookCommand,
+                result: std::result::Result<&CommandTiming, &HooksmithError>,
+            ) {
+                self.0.lock().unwrap().push(format!(
+                    "command finish {} {}",
+                    command.command,
+                    result.is_ok()
+                ));
             }
-        } else {
-            let mut cmd = Command::new("sh");
-            cmd.arg("-c").arg(command);
-            if let Some(dir) = working_directory {
-                cmd.current_dir(dir);
+
+            fn on_hook_finish(&self, report: &HookTiming) {
+                self.0.lock().unwrap().push(format!(
+                    "hook finish {} {}",
+                    report.hook_name,
+                    report.commands.len()
+                ));
             }
-            Ok(cmd.status()?)
         }
+
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(
+            dir.path(),
+            "pre-commit:\n  commands: [\"true\", \"false\"]\npre-push:\n  commands: [\"true\"]\n",
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        hs.set_observer(Box::new(Recorder(Arc::clone(&events))));
+
+        hs.run_hooks(
+            &["pre-push".to_string(), "pre-commit".to_string()],
+            &RunOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "hook start pre-push 0/2",
+                "command start true 0",
+                "command finish true true",
+                "hook finish pre-push 1",
+                "hook start pre-commit 1/2",
+                "command start true 0",
+                "command finish true true",
+                "command start false 1",
+                "command finish false false",
+            ]
+        );
     }
 
-    /// Read the configuration file and parse it into a Config struct.
-    ///
-    /// # Arguments
-    /// * `config_path` - Path to the configuration file
-    ///
-    /// # Errors
-    /// * If the configuration file cannot be read or parsed
-    ///
-    /// # Returns
-    /// * `Config` - Parsed configuration file
-    fn read_config(config_path: &Path) -> Result<Config> {
-        let config_string = fs::read_to_string(config_path)?;
+    #[test]
+    fn test_outcome_handlers() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let record = format!(
+            "echo \"$HOOKSMITH_HOOK_OUTCOME $HOOKSMITH_HANDLER_HOOKS $HOOKSMITH_HOOK_ERROR\" >> {}",
+            output.display()
+        );
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-commit:\n  commands:\n    - \"true\"\n  on_success:\n    - '{record}'\n    - \"false\"\n  on_failure:\n    - '{record}'\ncommit-msg:\n  commands:\n    - exit 3\n  on_success:\n    - '{record}'\n  on_failure:\n    - '{record}'\n    - 'test \"$HOOKSMITH_HOOK_DURATION_MS\" -ge 0 && echo timed >> {}'\n",
+                output.display()
+            ));
+        assert!(hs.validate_variables().is_ok());
+
+        // A failing handler is a warning, the hook still succeeds
+        hs.run_hook_with("pre-commit", RunOptions::new()).unwrap();
+        assert_eq!(hs.reporter().warning_count(), 1);
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "success pre-commit \n"
+        );
 
-        match serde_yaml::from_str(&config_string) {
-            Ok(config) => Ok(config),
-            Err(err) => Err(HooksmithError::Config(ConfigError::Parse(err))),
-        }
+        fs::remove_file(&output).unwrap();
+        assert!(hs.run_hook_with("commit-msg", RunOptions::new()).is_err());
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "failure commit-msg Hook execution error: Command failed with status code: 3\ntimed\n"
+        );
+
+        assert_eq!(handler_hooks_with("pre-push"), "pre-push");
+        assert!(!is_running_handler_of("pre-push"));
     }
 
-    /// Select hooks interactively using `dialoguer`.
-    ///
-    /// # Errors
-    /// * If the user cancels the selection, or an error occurs during selection
-    /// * If the selection is empty
-    ///
-    /// # Returns
-    /// * `Vec<String>` - Selected hooks
-    fn select_hooks_interactively(&self) -> Result<Vec<String>> {
-        let hooks = self.get_available_hooks();
+    #[test]
+    fn test_pre_run_post_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let order = dir.path().join("order.txt");
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - echo hook >> order.txt\npre-push:\n  commands:\n    - echo failing >> order.txt; exit 2\n");
+        let options = RunOptions::new()
+            .with_cwd(dir.path())
+            .with_pre_run(vec!["echo pre >> order.txt".to_string()])
+            .with_post_run(vec!["echo post >> order.txt".to_string()]);
+
+        hs.run_hook(Some(&["pre-commit".to_string()]), &options)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&order).unwrap(), "pre\nhook\npost\n");
+
+        // Post-run commands run after failing hooks, which still fail the run
+        fs::remove_file(&order).unwrap();
+        assert!(matches!(
+            hs.run_hook(Some(&["pre-push".to_string()]), &options),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(2)
+            ))
+        ));
+        assert_eq!(fs::read_to_string(&order).unwrap(), "pre\nfailing\npost\n");
+
+        // Hooks don't run when a pre-run command fails
+        fs::remove_file(&order).unwrap();
+        let options = options.with_pre_run(vec!["exit 1".to_string()]);
+        assert!(hs
+            .run_hook(Some(&["pre-commit".to_string()]), &options)
+            .is_err());
+        assert!(!order.exists());
+    }
 
-        if hooks.is_empty() {
-            return Err(HookExecutionError::HookNotFound(
-                "No hooks available in configuration".to_string(),
-            )
-            .into());
-        }
+    #[test]
+    fn test_exec_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "env:\n  GREETING: hello\npre-commit:\n  command_prefix: [env, TARGET=hook]\n  commands:\n    - echo ok\n");
+        let options = RunOptions::new()
+            .with_cwd(dir.path())
+            .with_extra_args(vec!["arg".to_string()]);
+        let command = "printf '%s %s %s' \"$GREETING\" \"${TARGET:-none}\" \"$1\" > out.txt";
+
+        hs.exec_command(command, None, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hello none arg"
+        );
 
-        let selections = MultiSelect::with_theme(&my_clap_theme::ColorfulTheme::default())
-            .with_prompt("Select hooks to run (Space to select, Enter to confirm)")
-            .items(&hooks)
-            .interact()
-            .map_err(|e| HookExecutionError::HookNotFound(e.to_string()))?;
+        // The hook's settings apply too
+        hs.exec_command(command, Some("pre-commit"), &options)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hello hook arg"
+        );
 
-        if selections.is_empty() {
-            return Err(HookExecutionError::HookNotFound("No hooks selected".to_string()).into());
-        }
+        assert!(matches!(
+            hs.exec_command("exit 3", None, &options),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(3)
+            ))
+        ));
+        assert!(hs.exec_command("true", Some("pre-push"), &options).is_err());
+    }
 
-        Ok(selections.into_iter().map(|i| hooks[i].clone()).collect())
+    #[test]
+    fn test_hook_timeout_stops_long_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(
+            dir.path(),
+            "pre-commit:\n  hook_timeout: 1\n  commands:\n    - sleep 10\n    - echo unreachable\n",
+        );
+        let start = Instant::now();
+        let result = hs.run_hooks(&["pre-commit".to_string()], &RunOptions::default());
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(HookExecutionError::HookTimeout { seconds: 1, ref command, .. }))
+                if command == "sleep 10"
+        ));
     }
-}
 
-/// Handles the dry run output for a command
-fn handle_dry_run(hook_command: &HookCommand, idx: usize, total_commands: usize) {
-    let current_dir = std::env::current_dir();
+    #[test]
+    fn test_command_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  command_timeout: 1\n  commands:\n    - sleep 10\n    - echo unreachable\npre-push:\n  hook_timeout: 60\n  command_timeout: 60\n  commands:\n    - \"true\"\n    - run: sleep 10\n      name: slow\n      timeout: 1\n");
+        let start = Instant::now();
+        let result = hs.run_hooks(&["pre-commit".to_string()], &RunOptions::default());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(HookExecutionError::CommandTimeout { seconds: 1, ref command, .. }))
+                if command == "sleep 10"
+        ));
+
+        // The command's own timeout wins over the hook's, and stops it before `hook_timeout`
+        let start = Instant::now();
+        let result = hs.run_hooks(&["pre-push".to_string()], &RunOptions::default());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(HookExecutionError::CommandTimeout { seconds: 1, ref command, .. }))
+                if command == "slow"
+        ));
+    }
 
-    println!("Step {} of {}:", idx + 1, total_commands);
-    if let Some(name) = &hook_command.name {
-        println!("  Command: {} ({})", name, hook_command.command);
-    } else {
-        println!("  Command: {}", hook_command.command);
+    #[test]
+    fn test_allow_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-commit:\n  commands:\n    - run: exit 3\n      name: spelling\n      allow_failure: true\n    - touch '{}'\npre-push:\n  commands:\n    - run: exit 3\n      allow_failure: true\n    - exit 4\n",
+                marker.display()
+            ));
+        let timing = hs
+            .run_hook_internal_with_timing("pre-commit", &RunOptions::default())
+            .unwrap();
+        assert!(marker.exists());
+        let failed = timing
+            .commands
+            .iter()
+            .map(|timing| timing.failed)
+            .collect::<Vec<_>>();
+        assert_eq!(failed, [true, false]);
+        // The allowed failure is a note: neither an error nor a warning
+        assert_eq!(hs.reporter().error_count(), 0);
+        assert_eq!(hs.reporter().warning_count(), 0);
+
+        // Commands without the flag still fail the hook
+        let result = hs.run_hooks(&["pre-push".to_string()], &RunOptions::default());
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(4)
+            ))
+        ));
+        assert_eq!(hs.reporter().error_count(), 1);
+        assert_eq!(hs.reporter().warning_count(), 0);
     }
 
-    if let Ok(dir) = current_dir {
-        println!("  Working directory: {}", dir.display());
+    #[test]
+    fn test_success_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  commands:\n    - run: exit 1\n      name: benign\n      success_codes: [1]\npre-push:\n  commands:\n    - exit 1\ncommit-msg:\n  commands:\n    - run: \"true\"\n      success_codes: [1]\n");
+        let command = &hs.config.hooks["pre-commit"].commands.as_ref().unwrap()[0];
+        assert_eq!(command.name.as_deref(), Some("benign"));
+        assert_eq!(command.command, "exit 1");
+
+        assert!(hs
+            .run_hooks(&["pre-commit".to_string()], &RunOptions::default())
+            .is_ok());
+        assert!(matches!(
+            hs.run_hooks(&["pre-push".to_string()], &RunOptions::default()),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(1)
+            ))
+        ));
+        assert!(matches!(
+            hs.run_hooks(&["commit-msg".to_string()], &RunOptions::default()),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(1)
+            ))
+        ));
     }
 
-    println!();
-}
+    #[test]
+    fn test_retry_on() {
+        let dir = tempfile::tempdir().unwrap();
+        // Fails with 7 until its third run
+        let flaky = "n=$(cat count 2>/dev/null || echo 0); n=$((n + 1)); echo $n > count; [ $n -ge 3 ] || exit 7";
+        let config_path = write_config(dir.path(), &format!("pre-commit:\n  commands:\n    - run: '{flaky}'\n      retries: 2\n      retry_on: [2, 7]\npre-push:\n  commands:\n    - run: '{flaky}'\n      retries: 2\n      retry_on: [2]\ncommit-msg:\n  commands:\n    - run: '{flaky}'\n      retries: 1\n"));
+        let count = dir.path().join("count");
+        let options = RunOptions::new().with_cwd(dir.path());
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let command = &hs.config.hooks["pre-commit"].commands.as_ref().unwrap()[0];
+        assert_eq!(command.retries, 2);
+        assert_eq!(command.retry_on, Some(vec![2, 7]));
+        assert!(serde_yaml::to_string(command)
+            .unwrap()
+            .contains("retry_on:"));
+
+        hs.run_hooks(&["pre-commit".to_string()], &options).unwrap();
+        assert_eq!(fs::read_to_string(&count).unwrap(), "3\n");
+
+        // 7 isn't listed: the failure is deterministic, it isn't retried
+        fs::remove_file(&count).unwrap();
+        assert!(hs.run_hooks(&["pre-push".to_string()], &options).is_err());
+        assert_eq!(fs::read_to_string(&count).unwrap(), "1\n");
+
+        // Without `retry_on`, any failure is retried, as long as attempts are left
+        fs::remove_file(&count).unwrap();
+        assert!(matches!(
+            hs.run_hooks(&["commit-msg".to_string()], &options),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(7)
+            ))
+        ));
+        assert_eq!(fs::read_to_string(&count).unwrap(), "2\n");
+    }
 
-/// Handles dry run output for a command with an explicit working directory
-fn handle_dry_run_with_dir(
-    hook_command: &HookCommand,
-    idx: usize,
-    total_commands: usize,
-    working_directory: Option<&str>,
-) {
-    println!("Step {} of {}:", idx + 1, total_commands);
-    if let Some(name) = &hook_command.name {
-        println!("  Command: {} ({})", name, hook_command.command);
-    } else {
-        println!("  Command: {}", hook_command.command);
+    #[test]
+    fn test_command_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("crates/api");
+        fs::create_dir_all(&sub).unwrap();
+        // Absolute directories are used as they are, relative ones are resolved from the
+        // root of the repository
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-commit:\n  commands:\n    - run: pwd > pwd.txt\n      working_dir: {}\npre-push:\n  commands:\n    - run: \"true\"\n      working_dir: {}\n",
+                sub.display(),
+                dir.path().join("missing").display()
+            ));
+        let command = &hs.config.hooks["pre-commit"].commands.as_ref().unwrap()[0];
+        assert_eq!(command.working_dir.as_deref(), Some(sub.to_str().unwrap()));
+        assert!(hs.config.to_yaml().unwrap().contains("working_dir:"));
+
+        hs.run_hook_with("pre-commit", RunOptions::new()).unwrap();
+        assert_eq!(
+            fs::read_to_string(sub.join("pwd.txt")).unwrap().trim(),
+            sub.canonicalize().unwrap().to_str().unwrap()
+        );
+
+        assert!(matches!(
+            hs.run_hook_with("pre-push", RunOptions::new()),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::WorkingDirNotFound(_)
+            ))
+        ));
+        assert_eq!(hs.reporter().error_count(), 1);
     }
 
-    if let Some(dir) = working_directory {
-        println!("  Working directory (override): {dir}");
-    } else if let Ok(dir) = std::env::current_dir() {
-        println!("  Working directory: {}", dir.display());
+    #[cfg(unix)]
+    #[test]
+    fn test_umask_applies_to_commands() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let created = dir.path().join("created");
+        let config_path = write_config(dir.path(), &format!(
+                "pre-commit:\n  umask: \"077\"\n  commands:\n    - touch {}\npre-push:\n  umask: 027\n  commands: [\"true\"]\n",
+                created.display()
+            ));
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        assert_eq!(hs.config.hooks["pre-push"].umask, Some(0o027));
+
+        hs.run_hooks(&["pre-commit".to_string()], &RunOptions::default())
+            .unwrap();
+        let mode = fs::metadata(&created).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::write(&config_path, "pre-commit:\n  umask: \"999\"\n").unwrap();
+        assert!(Hooksmith::new_from_config(&config_path, false, false).is_err());
     }
 
-    println!();
-}
+    #[test]
+    fn test_hook_and_command_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "env:\n  BASE: base\npre-commit:\n  env:\n    GREETING: hello\n    TARGET: ${BASE}/hook\n  commands:\n    - run: printf '%s %s %s' \"$GREETING\" \"$TARGET\" \"$SEARCH\" > command.txt\n      env:\n        TARGET: ${TARGET}/command\n        SEARCH: ${PATH}\n    - printf '%s %s' \"${GREETING}\" \"$TARGET\" > hook.txt\n");
+        assert!(hs.undefined_variable_usages().is_empty());
+
+        hs.run_hook_with("pre-commit", RunOptions::new().with_cwd(dir.path()))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("command.txt")).unwrap(),
+            format!("hello base/hook/command {}", std::env::var("PATH").unwrap())
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("hook.txt")).unwrap(),
+            "hello base/hook"
+        );
+    }
 
-impl Hooksmith {
-    /// Detect changed files for a given hook when possible.
-    ///
-    /// Behavior by hook name:
-    /// - `pre-commit`: Returns the list of staged files using `git diff --name-only --cached`.
-    /// - `pre-push`: Attempts to diff against the configured upstream with `@{u}..HEAD`.
-    ///   If no upstream is configured or that diff fails, falls back to `HEAD~1..HEAD`.
-    ///
-    /// # Arguments
-    /// * `hook_name` - The hook to compute changed files for.
-    ///
-    /// # Returns
-    /// * `Some(Vec<String>)` when detection succeeds (the vector may be empty if no files changed).
-    /// * `None` if the hook is not supported or if detection fails (e.g., not a Git repo, no upstream, or the git command fails).
-    ///
-    /// # Notes
-    /// This helper is best-effort and never returns an error. Callers should treat `None`
-    /// as "path-scoped execution not applicable" and continue with global commands.
-    fn detect_changed_files(hook_name: &str) -> Option<Vec<String>> {
-        match hook_name {
-            "pre-commit" => Self::git_diff_name_only(&["--cached"]).ok(),
-            "pre-push" => {
-                // Try to diff against upstream; fall back to last commit range
-                if let Ok(files) = Self::git_diff_upstream_range() {
-                    Some(files)
-                } else {
-                    Self::git_diff_name_only(&["HEAD~1..HEAD"]).ok()
-                }
-            }
-            _ => None,
-        }
+    #[test]
+    fn test_when_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-push:\n  when_env:\n    HOOKSMITH_TEST_CI: \"true\"\n    HOOKSMITH_TEST_DEPLOY: \"*\"\n    HOOKSMITH_TEST_SKIP: \"!*\"\n    HOOKSMITH_TEST_STAGE: \"!prod\"\n  commands:\n    - echo ran >> {}\n",
+                output.display()
+            ));
+        let run = |env: &[(&str, &str)]| {
+            let options = env.iter().fold(RunOptions::new(), |options, (key, value)| {
+                options.with_env(*key, *value)
+            });
+            hs.run_hook_with("pre-push", options).unwrap()
+        };
+
+        let timing = run(&[("HOOKSMITH_TEST_CI", "true")]);
+        assert_eq!(
+            timing.skipped[0].reason,
+            "`HOOKSMITH_TEST_DEPLOY` is not set (`when_env`)"
+        );
+        let timing = run(&[
+            ("HOOKSMITH_TEST_CI", "false"),
+            ("HOOKSMITH_TEST_DEPLOY", ""),
+        ]);
+        assert_eq!(
+            timing.skipped[0].reason,
+            "`HOOKSMITH_TEST_CI` is `false`, not `true` (`when_env`)"
+        );
+        run(&[
+            ("HOOKSMITH_TEST_CI", "true"),
+            ("HOOKSMITH_TEST_DEPLOY", "1"),
+            ("HOOKSMITH_TEST_STAGE", "prod"),
+        ]);
+        run(&[
+            ("HOOKSMITH_TEST_CI", "true"),
+            ("HOOKSMITH_TEST_DEPLOY", "1"),
+            ("HOOKSMITH_TEST_SKIP", ""),
+        ]);
+        assert!(!output.exists());
+
+        let timing = run(&[
+            ("HOOKSMITH_TEST_CI", "true"),
+            ("HOOKSMITH_TEST_DEPLOY", "1"),
+            ("HOOKSMITH_TEST_STAGE", "dev"),
+        ]);
+        assert!(timing.skipped.is_empty());
+        assert_eq!(fs::read_to_string(&output).unwrap(), "ran\n");
     }
 
-    /// Compute the list of files changed relative to the configured upstream branch.
-    ///
-    /// Attempts to diff `@{u}..HEAD` if an upstream is configured. If no upstream is
-    /// configured, returns an error so callers can fall back to an alternative range.
-    ///
-    /// # Errors
-    /// * If no upstream is configured or if running the underlying `git` command fails.
-    fn git_diff_upstream_range() -> Result<Vec<String>> {
-        // Check if upstream exists; if so, diff against it
-        let upstream_check = Command::new("git")
-            .args(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
-            .output();
+    #[cfg(windows)]
+    #[test]
+    fn test_cmd_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output.txt");
+        let hs = hooksmith_in(
+            dir.path(),
+            &format!(
+                "shell: cmd\npre-commit:\n  commands:\n    - echo hello from cmd> \"{}\"\n",
+                output.display()
+            ),
+        );
+        hs.run_hook_with("pre-commit", RunOptions::new()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap().trim_end(),
+            "hello from cmd"
+        );
+    }
 
-        if let Ok(output) = upstream_check {
-            if output.status.success() {
-                return Self::git_diff_name_only(&["@{u}..HEAD"]);
-            }
-        }
+    #[cfg(unix)]
+    #[test]
+    fn test_path_prepend() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("tools/bin");
+        fs::create_dir_all(&bin).unwrap();
+        let tool = bin.join("hooksmith-test-tool");
+        fs::write(&tool, "#!/bin/sh\necho found > found.txt\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hs = hooksmith_in(dir.path(), &format!(
+                "path_prepend: [.venv/bin, {}]\npre-commit:\n  commands:\n    - hooksmith-test-tool\n",
+                bin.display()
+            ));
+        hs.run_hook_with("pre-commit", RunOptions::new().with_cwd(dir.path()))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("found.txt")).unwrap(),
+            "found\n"
+        );
 
-        Err(HookExecutionError::HookNotFound("No upstream configured".to_string()).into())
+        // Relative directories are resolved from the repository root, in front of the
+        // `PATH` set by the configuration
+        let options = RunOptions::new().with_env("PATH", "/usr/bin");
+        let mut context =
+            ExecutionContext::new("pre-commit", &options, &hs.config.hooks["pre-commit"]);
+        context.path_prepend = hs.path_prepend_dirs();
+        let resolved = hs.resolve_command("true", None, &context);
+
+        let root = get_repo_root().unwrap();
+        let expected =
+            std::env::join_paths([root.join(".venv/bin"), bin, PathBuf::from("/usr/bin")]).unwrap();
+        assert_eq!(resolved.env["PATH"], expected.to_string_lossy());
     }
 
-    /// Run `git diff --name-only` with the provided arguments and return changed file paths.
-    ///
-    /// # Arguments
-    /// * `args` - Additional arguments or revision ranges to pass to `git diff`.
-    ///
-    /// # Returns
-    /// A vector of path strings for files reported by `git diff --name-only`.
-    ///
-    /// # Errors
-    /// * If the underlying `git diff` command fails.
-    fn git_diff_name_only(args: &[&str]) -> Result<Vec<String>> {
-        let mut cmd = Command::new("git");
-        cmd.arg("diff").arg("--name-only");
+    #[test]
+    fn test_command_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let config_path = write_config(dir.path(), "command_prefix: env PREFIXED=yes\npre-commit:\n  commands:\n    - cargo test\npre-push:\n  command_prefix: [nix, develop, \"path with space\", -c]\n  commands:\n    - cargo test\ncommit-msg:\n  command_prefix: []\n  commands:\n    - cargo test\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let options = RunOptions::new();
+        let resolve = |hook_name: &str| {
+            let context = ExecutionContext::new(hook_name, &options, &hs.config.hooks[hook_name]);
+            let resolved = hs.resolve_command("cargo test && cargo doc", None, &context);
+
+            std::iter::once(resolved.program)
+                .chain(resolved.args)
+                .collect::<Vec<_>>()
+        };
 
-        for a in args {
-            cmd.arg(a);
-        }
+        assert_eq!(
+            resolve("pre-commit"),
+            vec![
+                "env",
+                "PREFIXED=yes",
+                "sh",
+                "-c",
+                "cargo test && cargo doc",
+                "hooksmith"
+            ]
+        );
+        // The hook's prefix replaces the top-level one, arguments are kept as written
+        assert_eq!(
+            resolve("pre-push")[..5],
+            ["nix", "develop", "path with space", "-c", "sh"]
+        );
+        // An empty prefix runs the hook's commands directly
+        assert_eq!(resolve("commit-msg")[0], "sh");
+
+        // The prefix applies to the whole command line
+        let hook = &hs.config.hooks["pre-commit"];
+        let context = ExecutionContext::new("pre-commit", &options, hook);
+        let command = format!("true && echo \"$PREFIXED\" > {}", output.display());
+        let status = hs
+            .execute_command(
+                &HookCommand::new_unnamed(command.clone()),
+                &command,
+                None,
+                &context,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_to_string(&output).unwrap(), "yes\n");
 
-        let output = cmd.output()?;
+        fs::write(&config_path, "command_prefix: {a: b}\n").unwrap();
+        assert!(Hooksmith::new_from_config(&config_path, false, false).is_err());
+    }
 
-        if !output.status.success() {
-            return Err(HookExecutionError::HookNotFound(
-                "Failed to compute changed files".to_string(),
+    #[test]
+    fn test_resolve_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "shell: bash\nenv:\n  A: config\n  B: config\npre-commit:\n  commands:\n    - cargo fmt --check\n");
+
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        let hook = &hs.config.hooks["pre-commit"];
+        let options = RunOptions::new()
+            .with_env("B", "options")
+            .with_cwd("/repo")
+            .with_extra_args(vec!["a b".to_string()])
+            .with_resolve(true);
+        let context = ExecutionContext::new("pre-commit", &options, hook);
+
+        let resolved = hs.resolve_command("cargo fmt --check", Some(Path::new("web")), &context);
+        assert_eq!(resolved.program, "bash");
+        assert_eq!(
+            resolved.args,
+            vec!["-c", "cargo fmt --check", "hooksmith", "a b"]
+        );
+        assert_eq!(
+            resolved.env.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("A".to_string(), "config".to_string()),
+                ("B".to_string(), "options".to_string())
+            ]
+        );
+        assert_eq!(resolved.cwd, Some(PathBuf::from("/repo/web")));
+
+        // Resolving only prints, nothing is executed
+        hs.run_hooks(&["pre-commit".to_string()], &options).unwrap();
+
+        fs::write(
+            &config_path,
+            "shell: bash\nlogin_shell: true\npre-commit:\n  commands:\n    - cargo fmt --check\n",
+        )
+        .unwrap();
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        let resolved = hs.resolve_command("cargo fmt --check", None, &context);
+        assert_eq!(
+            resolved.args,
+            vec!["-l", "-c", "cargo fmt --check", "hooksmith", "a b"]
+        );
+
+        // The hook's shell wins over the one of the run, which wins over the configured one
+        fs::write(
+            &config_path,
+            "shell: bash\npre-commit:\n  commands: [Get-Date]\npre-push:\n  shell: pwsh -NoProfile -Command\n  commands: [Get-Date]\n",
+        )
+        .unwrap();
+        let hs = Hooksmith::new_from_config(&config_path, true, false).unwrap();
+        let options = RunOptions::new().with_shell(Some("zsh".to_string()));
+        let resolve = |hook_name: &str, options: &RunOptions| {
+            let context = ExecutionContext::new(hook_name, options, &hs.config.hooks[hook_name]);
+            let resolved = hs.resolve_command("Get-Date", None, &context);
+            (resolved.program, resolved.args)
+        };
+        assert_eq!(
+            resolve("pre-push", &options),
+            (
+                "pwsh".to_string(),
+                ["-NoProfile", "-Command", "Get-Date"]
+                    .map(String::from)
+                    .to_vec()
             )
-            .into());
+        );
+        assert_eq!(resolve("pre-commit", &options).0, "zsh");
+        assert_eq!(resolve("pre-commit", &RunOptions::new()).0, "bash");
+        assert_eq!(
+            shell_invocation("powershell", true),
+            (
+                vec!["powershell".to_string(), "-Command".to_string()],
+                false
+            )
+        );
+        assert_eq!(
+            shell_invocation(r"C:\Windows\System32\cmd.exe", false).0[1],
+            "/C"
+        );
+
+        assert_eq!(shell_quote("cargo"), "cargo");
+        assert_eq!(shell_quote("it's $1"), "'it'\\''s $1'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_task_runner_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = write_config(dir.path(), "pre-commit:\n  commands:\n    - make: greet NAME=hooksmith\npre-push:\n  commands:\n    - just: test\n");
+        fs::write(
+            dir.path().join("Makefile"),
+            "greet:\n\t@printf 'hello %s' $(NAME) > out.txt\n",
+        )
+        .unwrap();
+
+        let hs = Hooksmith::new_from_config(&config_path, false, false).unwrap();
+        let command = &hs.config.hooks["pre-commit"].commands.as_ref().unwrap()[0];
+        assert_eq!(command.command, "make greet NAME=hooksmith");
+        assert_eq!(command.task_runner, Some(TaskRunner::Make));
+
+        let options = RunOptions::new().with_cwd(dir.path());
+        if TaskRunner::Make.is_installed() {
+            hs.run_hooks(&["pre-commit".to_string()], &options).unwrap();
+            assert_eq!(
+                fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+                "hello hooksmith"
+            );
+        }
+        if !TaskRunner::Just.is_installed() {
+            assert!(matches!(
+                hs.run_hooks(&["pre-push".to_string()], &options),
+                Err(HooksmithError::HookExecution(
+                    HookExecutionError::CommandFailed(127)
+                ))
+            ));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let files = stdout
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>();
+        fs::write(&config_path, "pre-commit:\n  commands:\n    - just: \"\"\n").unwrap();
+        assert!(Hooksmith::new_from_config(&config_path, false, false).is_err());
+    }
+
+    #[test]
+    fn test_command_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-push:\n  groups:\n    - - first: sleep 0.5\n      - sleep 0.5\n    - - exit 3\n    - - touch {}\n",
+                marker.display()
+            ));
+        let hook = &hs.config.hooks["pre-push"];
+        assert_eq!(
+            hook.groups.as_ref().unwrap()[0][0].name.as_deref(),
+            Some("first")
+        );
 
-        Ok(files)
+        let started = Instant::now();
+        let result = hs.run_hook_internal_with_timing("pre-push", &RunOptions::default());
+
+        // The first group runs in parallel, the failing second group stops the third one
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(3)
+            ))
+        ));
+        assert!(!marker.exists());
     }
 
-    /// Print a formatted timing report showing execution times for hooks and commands.
-    ///
-    /// # Arguments
-    /// * `timing_report` - The timing report to print
-    fn print_timing_report(_: &Self, timing_report: &TimingReport) {
-        if timing_report.hooks.is_empty() {
-            return;
-        }
+    #[test]
+    fn test_max_output_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "pre-commit:\n  max_output_bytes: 16\n  commands:\n    - yes | head -c 100000\n    - exit 4\n");
+        let result = hs.run_hooks(&["pre-commit".to_string()], &RunOptions::default());
+
+        // Output is drained, so the command completes and the next one runs
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(4)
+            ))
+        ));
+    }
 
-        println!("\n⏱️  Hook execution summary:");
+    #[test]
+    fn test_tee_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("hooks.log");
+        fs::write(&log_path, "stale\n").unwrap();
+        let hs = hooksmith_in(
+            dir.path(),
+            "pre-commit:\n  commands:\n    - echo first; echo oops >&2\n    - echo second\n",
+        );
+        let options = RunOptions::new().with_tee(Some(log_path.clone()));
+        hs.run_hook_with("pre-commit", options).unwrap();
+
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(!log.contains("stale"));
+        assert!(log.contains("first\n"));
+        assert!(log.contains("oops\n"));
+        assert!(log.ends_with("second\n"));
+    }
 
-        for hook_timing in &timing_report.hooks {
-            if hook_timing.commands.is_empty() {
-                continue;
-            }
+    #[test]
+    fn test_builtin_commit_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let hs = hooksmith_in(dir.path(), "commit-msg:\n  commands:\n    - builtin: commit-format\n      name: message\n      subject_length: 20\n      body_width: false\n");
+        let command = &hs.config.hooks["commit-msg"].commands.as_ref().unwrap()[0];
+        assert_eq!(command.label(), "message");
+        assert_eq!(command.command, "builtin: commit-format");
+
+        // Options are kept when the configuration is written back
+        let yaml = hs.effective_config(false).unwrap();
+        assert!(yaml.contains("- name: message\n    builtin: commit-format\n    subject_length: 20\n    blank_line: true\n    body_width: false\n"));
+        assert_eq!(
+            Hooksmith::read_config_str(&yaml).unwrap().hooks,
+            hs.config.hooks
+        );
 
-            println!(
-                "  Hook '{}' ({})",
-                hook_timing.hook_name,
-                Self::format_duration(&hook_timing.total_duration)
-            );
+        let message = dir.path().join("COMMIT_EDITMSG");
+        let run = |content: &str| {
+            fs::write(&message, content).unwrap();
+            let options = RunOptions::new().with_extra_args(vec![message.display().to_string()]);
+            hs.run_hook_with("commit-msg", options)
+        };
 
-            for command_timing in &hook_timing.commands {
-                // Use command name if available, otherwise use the command itself
-                let display_command = if let Some(name) = &command_timing.name {
-                    if name.len() > 60 {
-                        format!("{}...", &name[..57])
-                    } else {
-                        name.clone()
-                    }
-                } else if command_timing.command.len() > 60 {
-                    format!("{}...", &command_timing.command[..57])
-                } else {
-                    command_timing.command.clone()
-                };
+        assert!(
+            run("Fix the parser\n\nA body that is allowed to be as long as it wants.\n").is_ok()
+        );
+        assert!(matches!(
+            run("Fix the parser of the configuration\n"),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(1)
+            ))
+        ));
+        assert!(matches!(
+            run("Fix the parser\nNo blank line\n"),
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandFailed(1)
+            ))
+        ));
+    }
 
-                println!(
-                    "    {}: {}",
-                    display_command,
-                    Self::format_duration(&command_timing.duration)
-                );
-            }
+    #[test]
+    fn test_commit_message_file() {
+        let file = CommitMessageFile::create("feat: add thing").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "feat: add thing\n");
+
+        // Each file gets its own name, so that a file planted in its place can't be used
+        let other = CommitMessageFile::create("fix: other thing").unwrap();
+        assert_ne!(other.path(), path);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
         }
 
-        println!(
-            "  Total: {}",
-            Self::format_duration(&timing_report.total_duration)
+        drop(file);
+        assert!(!path.exists());
+    }
+    #[test]
+    fn test_push_range_is_passed_to_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let hs = hooksmith_in(dir.path(), "pre-push:\n  commands:\n    - git log {push_range}\npre-commit:\n  commands:\n    - cat\n");
+        assert!(hs.config.hooks["pre-push"].uses_input("pre-push"));
+        assert!(!hs.config.hooks["pre-commit"].uses_input("pre-commit"));
+
+        let stdin = format!(
+            "refs/heads/main {a} refs/heads/main {b}\n(delete) {zero} refs/heads/old {b}\n",
+            a = "a".repeat(40),
+            b = "b".repeat(40),
+            zero = "0".repeat(40),
+        );
+        let options = RunOptions::new();
+        let hook = &hs.config.hooks["pre-push"];
+        let mut context = ExecutionContext::new("pre-push", &options, hook);
+        context.input = HookInput::parse("pre-push", stdin.as_bytes());
+
+        let resolved = hs.resolve_command("true", None, &context);
+        assert_eq!(
+            resolved.env[PUSH_RANGE_ENV],
+            format!("{}..{}", "b".repeat(40), "a".repeat(40))
         );
+        assert_eq!(resolved.env[PUSH_UPDATES_ENV], stdin.trim_end());
+
+        // Other hooks don't read the input of pre-push
+        assert!(!hs.config.hooks["pre-push"].uses_input("pre-commit"));
+
+        // The ref updates are replayed to the commands' stdin
+        let command = format!("cat > {}", output.display());
+        let status = hs
+            .execute_command(
+                &HookCommand::new_unnamed(command.clone()),
+                &command,
+                None,
+                &context,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_to_string(&output).unwrap(), stdin);
     }
 
-    /// Format a duration for display in the timing report.
-    ///
-    /// # Arguments
-    /// * `duration` - The duration to format
-    ///
-    /// # Returns
-    /// A formatted string representation of the duration
-    fn format_duration(duration: &Duration) -> String {
-        let total_millis = duration.as_millis();
+    #[test]
+    fn test_parallel_group_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = |name: &str| dir.path().join(name);
+        let hs = hooksmith_in(dir.path(), &format!(
+                "pre-push:\n  groups:\n    - - run: cat > {}\n        stdin: true\n      - cat > {}\npre-commit:\n  groups:\n    - - cat > {}\n      - cat > {}\n",
+                output("a").display(),
+                output("b").display(),
+                output("c").display(),
+                output("d").display()
+            ));
+        // A command reading stdin has the input of pre-push read for it
+        assert!(hs.config.hooks["pre-push"].uses_input("pre-push"));
+        assert!(hs.config.to_yaml().unwrap().contains("stdin: true"));
+
+        let stdin = format!(
+            "refs/heads/main {a} refs/heads/main {b}\n",
+            a = "a".repeat(40),
+            b = "b".repeat(40)
+        );
+        let options = RunOptions::new();
+        let hook = &hs.config.hooks["pre-push"];
+        let mut context = ExecutionContext::new("pre-push", &options, hook);
+        context.input = HookInput::parse("pre-push", stdin.as_bytes());
+        hs.run_parallel_group(&context, &hook.groups.as_ref().unwrap()[0])
+            .unwrap();
+        // The input is replayed to every command
+        assert_eq!(fs::read_to_string(output("a")).unwrap(), stdin);
+        assert_eq!(fs::read_to_string(output("b")).unwrap(), stdin);
+
+        // Without a replayed input, the commands not reading stdin get a closed one instead
+        // of competing for hooksmith's
+        let hook = &hs.config.hooks["pre-commit"];
+        let context = ExecutionContext::new("pre-commit", &options, hook);
+        hs.run_parallel_group(&context, &hook.groups.as_ref().unwrap()[0])
+            .unwrap();
+        assert_eq!(fs::read_to_string(output("c")).unwrap(), "");
+        assert_eq!(fs::read_to_string(output("d")).unwrap(), "");
+    }
 
-        if total_millis >= 1000 {
-            format!("{:.1}s", duration.as_secs_f64())
-        } else {
-            format!("{total_millis}ms")
-        }
+    #[test]
+    fn test_large_input_not_read() {
+        let config = "pre-push:\n  capture_output: true\n  commands:\n    - run: seq 1 100000\n      stdin: true\n    - run: sleep 10\n      stdin: true\n      timeout: 1\n";
+        let hs = Hooksmith::from_reader(config.as_bytes(), false, false).unwrap();
+
+        // More than a pipe holds, though less than an environment variable can
+        let stdin = format!(
+            "refs/heads/main {a} refs/heads/main {b}\n",
+            a = "a".repeat(40),
+            b = "b".repeat(40)
+        )
+        .repeat(800);
+        assert!(stdin.len() > 64 * 1024);
+
+        let options = RunOptions::new();
+        let hook = &hs.config.hooks["pre-push"];
+        let mut context = hs.execution_context("pre-push", &options, hook);
+        context.input = HookInput::parse("pre-push", stdin.as_bytes());
+        let commands = hook.commands.as_ref().unwrap();
+
+        // Filling its output without reading its input doesn't block the command
+        let started = Instant::now();
+        let code = hs
+            .execute_single_command(&context, &commands[0], None)
+            .unwrap();
+        assert_eq!(code, Some(0));
+
+        // Nor does the input keep the command from being stopped at its timeout
+        let result = hs.execute_single_command(&context, &commands[1], None);
+        assert!(matches!(
+            result,
+            Err(HooksmithError::HookExecution(
+                HookExecutionError::CommandTimeout { .. }
+            ))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(8));
     }
 }
@@ -0,0 +1,350 @@
+//! JUnit XML reports of hook runs, for CI systems that show test results.
+//!
+//! Each hook becomes a `<testsuite>` and each command a `<testcase>`. Failing commands get a
+//! `<failure>` element, and skipped path-based blocks or hooks a `<skipped>` one.
+
+use crate::{
+    error::HooksmithError,
+    hooksmith::{CommandTiming, HookCommand, HookTiming},
+    observer::HookObserver,
+    process::CapturedOutput,
+};
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Results of a run, collected by the observer returned by [`JunitReport::observer`].
+#[derive(Debug, Clone, Default)]
+pub struct JunitReport {
+    suites: Arc<Mutex<Vec<TestSuite>>>,
+}
+
+/// A hook, reported as a test suite.
+#[derive(Debug)]
+struct TestSuite {
+    name: String,
+    started: Instant,
+    /// Set once the hook finished successfully
+    duration: Option<Duration>,
+    cases: Vec<TestCase>,
+    /// Keys of the commands that started but haven't finished yet, with their start time
+    running: Vec<(String, Instant)>,
+}
+
+/// A command, or something that was skipped, reported as a test case.
+#[derive(Debug)]
+struct TestCase {
+    name: String,
+    duration: Duration,
+    outcome: Outcome,
+}
+
+/// How a test case ended.
+#[derive(Debug)]
+enum Outcome {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// Observer recording results into a `JunitReport`, and forwarding every notification
+/// to another observer.
+struct JunitObserver {
+    report: JunitReport,
+    inner: Box<dyn HookObserver>,
+}
+
+impl JunitReport {
+    /// Create an empty report.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an observer recording results into this report.
+    ///
+    /// # Arguments
+    /// * `inner` - An observer to forward every notification to, e.g. `StdoutObserver`
+    #[must_use]
+    pub fn observer(&self, inner: Box<dyn HookObserver>) -> Box<dyn HookObserver> {
+        Box::new(JunitObserver {
+            report: self.clone(),
+            inner,
+        })
+    }
+
+    /// Render the report as JUnit XML.
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let suites = self.suites();
+        let count = |outcome: fn(&Outcome) -> bool| {
+            suites
+                .iter()
+                .flat_map(|suite| &suite.cases)
+                .filter(|case| outcome(&case.outcome))
+                .count()
+        };
+        let total_duration: Duration = suites.iter().map(TestSuite::duration).sum();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"hooksmith\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            count(|_| true),
+            count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            count(|outcome| matches!(outcome, Outcome::Skipped(_))),
+            total_duration.as_secs_f64()
+        );
+
+        for suite in suites.iter() {
+            suite.write_xml(&mut xml);
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// Lock the collected suites, even if a panicking thread poisoned the lock.
+    fn suites(&self) -> MutexGuard<'_, Vec<TestSuite>> {
+        self.suites.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Apply a change to the suite of a hook, if it was started.
+    fn with_suite(&self, hook_name: &str, change: impl FnOnce(&mut TestSuite)) {
+        let mut suites = self.suites();
+
+        if let Some(suite) = suites
+            .iter_mut()
+            .rev()
+            .find(|suite| suite.name == hook_name)
+        {
+            change(suite);
+        }
+    }
+}
+
+impl TestSuite {
+    /// How long the hook ran, up to now if it didn't finish successfully.
+    fn duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    /// Append the suite to the XML document.
+    fn write_xml(&self, xml: &mut String) {
+        let count = |outcome: fn(&Outcome) -> bool| {
+            self.cases
+                .iter()
+                .filter(|case| outcome(&case.outcome))
+                .count()
+        };
+
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape_xml(&self.name),
+            self.cases.len(),
+            count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            count(|outcome| matches!(outcome, Outcome::Skipped(_))),
+            self.duration().as_secs_f64()
+        );
+
+        for case in &self.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&case.name),
+                escape_xml(&self.name),
+                case.duration.as_secs_f64()
+            );
+
+            match &case.outcome {
+                Outcome::Passed => xml.push_str("/>\n"),
+                Outcome::Failed(message) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                        escape_xml(message)
+                    );
+                }
+                Outcome::Skipped(reason) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                        escape_xml(reason)
+                    );
+                }
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+}
+
+impl HookObserver for JunitObserver {
+    fn on_hook_start(&self, hook_name: &str, index: usize, total: usize) {
+        self.report.suites().push(TestSuite {
+            name: hook_name.to_string(),
+            started: Instant::now(),
+            duration: None,
+            cases: Vec::new(),
+            running: Vec::new(),
+        });
+
+        self.inner.on_hook_start(hook_name, index, total);
+    }
+
+    fn on_command_start(&self, hook_name: &str, command: &HookCommand, index: usize, total: usize) {
+        self.report.with_suite(hook_name, |suite| {
+            suite.running.push((command.key(), Instant::now()));
+        });
+
+        self.inner
+            .on_command_start(hook_name, command, index, total);
+    }
+
+    fn on_command_output(&self, hook_name: &str, command: &HookCommand, output: &CapturedOutput) {
+        self.inner.on_command_output(hook_name, command, output);
+    }
+
+    fn on_command_finish(
+        &self,
+        hook_name: &str,
+        command: &HookCommand,
+        result: Result<&CommandTiming, &HooksmithError>,
+    ) {
+        self.report.with_suite(hook_name, |suite| {
+            let started = suite
+                .running
+                .iter()
+                .position(|(running, _)| *running == command.key())
+                .map(|idx| suite.running.remove(idx).1);
+
+            let (duration, outcome) = match result {
+                Ok(timing) => (timing.duration, Outcome::Passed),
+                Err(e) => (
+                    started.map_or(Duration::ZERO, |started| started.elapsed()),
+                    Outcome::Failed(e.to_string()),
+                ),
+            };
+
+            suite.cases.push(TestCase {
+                name: command
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| command.command.clone()),
+                duration,
+                outcome,
+            });
+        });
+
+        self.inner.on_command_finish(hook_name, command, result);
+    }
+
+    fn on_hook_finish(&self, report: &HookTiming) {
+        self.report.with_suite(&report.hook_name, |suite| {
+            suite.duration = Some(report.total_duration);
+
+            for skipped in &report.skipped {
+                suite.cases.push(TestCase {
+                    name: skipped.target.trim_matches('`').to_string(),
+                    duration: Duration::ZERO,
+                    outcome: Outcome::Skipped(skipped.reason.clone()),
+                });
+            }
+        });
+
+        self.inner.on_hook_finish(report);
+    }
+}
+
+/// Escape text for use in XML attributes and content. Characters that XML 1.0 can't
+/// represent at all (most control characters) are replaced with `U+FFFD`.
+///
+/// # Arguments
+/// * `text` - The text to escape
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Keep whitespace in attributes from being normalized away
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HookExecutionError;
+
+    /// Observer forwarding nothing.
+    struct Silent;
+
+    impl HookObserver for Silent {}
+
+    #[test]
+    fn test_junit_report() {
+        let report = JunitReport::new();
+        let observer = report.observer(Box::new(Silent));
+
+        let passing = HookCommand::new_named("fmt".to_string(), "cargo fmt".to_string());
+        let failing = HookCommand::new_unnamed("test \"$1\" -lt 3 && echo '<ok>'".to_string());
+        let error = HooksmithError::HookExecution(HookExecutionError::CommandFailed(2));
+
+        observer.on_hook_start("pre-commit", 0, 1);
+        observer.on_command_start("pre-commit", &passing, 0, 2);
+        observer.on_command_finish(
+            "pre-commit",
+            &passing,
+            Ok(&CommandTiming {
+                command: passing.command.clone(),
+                name: passing.name.clone(),
+                key: passing.key(),
+                duration: Duration::from_millis(1500),
+                failed: false,
+                exit_code: Some(0),
+            }),
+        );
+        observer.on_command_start("pre-commit", &failing, 1, 2);
+        observer.on_command_finish("pre-commit", &failing, Err(&error));
+
+        let xml = report.to_xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(
+            xml.contains("<testsuites name=\"hooksmith\" tests=\"2\" failures=\"1\" skipped=\"0\"")
+        );
+        assert!(xml.contains("<testcase name=\"fmt\" classname=\"pre-commit\" time=\"1.500\"/>"));
+        assert!(xml.contains(
+            "<testcase name=\"test &quot;$1&quot; -lt 3 &amp;&amp; echo &apos;&lt;ok&gt;&apos;\""
+        ));
+        assert!(xml.contains("<failure message=\"Hook execution error: "));
+        assert!(xml.ends_with("  </testsuite>\n</testsuites>\n"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("a<b>&\"c\"'"),
+            "a&lt;b&gt;&amp;&quot;c&quot;&apos;"
+        );
+        assert_eq!(
+            escape_xml("line\nnext\u{1b}[0m"),
+            "line&#10;next\u{fffd}[0m"
+        );
+    }
+}
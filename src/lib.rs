@@ -1,11 +1,51 @@
+pub(crate) mod api;
+pub(crate) mod bench;
+pub(crate) mod builtins;
+pub(crate) mod config_sources;
+pub(crate) mod disabled;
 pub mod error;
 pub(crate) mod git_related;
+pub(crate) mod global_config;
+pub(crate) mod hook_docs;
+pub(crate) mod hook_input;
 mod hooksmith;
+pub(crate) mod junit;
+pub(crate) mod manifest;
 pub(crate) mod my_clap_theme;
+pub(crate) mod observer;
+pub(crate) mod output;
+pub(crate) mod placeholders;
+pub(crate) mod pre_commit_import;
+pub(crate) mod process;
+pub(crate) mod reminder;
+pub(crate) mod run_report;
+pub(crate) mod run_state;
+pub(crate) mod stash;
+pub(crate) mod task_runner;
+pub(crate) mod templates;
 pub(crate) mod utils;
 
+pub use api::HOOKS_SCHEMA_VERSION;
+pub use builtins::{Builtin, CommitFormat, Violation};
 pub use error::{HooksmithError, Result};
-pub use hooksmith::Hooksmith;
+pub use git_related::{
+    is_inside_git_repo, parse_post_rewrite_stdin, parse_pre_push_stdin, parse_receive_stdin,
+    ReceivedRef, RefUpdate, RewrittenCommit,
+};
+pub use global_config::global_config_path;
+pub use hooksmith::{
+    CommandTiming, CommitMessageFile, HookCommand, HookTiming, Hooksmith, RunContext, RunOptions,
+    Skipped,
+};
+pub use junit::JunitReport;
+pub use manifest::{default_config_path, DEFAULT_CONFIG_FILE};
+pub use observer::{HookObserver, StdoutObserver};
+pub use output::redirect_stdout_to_stderr;
+pub use process::CapturedOutput;
+pub use reminder::{remind_if_due, NO_REMINDER_ENV};
+pub use run_report::{CommandReport, HookReport, RunRecorder, RunReport};
+pub use task_runner::TaskRunner;
+pub use utils::Reporter;
 
 /// Initialize Hooksmith by reading the configuration file and installing hooks.
 /// This is meant to be called from a `build.rs` script.
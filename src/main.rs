@@ -1,16 +1,75 @@
 mod cli;
 
 use clap::Parser;
-use cli::Command;
-use hooksmith::{error::ConfigError, Hooksmith, Result};
-use std::path::Path;
+use cli::{ApiCommand, CacheCommand, CliCommand, Command, OutputFormat, StandaloneCommand};
+use hooksmith::{
+    default_config_path,
+    error::{ConfigError, HookExecutionError},
+    global_config_path, is_inside_git_repo, redirect_stdout_to_stderr, remind_if_due,
+    CommitMessageFile, Hooksmith, HooksmithError, JunitReport, Reporter, Result, RunOptions,
+    RunRecorder, StdoutObserver, DEFAULT_CONFIG_FILE,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Exit status when hooksmith is run outside of a Git repository and can't do anything.
+const NOT_A_REPOSITORY_EXIT_CODE: i32 = 3;
 
 fn main() -> Result<()> {
     let cli = cli::Cli::parse();
+    let remind = !cli.quiet && !cli.no_global_config;
+
+    let result = run(cli);
+    if remind {
+        remind_if_due();
+    }
+
+    result
+}
 
-    let config_path = Path::new(&cli.config_path);
+/// Run the parsed command.
+fn run(cli: cli::Cli) -> Result<()> {
+    let reporter = || {
+        Reporter::new()
+            .with_concise(cli.concise_errors)
+            .with_quiet(cli.quiet)
+    };
+
+    let command = match cli.command {
+        CliCommand::Standalone(command) => {
+            return run_standalone(
+                command,
+                cli.config_path.as_deref(),
+                cli.dry_run,
+                cli.verbose,
+                &reporter(),
+            );
+        }
+        CliCommand::Configured(command) => command,
+    };
+
+    let config_path = cli
+        .config_path
+        .as_ref()
+        .map_or_else(default_config_path, PathBuf::from);
+    let config_path = config_path.as_path();
+
+    // A report printed to stdout leaves the rest of the output to stderr
+    if matches!(command, Command::Run { .. })
+        && matches!(cli.format, OutputFormat::Json | OutputFormat::Junit)
+        && cli.output.is_none()
+    {
+        redirect_stdout_to_stderr();
+    }
+
+    // Without a configuration, nothing can be done outside of a repository: say so before
+    // Git fails with a less helpful error
+    if !config_path.exists() {
+        exit_if_not_in_repository(&reporter());
 
-    if !config_path.exists() && !matches!(cli.command, Command::Init) {
         eprintln!(
             "{}",
             ConfigError::NotFound(config_path.to_str().unwrap().to_string())
@@ -19,39 +78,342 @@ fn main() -> Result<()> {
         std::process::exit(1);
     }
 
-    if cli.command == Command::Init {
-        return Hooksmith::init_interactive(config_path, cli.dry_run, cli.verbose);
+    let create_hooks_dir = !matches!(
+        command,
+        Command::Install {
+            no_create_dir: true,
+            ..
+        }
+    );
+    let force = matches!(command, Command::Install { force: true, .. });
+    let append = matches!(command, Command::Install { append: true, .. });
+    let mut hs = match &command {
+        Command::Run {
+            hook_names: Some(hook_names),
+            no_validate_all: true,
+            ..
+        } => Hooksmith::new_from_config_for_hooks(
+            config_path,
+            hook_names,
+            cli.dry_run,
+            cli.verbose,
+            reporter(),
+        ),
+        _ => Hooksmith::new_from_config_with_reporter(
+            config_path,
+            cli.dry_run,
+            cli.verbose,
+            reporter(),
+        ),
+    }?
+    .with_create_hooks_dir(create_hooks_dir)
+    .with_force(force)
+    .with_append(append);
+
+    if !cli.no_global_config {
+        if let Some(global_config_path) = global_config_path() {
+            hs = hs.with_global_config(&global_config_path)?;
+        }
     }
 
-    let hs = Hooksmith::new_from_config(config_path, cli.dry_run, cli.verbose)?;
+    let result = match command {
+        Command::Api { action } => match action {
+            ApiCommand::Hooks => hs.hooks_json().map(|json| println!("{json}")),
+        },
+        Command::Compare { fail_on_foreign } => hs.compare_hooks().and_then(|()| {
+            if fail_on_foreign {
+                hs.check_foreign_hooks()
+            } else {
+                Ok(())
+            }
+        }),
+        Command::Exec {
+            hook,
+            capture,
+            timeout,
+            tee,
+            command,
+        } => {
+            let mut options = RunOptions::new()
+                .with_capture(capture)
+                .with_tee(tee)
+                .with_shell(cli.shell.clone());
+            if let Some(seconds) = timeout {
+                options = options.with_timeout(Duration::from_secs(seconds));
+            }
 
-    match cli.command {
-        Command::Compare => hs.compare_hooks(),
-        Command::Init => Hooksmith::init_interactive(config_path, cli.dry_run, cli.verbose),
-        Command::Install => {
-            hs.validate_hooks_for_install()?;
+            exit_on_command_failure(hs.exec_command(&command.join(" "), hook.as_deref(), &options))
+        }
+        Command::Config {
+            explain: true,
+            json,
+            ..
+        } => hs.explain_config(json).map(|sources| {
+            println!("{}", sources.trim_end());
+        }),
+        Command::Config { tree: true, .. } => {
+            print!("{}", hs.config_tree());
+            Ok(())
+        }
+        Command::Config { json, .. } => hs.effective_config(json).map(|config| {
+            println!("{}", config.trim_end());
+        }),
+        Command::Install {
+            emit_to: Some(dir), ..
+        } => {
+            if !cli.dry_run {
+                eprintln!("Error: --emit-to only applies to dry runs, add --dry-run");
+                std::process::exit(1);
+            }
 
-            hs.install_hooks()
+            hs.validate_hooks_for_install()
+                .and_then(|()| hs.emit_hooks(&dir))
         }
-        Command::Uninstall { hook_name } => {
-            if let Some(item) = hook_name {
-                hs.uninstall_given_hook(&item)
-            } else {
+        Command::Install { .. } => hs
+            .validate_hooks_for_install()
+            .and_then(|()| hs.install_hooks()),
+        Command::List { installed_only } => hs.list_hooks(installed_only),
+        Command::PruneConfig { yes } => hs.prune_config(config_path, yes).map(|_| ()),
+        Command::Uninstall { hook_names, yes } => {
+            if hook_names.is_empty() {
                 hs.uninstall_hooks()
+            } else {
+                hs.uninstall_given_hooks(&hook_names, yes)
             }
         }
         Command::Run {
             hook_names,
             interactive,
+            all,
+            except,
+            skip,
+            no_validate_all: _,
             profile,
+            max_output_bytes,
+            since_last_run,
+            stash,
+            only_changed_hooks,
+            show_skipped,
+            hide_skipped,
+            resolve,
+            tee,
+            git_env,
+            pre_run,
+            post_run,
+            bench,
+            stdin_message,
+            hook_args,
         } => {
-            if hook_names.is_none() && !interactive {
-                eprintln!("Error: Either provide hook names or use --interactive (-i) flag");
+            if hook_names.is_none() && !interactive && !all {
+                eprintln!(
+                    "Error: Either provide hook names, use --all or the --interactive (-i) flag"
+                );
+                std::process::exit(1);
+            }
+            if resolve && !cli.dry_run {
+                eprintln!("Error: --resolve only applies to dry runs, add --dry-run");
+                std::process::exit(1);
+            }
+            if bench.is_some() && cli.dry_run {
+                eprintln!("Error: --bench measures commands by running them, remove --dry-run");
                 std::process::exit(1);
             }
 
-            hs.run_hook(hook_names.as_deref(), interactive, profile)
+            let message_file = stdin_message
+                .as_deref()
+                .map(CommitMessageFile::create)
+                .transpose()?;
+            let hook_args: Vec<String> = match &message_file {
+                Some(file) => vec![file.path().display().to_string()],
+                None => hook_args,
+            };
+
+            let junit = (cli.format == OutputFormat::Junit).then(|| {
+                let report = JunitReport::new();
+                hs.set_observer(report.observer(Box::new(StdoutObserver)));
+                report
+            });
+            // The captured output goes to the JSON report instead of being printed
+            let recorder = (cli.format == OutputFormat::Json).then(|| {
+                let recorder = RunRecorder::new();
+                hs.set_observer(recorder.observer(None));
+                recorder
+            });
+
+            let options = RunOptions::new()
+                .with_extra_args(hook_args)
+                .with_interactive(interactive)
+                .with_all(all)
+                .with_capture(recorder.is_some())
+                .with_except(except)
+                .with_skip(skip)
+                .with_profile(profile)
+                .with_max_output_bytes(max_output_bytes)
+                .with_since_last_run(since_last_run)
+                .with_stash(stash)
+                .with_only_changed_hooks(only_changed_hooks)
+                .with_resolve(resolve)
+                .with_bench(bench)
+                .with_tee(tee)
+                .with_show_git_env(git_env)
+                .with_pre_run(pre_run)
+                .with_post_run(post_run)
+                .with_shell(cli.shell.clone())
+                .with_jobs(cli.jobs.get())
+                .with_show_skipped(
+                    show_skipped
+                        .then_some(true)
+                        .or(hide_skipped.then_some(false)),
+                );
+
+            let result = hs.run_hook(hook_names.as_deref(), &options);
+
+            // Remove the temporary message file before a possible early exit
+            drop(message_file);
+
+            if let Some(report) = junit {
+                write_report(&report.to_xml(), cli.output.as_deref())?;
+            }
+            if let Some(recorder) = recorder {
+                let mut report = recorder.report();
+                report.success &= result.is_ok();
+                write_report(&report.to_json(), cli.output.as_deref())?;
+            }
+
+            exit_on_command_failure(result)
+        }
+        Command::Upgrade => hs.upgrade_hooks(),
+        Command::Validate => {
+            // Both report their problems, whichever fails
+            let hooks = hs.validate_hooks();
+            let variables = hs.validate_variables();
+
+            hooks.and(variables)
+        }
+        Command::Verify => hs.verify(),
+        Command::Doctor => hs.doctor(),
+    };
+
+    let warning_count = hs.reporter().warning_count();
+    if result.is_ok() && cli.warnings_as_errors && warning_count > 0 {
+        eprintln!("Error: {warning_count} warning(s) emitted and --warnings-as-errors is set");
+        std::process::exit(1);
+    }
+
+    result
+}
+
+/// Run a command that doesn't read the configuration.
+fn run_standalone(
+    command: StandaloneCommand,
+    config_path: Option<&str>,
+    dry_run: bool,
+    verbose: bool,
+    reporter: &Reporter,
+) -> Result<()> {
+    // `init` and `import` write the configuration, which they never do into a manifest
+    let config_path = config_path.map_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE), PathBuf::from);
+
+    match command {
+        StandaloneCommand::Explain { hook_name } => {
+            Hooksmith::explain_hook_with_reporter(&hook_name, reporter)
+        }
+        // These work on the Git directory
+        StandaloneCommand::Cache { action } => {
+            exit_if_not_in_repository(reporter);
+
+            match action {
+                CacheCommand::Clear => Hooksmith::clear_run_markers(),
+            }
+        }
+        StandaloneCommand::Disable => {
+            exit_if_not_in_repository(reporter);
+            Hooksmith::disable_hooks(dry_run)
+        }
+        StandaloneCommand::Enable => {
+            exit_if_not_in_repository(reporter);
+            Hooksmith::enable_hooks(dry_run)
+        }
+        StandaloneCommand::Import { from } => {
+            Hooksmith::import_pre_commit_config(&config_path, &from, dry_run, verbose, reporter)
         }
-        Command::Validate => hs.validate_hooks(),
+        StandaloneCommand::Init { template, append } => init(
+            &config_path,
+            template.as_deref(),
+            append,
+            dry_run,
+            verbose,
+            reporter,
+        ),
     }
 }
+
+/// Say that hooksmith isn't run inside a Git repository and exit, unless it is.
+fn exit_if_not_in_repository(reporter: &Reporter) {
+    if is_inside_git_repo() {
+        return;
+    }
+
+    reporter.error(
+        "Not inside a Git repository",
+        &format!(
+            "hooksmith manages the hooks of a Git repository, but {} isn't inside one.",
+            std::env::current_dir().map_or_else(
+                |_| "the current directory".to_string(),
+                |dir| dir.display().to_string()
+            )
+        ),
+        "cd into your repository first, or create one with `git init`.",
+    );
+
+    std::process::exit(NOT_A_REPOSITORY_EXIT_CODE);
+}
+
+/// Create the configuration file, from a template if one is given, interactively otherwise.
+fn init(
+    config_path: &Path,
+    template: Option<&str>,
+    append: bool,
+    dry_run: bool,
+    verbose: bool,
+    reporter: &Reporter,
+) -> Result<()> {
+    match template {
+        Some(template) => Hooksmith::init_from_template_with_reporter(
+            config_path,
+            template,
+            dry_run,
+            verbose,
+            reporter,
+        ),
+        None => Hooksmith::init_interactive(config_path, append, dry_run, verbose),
+    }
+}
+
+/// Write a report of the run to a file, or to stdout if no path is given.
+fn write_report(report: &str, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => fs::write(path, report)?,
+        None => print!("{report}"),
+    }
+
+    Ok(())
+}
+
+/// Exit with the failing command's status code, mirroring what git expects from a hook.
+/// The failure itself has already been reported by the time it reaches here. On an
+/// interrupt, exit with `128 + signal` (130 for Ctrl-C), as shells do.
+fn exit_on_command_failure(result: Result<()>) -> Result<()> {
+    match result {
+        Err(HooksmithError::HookExecution(HookExecutionError::CommandFailed(code))) => {
+            std::process::exit(code);
+        }
+        Err(HooksmithError::HookExecution(HookExecutionError::Interrupted(signal))) => {
+            eprintln!("🛑 Interrupted, the running commands were stopped");
+            std::process::exit(128 + signal);
+        }
+        _ => {}
+    }
+
+    result
+}
@@ -0,0 +1,213 @@
+//! Configuration embedded in a project manifest, to avoid a separate `hooksmith.yaml`: a
+//! `[package.metadata.hooksmith]` table in `Cargo.toml`, or a `"hooksmith"` object in
+//! `package.json`.
+//!
+//! The embedded configuration is turned into a YAML value, then read like `hooksmith.yaml`.
+
+use crate::error::{ConfigError, HooksmithError, Result};
+use serde_yaml::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Configuration file read when none is given, as long as it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "hooksmith.yaml";
+
+/// Manifests looked for when `hooksmith.yaml` doesn't exist, in order.
+const MANIFEST_FILES: [&str; 2] = ["Cargo.toml", "package.json"];
+
+/// The manifests a configuration can be embedded in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Manifest {
+    Cargo,
+    Package,
+}
+
+impl Manifest {
+    /// The manifest a configuration file is, decided by its name: `Cargo.toml`, or any
+    /// `.json` file. Other files are read as YAML.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the configuration file
+    fn of(path: &Path) -> Option<Self> {
+        if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+            Some(Self::Cargo)
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Some(Self::Package)
+        } else {
+            None
+        }
+    }
+
+    /// Where the configuration goes in the manifest, for messages.
+    const fn section(self) -> &'static str {
+        match self {
+            Self::Cargo => "a `[package.metadata.hooksmith]` table",
+            Self::Package => "a `\"hooksmith\"` object",
+        }
+    }
+}
+
+/// The configuration file used when none is given: `hooksmith.yaml` if it exists, otherwise
+/// the first of `Cargo.toml` and `package.json` holding a hooksmith configuration, in the
+/// current directory. A manifest that can't be parsed is picked too, so that reading it
+/// reports why. Falls back to `hooksmith.yaml`, so that errors name the usual file.
+#[must_use]
+pub fn default_config_path() -> PathBuf {
+    let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+    if default.exists() {
+        return default;
+    }
+
+    MANIFEST_FILES
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| {
+            let Some(manifest) = Manifest::of(path) else {
+                return false;
+            };
+
+            fs::read_to_string(path)
+                .is_ok_and(|content| !matches!(manifest_section(manifest, &content), Ok(None)))
+        })
+        .unwrap_or(default)
+}
+
+/// Extract the configuration embedded in a manifest. For `Cargo.toml`, the
+/// `[package.metadata.hooksmith]` table is read, or `[workspace.metadata.hooksmith]` in a
+/// virtual workspace.
+///
+/// # Arguments
+/// * `path` - Path of the configuration file
+/// * `content` - Content of the file
+///
+/// # Errors
+/// * `ConfigError::Embedded` if the manifest can't be parsed or holds no configuration
+///
+/// # Returns
+/// * The configuration, as a YAML value, or `None` if the file isn't a manifest
+pub fn embedded_config(path: &Path, content: &str) -> Result<Option<Value>> {
+    let Some(manifest) = Manifest::of(path) else {
+        return Ok(None);
+    };
+    let error = |message: String| -> HooksmithError {
+        ConfigError::Embedded {
+            path: path.display().to_string(),
+            message,
+        }
+        .into()
+    };
+
+    match manifest_section(manifest, content).map_err(error)? {
+        Some(config) => Ok(Some(config)),
+        None => Err(error(format!(
+            "no hooksmith configuration, add it as {}",
+            manifest.section()
+        ))),
+    }
+}
+
+/// Read the hooksmith section of a manifest.
+///
+/// # Arguments
+/// * `manifest` - The kind of manifest
+/// * `content` - Content of the manifest
+///
+/// # Errors
+/// * A message saying why the manifest can't be parsed
+///
+/// # Returns
+/// * The configuration, as a YAML value, or `None` if the manifest has no hooksmith section
+fn manifest_section(
+    manifest: Manifest,
+    content: &str,
+) -> std::result::Result<Option<Value>, String> {
+    match manifest {
+        Manifest::Cargo => {
+            let manifest: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+            ["package", "workspace"]
+                .into_iter()
+                .find_map(|table| manifest.get(table)?.get("metadata")?.get("hooksmith"))
+                .map(serde_yaml::to_value)
+                .transpose()
+                .map_err(|e| e.to_string())
+        }
+        Manifest::Package => {
+            let manifest: serde_json::Value =
+                serde_json::from_str(content).map_err(|e| e.to_string())?;
+            manifest
+                .get("hooksmith")
+                .map(serde_yaml::to_value)
+                .transpose()
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_config() {
+        let cargo = "[package]\nname = \"demo\"\n\n[package.metadata.hooksmith.pre-commit]\ncommands = [\"cargo test\"]\n";
+        let config = embedded_config(Path::new("Cargo.toml"), cargo)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            config["pre-commit"]["commands"][0],
+            Value::from("cargo test")
+        );
+
+        let workspace = "[workspace]\nmembers = [\"a\"]\n[workspace.metadata.hooksmith.pre-push]\ncommands = [\"cargo test\"]\n";
+        let config = embedded_config(Path::new("sub/Cargo.toml"), workspace)
+            .unwrap()
+            .unwrap();
+        assert!(config.get("pre-push").is_some());
+
+        let package =
+            r#"{"name": "demo", "hooksmith": {"pre-commit": {"commands": ["npm test"]}}}"#;
+        let config = embedded_config(Path::new("package.json"), package)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config["pre-commit"]["commands"][0], Value::from("npm test"));
+
+        // Other files are read as YAML
+        assert!(
+            embedded_config(Path::new("hooksmith.yaml"), "pre-commit: {}")
+                .unwrap()
+                .is_none()
+        );
+
+        let error = embedded_config(Path::new("package.json"), r#"{"name": "demo"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no hooksmith configuration"), "{error}");
+        assert!(embedded_config(Path::new("Cargo.toml"), "[package\n").is_err());
+    }
+
+    #[test]
+    fn test_manifest_section() {
+        // Any valid TOML is read, dates included
+        let cargo = "[package]\nname = \"demo\"\npublished = 1979-05-27\n\n[package.metadata.hooksmith]\nshell = \"bash\"\n";
+        let config = manifest_section(Manifest::Cargo, cargo).unwrap().unwrap();
+        assert_eq!(config["shell"], Value::from("bash"));
+
+        assert_eq!(
+            manifest_section(Manifest::Cargo, "[package]\nname = \"demo\"\n"),
+            Ok(None)
+        );
+        assert!(
+            manifest_section(Manifest::Cargo, "[package]\nname = \"demo\"\nname = 1\n").is_err()
+        );
+        assert_eq!(
+            manifest_section(Manifest::Package, r#"{"name": "demo"}"#),
+            Ok(None)
+        );
+        assert!(manifest_section(Manifest::Package, "{").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Callbacks notified while hooks run, so that embedders can render their own progress.
+
+use crate::{
+    error::HooksmithError,
+    hooksmith::{CommandTiming, HookCommand, HookTiming},
+    output::{self, outln},
+    process::CapturedOutput,
+};
+
+/// Receives progress notifications while hooks run.
+///
+/// # Contract
+/// * Callbacks are invoked synchronously, on the thread running the hooks, in execution
+///   order: `on_hook_start`, then `on_command_start`/`on_command_finish` for each command,
+///   then `on_hook_finish`. Unless run with `jobs`, hooks run one after the other, so
+///   callbacks never overlap.
+/// * Hooks run with `jobs` greater than 1 run concurrently: callbacks of different hooks are
+///   invoked from their threads, at the same time. Observers tell them apart by hook name.
+/// * The commands of a parallel group (`groups`) run concurrently: callbacks are invoked
+///   from their threads and may interleave between commands, but are still serialized.
+/// * Execution waits for each callback to return, so callbacks should be quick.
+/// * `on_hook_finish` is only invoked for hooks whose commands all succeeded, or only failed
+///   with `allow_failure`; a failing command is reported by `on_command_finish` and stops
+///   the run, unless it is allowed to fail.
+/// * Dry runs notify `on_hook_start` and `on_hook_finish` only, since no command is executed.
+/// * Observers must be `Send + Sync` so that a `Hooksmith` holding one can be shared.
+///
+/// Every method has a default implementation that does nothing, except `on_command_output`
+/// which prints the output, so observers only need to implement the notifications they
+/// care about.
+pub trait HookObserver: Send + Sync {
+    /// A hook is about to run.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook
+    /// * `index` - Position of the hook in the run, starting at 0
+    /// * `total` - Number of hooks in the run
+    fn on_hook_start(&self, hook_name: &str, index: usize, total: usize) {
+        let _ = (hook_name, index, total);
+    }
+
+    /// A command is about to run.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook the command belongs to
+    /// * `command` - The command
+    /// * `index` - Position of the command in its block, starting at 0
+    /// * `total` - Number of commands in the block
+    fn on_command_start(&self, hook_name: &str, command: &HookCommand, index: usize, total: usize) {
+        let _ = (hook_name, command, index, total);
+    }
+
+    /// A command whose output is captured (`capture_output`) exited, with its output. Invoked
+    /// for each attempt of a retried command, before `on_command_finish`, and for the
+    /// `on_success` and `on_failure` commands too.
+    ///
+    /// The default implementation prints the output, followed by a notice if it was
+    /// truncated; observers overriding it decide whether it is printed.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook the command belongs to
+    /// * `command` - The command
+    /// * `output` - The captured output
+    fn on_command_output(&self, hook_name: &str, command: &HookCommand, output: &CapturedOutput) {
+        let _ = (hook_name, command);
+
+        output::write_stdout(&output.stdout);
+        output::write_stderr(&output.stderr);
+
+        if output.omitted > 0 {
+            if output.stdout.last().is_some_and(|byte| *byte != b'\n') {
+                outln!();
+            }
+
+            outln!("... (truncated, {} bytes omitted)", output.omitted);
+        }
+    }
+
+    /// A command finished running.
+    ///
+    /// # Arguments
+    /// * `hook_name` - The name of the hook the command belongs to
+    /// * `command` - The command
+    /// * `result` - The timing of the command if it succeeded, the error otherwise
+    fn on_command_finish(
+        &self,
+        hook_name: &str,
+        command: &HookCommand,
+        result: Result<&CommandTiming, &HooksmithError>,
+    ) {
+        let _ = (hook_name, command, result);
+    }
+
+    /// A hook finished running successfully.
+    ///
+    /// # Arguments
+    /// * `report` - The timing of the hook and of its commands
+    fn on_hook_finish(&self, report: &HookTiming) {
+        let _ = report;
+    }
+}
+
+/// The default observer, printing progress to stdout, or to stderr when a report takes
+/// stdout (see `redirect_stdout_to_stderr`).
+#[derive(Debug, Default)]
+pub struct StdoutObserver;
+
+impl HookObserver for StdoutObserver {
+    fn on_hook_start(&self, hook_name: &str, index: usize, total: usize) {
+        outln!("running `{hook_name}`, {}/{total} steps:", index + 1);
+    }
+
+    fn on_command_start(&self, _: &str, command: &HookCommand, index: usize, total: usize) {
+        let display = command.name.as_deref().unwrap_or(&command.command);
+        outln!("  running `{display}` {}/{total}", index + 1);
+    }
+
+    fn on_hook_finish(&self, report: &HookTiming) {
+        // Without failures allowed by `allow_failure`, the progress already tells it all
+        let failed = report
+            .commands
+            .iter()
+            .filter(|timing| timing.failed)
+            .count();
+        if failed == 0 {
+            return;
+        }
+
+        outln!(
+            "`{}`: {} passed, {failed} failed (allowed)",
+            report.hook_name,
+            report.commands.len() - failed
+        );
+        for timing in &report.commands {
+            let display = timing.name.as_deref().unwrap_or(&timing.command);
+            let status = if timing.failed { "❌" } else { "✅" };
+            outln!("  {status} `{display}`");
+        }
+    }
+}
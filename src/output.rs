@@ -0,0 +1,237 @@
+//! Human-readable output of hooksmith: messages, progress, and the output of commands.
+//!
+//! It goes to stdout and stderr, unless a machine-readable report takes stdout (`run
+//! --format json`): everything meant for stdout then goes to stderr, so that stdout only
+//! holds the report. Each thread can also send its output elsewhere with `redirect`, e.g.
+//! nowhere while collecting the results of a run, or to a buffer holding the output of a
+//! hook running alongside others until it is done.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// Whether the output meant for stdout goes to stderr.
+static STDOUT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Where the output of the current thread goes.
+    static SINK: RefCell<Sink> = const { RefCell::new(Sink::Terminal) };
+}
+
+/// Where the output of a thread goes.
+#[derive(Debug, Clone, Default)]
+pub enum Sink {
+    /// Stdout and stderr
+    #[default]
+    Terminal,
+    /// A buffer, printed as a whole once flushed
+    Buffer(Buffer),
+    /// Nowhere
+    Discard,
+}
+
+/// Output held until it is printed as a whole with `flush`.
+#[derive(Debug, Clone, Default)]
+pub struct Buffer {
+    /// Chunks in the order they were written
+    chunks: Arc<Mutex<Vec<Chunk>>>,
+}
+
+/// A chunk of buffered output.
+#[derive(Debug, PartialEq, Eq)]
+struct Chunk {
+    /// Whether the chunk is meant for stderr rather than stdout
+    to_stderr: bool,
+    bytes: Vec<u8>,
+}
+
+/// Held while a buffer is flushed, so that buffers flushed at the same time don't
+/// interleave.
+static FLUSH: Mutex<()> = Mutex::new(());
+
+impl Buffer {
+    /// Create an empty buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print the buffered output where the current thread prints, and empty the buffer.
+    pub fn flush(&self) {
+        let chunks =
+            std::mem::take(&mut *self.chunks.lock().unwrap_or_else(PoisonError::into_inner));
+        let _flush = FLUSH.lock().unwrap_or_else(PoisonError::into_inner);
+
+        for chunk in chunks {
+            if chunk.to_stderr {
+                write_stderr(&chunk.bytes);
+            } else {
+                write_stdout(&chunk.bytes);
+            }
+        }
+    }
+
+    /// Append a chunk of output.
+    fn push(&self, to_stderr: bool, bytes: &[u8]) {
+        self.chunks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Chunk {
+                to_stderr,
+                bytes: bytes.to_vec(),
+            });
+    }
+}
+
+/// Sends the output of the current thread back where it went before `redirect` when
+/// dropped.
+pub struct Redirect {
+    previous: Sink,
+}
+
+/// Send the output of the current thread to `sink` until the returned guard is dropped.
+/// Threads started meanwhile keep sending theirs to the terminal, unless they are
+/// redirected with the sink of their parent (see `current`).
+///
+/// # Arguments
+/// * `sink` - Where the output goes
+#[must_use]
+pub fn redirect(sink: Sink) -> Redirect {
+    Redirect {
+        previous: SINK.with(|current| current.replace(sink)),
+    }
+}
+
+/// Where the output of the current thread goes.
+pub fn current() -> Sink {
+    SINK.with(|current| current.borrow().clone())
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        SINK.with(|current| current.replace(previous));
+    }
+}
+
+/// Send the human-readable output meant for stdout to stderr from now on, including the
+/// output of commands, so that stdout is left to a report. Used by the CLI when a JSON or
+/// JUnit report is printed rather than written to a file.
+pub fn redirect_stdout_to_stderr() {
+    STDOUT_TO_STDERR.store(true, Ordering::SeqCst);
+}
+
+/// Whether the output meant for stdout goes to stderr, see `redirect_stdout_to_stderr`.
+pub fn is_stdout_redirected() -> bool {
+    STDOUT_TO_STDERR.load(Ordering::SeqCst)
+}
+
+/// Write output meant for stdout. Write errors are ignored, as a closed terminal shouldn't
+/// stop the hooks.
+///
+/// # Arguments
+/// * `bytes` - What to write
+pub fn write_stdout(bytes: &[u8]) {
+    match current() {
+        Sink::Terminal if is_stdout_redirected() => {
+            let _ = io::stderr().write_all(bytes);
+        }
+        Sink::Terminal => {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(bytes);
+            let _ = stdout.flush();
+        }
+        Sink::Buffer(buffer) => buffer.push(false, bytes),
+        Sink::Discard => {}
+    }
+}
+
+/// Write output meant for stderr. Write errors are ignored.
+///
+/// # Arguments
+/// * `bytes` - What to write
+pub fn write_stderr(bytes: &[u8]) {
+    match current() {
+        Sink::Terminal => {
+            let _ = io::stderr().write_all(bytes);
+        }
+        Sink::Buffer(buffer) => buffer.push(true, bytes),
+        Sink::Discard => {}
+    }
+}
+
+/// Print to the output meant for stdout, as `print!` does.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::write_stdout(format!($($arg)*).as_bytes())
+    };
+}
+
+/// Print a line to the output meant for stdout, as `println!` does.
+macro_rules! outln {
+    () => {
+        $crate::output::write_stdout(b"\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write_stdout(format!("{}\n", format_args!($($arg)*)).as_bytes())
+    };
+}
+
+pub(crate) use {out, outln};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_is_restored() {
+        assert!(matches!(current(), Sink::Terminal));
+
+        {
+            let _silence = redirect(Sink::Discard);
+            assert!(matches!(current(), Sink::Discard));
+
+            // Other threads aren't affected
+            let other = std::thread::spawn(current).join().unwrap();
+            assert!(matches!(other, Sink::Terminal));
+        }
+
+        assert!(matches!(current(), Sink::Terminal));
+    }
+
+    #[test]
+    fn test_buffer_holds_output() {
+        let buffer = Buffer::new();
+        let target = Buffer::new();
+
+        {
+            let _buffer = redirect(Sink::Buffer(buffer.clone()));
+            outln!("checked");
+            write_stderr(b"oops\n");
+
+            // Flushing prints where the flushing thread prints
+            let _inner = redirect(Sink::Buffer(target.clone()));
+            buffer.flush();
+        }
+
+        assert!(buffer.chunks.lock().unwrap().is_empty());
+        assert_eq!(
+            *target.chunks.lock().unwrap(),
+            [
+                Chunk {
+                    to_stderr: false,
+                    bytes: b"checked\n".to_vec()
+                },
+                Chunk {
+                    to_stderr: true,
+                    bytes: b"oops\n".to_vec()
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! Named placeholders (`{msg_file}`, `{upstream}`, ...) that can be used in hook commands
+//! instead of raw positional arguments.
+
+use crate::utils::shell_quote;
+use std::path::PathBuf;
+
+/// Names given to the positional arguments git passes to each hook, in order.
+/// Hooks that receive no arguments are not listed.
+const HOOK_ARGUMENTS: [(&str, &[&str]); 12] = [
+    ("applypatch-msg", &["msg_file"]),
+    ("commit-msg", &["msg_file"]),
+    (
+        "prepare-commit-msg",
+        &["msg_file", "commit_source", "commit_sha"],
+    ),
+    ("pre-rebase", &["upstream", "branch"]),
+    (
+        "post-checkout",
+        &["prev_head", "new_head", "branch_checkout"],
+    ),
+    ("post-merge", &["squash"]),
+    ("pre-push", &["remote_name", "remote_url"]),
+    ("post-rewrite", &["rewrite_type"]),
+    ("update", &["ref_name", "old_sha", "new_sha"]),
+    ("reference-transaction", &["state"]),
+    ("push-to-checkout", &["new_commit"]),
+    ("sendemail-validate", &["patch_file"]),
+];
+
+/// Environment variable holding the revision ranges being pushed, one per line, for
+/// `pre-push` hooks, and the ranges received for `pre-receive` and `post-receive` hooks.
+pub const PUSH_RANGE_ENV: &str = "HOOKSMITH_PUSH_RANGE";
+
+/// Placeholders expanding to an environment variable set by hooksmith, by hook.
+const HOOK_VARIABLES: [(&str, &[(&str, &str)]); 3] = [
+    ("pre-push", &[("push_range", PUSH_RANGE_ENV)]),
+    ("pre-receive", &[("push_range", PUSH_RANGE_ENV)]),
+    ("post-receive", &[("push_range", PUSH_RANGE_ENV)]),
+];
+
+/// Placeholder expanding to the staged files, available for every hook: `{staged_files}`, or
+/// `{staged_files:*.rs}` for the ones matching a glob pattern.
+pub const STAGED_FILES: &str = "staged_files";
+
+/// Comment that silences the unquoted argument lint for a command, e.g. `rm $1 # noqa`.
+pub const NOQA_MARKER: &str = "# noqa";
+
+/// A `{name}` or `{name:filter}` placeholder found in a command string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Byte offset of the opening brace
+    pub start: usize,
+    /// Byte offset just past the closing brace
+    pub end: usize,
+    /// Placeholder name
+    pub name: String,
+    /// Optional filter after the colon
+    pub filter: Option<String>,
+}
+
+/// Get the placeholder names available for the arguments of a given hook.
+///
+/// # Arguments
+/// * `hook_name` - The name of the git hook
+///
+/// # Returns
+/// * The argument names in positional order, empty if the hook takes no arguments
+pub fn hook_argument_names(hook_name: &str) -> &'static [&'static str] {
+    HOOK_ARGUMENTS
+        .iter()
+        .find(|(name, _)| *name == hook_name)
+        .map_or(&[], |(_, args)| args)
+}
+
+/// Get the environment variable a placeholder of a given hook expands to, if any.
+///
+/// # Arguments
+/// * `hook_name` - The name of the git hook
+/// * `placeholder` - The placeholder name, e.g. `push_range`
+fn hook_variable(hook_name: &str, placeholder: &str) -> Option<&'static str> {
+    HOOK_VARIABLES
+        .iter()
+        .find(|(name, _)| *name == hook_name)
+        .and_then(|(_, variables)| variables.iter().find(|(name, _)| *name == placeholder))
+        .map(|(_, variable)| *variable)
+}
+
+/// Find the placeholders in a command string.
+///
+/// A placeholder is an identifier (lowercase letters, digits and underscores, not starting
+/// with a digit) between braces, optionally followed by `:filter`. Shell parameter expansions
+/// such as `${VAR}` and braces containing whitespace (`{ a; b; }`, awk programs) are ignored.
+///
+/// # Arguments
+/// * `command` - The command string to scan
+pub fn find_placeholders(command: &str) -> Vec<Placeholder> {
+    let mut placeholders = Vec::new();
+    let bytes = command.as_bytes();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if bytes[idx] != b'{' || (idx > 0 && bytes[idx - 1] == b'$') {
+            idx += 1;
+            continue;
+        }
+
+        let Some(len) = command[idx + 1..].find('}') else {
+            break;
+        };
+        let inner = &command[idx + 1..idx + 1 + len];
+        let (name, filter) = match inner.split_once(':') {
+            Some((name, filter)) => (name, Some(filter)),
+            None => (inner, None),
+        };
+
+        let is_identifier = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        let filter_is_valid = filter
+            .is_none_or(|f| !f.is_empty() && !f.contains(|c: char| c.is_whitespace() || c == '{'));
+
+        if is_identifier && filter_is_valid {
+            placeholders.push(Placeholder {
+                start: idx,
+                end: idx + len + 2,
+                name: name.to_string(),
+                filter: filter.map(str::to_string),
+            });
+            idx += len + 2;
+        } else {
+            idx += 1;
+        }
+    }
+
+    placeholders
+}
+
+/// Replace the hook argument placeholders of a command with the matching positional
+/// parameter (`{msg_file}` becomes `${1}` for `commit-msg`), so the shell expands them
+/// exactly like `$1`, and the placeholders of variables set by hooksmith with the variable
+/// (`{push_range}` becomes `${HOOKSMITH_PUSH_RANGE}` for `pre-push`). Other placeholders
+/// are left untouched.
+///
+/// # Arguments
+/// * `hook_name` - The name of the hook the command belongs to
+/// * `command` - The command string
+pub fn expand_argument_placeholders(hook_name: &str, command: &str) -> String {
+    let argument_names = hook_argument_names(hook_name);
+    let mut expanded = String::with_capacity(command.len());
+    let mut last = 0;
+
+    for placeholder in find_placeholders(command) {
+        let parameter = if let Some(position) = argument_names
+            .iter()
+            .position(|name| *name == placeholder.name)
+        {
+            (position + 1).to_string()
+        } else if let Some(variable) = hook_variable(hook_name, &placeholder.name) {
+            variable.to_string()
+        } else {
+            continue;
+        };
+
+        expanded.push_str(&command[last..placeholder.start]);
+        expanded.push_str(&format!("${{{parameter}}}"));
+        last = placeholder.end;
+    }
+
+    expanded.push_str(&command[last..]);
+    expanded
+}
+
+/// Replace the `{staged_files}` placeholders of a command with the staged files matching
+/// their filter, shell-quoted and separated by spaces. The staged files are only looked up
+/// when the command uses the placeholder.
+///
+/// # Arguments
+/// * `command` - The command string
+/// * `staged_files` - Looks up the staged files matching an optional glob pattern
+///
+/// # Errors
+/// * If the staged files can't be looked up
+///
+/// # Returns
+/// * `None` if a placeholder matches no staged file, in which case the command has nothing
+///   to run on
+pub fn expand_staged_files<E>(
+    command: &str,
+    mut staged_files: impl FnMut(Option<&str>) -> Result<Vec<PathBuf>, E>,
+) -> Result<Option<String>, E> {
+    let mut expanded = String::with_capacity(command.len());
+    let mut last = 0;
+
+    for placeholder in find_placeholders(command) {
+        if placeholder.name != STAGED_FILES {
+            continue;
+        }
+
+        let files = staged_files(placeholder.filter.as_deref())?;
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let files = files
+            .iter()
+            .map(|file| shell_quote(&file.to_string_lossy()))
+            .collect::<Vec<_>>();
+
+        expanded.push_str(&command[last..placeholder.start]);
+        expanded.push_str(&files.join(" "));
+        last = placeholder.end;
+    }
+
+    expanded.push_str(&command[last..]);
+    Ok(Some(expanded))
+}
+
+/// List the placeholders of a command that are not known for the given hook.
+///
+/// # Arguments
+/// * `hook_name` - The name of the hook the command belongs to
+/// * `command` - The command string
+pub fn unknown_placeholders(hook_name: &str, command: &str) -> Vec<String> {
+    let argument_names = hook_argument_names(hook_name);
+
+    find_placeholders(command)
+        .into_iter()
+        .filter(|placeholder| {
+            !argument_names.contains(&placeholder.name.as_str())
+                && hook_variable(hook_name, &placeholder.name).is_none()
+                && placeholder.name != STAGED_FILES
+        })
+        .map(|placeholder| placeholder.name)
+        .collect()
+}
+
+/// List the hook arguments a command expands outside of double quotes: positional
+/// parameters (`$1`, `${2}`, `$@`, `$*`) and the placeholders of the hook (`{msg_file}`).
+/// Unquoted expansions are split on whitespace and glob-expanded by the shell, which breaks
+/// on paths containing spaces.
+///
+/// This is a best-effort scan: single and double quotes, backslash escapes and comments are
+/// understood, other shell constructs are not. Commands containing `# noqa` are skipped.
+///
+/// # Arguments
+/// * `hook_name` - The name of the hook the command belongs to
+/// * `command` - The command string
+///
+/// # Returns
+/// * The unquoted expansions, as written in the command
+pub fn unquoted_arguments(hook_name: &str, command: &str) -> Vec<String> {
+    if command.contains(NOQA_MARKER) {
+        return Vec::new();
+    }
+
+    let argument_names = hook_argument_names(hook_name);
+    let placeholders = find_placeholders(command)
+        .into_iter()
+        .filter(|placeholder| argument_names.contains(&placeholder.name.as_str()))
+        .collect::<Vec<_>>();
+
+    let bytes = command.as_bytes();
+    let (mut in_single, mut in_double) = (false, false);
+    let mut unquoted = Vec::new();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let quoted = in_single || in_double;
+
+        match bytes[idx] {
+            b'\\' if !in_single => {
+                idx += 2;
+                continue;
+            }
+            b'\'' if !in_double => in_single = !in_single,
+            b'"' if !in_single => in_double = !in_double,
+            b'#' if !quoted && (idx == 0 || bytes[idx - 1].is_ascii_whitespace()) => break,
+            b'$' if !quoted => {
+                if let Some(len) = positional_parameter_len(&bytes[idx + 1..]) {
+                    unquoted.push(command[idx..=idx + len].to_string());
+                    idx += len + 1;
+                    continue;
+                }
+            }
+            b'{' if !quoted => {
+                if let Some(placeholder) = placeholders.iter().find(|p| p.start == idx) {
+                    unquoted.push(command[placeholder.start..placeholder.end].to_string());
+                    idx = placeholder.end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        idx += 1;
+    }
+
+    unquoted
+}
+
+/// Expand the `${NAME}` and `${NAME:-default}` references of an `env` value, so that
+/// values can be composed from other variables (e.g. `${HOME}/.cache`). Unset variables
+/// expand to an empty string (or their default), as in the shell; other references,
+/// such as `$NAME`, are kept as they are.
+///
+/// # Arguments
+/// * `value` - The value of the variable
+/// * `lookup` - The value of a variable, `None` if it is unset
+pub fn interpolate_variables(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut interpolated = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        interpolated.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let len = identifier_len(reference.as_bytes());
+        let name = &reference[..len];
+
+        let expansion = match reference[len..].split_once('}') {
+            Some(("", after)) if len > 0 => Some((lookup(name).unwrap_or_default(), after)),
+            Some((operator, after)) if len > 0 && operator.starts_with(":-") => Some((
+                lookup(name)
+                    .filter(|value| !value.is_empty())
+                    .unwrap_or_else(|| operator[2..].to_string()),
+                after,
+            )),
+            _ => None,
+        };
+        match expansion {
+            Some((expanded, after)) => {
+                interpolated.push_str(&expanded);
+                rest = after;
+            }
+            None => {
+                interpolated.push_str("${");
+                rest = reference;
+            }
+        }
+    }
+    interpolated.push_str(rest);
+
+    interpolated
+}
+
+/// List the environment variables a command requires: `${NAME}` references without a
+/// default (`${NAME:-default}`, `${NAME-default}`) or any other operator. Variables the
+/// command assigns itself (`NAME=value`, `for NAME in`) are left out, as are unbraced
+/// references (`$NAME`), which are commonly used for shell variables.
+///
+/// Like `unquoted_arguments`, this is a best-effort scan: single quotes, backslash escapes
+/// and comments are understood, other shell constructs are not.
+///
+/// # Arguments
+/// * `command` - The command string
+///
+/// # Returns
+/// * The names of the variables, in order of first use
+pub fn required_variables(command: &str) -> Vec<String> {
+    let bytes = command.as_bytes();
+    let (mut in_single, mut in_double) = (false, false);
+    let mut assigned = Vec::new();
+    let mut required = Vec::new();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let quoted = in_single || in_double;
+        let at_word_start = idx == 0 || b" \t\n;&|(".contains(&bytes[idx - 1]);
+
+        match bytes[idx] {
+            b'\\' if !in_single => {
+                idx += 2;
+                continue;
+            }
+            b'\'' if !in_double => in_single = !in_single,
+            b'"' if !in_single => in_double = !in_double,
+            b'#' if !quoted && at_word_start => {
+                // Skip the comment, up to the end of its line
+                idx = command[idx..]
+                    .find('\n')
+                    .map_or(bytes.len(), |len| idx + len);
+                continue;
+            }
+            b'$' if !in_single && bytes.get(idx + 1) == Some(&b'{') => {
+                let len = identifier_len(&bytes[idx + 2..]);
+                if len > 0 && bytes.get(idx + 2 + len) == Some(&b'}') {
+                    let name = &command[idx + 2..idx + 2 + len];
+                    if !required.iter().any(|required| required == name) {
+                        required.push(name.to_string());
+                    }
+                }
+            }
+            _ if !quoted && at_word_start => {
+                let rest = &command[idx..];
+                let len = identifier_len(rest.as_bytes());
+                if len > 0 && rest.as_bytes().get(len) == Some(&b'=') {
+                    assigned.push(&rest[..len]);
+                } else if let Some(loop_variable) = rest.strip_prefix("for ") {
+                    let loop_variable = loop_variable.trim_start();
+                    assigned.push(&loop_variable[..identifier_len(loop_variable.as_bytes())]);
+                }
+            }
+            _ => {}
+        }
+
+        idx += 1;
+    }
+
+    required.retain(|name| !assigned.contains(&name.as_str()));
+    required
+}
+
+/// Length of the shell identifier (letters, digits and underscores, not starting with a
+/// digit) at the start of `rest`, 0 if there is none.
+fn identifier_len(rest: &[u8]) -> usize {
+    match rest.first() {
+        Some(b) if b.is_ascii_alphabetic() || *b == b'_' => rest
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count(),
+        _ => 0,
+    }
+}
+
+/// Length of the positional parameter reference following a `$` (`1`, `{12}`, `@`, `*`).
+///
+/// # Returns
+/// * `None` if the `$` doesn't reference a positional parameter
+fn positional_parameter_len(rest: &[u8]) -> Option<usize> {
+    match rest.first()? {
+        b'0'..=b'9' | b'@' | b'*' => Some(1),
+        b'{' => {
+            let digits = rest[1..].iter().take_while(|b| b.is_ascii_digit()).count();
+            (digits > 0 && rest.get(digits + 1) == Some(&b'}')).then_some(digits + 2)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_staged_files() {
+        let staged = |filter: Option<&str>| -> Result<Vec<PathBuf>, ()> {
+            Ok(["src/lib.rs", "my file.rs", "README.md"]
+                .into_iter()
+                .filter(|file| filter.is_none_or(|f| file.ends_with(&f[1..])))
+                .map(PathBuf::from)
+                .collect())
+        };
+
+        assert_eq!(
+            expand_staged_files("rustfmt --check {staged_files:*.rs}", staged),
+            Ok(Some("rustfmt --check src/lib.rs 'my file.rs'".to_string()))
+        );
+        assert_eq!(
+            expand_staged_files("prettier {staged_files}", staged),
+            Ok(Some(
+                "prettier src/lib.rs 'my file.rs' README.md".to_string()
+            ))
+        );
+        assert_eq!(
+            expand_staged_files("taplo {staged_files:*.toml}", staged),
+            Ok(None)
+        );
+        assert_eq!(
+            expand_staged_files("cargo test", |_| Err(())),
+            Ok(Some("cargo test".to_string()))
+        );
+        assert!(unknown_placeholders("pre-commit", "ls {staged_files:*.rs}").is_empty());
+    }
+
+    #[test]
+    fn test_find_placeholders() {
+        let found = find_placeholders("./check.sh {msg_file} {files:*.rs}");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "msg_file");
+        assert_eq!(found[0].filter, None);
+        assert_eq!(found[1].name, "files");
+        assert_eq!(found[1].filter.as_deref(), Some("*.rs"));
+
+        // Shell syntax is not mistaken for placeholders
+        assert!(find_placeholders("echo ${HOME} && { true; }").is_empty());
+        assert!(find_placeholders("awk '{print $1}' file").is_empty());
+        assert!(find_placeholders("find . -exec rm {} \\;").is_empty());
+    }
+
+    #[test]
+    fn test_expand_argument_placeholders() {
+        assert_eq!(
+            expand_argument_placeholders(
+                "prepare-commit-msg",
+                "./prepare.sh \"{msg_file}\" {commit_source} {commit_sha}"
+            ),
+            "./prepare.sh \"${1}\" ${2} ${3}"
+        );
+        assert_eq!(
+            expand_argument_placeholders("pre-rebase", "echo {branch} onto {upstream}"),
+            "echo ${2} onto ${1}"
+        );
+        assert_eq!(
+            expand_argument_placeholders("post-rewrite", "test \"{rewrite_type}\" = amend"),
+            "test \"${1}\" = amend"
+        );
+
+        // Unknown placeholders and other hooks are left untouched
+        assert_eq!(
+            expand_argument_placeholders("pre-commit", "echo {msg_file}"),
+            "echo {msg_file}"
+        );
+        assert_eq!(
+            expand_argument_placeholders("pre-commit", "git log {push_range}"),
+            "git log {push_range}"
+        );
+
+        // Variables set by hooksmith
+        assert_eq!(
+            expand_argument_placeholders("pre-push", "git log {push_range} -- {remote_name}"),
+            "git log ${HOOKSMITH_PUSH_RANGE} -- ${1}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_placeholders() {
+        assert!(unknown_placeholders("commit-msg", "check {msg_file}").is_empty());
+        assert!(unknown_placeholders("pre-push", "git log {push_range}").is_empty());
+        assert_eq!(
+            unknown_placeholders("pre-commit", "git log {push_range}"),
+            vec!["push_range".to_string()]
+        );
+        assert_eq!(
+            unknown_placeholders("commit-msg", "check {msg_fle} {upstream}"),
+            vec!["msg_fle".to_string(), "upstream".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_required_variables() {
+        assert_eq!(
+            required_variables("deploy --token \"${API_TOKEN}\" ${REGION} ${API_TOKEN}"),
+            vec!["API_TOKEN", "REGION"]
+        );
+        // Defaults and other operators make a variable optional
+        assert!(required_variables("echo ${A:-a} ${B-b} ${C:+c} ${#D} ${E%.rs}").is_empty());
+        // Unbraced, single-quoted, escaped and commented references are ignored
+        assert!(required_variables("echo $HOME '${A}' \\${B} # ${C}").is_empty());
+        assert_eq!(required_variables("echo \"it's ${A}\""), vec!["A"]);
+        // Positional and special parameters aren't variables
+        assert!(required_variables("echo ${1} ${@}").is_empty());
+        // Nor are variables the command assigns
+        assert_eq!(
+            required_variables("OUT=dist; for f in *.rs; do cp ${f} ${OUT}/${DEST}; done"),
+            vec!["DEST"]
+        );
+    }
+
+    #[test]
+    fn test_interpolate_variables() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/me".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+
+        assert_eq!(
+            interpolate_variables("${HOME}/.cache:${UNSET}", lookup),
+            "/home/me/.cache:"
+        );
+        assert_eq!(
+            interpolate_variables("${UNSET:-a}-${EMPTY:-b}-${HOME:-c}", lookup),
+            "a-b-/home/me"
+        );
+        // Other references are kept
+        assert_eq!(
+            interpolate_variables("$HOME ${1} ${HOME%/me} ${HOME", lookup),
+            "$HOME ${1} ${HOME%/me} ${HOME"
+        );
+    }
+
+    #[test]
+    fn test_unquoted_arguments() {
+        assert_eq!(
+            unquoted_arguments("commit-msg", "./check.sh $1 {msg_file} ${1} $@"),
+            vec!["$1", "{msg_file}", "${1}", "$@"]
+        );
+
+        // Quoted, escaped or commented expansions are fine
+        assert!(unquoted_arguments("commit-msg", "./check.sh \"$1\" \"{msg_file}\"").is_empty());
+        assert!(unquoted_arguments("commit-msg", "awk '{print $1}' \"$@\"").is_empty());
+        assert!(unquoted_arguments("commit-msg", "echo \\$1 # uses $1").is_empty());
+        assert!(unquoted_arguments("commit-msg", "echo $HOME ${PATH}").is_empty());
+
+        // Placeholders of other hooks are not expanded, so not reported
+        assert!(unquoted_arguments("pre-commit", "echo {msg_file}").is_empty());
+
+        // Suppressed with `# noqa`
+        assert!(unquoted_arguments("commit-msg", "./check.sh $1 # noqa").is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+//! Conversion of a `.pre-commit-config.yaml` of the pre-commit framework
+//! (<https://pre-commit.com>) into a hooksmith configuration, used by `hooksmith import`.
+//!
+//! The conversion is best-effort: each hook of a `local` repository becomes a command
+//! running its `entry` and `args`, followed by the files pre-commit would pass it unless
+//! `pass_filenames: false`: the staged files before a commit, the message file for the
+//! commit message hooks. What has no hooksmith equivalent, such as hooks of remote
+//! repositories, `types` filters or file names in other stages, is kept as commented-out
+//! lines so that it isn't lost.
+
+use crate::{
+    hook_docs::find_hook_doc,
+    placeholders::{hook_argument_names, STAGED_FILES},
+    utils::shell_quote,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// A hooksmith configuration converted from a pre-commit configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedConfig {
+    /// The configuration file content
+    pub content: String,
+    /// What couldn't be converted, commented out in `content`
+    pub unsupported: Vec<String>,
+}
+
+/// A `.pre-commit-config.yaml` file.
+#[derive(Debug, Deserialize)]
+struct PreCommitConfig {
+    #[serde(default)]
+    repos: Vec<Repository>,
+    /// Stages of the hooks that don't set `stages`
+    #[serde(default)]
+    default_stages: Option<Vec<String>>,
+    /// Every other top-level setting, none of which is supported
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// A repository of a pre-commit configuration, and the hooks used from it.
+#[derive(Debug, Deserialize)]
+struct Repository {
+    /// URL of the repository, or `local` / `meta`
+    repo: String,
+    #[serde(default)]
+    rev: Option<String>,
+    #[serde(default)]
+    hooks: Vec<PreCommitHook>,
+}
+
+/// A hook of a pre-commit configuration.
+#[derive(Debug, Deserialize)]
+struct PreCommitHook {
+    id: String,
+    /// Command to run, only given by local hooks
+    #[serde(default)]
+    entry: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    stages: Option<Vec<String>>,
+    /// Whether the file names are passed to `entry`
+    #[serde(default = "default_pass_filenames")]
+    pass_filenames: bool,
+    /// Every other setting of the hook
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// Settings of a hook that don't change what it runs, and are dropped silently.
+const IGNORED_HOOK_SETTINGS: [&str; 3] = ["name", "language", "description"];
+
+/// pre-commit passes the file names to hooks by default.
+const fn default_pass_filenames() -> bool {
+    true
+}
+
+/// Convert a pre-commit configuration into a hooksmith configuration.
+///
+/// # Arguments
+/// * `content` - Content of the `.pre-commit-config.yaml` file
+/// * `source` - Name of the file, mentioned in the header of the configuration
+///
+/// # Errors
+/// * If the content isn't a valid pre-commit configuration
+pub fn import_pre_commit_config(
+    content: &str,
+    source: &str,
+) -> Result<ImportedConfig, serde_yaml::Error> {
+    let config: PreCommitConfig = serde_yaml::from_str(content)?;
+    // Lines of the `commands` list of each hook
+    let mut hooks: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut header = vec![format!("# Imported from {source} by `hooksmith import`.")];
+    let mut unsupported = Vec::new();
+
+    for (key, value) in &config.other {
+        header.push(format!("# {key}: {}", inline_yaml(value)));
+        unsupported.push(format!("top-level `{key}`"));
+    }
+
+    for repository in &config.repos {
+        for hook in &repository.hooks {
+            let stages = hook
+                .stages
+                .as_ref()
+                .or(config.default_stages.as_ref())
+                .map_or_else(|| vec!["pre-commit".to_string()], Clone::clone);
+
+            for stage in stages {
+                let Some(hook_name) = git_hook_name(&stage) else {
+                    unsupported.push(format!("`{}`: stage `{stage}`", hook.id));
+                    header.push(format!("# {}: stage `{stage}` is not a Git hook", hook.id));
+                    continue;
+                };
+
+                let lines = hooks.entry(hook_name.to_string()).or_default();
+                import_hook(repository, hook, hook_name, lines, &mut unsupported);
+            }
+        }
+    }
+
+    // A hook run in several stages is reported once
+    let mut reported = Vec::new();
+    unsupported.retain(|item| {
+        let first = !reported.contains(item);
+        reported.push(item.clone());
+        first
+    });
+    if !unsupported.is_empty() {
+        header.push(
+            "# The commented-out lines are settings hooksmith doesn't support, review them."
+                .to_string(),
+        );
+    }
+
+    let mut content = header.join("\n") + "\n";
+    for (hook_name, lines) in &hooks {
+        content.push_str(&format!("\n{hook_name}:\n  commands:\n"));
+        for line in lines {
+            content.push_str(&format!("    {line}\n"));
+        }
+    }
+
+    Ok(ImportedConfig {
+        content,
+        unsupported,
+    })
+}
+
+/// Convert a hook into the lines of a hooksmith `commands` list: a named command when it
+/// has a command to run, followed by its unsupported settings, commented out.
+///
+/// # Arguments
+/// * `repository` - The repository of the hook
+/// * `hook` - The hook
+/// * `hook_name` - The Git hook the command runs in
+/// * `lines` - The lines of the `commands` list to add to
+/// * `unsupported` - What couldn't be converted, to add to
+fn import_hook(
+    repository: &Repository,
+    hook: &PreCommitHook,
+    hook_name: &str,
+    lines: &mut Vec<String>,
+    unsupported: &mut Vec<String>,
+) {
+    let Some(entry) = hook.entry.as_deref().filter(|_| repository.repo == "local") else {
+        let rev = repository
+            .rev
+            .as_deref()
+            .map_or_else(String::new, |rev| format!(" {rev}"));
+        lines.push(format!(
+            "# - {}: hook of {}{rev}, which defines its command",
+            hook.id, repository.repo
+        ));
+        unsupported.push(format!("`{}`: hook of a remote repository", hook.id));
+        return;
+    };
+
+    let mut command = entry.to_string();
+    for arg in &hook.args {
+        command.push(' ');
+        command.push_str(&shell_quote(arg));
+    }
+    let filenames = hook.pass_filenames.then(|| passed_filenames(hook_name));
+    if let Some(Some(filenames)) = &filenames {
+        command.push(' ');
+        command.push_str(filenames);
+    }
+
+    lines.push(format!(
+        "- {}: {}",
+        inline_yaml(&hook.id.clone().into()),
+        inline_yaml(&command.into())
+    ));
+
+    if filenames == Some(None) {
+        lines.push(format!(
+            "#   pass_filenames: true (there are no files to pass in `{hook_name}`)"
+        ));
+        unsupported.push(format!("`{}`: `pass_filenames` in `{hook_name}`", hook.id));
+    }
+
+    for (key, value) in &hook.other {
+        if IGNORED_HOOK_SETTINGS.contains(&key.as_str()) {
+            continue;
+        }
+
+        lines.push(format!("#   {key}: {}", inline_yaml(value)));
+        unsupported.push(format!("`{}`: `{key}`", hook.id));
+    }
+}
+
+/// The placeholder standing for the files pre-commit passes to a hook run in the given Git
+/// hook: the staged files before a commit, the message file (git's first argument) for the
+/// commit message hooks. `None` for the other hooks, which have no files to pass.
+///
+/// # Arguments
+/// * `hook_name` - The Git hook
+fn passed_filenames(hook_name: &str) -> Option<String> {
+    match hook_name {
+        "pre-commit" | "pre-merge-commit" => Some(format!("{{{STAGED_FILES}}}")),
+        "commit-msg" | "prepare-commit-msg" => {
+            Some(format!("\"{{{}}}\"", hook_argument_names(hook_name)[0]))
+        }
+        _ => None,
+    }
+}
+
+/// The Git hook a pre-commit stage runs in, accepting the legacy stage names (`commit`,
+/// `push`, `merge-commit`). `None` for stages that aren't Git hooks, such as `manual`.
+///
+/// # Arguments
+/// * `stage` - The stage
+fn git_hook_name(stage: &str) -> Option<&str> {
+    match stage {
+        "commit" => Some("pre-commit"),
+        "push" => Some("pre-push"),
+        "merge-commit" => Some("pre-merge-commit"),
+        _ => find_hook_doc(stage).map(|_| stage),
+    }
+}
+
+/// A YAML value on a single line, quoted when needed.
+///
+/// # Arguments
+/// * `value` - The value
+fn inline_yaml(value: &serde_yaml::Value) -> String {
+    let flow = match value {
+        serde_yaml::Value::Sequence(items) => {
+            let items = items.iter().map(inline_yaml).collect::<Vec<_>>();
+            return format!("[{}]", items.join(", "));
+        }
+        serde_yaml::Value::Mapping(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", inline_yaml(key), inline_yaml(value)))
+                .collect::<Vec<_>>();
+            return format!("{{{}}}", entries.join(", "));
+        }
+        value => serde_yaml::to_string(value).unwrap_or_default(),
+    };
+
+    flow.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_pre_commit_config() {
+        let config = r"
+fail_fast: true
+repos:
+  - repo: local
+    hooks:
+      - id: cargo-fmt
+        name: cargo fmt
+        entry: cargo fmt --all -- --check
+        language: system
+        pass_filenames: false
+      - id: clippy
+        entry: cargo clippy
+        args: [--fix, '-D warnings']
+        types: [rust]
+        stages: [push, manual]
+  - repo: https://github.com/pre-commit/pre-commit-hooks
+    rev: v4.5.0
+    hooks:
+      - id: trailing-whitespace
+";
+
+        let imported = import_pre_commit_config(config, ".pre-commit-config.yaml").unwrap();
+
+        assert_eq!(
+            imported.content,
+            "\
+# Imported from .pre-commit-config.yaml by `hooksmith import`.
+# fail_fast: true
+# clippy: stage `manual` is not a Git hook
+# The commented-out lines are settings hooksmith doesn't support, review them.
+
+pre-commit:
+  commands:
+    - cargo-fmt: cargo fmt --all -- --check
+    # - trailing-whitespace: hook of https://github.com/pre-commit/pre-commit-hooks v4.5.0, which defines its command
+
+pre-push:
+  commands:
+    - clippy: cargo clippy --fix '-D warnings'
+    #   pass_filenames: true (there are no files to pass in `pre-push`)
+    #   types: [rust]
+"
+        );
+        assert_eq!(
+            imported.unsupported,
+            vec![
+                "top-level `fail_fast`",
+                "`clippy`: `pass_filenames` in `pre-push`",
+                "`clippy`: `types`",
+                "`clippy`: stage `manual`",
+                "`trailing-whitespace`: hook of a remote repository",
+            ]
+        );
+
+        // The imported configuration is a valid hooksmith configuration
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("hooksmith.yaml");
+        std::fs::write(&config_path, &imported.content).unwrap();
+        assert!(crate::Hooksmith::new_from_config(&config_path, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_import_passed_filenames_by_stage() {
+        let config = r"
+repos:
+  - repo: local
+    hooks:
+      - id: check
+        entry: ./check.sh
+        stages: [pre-commit, merge-commit, commit-msg, prepare-commit-msg, pre-push, post-checkout]
+      - id: quiet
+        entry: ./quiet.sh
+        pass_filenames: false
+        stages: [pre-push]
+";
+
+        let imported = import_pre_commit_config(config, ".pre-commit-config.yaml").unwrap();
+        let commands = |hook_name: &str| {
+            let config: serde_yaml::Value = serde_yaml::from_str(&imported.content).unwrap();
+            config[hook_name]["commands"]
+                .as_sequence()
+                .unwrap()
+                .iter()
+                .map(|command| {
+                    let (_, command) = command.as_mapping().unwrap().iter().next().unwrap();
+                    command.as_str().unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(commands("pre-commit"), vec!["./check.sh {staged_files}"]);
+        assert_eq!(
+            commands("pre-merge-commit"),
+            vec!["./check.sh {staged_files}"]
+        );
+        assert_eq!(commands("commit-msg"), vec!["./check.sh \"{msg_file}\""]);
+        assert_eq!(
+            commands("prepare-commit-msg"),
+            vec!["./check.sh \"{msg_file}\""]
+        );
+        assert_eq!(commands("pre-push"), vec!["./check.sh", "./quiet.sh"]);
+        assert_eq!(commands("post-checkout"), vec!["./check.sh"]);
+
+        assert!(imported.content.contains(
+            "    - check: ./check.sh\n    #   pass_filenames: true (there are no files to pass in `post-checkout`)\n"
+        ));
+        assert_eq!(
+            imported.unsupported,
+            vec![
+                "`check`: `pass_filenames` in `pre-push`",
+                "`check`: `pass_filenames` in `post-checkout`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,650 @@
+//! Helpers for waiting on and terminating spawned commands, and for deferring signals.
+
+use crate::output;
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    process::{Child, ChildStdin, Command, ExitStatus},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::sync::{
+    atomic::{AtomicBool, AtomicI32, Ordering},
+    PoisonError,
+};
+
+/// How often a running child is polled while waiting with a deadline.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long a child is given to exit after being asked to stop before it is killed.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// The signal asking a child to stop when it runs out of time.
+#[cfg(unix)]
+const TERMINATE_SIGNAL: i32 = libc::SIGTERM;
+#[cfg(not(unix))]
+const TERMINATE_SIGNAL: i32 = 15;
+
+/// Spawn the command in its own process group (Unix only), so that the command and
+/// everything it started can be terminated together.
+///
+/// A process group outside of the foreground can't read from the terminal, so with
+/// `foreground` the group is given the terminal on standard input before the command
+/// starts. The returned guard gives it back to hooksmith once dropped.
+///
+/// # Arguments
+/// * `cmd` - The command to configure
+/// * `foreground` - Whether to give the terminal to the command, see `owns_terminal`
+pub fn isolate_process_group(cmd: &mut Command, foreground: bool) -> Option<TerminalGuard> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        cmd.process_group(0);
+        if !foreground {
+            return None;
+        }
+
+        // SAFETY: `getpid`, `tcsetpgrp` and `pthread_sigmask` are async-signal-safe. The
+        // child is already in its own group when the closure runs.
+        unsafe {
+            cmd.pre_exec(|| {
+                give_terminal(libc::getpid());
+                Ok(())
+            });
+        }
+
+        // SAFETY: `getpgrp` cannot fail
+        Some(TerminalGuard {
+            group: unsafe { libc::getpgrp() },
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (cmd, foreground);
+        None
+    }
+}
+
+/// Whether the standard input of hooksmith is a terminal with hooksmith in its foreground
+/// (Unix only), in which case a command inheriting it can read from it.
+pub fn owns_terminal() -> bool {
+    #[cfg(unix)]
+    // SAFETY: these calls have no memory safety requirements
+    unsafe {
+        libc::isatty(libc::STDIN_FILENO) == 1
+            && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp()
+    }
+
+    #[cfg(not(unix))]
+    false
+}
+
+/// Gives the terminal back to the process group of hooksmith when dropped, after it was
+/// handed to a command by `isolate_process_group`.
+pub struct TerminalGuard {
+    #[cfg(unix)]
+    group: libc::pid_t,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        give_terminal(self.group);
+    }
+}
+
+/// Make a process group the foreground group of the terminal on standard input. Outside of
+/// the foreground, this raises `SIGTTOU`, which would stop the caller: it is blocked
+/// meanwhile, so that the call goes through.
+#[cfg(unix)]
+fn give_terminal(group: libc::pid_t) {
+    // SAFETY: both signal sets are initialized before being used
+    unsafe {
+        let mut blocked: libc::sigset_t = std::mem::zeroed();
+        let mut previous: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut blocked);
+        libc::sigaddset(&mut blocked, libc::SIGTTOU);
+
+        libc::pthread_sigmask(libc::SIG_BLOCK, &blocked, &mut previous);
+        libc::tcsetpgrp(libc::STDIN_FILENO, group);
+        libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut());
+    }
+}
+
+/// Output captured from a command, truncated to a maximum size.
+#[derive(Debug, Default)]
+pub struct CapturedOutput {
+    /// The beginning of the standard output
+    pub stdout: Vec<u8>,
+    /// The beginning of the standard error
+    pub stderr: Vec<u8>,
+    /// Number of bytes dropped once the maximum size was reached
+    pub omitted: u64,
+}
+
+/// Log file receiving a copy of the output of commands, shared by the readers of a child.
+pub type OutputLog = Arc<Mutex<File>>;
+
+/// Background capture of the output of a running child.
+pub struct OutputCapture {
+    output: Arc<Mutex<CapturedOutput>>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl OutputCapture {
+    /// Wait until the child's output is fully read and return it.
+    pub fn finish(self) -> CapturedOutput {
+        for reader in self.readers {
+            let _ = reader.join();
+        }
+
+        Arc::try_unwrap(self.output)
+            .map(|output| output.into_inner().unwrap_or_default())
+            .unwrap_or_default()
+    }
+}
+
+/// Background copy of the output of a running child to the terminal and a log file.
+pub struct OutputTee {
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl OutputTee {
+    /// Wait until the child's output is fully read.
+    pub fn finish(self) {
+        for reader in self.readers {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Start reading the piped standard output and error of a child in the background, keeping
+/// at most `max_bytes` bytes of both streams together. The rest is read and dropped, so the
+/// child never blocks on a full pipe. All of the output is also written to `log`, if any.
+///
+/// # Arguments
+/// * `child` - The running child, spawned with piped stdout and stderr
+/// * `max_bytes` - The maximum number of bytes to keep
+/// * `log` - The file receiving a full copy of the output
+pub fn capture_output(
+    child: &mut Child,
+    max_bytes: usize,
+    log: Option<&OutputLog>,
+) -> OutputCapture {
+    let output = Arc::new(Mutex::new(CapturedOutput::default()));
+    let mut readers = Vec::new();
+
+    let streams = [
+        child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+    ];
+    for (stream, is_stderr) in streams.into_iter().zip([false, true]) {
+        let Some(stream) = stream else {
+            continue;
+        };
+        let output = Arc::clone(&output);
+        let log = log.cloned();
+
+        readers.push(spawn_reader(stream, move |chunk| {
+            write_to_log(log.as_ref(), chunk);
+
+            let Ok(mut output) = output.lock() else {
+                return;
+            };
+
+            let room = max_bytes.saturating_sub(output.stdout.len() + output.stderr.len());
+            let kept = chunk.len().min(room);
+            let buffer = if is_stderr {
+                &mut output.stderr
+            } else {
+                &mut output.stdout
+            };
+
+            buffer.extend_from_slice(&chunk[..kept]);
+            output.omitted += (chunk.len() - kept) as u64;
+        }));
+    }
+
+    OutputCapture { output, readers }
+}
+
+/// Start copying the piped standard output and error of a child to the output of hooksmith
+/// (see `output`) as they come, and to `log`. Chunks are written as soon as they are
+/// read, so the output is never held in memory.
+///
+/// # Arguments
+/// * `child` - The running child, spawned with piped stdout and stderr
+/// * `log` - The file receiving a copy of the output
+pub fn tee_output(child: &mut Child, log: &OutputLog) -> OutputTee {
+    let mut readers = Vec::new();
+
+    if let Some(stdout) = child.stdout.take() {
+        let log = Arc::clone(log);
+        readers.push(spawn_reader(stdout, move |chunk| {
+            output::write_stdout(chunk);
+            write_to_log(Some(&log), chunk);
+        }));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let log = Arc::clone(log);
+        readers.push(spawn_reader(stderr, move |chunk| {
+            output::write_stderr(chunk);
+            write_to_log(Some(&log), chunk);
+        }));
+    }
+
+    OutputTee { readers }
+}
+
+/// Write the input of a child to its standard input in the background, then close it. The
+/// child may exit, or be stopped, without reading it all: the rest is dropped. Writing in
+/// the background keeps a child that doesn't read its input, or fills its output first,
+/// from blocking hooksmith, whose readers and deadline are already running.
+///
+/// # Arguments
+/// * `stdin` - The piped standard input of the child
+/// * `input` - What to write
+pub fn write_input(mut stdin: ChildStdin, input: Vec<u8>) {
+    thread::spawn(move || {
+        // Commands that don't read their input close it early, which is fine
+        let _ = stdin.write_all(&input);
+    });
+}
+
+/// Append a chunk of output to the log file, if any. Write errors are ignored, so that a
+/// full disk doesn't stop the command.
+fn write_to_log(log: Option<&OutputLog>, chunk: &[u8]) {
+    if let Some(Ok(mut file)) = log.map(|log| log.lock()) {
+        let _ = file.write_all(chunk);
+    }
+}
+
+/// Read a stream until its end in the background, handing each chunk to `on_chunk`. The
+/// reader prints where the current thread does.
+fn spawn_reader<R: Read + Send + 'static>(
+    mut stream: R,
+    mut on_chunk: impl FnMut(&[u8]) + Send + 'static,
+) -> JoinHandle<()> {
+    let sink = output::current();
+
+    thread::spawn(move || {
+        let _sink = output::redirect(sink);
+        let mut chunk = [0; 8192];
+
+        while let Ok(read @ 1..) = stream.read(&mut chunk) {
+            on_chunk(&chunk[..read]);
+        }
+    })
+}
+
+/// Set the file mode creation mask of the spawned command (Unix only). The mask only
+/// applies to the child, so the umask of hooksmith itself is left untouched.
+///
+/// # Arguments
+/// * `cmd` - The command to configure
+/// * `mask` - The umask, e.g. `0o022`
+#[cfg(unix)]
+pub fn set_umask(cmd: &mut Command, mask: u32) {
+    use std::os::unix::process::CommandExt;
+
+    let mask = mask as libc::mode_t;
+
+    // SAFETY: `umask` is async-signal-safe and cannot fail
+    unsafe {
+        cmd.pre_exec(move || {
+            libc::umask(mask);
+            Ok(())
+        });
+    }
+}
+
+/// Wait for a child to exit, terminating it if it is still running at the deadline, or
+/// when a termination signal is received while a `SignalGuard` is alive.
+///
+/// # Arguments
+/// * `child` - The running child
+/// * `deadline` - When to give up on the child, if ever
+///
+/// # Errors
+/// * If the child status cannot be polled
+///
+/// # Returns
+/// * `Some(status)` if the child exited on its own, `None` if it was terminated
+pub fn wait_until(child: &mut Child, deadline: Option<Instant>) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        // The signal doesn't reach a child in its own process group
+        if let Some(signal) = pending_signal() {
+            terminate(child, signal)?;
+
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if now >= deadline => {
+                terminate(child, TERMINATE_SIGNAL)?;
+
+                return Ok(None);
+            }
+            Some(deadline) => thread::sleep(POLL_INTERVAL.min(deadline - now)),
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Terminate a child along with its process group, if it was spawned with
+/// `isolate_process_group`: the given signal first, then `SIGKILL` if it is still running
+/// after a short grace period. On other platforms the child is killed.
+///
+/// # Arguments
+/// * `child` - The running child
+/// * `signal` - The signal asking the child to stop, e.g. `SIGTERM`
+///
+/// # Errors
+/// * If the child status cannot be polled
+pub fn terminate(child: &mut Child, signal: i32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(child.id()) else {
+            child.kill()?;
+            child.wait()?;
+
+            return Ok(());
+        };
+
+        // A child sharing the process group of hooksmith is signaled alone
+        // SAFETY: `getpgid` has no memory safety requirements
+        let target = if unsafe { libc::getpgid(pid) } == pid {
+            -pid
+        } else {
+            pid
+        };
+        send_signal(target, signal);
+
+        let grace_deadline = Instant::now() + KILL_GRACE_PERIOD;
+        while Instant::now() < grace_deadline {
+            if child.try_wait()?.is_some() {
+                // The shell is gone, make sure nothing it started outlives it
+                if target < 0 {
+                    send_signal(target, libc::SIGKILL);
+                }
+
+                return Ok(());
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        send_signal(target, libc::SIGKILL);
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal;
+        child.kill()?;
+    }
+
+    child.wait()?;
+
+    Ok(())
+}
+
+/// Send a signal to a process, or to a process group given a negative pid, ignoring
+/// processes that no longer exist.
+#[cfg(unix)]
+fn send_signal(target: libc::pid_t, signal: libc::c_int) {
+    // SAFETY: `kill` has no memory safety requirements
+    unsafe {
+        libc::kill(target, signal);
+    }
+}
+
+/// Termination signals deferred by a `SignalGuard`.
+#[cfg(unix)]
+const DEFERRED_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+/// The last termination signal received while a `SignalGuard` was alive, 0 if none.
+#[cfg(unix)]
+static DEFERRED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Whether the deferred signal was handled with `SignalGuard::take_signal`.
+#[cfg(unix)]
+static SIGNAL_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// The guards alive, and the signal handlers that the first one replaced.
+#[cfg(unix)]
+static GUARDS: Mutex<(usize, Vec<(libc::c_int, libc::sigaction)>)> = Mutex::new((0, Vec::new()));
+
+/// Defers termination signals (Unix only) while alive, so that hooksmith can finish cleaning
+/// up before exiting. Commands waited on with `wait_until` are terminated when a signal is
+/// received, since those in their own process group don't receive it themselves.
+///
+/// Guards can be nested and shared by threads running hooks at the same time: the first
+/// guard installs the handlers, and the last one dropped reinstates the previous ones. A
+/// signal received meanwhile is then raised again, so hooksmith exits as it would have
+/// without the guards, unless the signal was handled with `take_signal`.
+pub struct SignalGuard {
+    _private: (),
+}
+
+impl SignalGuard {
+    /// Start deferring termination signals.
+    #[must_use]
+    pub fn install() -> Self {
+        #[cfg(unix)]
+        {
+            let mut guards = GUARDS.lock().unwrap_or_else(PoisonError::into_inner);
+            let (count, previous) = &mut *guards;
+
+            if *count == 0 {
+                *previous = DEFERRED_SIGNALS
+                    .iter()
+                    .filter_map(|&signal| {
+                        // SAFETY: the handler only stores to an atomic, which is
+                        // async-signal-safe, and both actions are fully initialized before
+                        // being used
+                        unsafe {
+                            let mut action: libc::sigaction = std::mem::zeroed();
+                            action.sa_sigaction =
+                                record_signal as extern "C" fn(libc::c_int) as usize;
+                            action.sa_flags = libc::SA_RESTART;
+                            libc::sigemptyset(&mut action.sa_mask);
+
+                            let mut previous: libc::sigaction = std::mem::zeroed();
+                            (libc::sigaction(signal, &action, &mut previous) == 0)
+                                .then_some((signal, previous))
+                        }
+                    })
+                    .collect();
+            }
+            *count += 1;
+        }
+
+        Self { _private: () }
+    }
+
+    /// The termination signal received while guards were alive, if any, handled by the
+    /// caller from now on: it isn't raised again once the last guard is dropped. It stays
+    /// pending until then, so that hooks running at the same time see it too.
+    #[must_use]
+    pub fn take_signal(&self) -> Option<i32> {
+        let signal = pending_signal();
+
+        #[cfg(unix)]
+        if signal.is_some() {
+            SIGNAL_TAKEN.store(true, Ordering::SeqCst);
+        }
+
+        signal
+    }
+}
+
+/// The termination signal received while a `SignalGuard` is alive, if any.
+pub fn pending_signal() -> Option<i32> {
+    #[cfg(unix)]
+    {
+        let signal = DEFERRED_SIGNAL.load(Ordering::SeqCst);
+
+        (signal != 0).then_some(signal)
+    }
+
+    #[cfg(not(unix))]
+    None
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let mut guards = GUARDS.lock().unwrap_or_else(PoisonError::into_inner);
+            let (count, previous) = &mut *guards;
+
+            *count -= 1;
+            if *count > 0 {
+                return;
+            }
+
+            for (signal, previous) in previous.drain(..) {
+                // SAFETY: `previous` was filled in by `sigaction` when the guard was installed
+                unsafe {
+                    libc::sigaction(signal, &previous, std::ptr::null_mut());
+                }
+            }
+
+            let signal = DEFERRED_SIGNAL.swap(0, Ordering::SeqCst);
+            let taken = SIGNAL_TAKEN.swap(false, Ordering::SeqCst);
+            if signal != 0 && !taken {
+                // SAFETY: `raise` has no memory safety requirements
+                unsafe {
+                    libc::raise(signal);
+                }
+            }
+        }
+    }
+}
+
+/// Signal handler recording the signal for `SignalGuard`.
+#[cfg(unix)]
+extern "C" fn record_signal(signal: libc::c_int) {
+    DEFERRED_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+
+    #[test]
+    fn test_capture_output_truncates() {
+        let mut child = Command::new("sh")
+            .args(["-c", "printf 0123456789; printf abc >&2"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let capture = capture_output(&mut child, 8, None);
+        child.wait().unwrap();
+        let output = capture.finish();
+
+        assert_eq!(output.stdout.len() + output.stderr.len(), 8);
+        assert_eq!(output.omitted, 5);
+    }
+
+    #[test]
+    fn test_tee_output_writes_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output.log");
+        let log = Arc::new(Mutex::new(File::create(&path).unwrap()));
+
+        let mut child = Command::new("sh")
+            .args(["-c", "seq 1 20000; echo done >&2"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let tee = tee_output(&mut child, &log);
+        child.wait().unwrap();
+        tee.finish();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\n20000\n"));
+        assert!(written.contains("done\n"));
+        assert_eq!(written.lines().count(), 20001);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nested_signal_guards() {
+        let handler = || {
+            // SAFETY: the action is only filled in by `sigaction`
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                libc::sigaction(libc::SIGTERM, std::ptr::null(), &mut action);
+                action.sa_sigaction
+            }
+        };
+        let recording = record_signal as extern "C" fn(libc::c_int) as usize;
+
+        let outer = SignalGuard::install();
+        let inner = SignalGuard::install();
+        assert_eq!(handler(), recording);
+
+        // The outer guard still defers signals
+        drop(inner);
+        assert_eq!(handler(), recording);
+
+        drop(outer);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate_stops_process_group() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "sleep 30 & echo $!; wait"])
+            .stdout(Stdio::piped());
+        let _ = isolate_process_group(&mut cmd, false);
+        let mut child = cmd.spawn().unwrap();
+
+        let mut line = String::new();
+        io::BufRead::read_line(
+            &mut io::BufReader::new(child.stdout.take().unwrap()),
+            &mut line,
+        )
+        .unwrap();
+        let sleep: libc::pid_t = line.trim().parse().unwrap();
+
+        terminate(&mut child, libc::SIGINT).unwrap();
+
+        // The orphaned `sleep` may linger as a zombie until it is reaped
+        let is_running = || {
+            // SAFETY: signal 0 only checks whether the process exists
+            let exists = unsafe { libc::kill(sleep, 0) } == 0;
+            let is_zombie = std::fs::read_to_string(format!("/proc/{sleep}/stat"))
+                .is_ok_and(|stat| stat.contains(") Z "));
+
+            exists && !is_zombie
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_running() && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!is_running());
+    }
+}
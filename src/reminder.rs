@@ -0,0 +1,103 @@
+//! Weekly reminder to update hooksmith and review the hooks, enabled with `reminder: true`
+//! in the global configuration.
+//!
+//! Nothing leaves the machine: the time of the last reminder is stored in the user's
+//! configuration directory (e.g. `~/.config/hooksmith/last-reminder` on Linux).
+
+use crate::global_config::{global_config_path, read_global_config};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the file holding the time of the last reminder, next to the global configuration.
+const STATE_FILE: &str = "last-reminder";
+
+/// Environment variable turning the reminder off, whatever the configuration says.
+pub const NO_REMINDER_ENV: &str = "HOOKSMITH_NO_REMINDER";
+
+/// Time between two reminders.
+const REMINDER_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Print the reminder on stderr if the user enabled it and the last one is a week old.
+///
+/// The first run only records the time, so the first reminder comes a week after it was
+/// enabled. Failures to read or write the state are ignored: the reminder is never worth
+/// failing a command for.
+pub fn remind_if_due() {
+    if std::env::var_os(NO_REMINDER_ENV).is_some() {
+        return;
+    }
+
+    let Some(config_path) = global_config_path() else {
+        return;
+    };
+    let enabled = read_global_config(&config_path)
+        .ok()
+        .flatten()
+        .and_then(|config| config.reminder)
+        .unwrap_or(false);
+
+    if enabled && is_due(&config_path.with_file_name(STATE_FILE), SystemTime::now()) {
+        eprintln!(
+            "💡 Weekly reminder: check for a newer hooksmith than v{} and review your hooks with `hooksmith config --explain` (`reminder: false` in {} turns this off)",
+            env!("CARGO_PKG_VERSION"),
+            config_path.display()
+        );
+    }
+}
+
+/// Whether a reminder is due, recording `now` as the time of the last one when it is, or
+/// when none was recorded yet.
+///
+/// # Arguments
+/// * `state_path` - File holding the time of the last reminder, in seconds since the epoch
+/// * `now` - The current time
+fn is_due(state_path: &Path, now: SystemTime) -> bool {
+    let now_secs = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let last = fs::read_to_string(state_path)
+        .ok()
+        .and_then(|content| content.trim().parse::<u64>().ok());
+
+    let due = last.is_some_and(|last| now_secs.saturating_sub(last) >= REMINDER_INTERVAL.as_secs());
+    if last.is_none() || due {
+        if let Some(parent) = state_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(state_path, format!("{now_secs}\n"));
+    }
+
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hooksmith").join(STATE_FILE);
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        // The first run only records the time
+        assert!(!is_due(&path, start));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1000000\n");
+
+        assert!(!is_due(&path, start + Duration::from_secs(60 * 60)));
+        assert!(is_due(&path, start + REMINDER_INTERVAL));
+        // Once per week
+        assert!(!is_due(
+            &path,
+            start + REMINDER_INTERVAL + Duration::from_secs(60)
+        ));
+        assert!(is_due(&path, start + REMINDER_INTERVAL * 2));
+
+        // A corrupted file starts over
+        fs::write(&path, "yesterday").unwrap();
+        assert!(!is_due(&path, start));
+    }
+}
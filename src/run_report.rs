@@ -0,0 +1,370 @@
+//! Machine-readable results of a run of hooks, for CI dashboards and other tools
+//! (`hooksmith run --format json`).
+//!
+//! A [`RunRecorder`] collects the results through its observer while the hooks run, and
+//! [`RunRecorder::report`] turns them into a serializable [`RunReport`].
+
+use crate::{
+    error::{HookExecutionError, HooksmithError},
+    hooksmith::{CommandTiming, HookCommand, HookTiming, Skipped},
+    observer::HookObserver,
+    process::CapturedOutput,
+};
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Results of a run of hooks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RunReport {
+    /// Whether every hook succeeded
+    pub success: bool,
+    /// How long the hooks ran, in milliseconds
+    pub duration_ms: u64,
+    /// The hooks, in the order they ran
+    pub hooks: Vec<HookReport>,
+}
+
+/// Results of a hook.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HookReport {
+    pub name: String,
+    /// Whether the hook succeeded: its commands all succeeded, or only failed with
+    /// `allow_failure`
+    pub success: bool,
+    /// How long the hook ran, in milliseconds
+    pub duration_ms: u64,
+    /// The commands that ran, in the order they finished
+    pub commands: Vec<CommandReport>,
+    /// What was intentionally not run
+    pub skipped: Vec<Skipped>,
+}
+
+/// Result of a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CommandReport {
+    /// Name of the command, `null` when it has none
+    pub name: Option<String>,
+    /// Command line run by the shell
+    pub command: String,
+    pub success: bool,
+    /// Status code the command exited with, `null` when no process ran or it was stopped
+    pub exit_code: Option<i32>,
+    /// How long the command ran, in milliseconds
+    pub duration_ms: u64,
+    /// Why the command failed, `null` when it succeeded
+    pub error: Option<String>,
+    /// Captured standard output, `null` when the output wasn't captured
+    pub stdout: Option<String>,
+    /// Captured standard error, `null` when the output wasn't captured
+    pub stderr: Option<String>,
+}
+
+/// Collects the results of a run through the observer returned by
+/// [`RunRecorder::observer`].
+#[derive(Debug, Clone, Default)]
+pub struct RunRecorder {
+    recording: Arc<Mutex<Recording>>,
+}
+
+/// Results collected so far.
+#[derive(Debug, Default)]
+struct Recording {
+    hooks: Vec<HookReport>,
+    /// Start time of each hook in `hooks`
+    started: Vec<Instant>,
+    /// Keys of the commands that started but haven't finished yet, with their start time.
+    /// Keys include the hook name, as hooks run with `--jobs` can run the same command.
+    running: Vec<(String, Instant)>,
+    /// Output of the commands that exited but haven't finished yet, by key
+    outputs: Vec<(String, CapturedOutput)>,
+}
+
+/// Observer recording results into a `RunRecorder`, and forwarding every notification
+/// to another observer, if any.
+struct RecordingObserver {
+    recorder: RunRecorder,
+    inner: Option<Box<dyn HookObserver>>,
+}
+
+impl RunRecorder {
+    /// Create a recorder with no results.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an observer recording results into this recorder.
+    ///
+    /// # Arguments
+    /// * `inner` - An observer to forward every notification to, e.g. `StdoutObserver`,
+    ///   `None` to print neither progress nor captured output
+    #[must_use]
+    pub fn observer(&self, inner: Option<Box<dyn HookObserver>>) -> Box<dyn HookObserver> {
+        Box::new(RecordingObserver {
+            recorder: self.clone(),
+            inner,
+        })
+    }
+
+    /// The results collected so far. Hooks that haven't finished successfully are reported
+    /// as failed, having run up to now.
+    #[must_use]
+    pub fn report(&self) -> RunReport {
+        let recording = self.recording();
+        let mut hooks = recording.hooks.clone();
+
+        for (hook, started) in hooks.iter_mut().zip(&recording.started) {
+            if !hook.success {
+                hook.duration_ms = millis(started.elapsed());
+            }
+        }
+
+        RunReport {
+            success: hooks.iter().all(|hook| hook.success),
+            duration_ms: hooks.iter().map(|hook| hook.duration_ms).sum(),
+            hooks,
+        }
+    }
+
+    /// Lock the collected results, even if a panicking thread poisoned the lock.
+    fn recording(&self) -> MutexGuard<'_, Recording> {
+        self.recording
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl RunReport {
+    /// Render the report as pretty-printed JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        // Serializing plain strings, numbers and booleans can't fail
+        serde_json::to_string_pretty(self).unwrap_or_default() + "\n"
+    }
+}
+
+impl Recording {
+    /// The last hook started with the given name, if any.
+    fn hook(&mut self, hook_name: &str) -> Option<&mut HookReport> {
+        self.hooks
+            .iter_mut()
+            .rev()
+            .find(|hook| hook.name == hook_name)
+    }
+}
+
+impl HookObserver for RecordingObserver {
+    fn on_hook_start(&self, hook_name: &str, index: usize, total: usize) {
+        let mut recording = self.recorder.recording();
+        recording.hooks.push(HookReport {
+            name: hook_name.to_string(),
+            ..HookReport::default()
+        });
+        recording.started.push(Instant::now());
+        drop(recording);
+
+        if let Some(inner) = &self.inner {
+            inner.on_hook_start(hook_name, index, total);
+        }
+    }
+
+    fn on_command_start(&self, hook_name: &str, command: &HookCommand, index: usize, total: usize) {
+        self.recorder
+            .recording()
+            .running
+            .push((running_key(hook_name, command), Instant::now()));
+
+        if let Some(inner) = &self.inner {
+            inner.on_command_start(hook_name, command, index, total);
+        }
+    }
+
+    fn on_command_output(&self, hook_name: &str, command: &HookCommand, output: &CapturedOutput) {
+        let mut recording = self.recorder.recording();
+        let key = running_key(hook_name, command);
+        // A retried command keeps the output of its last attempt
+        recording.outputs.retain(|(running, _)| *running != key);
+        recording.outputs.push((
+            key,
+            CapturedOutput {
+                stdout: output.stdout.clone(),
+                stderr: output.stderr.clone(),
+                omitted: output.omitted,
+            },
+        ));
+        drop(recording);
+
+        if let Some(inner) = &self.inner {
+            inner.on_command_output(hook_name, command, output);
+        }
+    }
+
+    fn on_command_finish(
+        &self,
+        hook_name: &str,
+        command: &HookCommand,
+        result: Result<&CommandTiming, &HooksmithError>,
+    ) {
+        let mut recording = self.recorder.recording();
+        let key = running_key(hook_name, command);
+        let started = recording
+            .running
+            .iter()
+            .position(|(running, _)| *running == key)
+            .map(|idx| recording.running.remove(idx).1);
+        let output = recording
+            .outputs
+            .iter()
+            .position(|(running, _)| *running == key)
+            .map(|idx| recording.outputs.remove(idx).1);
+
+        let mut report = CommandReport {
+            name: command.name.clone(),
+            command: command.command.clone(),
+            stdout: output
+                .as_ref()
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned()),
+            stderr: output
+                .as_ref()
+                .map(|output| String::from_utf8_lossy(&output.stderr).into_owned()),
+            ..CommandReport::default()
+        };
+        match result {
+            Ok(timing) => {
+                report.success = true;
+                report.exit_code = timing.exit_code;
+                report.duration_ms = millis(timing.duration);
+            }
+            Err(e) => {
+                if let HooksmithError::HookExecution(HookExecutionError::CommandFailed(code)) = e {
+                    report.exit_code = Some(*code);
+                }
+                report.duration_ms = started.map_or(0, |started| millis(started.elapsed()));
+                report.error = Some(e.to_string());
+            }
+        }
+
+        if let Some(hook) = recording.hook(hook_name) {
+            hook.commands.push(report);
+        }
+        drop(recording);
+
+        if let Some(inner) = &self.inner {
+            inner.on_command_finish(hook_name, command, result);
+        }
+    }
+
+    fn on_hook_finish(&self, report: &HookTiming) {
+        if let Some(hook) = self.recorder.recording().hook(&report.hook_name) {
+            hook.success = true;
+            hook.duration_ms = millis(report.total_duration);
+            hook.skipped.clone_from(&report.skipped);
+        }
+
+        if let Some(inner) = &self.inner {
+            inner.on_hook_finish(report);
+        }
+    }
+}
+
+/// A duration in whole milliseconds.
+///
+/// # Arguments
+/// * `duration` - The duration
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Key of a running command, telling apart the same command run by different hooks.
+///
+/// # Arguments
+/// * `hook_name` - Name of the hook running the command
+/// * `command` - The command
+fn running_key(hook_name: &str, command: &HookCommand) -> String {
+    format!("{hook_name}:{}", command.key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_report() {
+        let recorder = RunRecorder::new();
+        let observer = recorder.observer(None);
+
+        let passing = HookCommand::new_named("fmt".to_string(), "cargo fmt".to_string());
+        let failing = HookCommand::new_unnamed("cargo test".to_string());
+        let error = HooksmithError::HookExecution(HookExecutionError::CommandFailed(101));
+
+        observer.on_hook_start("pre-commit", 0, 2);
+        observer.on_command_start("pre-commit", &passing, 0, 1);
+        observer.on_command_output(
+            "pre-commit",
+            &passing,
+            &CapturedOutput {
+                stdout: b"formatted\n".to_vec(),
+                stderr: b"warning: \xff\n".to_vec(),
+                omitted: 0,
+            },
+        );
+        observer.on_command_finish(
+            "pre-commit",
+            &passing,
+            Ok(&CommandTiming {
+                command: passing.command.clone(),
+                name: passing.name.clone(),
+                key: passing.key(),
+                duration: Duration::from_millis(1500),
+                failed: false,
+                exit_code: Some(0),
+            }),
+        );
+        observer.on_hook_finish(&HookTiming {
+            hook_name: "pre-commit".to_string(),
+            commands: Vec::new(),
+            total_duration: Duration::from_millis(1600),
+            skipped: vec![Skipped {
+                target: "`paths: web/`".to_string(),
+                reason: "no changed file matches".to_string(),
+            }],
+        });
+        observer.on_hook_start("pre-push", 1, 2);
+        observer.on_command_start("pre-push", &failing, 0, 1);
+        observer.on_command_finish("pre-push", &failing, Err(&error));
+
+        let json: serde_json::Value = serde_json::from_str(&recorder.report().to_json()).unwrap();
+
+        assert_eq!(json["success"], false);
+        assert_eq!(json["hooks"][0]["name"], "pre-commit");
+        assert_eq!(json["hooks"][0]["success"], true);
+        assert_eq!(json["hooks"][0]["duration_ms"], 1600);
+        assert_eq!(
+            json["hooks"][0]["skipped"][0]["reason"],
+            "no changed file matches"
+        );
+
+        let command = &json["hooks"][0]["commands"][0];
+        assert_eq!(command["name"], "fmt");
+        assert_eq!(command["command"], "cargo fmt");
+        assert_eq!(command["exit_code"], 0);
+        assert_eq!(command["duration_ms"], 1500);
+        assert_eq!(command["stdout"], "formatted\n");
+        assert_eq!(command["stderr"], "warning: \u{fffd}\n");
+        assert!(command["error"].is_null());
+
+        let command = &json["hooks"][1]["commands"][0];
+        assert_eq!(json["hooks"][1]["success"], false);
+        assert!(command["name"].is_null());
+        assert_eq!(command["success"], false);
+        assert_eq!(command["exit_code"], 101);
+        assert!(command["stdout"].is_null());
+        assert!(command["error"]
+            .as_str()
+            .unwrap()
+            .contains("status code: 101"));
+    }
+}
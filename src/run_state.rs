@@ -0,0 +1,95 @@
+//! Markers recording the commit of each hook's last successful run, used by `--since-last-run`.
+//!
+//! Markers live under `.git/hooksmith-state/<hook>` and hold a single commit SHA.
+
+use crate::{error::Result, git_related::get_git_path_in};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Name of the state directory inside the Git directory.
+const STATE_DIR: &str = "hooksmith-state";
+
+/// Path of the marker file of a hook.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+/// * `hook_name` - The name of the hook
+///
+/// # Errors
+/// * If the directory is not inside a Git repository
+fn marker_path(directory: &Path, hook_name: &str) -> Result<PathBuf> {
+    Ok(get_git_path_in(directory, STATE_DIR)?.join(hook_name))
+}
+
+/// Read the commit recorded by the last successful run of a hook.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+/// * `hook_name` - The name of the hook
+///
+/// # Returns
+/// * `Some(sha)` if a marker exists, `None` otherwise
+pub fn read_marker(directory: &Path, hook_name: &str) -> Option<String> {
+    let content = fs::read_to_string(marker_path(directory, hook_name).ok()?).ok()?;
+    let sha = content.trim();
+
+    (!sha.is_empty()).then(|| sha.to_string())
+}
+
+/// Record the commit of a successful run of a hook, replacing any previous marker.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+/// * `hook_name` - The name of the hook
+/// * `sha` - The commit `HEAD` pointed to when the hook succeeded
+///
+/// # Errors
+/// * If the directory is not inside a Git repository
+/// * If the marker cannot be written
+pub fn write_marker(directory: &Path, hook_name: &str, sha: &str) -> Result<()> {
+    let path = marker_path(directory, hook_name)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, format!("{sha}\n"))?;
+
+    Ok(())
+}
+
+/// Remove every recorded marker, so that the next runs process all files.
+///
+/// # Arguments
+/// * `directory` - A directory inside the repository
+///
+/// # Errors
+/// * If the directory is not inside a Git repository
+/// * If the state directory cannot be read or a marker cannot be removed
+///
+/// # Returns
+/// * The names of the hooks whose marker was removed, sorted
+pub fn clear_markers(directory: &Path) -> Result<Vec<String>> {
+    let state_dir = get_git_path_in(directory, STATE_DIR)?;
+
+    let entries = match fs::read_dir(&state_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut cleared = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+
+        fs::remove_file(entry.path())?;
+        cleared.push(entry.file_name().to_string_lossy().to_string());
+    }
+
+    fs::remove_dir(&state_dir)?;
+    cleared.sort();
+
+    Ok(cleared)
+}
@@ -0,0 +1,316 @@
+//! Temporarily removing unstaged changes, so that hooks only see what is being committed.
+//!
+//! Unstaged changes to tracked files are set aside with `git stash push --keep-index`, and
+//! brought back with `git stash pop` once the hook finishes. Staged changes and untracked
+//! files are left untouched.
+//!
+//! The changes are held by a [`StashGuard`], which restores them when dropped, so that they
+//! are never lost when a hook fails, times out, panics, or is interrupted. Changes that
+//! can't be restored stay in the stash.
+
+use crate::{
+    error::{GitError, Result},
+    git_related::{pop_stashed_changes, stash_unstaged_changes},
+    process::SignalGuard,
+    utils::Reporter,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Unstaged changes removed from the working tree, restored when the guard is dropped.
+///
+/// Prefer calling [`StashGuard::restore`], which reports failures; dropping the guard
+/// restores the changes too, and is what happens on early returns and panics. While the
+/// guard is alive, termination signals are deferred until the changes are restored.
+pub struct StashGuard<'a> {
+    /// The stashed changes, `None` once restored
+    stashed: Option<StashedChanges>,
+    /// Reports a failure to restore the changes when the guard is dropped
+    reporter: &'a Reporter,
+    /// Defers termination signals; dropped after the changes are restored
+    _signals: SignalGuard,
+}
+
+/// Unstaged changes held in the stash.
+struct StashedChanges {
+    /// Root of the working tree the changes were stashed from
+    toplevel: PathBuf,
+    /// The stash commit holding the changes
+    commit: String,
+}
+
+/// Stash the unstaged changes to tracked files, removing them from the working tree.
+///
+/// # Arguments
+/// * `directory` - A directory inside the working tree
+/// * `reporter` - Reports a failure to restore the changes when the guard is dropped
+///
+/// # Errors
+/// * If the directory is not inside a Git repository
+/// * If the changes cannot be stashed
+///
+/// # Returns
+/// * `None` if there are no unstaged changes
+pub fn stash_unstaged<'a>(
+    directory: &Path,
+    reporter: &'a Reporter,
+) -> Result<Option<StashGuard<'a>>> {
+    let toplevel = toplevel(directory)?;
+
+    // Defer signals before touching the working tree, so that an interrupt can't leave
+    // the changes removed but not restored
+    let signals = SignalGuard::install();
+
+    let Some(commit) = stash_unstaged_changes(&toplevel)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(StashGuard {
+        stashed: Some(StashedChanges { toplevel, commit }),
+        reporter,
+        _signals: signals,
+    }))
+}
+
+impl StashGuard<'_> {
+    /// The stash commit holding the changes, which can be restored manually with
+    /// `git stash apply`.
+    pub fn commit(&self) -> &str {
+        self.stashed.as_ref().map_or("", |stashed| &stashed.commit)
+    }
+
+    /// Bring the stashed changes back into the working tree, see `pop_stashed_changes`.
+    ///
+    /// # Errors
+    /// * `GitError::StashRestore` if the changes conflict with modifications made by the
+    ///   hook (e.g. a formatter); they are then kept in the stash
+    pub fn restore(mut self) -> Result<()> {
+        match self.stashed.take() {
+            Some(stashed) => stashed.restore(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for StashGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(stashed) = self.stashed.take() {
+            if let Err(e) = stashed.restore() {
+                self.reporter.error(
+                    "Unstaged changes not restored",
+                    &e.to_string(),
+                    "Resolve the conflicts, then bring the changes back with `git stash pop`.",
+                );
+            }
+        }
+    }
+}
+
+impl StashedChanges {
+    /// See [`StashGuard::restore`].
+    fn restore(self) -> Result<()> {
+        Ok(pop_stashed_changes(&self.toplevel, &self.commit)?)
+    }
+}
+
+/// Root of the working tree of the given directory.
+fn toplevel(directory: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(directory)
+        .output()
+        .map_err(GitError::Command)?;
+
+    if !output.status.success() {
+        return Err(GitError::NotGitRepo.into());
+    }
+
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_related::leftover_stashes;
+    use std::fs;
+
+    /// Run a shell script in the given directory, failing the test if it fails.
+    fn sh(dir: &Path, script: &str) {
+        let status = Command::new("sh")
+            .args(["-c", script])
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "setup failed: {script}");
+    }
+
+    /// Run a git command in the given directory and return its trimmed standard output.
+    fn git_stdout(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Create a repository with a committed file, staged and unstaged changes to it,
+    /// another committed file with unstaged changes, and an untracked file.
+    fn repository_with_changes() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let sh = |script: &str| sh(dir.path(), script);
+
+        sh("git init -q && git config user.email t@t && git config user.name t");
+        sh("printf 'one\\n' > file.txt && printf 'a\\n' > other.txt && git add . && git commit -qm init");
+        sh("printf 'one\\nstaged\\n' > file.txt && git add file.txt");
+        sh("printf 'one\\nstaged\\nunstaged\\n' > file.txt && printf 'a\\nb\\n' > other.txt");
+        sh("printf 'new\\n' > untracked.txt");
+
+        dir
+    }
+
+    /// Snapshot of the working tree, the index and the status of the repository.
+    fn snapshot(dir: &Path) -> (String, String, String, String) {
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+
+        (
+            read("file.txt"),
+            read("untracked.txt"),
+            git_stdout(dir, &["diff", "--cached"]),
+            git_stdout(dir, &["status", "--porcelain"]),
+        )
+    }
+
+    /// Stash the unstaged changes, run a script standing in for a hook command, and
+    /// return early with `?` if it fails.
+    fn run_hook(dir: &Path, script: &str) -> Result<()> {
+        let reporter = Reporter::new();
+        let _guard = stash_unstaged(dir, &reporter)?;
+
+        let status = Command::new("sh")
+            .args(["-c", script])
+            .current_dir(dir)
+            .status()?;
+        if !status.success() {
+            return Err(crate::error::HookExecutionError::CommandFailed(1).into());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_hides_unstaged_changes() {
+        let dir = repository_with_changes();
+        let reporter = Reporter::new();
+        // An entry of the user's own, which must be left alone
+        sh(dir.path(), "git stash push -q -m mine -- other.txt");
+        let before = snapshot(dir.path());
+
+        let guard = stash_unstaged(dir.path(), &reporter).unwrap().unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "one\nstaged\n"
+        );
+        assert!(dir.path().join("untracked.txt").exists());
+        assert_eq!(leftover_stashes(dir.path()), vec!["stash@{0}"]);
+
+        guard.restore().unwrap();
+        assert_eq!(snapshot(dir.path()), before);
+        assert!(leftover_stashes(dir.path()).is_empty());
+        assert_eq!(
+            git_stdout(dir.path(), &["stash", "list", "--format=%gs"]),
+            "On master: mine"
+        );
+
+        // Nothing left to stash once the unstaged changes are staged
+        sh(dir.path(), "git add file.txt");
+        assert!(stash_unstaged(dir.path(), &reporter).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failing_hook_restores_changes() {
+        let dir = repository_with_changes();
+        let before = snapshot(dir.path());
+
+        let result = run_hook(dir.path(), "exit 1");
+
+        assert!(result.is_err());
+        assert_eq!(snapshot(dir.path()), before);
+        assert!(leftover_stashes(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_hook_changes_are_kept() {
+        let dir = repository_with_changes();
+
+        // The hook reformats a staged file, while another file only has unstaged changes
+        sh(dir.path(), "git add other.txt && git reset -q file.txt");
+        run_hook(dir.path(), "printf 'A\\nB\\n' > other.txt").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("other.txt")).unwrap(),
+            "A\nB\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "one\nstaged\nunstaged\n"
+        );
+        assert!(leftover_stashes(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_hook_changes_are_reported() {
+        let dir = repository_with_changes();
+        let reporter = Reporter::new();
+
+        // The hook rewrites the file in a way that conflicts with the unstaged changes
+        let guard = stash_unstaged(dir.path(), &reporter).unwrap().unwrap();
+        sh(dir.path(), "printf 'formatted\\n' > file.txt");
+
+        assert!(matches!(
+            guard.restore(),
+            Err(crate::HooksmithError::Git(GitError::StashRestore(_)))
+        ));
+        // Neither the changes of the hook nor the stashed ones are lost
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.txt")).unwrap(),
+            "formatted\n"
+        );
+        assert_eq!(leftover_stashes(dir.path()), vec!["stash@{0}"]);
+    }
+
+    #[test]
+    fn test_dropped_guard_reports_failure() {
+        let dir = repository_with_changes();
+        let reporter = Reporter::new();
+
+        let guard = stash_unstaged(dir.path(), &reporter).unwrap().unwrap();
+        sh(dir.path(), "printf 'formatted\\n' > file.txt");
+        drop(guard);
+
+        assert_eq!(reporter.error_count(), 1);
+        assert_eq!(leftover_stashes(dir.path()), vec!["stash@{0}"]);
+    }
+
+    #[test]
+    fn test_panicking_hook_restores_changes() {
+        let dir = repository_with_changes();
+        let reporter = Reporter::new();
+        let before = snapshot(dir.path());
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = stash_unstaged(dir.path(), &reporter).unwrap().unwrap();
+            panic!("hook crashed");
+        });
+
+        assert!(result.is_err());
+        assert_eq!(snapshot(dir.path()), before);
+        assert!(leftover_stashes(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+//! Commands delegating to targets of an existing task runner, written `just: test` or
+//! `make: lint` in the configuration.
+
+use std::{env, path::Path};
+
+/// A task runner whose targets can be used as commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskRunner {
+    /// `just`, running recipes of a `justfile`
+    Just,
+    /// `make`, running targets of a `Makefile`
+    Make,
+}
+
+impl TaskRunner {
+    /// Every supported task runner.
+    pub const ALL: [Self; 2] = [Self::Just, Self::Make];
+
+    /// Name of the program, also used as the key of the command in the configuration.
+    #[must_use]
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Just => "just",
+            Self::Make => "make",
+        }
+    }
+
+    /// Find the task runner whose configuration key is the given one.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the command, e.g. `just`
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|runner| runner.program() == key)
+    }
+
+    /// Build the shell command running the given target.
+    ///
+    /// # Arguments
+    /// * `target` - The target, optionally followed by arguments (e.g. `test --release`)
+    #[must_use]
+    pub fn command(self, target: &str) -> String {
+        format!("{} {target}", self.program())
+    }
+
+    /// Whether the program can be found in a directory of `PATH`.
+    #[must_use]
+    pub fn is_installed(self) -> bool {
+        env::var_os("PATH").is_some_and(|path| env::split_paths(&path).any(|dir| self.is_in(&dir)))
+    }
+
+    /// Whether the program is an executable of the given directory.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory
+    #[must_use]
+    pub fn is_in(self, dir: &Path) -> bool {
+        is_executable(&dir.join(self.program()))
+    }
+}
+
+/// Whether the file exists and can be executed.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether the file exists and can be executed.
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+    path.with_extension("exe").is_file() || path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_runner_keys() {
+        assert_eq!(TaskRunner::from_key("just"), Some(TaskRunner::Just));
+        assert_eq!(TaskRunner::from_key("make"), Some(TaskRunner::Make));
+        assert_eq!(TaskRunner::from_key("clippy"), None);
+        assert_eq!(TaskRunner::Make.command("lint FIX=1"), "make lint FIX=1");
+    }
+}
@@ -0,0 +1,116 @@
+//! Built-in starter configurations, used by `hooksmith init --template <name>`.
+
+/// A starter configuration.
+pub struct Template {
+    /// The name given to `--template`
+    pub name: &'static str,
+    /// What the template sets up
+    pub description: &'static str,
+    /// The configuration file content
+    pub content: &'static str,
+}
+
+/// Every built-in template.
+pub const TEMPLATES: [Template; 5] = [
+    Template {
+        name: "rust",
+        description: "Format and lint before committing, test before pushing",
+        content: "\
+pre-commit:
+  commands:
+    - cargo fmt --all -- --check
+    - cargo clippy --workspace --all-targets -- -D warnings
+
+pre-push:
+  commands:
+    - cargo test --workspace
+",
+    },
+    Template {
+        name: "rust-strict",
+        description: "Like `rust`, with pedantic lints, documentation and release build checks",
+        content: "\
+pre-commit:
+  commands:
+    - cargo fmt --all -- --check
+    - clippy: cargo clippy --workspace --all-targets --all-features -- -D warnings -W clippy::pedantic
+
+pre-push:
+  commands:
+    - cargo test --workspace --all-features
+    - docs: cargo doc --workspace --no-deps
+    - cargo build --release
+",
+    },
+    Template {
+        name: "node",
+        description: "Lint and format with npm scripts before committing, test before pushing",
+        content: "\
+pre-commit:
+  commands:
+    - npm run lint
+    - npx prettier --check .
+
+pre-push:
+  commands:
+    - npm test
+",
+    },
+    Template {
+        name: "python",
+        description: "Lint and format with ruff before committing, test with pytest before pushing",
+        content: "\
+pre-commit:
+  commands:
+    - ruff check .
+    - ruff format --check .
+
+pre-push:
+  commands:
+    - pytest
+",
+    },
+    Template {
+        name: "generic",
+        description: "Placeholder pre-commit, commit-msg and pre-push hooks to fill in",
+        content: "\
+pre-commit:
+  commands:
+    - echo \"Running pre-commit checks...\"
+
+commit-msg:
+  commands:
+    # The commit message file is available as {msg_file}
+    - test -s {msg_file}
+
+pre-push:
+  commands:
+    - echo \"Running pre-push checks...\"
+",
+    },
+];
+
+/// Find a built-in template by name.
+///
+/// # Arguments
+/// * `name` - The name of the template
+pub fn find_template(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|template| template.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_are_valid_yaml() {
+        for template in &TEMPLATES {
+            let parsed: serde_yaml::Mapping = serde_yaml::from_str(template.content)
+                .unwrap_or_else(|e| panic!("template '{}' is invalid: {e}", template.name));
+            assert!(!parsed.is_empty());
+        }
+
+        assert!(find_template("rust").is_some());
+        assert!(find_template("cobol").is_none());
+    }
+}
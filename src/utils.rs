@@ -1,10 +1,21 @@
-use std::fmt::Display;
+use crate::output;
+use console::{style, Color};
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 /// Trait for message types.
 trait MessageType {
     /// The emoji prefix for each message type (e.g., "🚨 ERROR")
     const PREFIX: &'static str;
 
+    /// The plain prefix used when emojis are disabled (e.g., "ERROR")
+    const ASCII_PREFIX: &'static str;
+
+    /// The color of the prefix when colored output is enabled
+    const COLOR: Color;
+
     /// Whether to output to stderr (true) or stdout (false)
     const TO_STDERR: bool = false;
 }
@@ -13,127 +24,433 @@ trait MessageType {
 struct Error;
 struct Warning;
 struct Success;
+struct Note;
 
 // Implement the MessageType trait for each type
 impl MessageType for Error {
     const PREFIX: &'static str = "🚨 ERROR";
+    const ASCII_PREFIX: &'static str = "ERROR";
+    const COLOR: Color = Color::Red;
     const TO_STDERR: bool = true;
 }
 
 impl MessageType for Warning {
     const PREFIX: &'static str = "⚠️ WARNING";
+    const ASCII_PREFIX: &'static str = "WARNING";
+    const COLOR: Color = Color::Yellow;
 }
 
 impl MessageType for Success {
     const PREFIX: &'static str = "✅ SUCCESS";
+    const ASCII_PREFIX: &'static str = "SUCCESS";
+    const COLOR: Color = Color::Green;
 }
 
-/// Formats a message without suggestion.
+impl MessageType for Note {
+    const PREFIX: &'static str = "ℹ️ NOTE";
+    const ASCII_PREFIX: &'static str = "NOTE";
+    const COLOR: Color = Color::Cyan;
+}
+
+/// Formats a message with the given prefix.
 ///
 /// # Arguments
+/// * `prefix` - The prefix of the message (e.g., "🚨 ERROR").
 /// * `title` - The title of the message.
 /// * `details` - The details of the message.
 ///
 /// # Returns
 /// * String - The formatted message.
-fn format_message<T: MessageType>(title: &str, details: &str) -> String {
-    format!("{}: {title}\n\n{details}", T::PREFIX)
+fn format_message_with_prefix(prefix: &str, title: &str, details: &str) -> String {
+    format!("{prefix}: {title}\n\n{details}")
 }
 
-/// Formats a message with suggestion.
+/// Prints user-facing messages in a consistent format and keeps track of how many
+/// warnings and errors were reported, so that the final status can account for them.
 ///
-/// # Arguments
-/// * `title` - The title of the message.
-/// * `details` - The details of the message.
-/// * `suggestion` - The suggestion for the message.
-///
-/// # Returns
-/// * String - The formatted message.
-fn format_message_with_suggestion<T: MessageType>(
-    title: &str,
-    details: &str,
-    suggestion: &str,
-) -> String {
-    format!("{}\n\n{suggestion}", format_message::<T>(title, details))
+/// By default messages are printed with emoji prefixes and no colors.
+#[derive(Debug, Default)]
+pub struct Reporter {
+    /// Hide warnings, notes and success messages (warnings are still counted)
+    quiet: bool,
+    /// Use plain text prefixes instead of emojis
+    ascii: bool,
+    /// Color the message prefixes
+    color: bool,
+    /// Print errors on a single line, without their title and suggestion
+    concise: bool,
+    warnings: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl Reporter {
+    /// Create a reporter with the default output format.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hide warnings, notes and success messages. Errors are always printed.
+    ///
+    /// # Arguments
+    /// * `quiet` - Whether to hide non-error messages
+    #[must_use]
+    pub const fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Use plain text prefixes (`ERROR`, `WARNING`, `SUCCESS`) instead of emojis.
+    ///
+    /// # Arguments
+    /// * `ascii` - Whether to avoid emojis
+    #[must_use]
+    pub const fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Color the message prefixes.
+    ///
+    /// # Arguments
+    /// * `color` - Whether to use colors
+    #[must_use]
+    pub const fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Print errors on a single line (`ERROR: <details>`), which is easier to scan in logs.
+    ///
+    /// # Arguments
+    /// * `concise` - Whether to print errors on a single line
+    #[must_use]
+    pub const fn with_concise(mut self, concise: bool) -> Self {
+        self.concise = concise;
+        self
+    }
+
+    /// Prints an error message with a consistent format for user-friendly display.
+    /// In concise mode, only the details are printed, on a single line.
+    ///
+    /// # Arguments
+    /// - `title`: The title of the error message.
+    /// - `details`: The details of the error message.
+    /// - `suggestion`: The suggestion for resolving the error.
+    pub fn error(&self, title: &str, details: &str, suggestion: &str) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+
+        print_message::<Error>(&self.format_error(title, details, suggestion));
+    }
+
+    /// Prints a warning message with a consistent format for user-friendly display.
+    ///
+    /// # Arguments
+    /// - `title`: The title of the warning message.
+    /// - `details`: The details of the warning message.
+    pub fn warning(&self, title: &str, details: &str) {
+        self.warnings.fetch_add(1, Ordering::Relaxed);
+
+        if !self.quiet {
+            print_message::<Warning>(&self.format::<Warning>(title, details));
+        }
+    }
+
+    /// Prints a success message with a consistent format for user-friendly display.
+    ///
+    /// # Arguments
+    /// - `title`: The title of the success message.
+    /// - `details`: The details of the success message.
+    pub fn success(&self, title: &str, details: &str) {
+        if !self.quiet {
+            print_message::<Success>(&self.format::<Success>(title, details));
+        }
+    }
+
+    /// Prints a note: something worth knowing that isn't a problem, so unlike warnings,
+    /// notes aren't counted.
+    ///
+    /// # Arguments
+    /// - `title`: The title of the note.
+    /// - `details`: The details of the note.
+    pub fn note(&self, title: &str, details: &str) {
+        if !self.quiet {
+            print_message::<Note>(&self.format::<Note>(title, details));
+        }
+    }
+
+    /// Whether plain text is used instead of emojis.
+    pub const fn is_ascii(&self) -> bool {
+        self.ascii
+    }
+
+    /// Whether output is colored.
+    pub const fn is_color(&self) -> bool {
+        self.color
+    }
+
+    /// Returns the number of warnings reported so far.
+    pub fn warning_count(&self) -> usize {
+        self.warnings.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of errors reported so far.
+    pub fn error_count(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Formats an error message according to the reporter options: on a single line in
+    /// concise mode, with its title and suggestion otherwise.
+    fn format_error(&self, title: &str, details: &str, suggestion: &str) -> String {
+        if self.concise {
+            return format!("{}: {}", self.prefix::<Error>(), single_line(details));
+        }
+
+        format!("{}\n\n{suggestion}", self.format::<Error>(title, details))
+    }
+
+    /// Formats a message according to the reporter options.
+    fn format<T: MessageType>(&self, title: &str, details: &str) -> String {
+        format_message_with_prefix(&self.prefix::<T>(), title, details)
+    }
+
+    /// The prefix of a message type according to the reporter options.
+    fn prefix<T: MessageType>(&self) -> String {
+        let prefix = if self.ascii {
+            T::ASCII_PREFIX
+        } else {
+            T::PREFIX
+        };
+
+        if self.color {
+            let prefix = style(prefix).fg(T::COLOR).bold();
+            let prefix = if T::TO_STDERR {
+                prefix.for_stderr()
+            } else {
+                prefix.for_stdout()
+            };
+
+            prefix.to_string()
+        } else {
+            prefix.to_string()
+        }
+    }
 }
 
-/// Prints a message without suggestion.
+/// Joins the lines of a message into a single line: lines are separated with `; `, list
+/// items (see `format_list`) are inlined after the line introducing them.
 ///
 /// # Arguments
-/// * `title` - The title of the message.
-/// * `details` - The details of the message.
+/// * `text` - The message to join.
+fn single_line(text: &str) -> String {
+    let mut line = String::new();
+
+    for part in text.lines().map(str::trim).filter(|part| !part.is_empty()) {
+        if !line.is_empty() {
+            line.push_str(if line.ends_with(':') { " " } else { "; " });
+        }
+        line.push_str(part.strip_prefix("- ").unwrap_or(part));
+    }
+
+    line
+}
+
+/// Prints an already formatted message to the output of its type.
 ///
-/// # Returns
-/// * String - The formatted message.
-fn print_message<T: MessageType>(title: &str, details: &str) {
-    let message = format_message::<T>(title, details);
+/// # Arguments
+/// * `message` - The formatted message.
+fn print_message<T: MessageType>(message: &str) {
+    let line = format!("{message}\n");
 
     if T::TO_STDERR {
-        eprintln!("{message}");
+        output::write_stderr(line.as_bytes());
     } else {
-        println!("{message}");
+        output::write_stdout(line.as_bytes());
     }
 }
 
-/// Prints a message with suggestion.
+/// Formats a list of items with a consistent format for user-friendly display.
 ///
 /// # Arguments
-/// * `title` - The title of the message.
-/// * `details` - The details of the message.
-/// * `suggestion` - The suggestion for resolving the message.
+/// - `items`: The list of items to format.
 ///
 /// # Returns
-/// * String - The formatted message.
-fn print_message_with_suggestion<T: MessageType>(title: &str, details: &str, suggestion: &str) {
-    let message = format_message_with_suggestion::<T>(title, details, suggestion);
-    if T::TO_STDERR {
-        eprintln!("{message}");
-    } else {
-        println!("{message}");
-    }
+/// * String - A formatted string representation of the list.
+pub fn format_list<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| format!("  - {item}"))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-/// Prints an error message with a consistent format for user-friendly display.
+/// Checks whether a string contains glob metacharacters (`*`, `?` or `[`).
 ///
 /// # Arguments
-/// - `title`: The title of the error message.
-/// - `details`: The details of the error message.
-/// - `suggestion`: The suggestion for resolving the error.
-pub fn print_error(title: &str, details: &str, suggestion: &str) {
-    print_message_with_suggestion::<Error>(title, details, suggestion);
+/// - `pattern`: The string to check.
+pub fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
 }
 
-/// Prints a warning message with a consistent format for user-friendly display.
+/// Quote a string for POSIX shells, leaving it as is when it needs no quoting.
 ///
 /// # Arguments
-/// - `title`: The title of the warning message.
-/// - `details`: The details of the warning message.
-pub fn print_warning(title: &str, details: &str) {
-    print_message::<Warning>(title, details);
+/// - `text`: The string to quote.
+pub fn shell_quote(text: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+
+    if !text.is_empty() && text.chars().all(is_safe) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
 }
 
-/// Prints a success message with a consistent format for user-friendly display.
+/// Finds the quote a shell command leaves open, if any. Escaped quotes, quotes inside the
+/// other kind of quotes and quotes in a trailing `#` comment don't count.
 ///
 /// # Arguments
-/// - `title`: The title of the success message.
-/// - `details`: The details of the success message.
-pub fn print_success(title: &str, details: &str) {
-    print_message::<Success>(title, details);
+/// - `command`: The shell command.
+///
+/// # Returns
+/// * `Some('\'')` or `Some('"')` for the quote left open, `None` if every quote is closed.
+pub fn unbalanced_quote(command: &str) -> Option<char> {
+    let mut open = None;
+    let mut escaped = false;
+    let mut word_start = true;
+
+    for c in command.chars() {
+        match open {
+            Some('\'') => {
+                if c == '\'' {
+                    open = None;
+                }
+            }
+            Some(_) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    open = None;
+                }
+            }
+            None => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '#' && word_start {
+                    return None;
+                } else if c == '\'' || c == '"' {
+                    open = Some(c);
+                }
+            }
+        }
+
+        word_start = c.is_whitespace() || ";|&(".contains(c);
+    }
+
+    open
 }
 
-/// Formats a list of items with a consistent format for user-friendly display.
+/// Finds the program a shell command runs first, after its `NAME=value` assignments.
+/// Words the shell would expand or that aren't plain names, such as `$TOOL`, `"quoted"`
+/// or `(subshell`, give `None`.
 ///
 /// # Arguments
-/// - `items`: The list of items to format.
+/// - `command`: The shell command.
+pub fn command_program(command: &str) -> Option<&str> {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./+".contains(c);
+
+    command
+        .split_whitespace()
+        .find(|word| {
+            !word
+                .split_once('=')
+                .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(is_plain))
+        })
+        .filter(|word| word.chars().all(is_plain))
+}
+
+/// Matches a string against a simple glob pattern.
+///
+/// Supports `*` (any sequence of characters), `?` (any single character) and
+/// character classes such as `[abc]`, `[a-z]` or `[!abc]`.
+///
+/// # Arguments
+/// - `pattern`: The glob pattern.
+/// - `text`: The string to match.
 ///
 /// # Returns
-/// * String - A formatted string representation of the list.
-pub fn format_list<T: Display>(items: &[T]) -> String {
-    items
+/// * bool - Whether the whole string matches the pattern.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text it was matched against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match_class(&pattern[p..], text[t]),
+            Some(c) if *c == text[t] => Some(1),
+            _ => None,
+        };
+
+        if let Some(pattern_len) = step {
+            p += pattern_len;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` absorb one more character and retry
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Matches a character against the character class at the start of `pattern`.
+///
+/// # Returns
+/// * `Some(len)` with the length of the class in the pattern if the character matches, `None` otherwise.
+fn match_class(pattern: &[char], c: char) -> Option<usize> {
+    let negated = matches!(pattern.get(1), Some('!' | '^'));
+    let start = if negated { 2 } else { 1 };
+
+    // A `]` right after the opening bracket is a literal
+    let close = pattern
         .iter()
-        .map(|item| format!("  - {item}"))
-        .collect::<Vec<_>>()
-        .join("\n")
+        .skip(start + 1)
+        .position(|ch| *ch == ']')
+        .map(|idx| idx + start + 1)?;
+
+    let class = &pattern[start..close];
+    let mut matched = false;
+    let mut idx = 0;
+
+    while idx < class.len() {
+        if idx + 2 < class.len() && class[idx + 1] == '-' {
+            matched |= (class[idx]..=class[idx + 2]).contains(&c);
+            idx += 3;
+        } else {
+            matched |= class[idx] == c;
+            idx += 1;
+        }
+    }
+
+    (matched != negated).then_some(close + 1)
 }
 
 #[cfg(test)]
@@ -141,55 +458,81 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_format_message() {
+    fn test_reporter_format() {
         let title = "Test Title";
         let details = "Test Details";
+        let reporter = Reporter::new();
+
+        let error_msg = reporter.format::<Error>(title, details);
+        assert_eq!(error_msg, "🚨 ERROR: Test Title\n\nTest Details");
 
-        // Test error message
-        let error_msg = format_message::<Error>(title, details);
-        assert!(error_msg.contains("🚨 ERROR"));
-        assert!(error_msg.contains(title));
-        assert!(error_msg.contains(details));
+        let warning_msg = reporter.format::<Warning>(title, details);
+        assert_eq!(warning_msg, "⚠️ WARNING: Test Title\n\nTest Details");
 
-        // Test warning message
-        let warning_msg = format_message::<Warning>(title, details);
-        assert!(warning_msg.contains("⚠️ WARNING"));
-        assert!(warning_msg.contains(title));
-        assert!(warning_msg.contains(details));
+        let success_msg = reporter.format::<Success>(title, details);
+        assert_eq!(success_msg, "✅ SUCCESS: Test Title\n\nTest Details");
 
-        // Test success message
-        let success_msg = format_message::<Success>(title, details);
-        assert!(success_msg.contains("✅ SUCCESS"));
-        assert!(success_msg.contains(title));
-        assert!(success_msg.contains(details));
+        let note_msg = reporter.format::<Note>(title, details);
+        assert_eq!(note_msg, "ℹ️ NOTE: Test Title\n\nTest Details");
+
+        let reporter = Reporter::new().with_ascii(true);
+        assert_eq!(
+            reporter.format::<Error>(title, details),
+            "ERROR: Test Title\n\nTest Details"
+        );
     }
 
     #[test]
-    fn test_format_message_with_suggestion() {
-        let title = "Test Title";
-        let details = "Test Details";
-        let suggestion = "Test Suggestion";
-
-        // Test error message with suggestion
-        let error_msg = format_message_with_suggestion::<Error>(title, details, suggestion);
-        assert!(error_msg.contains("🚨 ERROR"));
-        assert!(error_msg.contains(title));
-        assert!(error_msg.contains(details));
-        assert!(error_msg.contains(suggestion));
-
-        // Test warning message with suggestion
-        let warning_msg = format_message_with_suggestion::<Warning>(title, details, suggestion);
-        assert!(warning_msg.contains("⚠️ WARNING"));
-        assert!(warning_msg.contains(title));
-        assert!(warning_msg.contains(details));
-        assert!(warning_msg.contains(suggestion));
-
-        // Test success message with suggestion
-        let success_msg = format_message_with_suggestion::<Success>(title, details, suggestion);
-        assert!(success_msg.contains("✅ SUCCESS"));
-        assert!(success_msg.contains(title));
-        assert!(success_msg.contains(details));
-        assert!(success_msg.contains(suggestion));
+    fn test_reporter_format_error() {
+        let reporter = Reporter::new();
+        assert_eq!(
+            reporter.format_error("Test Title", "Test Details", "Test Suggestion"),
+            "🚨 ERROR: Test Title\n\nTest Details\n\nTest Suggestion"
+        );
+
+        // Concise errors drop the title and the suggestion
+        let reporter = Reporter::new().with_concise(true).with_ascii(true);
+        assert_eq!(
+            reporter.format_error("Test Title", "Test\nDetails", "Test Suggestion"),
+            "ERROR: Test; Details"
+        );
+    }
+
+    #[test]
+    fn test_reporter_counts_messages() {
+        // Quiet reporters still count the warnings they hide
+        let reporter = Reporter::new().with_quiet(true);
+
+        reporter.warning("First", "details");
+        reporter.warning("Second", "details");
+        reporter.success("Done", "details");
+        reporter.note("Noted", "details");
+        reporter.error("Failed", "details", "suggestion");
+
+        assert_eq!(reporter.warning_count(), 2);
+        assert_eq!(reporter.error_count(), 1);
+
+        let reporter = Reporter::new().with_concise(true);
+        reporter.warning("Only", "details");
+        reporter.error("Failed", "details", "suggestion");
+
+        assert_eq!(reporter.warning_count(), 1);
+        assert_eq!(reporter.error_count(), 1);
+    }
+
+    #[test]
+    fn test_single_line() {
+        assert_eq!(
+            single_line("Hook 'pre-commit' command `cargo test` failed with status code 101"),
+            "Hook 'pre-commit' command `cargo test` failed with status code 101"
+        );
+        assert_eq!(
+            single_line(&format!(
+                "2 problem(s) found:\n{}\nSee above.\n",
+                format_list(&["first", "second"])
+            )),
+            "2 problem(s) found: first; second; See above."
+        );
     }
 
     #[test]
@@ -205,4 +548,53 @@ mod tests {
         assert!(formatted.contains("  - item1"));
         assert!(formatted.contains("  - item2"));
     }
+
+    #[test]
+    fn test_unbalanced_quote() {
+        assert_eq!(unbalanced_quote("echo 'done'"), None);
+        assert_eq!(unbalanced_quote("echo \"it's done\""), None);
+        assert_eq!(unbalanced_quote("echo it\\'s done"), None);
+        assert_eq!(unbalanced_quote("echo \"say \\\"hi\\\"\""), None);
+        assert_eq!(unbalanced_quote("cargo test # don't skip"), None);
+        assert_eq!(unbalanced_quote("echo a#'b'"), None);
+        assert_eq!(unbalanced_quote("echo 'done"), Some('\''));
+        assert_eq!(unbalanced_quote("echo \"done"), Some('"'));
+        assert_eq!(unbalanced_quote("echo a#'b"), Some('\''));
+    }
+
+    #[test]
+    fn test_command_program() {
+        assert_eq!(command_program("cargo fmt --check"), Some("cargo"));
+        assert_eq!(
+            command_program("  RUST_LOG=debug cargo test"),
+            Some("cargo")
+        );
+        assert_eq!(
+            command_program("./scripts/lint.sh"),
+            Some("./scripts/lint.sh")
+        );
+        assert_eq!(command_program("$TOOL run"), None);
+        assert_eq!(command_program("\"my tool\" run"), None);
+        assert_eq!(command_program("(cd web && npm test)"), None);
+        assert_eq!(command_program("A=1"), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("pre-*", "pre-commit"));
+        assert!(glob_match("pre-*", "pre-push"));
+        assert!(!glob_match("pre-*", "post-commit"));
+        assert!(glob_match("*-commit", "pre-merge-commit"));
+        assert!(glob_match("p?st-*", "post-merge"));
+        assert!(glob_match("*commit*", "prepare-commit-msg"));
+        assert!(glob_match("pre-[cp]*", "pre-push"));
+        assert!(!glob_match("pre-[!cp]*", "pre-push"));
+        assert!(glob_match("pre-[a-d]*", "pre-commit"));
+        assert!(glob_match("pre-commit", "pre-commit"));
+        assert!(!glob_match("pre-commit", "pre-commit-msg"));
+        assert!(glob_match("*", ""));
+
+        assert!(is_glob_pattern("pre-*"));
+        assert!(!is_glob_pattern("pre-commit"));
+    }
 }
@@ -0,0 +1,474 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// Create a Git repository holding the given configuration.
+fn repository(config: &str) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let status = Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::write(dir.path().join("hooksmith.yaml"), config).unwrap();
+
+    dir
+}
+
+/// Run git in the given directory, as a user that can commit.
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Run hooksmith in the given directory, ignoring the user's global configuration.
+fn hooksmith(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hooksmith"))
+        .arg("--no-global-config")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_warnings_as_errors() {
+    let dir = repository("pre-commit:\n  commands:\n    - echo lint\n    - echo lint\n");
+
+    let output = hooksmith(dir.path(), &["validate"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Duplicate commands"));
+
+    // The flag is accepted after the subcommand too
+    let output = hooksmith(dir.path(), &["validate", "--warnings-as-errors"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 warning(s) emitted"));
+
+    let dir = repository("pre-commit:\n  commands:\n    - echo lint\n");
+    let output = hooksmith(dir.path(), &["--warnings-as-errors", "validate"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_quiet_hides_warnings() {
+    let dir = repository("pre-commit:\n  commands:\n    - echo lint\n    - echo lint\n");
+
+    let output = hooksmith(dir.path(), &["validate", "--quiet"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Duplicate commands"));
+
+    // Hidden warnings still count
+    let output = hooksmith(dir.path(), &["validate", "-q", "--warnings-as-errors"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_since_last_run_skips_unchanged_hooks() {
+    let log = tempfile::NamedTempFile::new().unwrap();
+    let dir = repository(&format!(
+        "pre-commit:\n  commands:\n    - echo ran >> '{}'\n",
+        log.path().display()
+    ));
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    };
+    git(&["add", "."]);
+    git(&["commit", "-qm", "initial"]);
+
+    let run = || {
+        let output = hooksmith(
+            dir.path(),
+            &["run", "pre-commit", "--since-last-run", "--show-skipped"],
+        );
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let runs = || std::fs::read_to_string(log.path()).unwrap().lines().count();
+
+    run();
+    assert_eq!(runs(), 1);
+
+    let stdout = run();
+    assert!(stdout.contains("no files changed since its last successful run"));
+    assert_eq!(runs(), 1);
+
+    std::fs::write(dir.path().join("README.md"), "# Readme\n").unwrap();
+    git(&["add", "README.md"]);
+    run();
+    assert_eq!(runs(), 2);
+
+    assert!(hooksmith(dir.path(), &["cache", "clear"]).status.success());
+    run();
+    assert_eq!(runs(), 3);
+}
+
+#[test]
+fn test_disable_and_enable_hooks() {
+    let log = tempfile::NamedTempFile::new().unwrap();
+    let dir = repository(&format!(
+        "pre-commit:\n  commands:\n    - echo ran >> '{}'\n",
+        log.path().display()
+    ));
+    let runs = || std::fs::read_to_string(log.path()).unwrap().lines().count();
+    let stdout = |output: Output| {
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let disabled = stdout(hooksmith(dir.path(), &["disable"]));
+    assert!(disabled.contains("Hooks disabled"));
+    assert!(dir.path().join(".git/hooksmith-disabled").exists());
+    assert!(stdout(hooksmith(dir.path(), &["disable"])).contains("already disabled"));
+
+    let run = stdout(hooksmith(dir.path(), &["run", "pre-commit"]));
+    assert!(run.contains("Hooks are disabled, `pre-commit` was not run"));
+    assert_eq!(runs(), 0);
+
+    assert!(stdout(hooksmith(dir.path(), &["enable"])).contains("Hooks enabled"));
+    assert!(!dir.path().join(".git/hooksmith-disabled").exists());
+
+    let run = stdout(hooksmith(dir.path(), &["run", "pre-commit"]));
+    assert!(!run.contains("Hooks are disabled"));
+    assert_eq!(runs(), 1);
+    assert!(stdout(hooksmith(dir.path(), &["enable"])).contains("not disabled"));
+}
+
+#[test]
+fn test_config_in_cargo_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap()
+        .success());
+    let manifest = dir.path().join("Cargo.toml");
+
+    std::fs::write(
+        &manifest,
+        "[package]\nname = \"demo\"\npublished = 1979-05-27\n\n[package.metadata.hooksmith.pre-commit]\ncommands = [\"echo hi\"]\n",
+    )
+    .unwrap();
+    assert!(hooksmith(dir.path(), &["validate"]).status.success());
+
+    // Broken manifests are reported, rather than looking for `hooksmith.yaml` instead
+    for content in [
+        "[package\n",
+        "[package]\nname = \"demo\"\n[package.metadata.hooksmith]\npre-commit = 3\n",
+    ] {
+        std::fs::write(&manifest, content).unwrap();
+        let output = hooksmith(dir.path(), &["validate"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success());
+        assert!(!stderr.contains("not found"), "{stderr}");
+    }
+}
+
+#[test]
+fn test_allowed_failure_is_a_note() {
+    let dir = repository(
+        "pre-commit:\n  commands:\n    - run: exit 3\n      name: spelling\n      allow_failure: true\n    - echo next\n",
+    );
+
+    let output = hooksmith(dir.path(), &["run", "pre-commit", "--warnings-as-errors"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}{stderr}");
+    assert_eq!(
+        stdout.matches("NOTE: Failure allowed").count(),
+        1,
+        "{stdout}"
+    );
+    assert!(stdout.contains("command `spelling` failed with status code 3"));
+    assert!(stdout.contains("next"));
+    assert!(
+        !stdout.contains("WARNING") && !stderr.contains("ERROR"),
+        "{stdout}{stderr}"
+    );
+}
+
+#[test]
+fn test_json_report_owns_stdout() {
+    let dir = repository(
+        "pre-commit:\n  commands:\n    - echo lint\npre-push:\n  commands:\n    - echo push\n",
+    );
+    std::fs::write(dir.path().join("notes.txt"), "draft\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "initial"]);
+    std::fs::write(dir.path().join("notes.txt"), "unstaged\n").unwrap();
+
+    let output = hooksmith(
+        dir.path(),
+        &[
+            "run",
+            "pre-commit",
+            "pre-push",
+            "--skip",
+            "pre-push",
+            "--stash",
+            "--jobs",
+            "2",
+            "--format",
+            "json",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}{stderr}");
+
+    let report: serde_json::Value = serde_json::from_str(&stdout).expect(&stdout);
+    assert_eq!(report["success"], true);
+    assert_eq!(report["hooks"][0]["commands"][0]["stdout"], "lint\n");
+
+    // Warnings, skips and stash notices still reach the user
+    assert!(stderr.contains("Hooks run one at a time"), "{stderr}");
+    assert!(stderr.contains("`pre-push` skipped"), "{stderr}");
+    assert!(stderr.contains("Stashed unstaged changes"), "{stderr}");
+}
+
+#[test]
+fn test_junit_report_owns_stdout() {
+    let dir = repository("pre-commit:\n  commands:\n    - echo lint\n");
+
+    let output = hooksmith(dir.path(), &["run", "pre-commit", "--format", "junit"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}{stderr}");
+
+    assert!(stdout.starts_with("<?xml"), "{stdout}");
+    assert!(stdout.ends_with("</testsuites>\n"), "{stdout}");
+    assert!(
+        stdout.contains("<testcase name=\"echo lint\" classname=\"pre-commit\""),
+        "{stdout}"
+    );
+
+    // The progress and the output of the command still reach the user
+    assert!(stderr.contains("running `pre-commit`"), "{stderr}");
+    assert!(stderr.contains("lint\n"), "{stderr}");
+}
+
+#[test]
+fn test_pre_push_commands_read_the_ref_updates() {
+    let dir = repository(
+        "pre-push:\n  commands:\n    - run: cat > first.txt\n      stdin: true\n    - run: cat > second.txt\n      stdin: true\n",
+    );
+    let refs = format!(
+        "refs/heads/main {} refs/heads/main {}\n",
+        "a".repeat(40),
+        "0".repeat(40)
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hooksmith"))
+        .args(["--no-global-config", "run", "pre-push"])
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(refs.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Each command reading stdin gets the lines Git wrote
+    for file in ["first.txt", "second.txt"] {
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(file)).unwrap(),
+            refs
+        );
+    }
+}
+
+#[test]
+fn test_concurrent_hooks_group_their_output() {
+    let dir = repository(
+        "pre-commit:\n  commands:\n    - echo commit-1; sleep 0.5\n    - echo commit-2\npre-push:\n  commands:\n    - sleep 0.2; echo push-1\n    - echo push-2\n",
+    );
+
+    let output = hooksmith(
+        dir.path(),
+        &["run", "pre-commit", "pre-push", "--jobs", "2"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+
+    // Each hook is printed as a whole, from its progress to the output of its last command
+    let lines: Vec<&str> = stdout.lines().collect();
+    for (hook, marker, other) in [
+        ("pre-commit", "commit-", "push"),
+        ("pre-push", "push-", "commit"),
+    ] {
+        let start = lines
+            .iter()
+            .position(|line| line.contains(&format!("running `{hook}`")))
+            .expect(&stdout);
+        let end = lines
+            .iter()
+            .position(|line| *line == format!("{marker}2"))
+            .expect(&stdout);
+        assert!(start < end, "{stdout}");
+        assert!(
+            lines[start..end].iter().all(|line| !line.contains(other)),
+            "{stdout}"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupt_stops_concurrent_hooks() {
+    use std::time::{Duration, Instant};
+
+    let dir = repository(
+        "pre-commit:\n  commands:\n    - touch commit-started; sleep 30\npre-push:\n  commands:\n    - touch push-started; sleep 30\n",
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hooksmith"))
+        .args([
+            "--no-global-config",
+            "run",
+            "pre-commit",
+            "pre-push",
+            "--jobs",
+            "2",
+        ])
+        .current_dir(dir.path())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !(dir.path().join("commit-started").exists() && dir.path().join("push-started").exists())
+    {
+        assert!(Instant::now() < deadline, "the hooks didn't start");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    // Only hooksmith gets the signal: it stops both hooks itself
+    // SAFETY: `kill` has no memory safety requirements
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGINT);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("the hooks kept running after the interrupt");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(status.code(), Some(130));
+}
+
+/// Run hooksmith in the given directory on a new pseudo-terminal, as its controlling
+/// terminal and standard streams, typing `input` into it. Returns what was printed.
+#[cfg(unix)]
+fn hooksmith_in_terminal(dir: &Path, args: &[&str], input: &str) -> (bool, String) {
+    use std::{
+        fs::File,
+        io::{Read, Write},
+        os::{
+            fd::{FromRawFd, OwnedFd},
+            unix::process::CommandExt,
+        },
+        time::{Duration, Instant},
+    };
+
+    let (mut master, mut slave) = (0, 0);
+    // SAFETY: `openpty` fills in both descriptors, owned from then on
+    let (mut master, slave) = unsafe {
+        let opened = libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null(),
+        );
+        assert_eq!(opened, 0);
+        (File::from_raw_fd(master), OwnedFd::from_raw_fd(slave))
+    };
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_hooksmith"));
+    cmd.arg("--no-global-config")
+        .args(args)
+        .current_dir(dir)
+        .stdin(slave.try_clone().unwrap())
+        .stdout(slave.try_clone().unwrap())
+        .stderr(slave);
+    // SAFETY: `setsid` and `ioctl` are async-signal-safe
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn().unwrap();
+    drop(cmd);
+
+    master.write_all(input.as_bytes()).unwrap();
+    let mut reader = master.try_clone().unwrap();
+    let printed = std::thread::spawn(move || {
+        let mut printed = Vec::new();
+        // Reading fails once the terminal is closed on the other side
+        let _ = reader.read_to_end(&mut printed);
+        String::from_utf8_lossy(&printed).into_owned()
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("hooksmith hung on the terminal");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    (status.success(), printed.join().unwrap())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_commands_read_from_the_terminal() {
+    let dir = repository(
+        "pre-commit:\n  commands:\n    - read answer </dev/tty; echo \"got $answer\"\n    - run: read answer </dev/tty; echo \"timed $answer\"\n      timeout: 30\n",
+    );
+
+    let (success, printed) = hooksmith_in_terminal(dir.path(), &["run", "pre-commit"], "hi\nho\n");
+    assert!(success, "{printed}");
+    assert!(printed.contains("got hi"), "{printed}");
+    assert!(printed.contains("timed ho"), "{printed}");
+}